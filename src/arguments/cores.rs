@@ -0,0 +1,133 @@
+//! The number of CPU cores that jobs may use, which is the default number of job slots. Under
+//! `taskset`, or within a container, a process may be confined to fewer cores than the machine
+//! has, by its CPU affinity or by the CPU quota of its cgroup, and so the number of cores is the
+//! least of those limits, unless `--hardware-cores` was given.
+
+use num_cpus;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static HARDWARE: AtomicBool = AtomicBool::new(false);
+
+/// Counts every core of the machine, disregarding the limits of the process.
+pub fn use_hardware() {
+    HARDWARE.store(true, Ordering::Relaxed);
+}
+
+/// The number of cores which jobs may use.
+pub fn get() -> usize {
+    let hardware = num_cpus::get();
+    if HARDWARE.load(Ordering::Relaxed) { return hardware }
+    limit().map_or(hardware, |limit| limit.min(hardware).max(1))
+}
+
+/// The least of the limits of the process, if it has any.
+#[cfg(target_os = "linux")]
+fn limit() -> Option<usize> {
+    use std::fs;
+    let affinity = fs::read_to_string("/proc/self/status").ok().and_then(|status| parse_status(&status));
+    let quota = fs::read_to_string("/proc/self/cgroup").ok().and_then(|cgroup| quota(&cgroup));
+    least(affinity, quota)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn limit() -> Option<usize> { None }
+
+#[cfg(target_os = "linux")]
+fn least(first: Option<usize>, second: Option<usize>) -> Option<usize> {
+    match (first, second) {
+        (Some(first), Some(second)) => Some(first.min(second)),
+        (first, second) => first.or(second)
+    }
+}
+
+/// The number of cores which the CPU quota of the cgroups of the process allows, where `cgroup` is
+/// the contents of `/proc/self/cgroup`. The quota of a cgroup also applies to its descendants.
+#[cfg(target_os = "linux")]
+fn quota(cgroup: &str) -> Option<usize> {
+    use std::fs;
+    use std::path::Path;
+
+    let mut limit = None;
+    for line in cgroup.lines() {
+        let mut fields = line.splitn(3, ':');
+        let (hierarchy, controllers, path) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(hierarchy), Some(controllers), Some(path)) => (hierarchy, controllers, path.trim_start_matches('/')),
+            _ => continue
+        };
+
+        if hierarchy == "0" && controllers.is_empty() {
+            let root = Path::new("/sys/fs/cgroup");
+            let mut directory = root.join(path);
+            loop {
+                let max = fs::read_to_string(directory.join("cpu.max")).ok();
+                limit = least(limit, max.and_then(|max| parse_cpu_max(&max)));
+                if directory == root || !directory.pop() { break }
+            }
+        } else if controllers.split(',').any(|controller| controller == "cpu") {
+            let read = |directory: &Path, file: &str| fs::read_to_string(directory.join(file)).ok()
+                .and_then(|value| value.trim().parse::<i64>().ok());
+            for root in &["/sys/fs/cgroup/cpu", "/sys/fs/cgroup/cpu,cpuacct"] {
+                let root = Path::new(root);
+                for directory in &[root.join(path), root.to_path_buf()] {
+                    let (quota, period) = (read(directory, "cpu.cfs_quota_us"), read(directory, "cpu.cfs_period_us"));
+                    if let (Some(quota), Some(period)) = (quota, period) {
+                        // A quota of -1 is unlimited.
+                        if quota > 0 && period > 0 { limit = least(limit, Some(cores(quota as u64, period as u64))); }
+                    }
+                }
+            }
+        }
+    }
+    limit
+}
+
+/// Parses the `cpu.max` file of a cgroup, which holds the quota and the period of the cgroup in
+/// microseconds, where a quota of `max` is unlimited.
+#[cfg(target_os = "linux")]
+fn parse_cpu_max(max: &str) -> Option<usize> {
+    let mut fields = max.split_whitespace();
+    let quota = fields.next()?.parse::<u64>().ok()?;
+    let period = fields.next().map_or(Some(100_000), |period| period.parse::<u64>().ok())?;
+    if quota == 0 || period == 0 { return None }
+    Some(cores(quota, period))
+}
+
+/// A quota of part of a core still requires a core of its own.
+#[cfg(target_os = "linux")]
+fn cores(quota: u64, period: u64) -> usize {
+    quota.div_ceil(period) as usize
+}
+
+/// Counts the cores of the `Cpus_allowed_list` field of `/proc/self/status`, which is the CPU
+/// affinity of the process, written as a list of ranges, such as `0-3,8`.
+#[cfg(target_os = "linux")]
+fn parse_status(status: &str) -> Option<usize> {
+    let line = status.lines().find(|line| line.starts_with("Cpus_allowed_list:"))?;
+    let mut count = 0;
+    for range in line["Cpus_allowed_list:".len()..].trim().split(',') {
+        count += match range.find('-') {
+            Some(dash) => {
+                let (first, last) = (range[..dash].parse::<usize>().ok()?, range[dash+1..].parse::<usize>().ok()?);
+                last.checked_sub(first)? + 1
+            },
+            None => { range.parse::<usize>().ok()?; 1 }
+        };
+    }
+    Some(count).filter(|&count| count != 0)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits() {
+        assert_eq!(parse_status("Name:\tparallel\nCpus_allowed:\tff\nCpus_allowed_list:\t0-3,6,8-9\n"), Some(7));
+        assert_eq!(parse_status("Name:\tparallel\n"), None);
+        assert_eq!(parse_cpu_max("250000 100000\n"), Some(3));
+        assert_eq!(parse_cpu_max("200000 100000\n"), Some(2));
+        assert_eq!(parse_cpu_max("max 100000\n"), None);
+        assert_eq!(least(Some(4), None), Some(4));
+        assert_eq!(least(Some(4), Some(2)), Some(2));
+    }
+}
@@ -1,7 +1,8 @@
+use std::error::Error;
 use std::fmt;
-use std::io::{self, Write, stderr, stdout};
+use std::io;
 use std::path::PathBuf;
-use std::process::exit;
+use super::suggestions;
 
 /// A list of all the possible errors that may happen when working with files.
 #[derive(Debug)]
@@ -21,41 +22,400 @@ impl fmt::Display for FileErr {
     }
 }
 
-/// The error type for the argument module.
+impl Error for FileErr {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            FileErr::Open(_, ref io) | FileErr::Read(_, ref io) | FileErr::Write(_, ref io) => Some(io)
+        }
+    }
+}
+
+/// The error type for the argument module. Variants which concern an invalid parameter
+/// contain the value that was supplied.
 #[derive(Debug)]
 pub enum ParseErr {
-    /// The value of the job delay parameter is not set to a number.
-    DelayNaN(usize),
+    /// The allow overlap parameter was given without the every parameter.
+    AllowOverlapWithoutEvery,
+    /// The arg file parameter was not set.
+    ArgFileNoValue,
+    /// The arg file separator parameter was not set, or was empty.
+    ArgFileSepNoValue,
+    /// The arg separator parameter was not set, or was empty.
+    ArgSepNoValue,
+    /// The basefile parameter was not set.
+    BasefileNoValue,
+    /// The chunk file parameter was not a size, such as 100M.
+    ChunkFileInvalid(String),
+    /// The chunk file parameter was not set.
+    ChunkFileNoValue,
+    /// The chunk file parameter may not be combined with the given source of inputs.
+    ChunkFileUnsupported(&'static str),
+    /// The class parameter was not of the form PATTERN=N, where N is a positive number.
+    ClassInvalid(String),
+    /// The class parameter was not set.
+    ClassNoValue,
+    /// The command file parameter was not set.
+    CmdFileNoValue,
+    /// The collect parameter was not set.
+    CollectNoValue,
+    /// The colsep parameter is not a valid regular expression, for the given reason.
+    ColsepInvalid(String, String),
+    /// The colsep parameter was not set.
+    ColsepNoValue,
+    /// The cost per second parameter was not a non-negative number.
+    CostInvalid(String),
+    /// The cost per second parameter was not set.
+    CostNoValue,
+    /// The value of the job delay parameter is not set to a duration.
+    DelayInvalid(String),
     /// The job delay parameter was not set.
     DelayNoValue,
+    /// The discard output parameter was combined with a parameter which uses the output of the jobs.
+    DiscardOutputWith(&'static str),
+    /// The env parameter was not set.
+    EnvNoValue,
+    /// The file of `--record-env` could not be located, as `HOME` is not set.
+    EnvWithoutHome,
+    /// The value of the epipe status parameter is not set to a number.
+    EpipeStatusNaN(String),
+    /// The epipe status parameter was not set.
+    EpipeStatusNoValue,
+    /// The every parameter was not set to a positive duration.
+    EveryInvalid(String),
+    /// The every parameter was not set.
+    EveryNoValue,
+    /// The every parameter was given without inputs, which would be read from the standard input only once.
+    EveryWithStdin,
+    /// The file given to the execute plan parameter is not a plan.
+    ExecutePlanInvalid(PathBuf, String),
+    /// The execute plan parameter was not set.
+    ExecutePlanNoValue,
+    /// The execute plan parameter was given along with a command or inputs.
+    ExecutePlanWithCommand,
+    /// The expired parameter was not one of skip or fail.
+    ExpiredInvalid(String),
+    /// The expired parameter was not set.
+    ExpiredNoValue,
+    /// The export parameter was not set to a supported scheduler.
+    ExportInvalid(String),
+    /// The export parameter was not set.
+    ExportNoValue,
     /// An error occurred with accessing the unprocessed file.
     File(FileErr),
+    /// Under the GNU compat parameter, a short option denotes a different option in GNU Parallel,
+    /// given along with the long form of the option here.
+    GnuConflict(char, &'static str, &'static str),
+    /// An option of GNU Parallel which is not implemented, along with the nearest equivalent.
+    GnuUnsupported(String, Option<&'static str>),
+    /// The GPUs parameter was not set to a positive number.
+    GpusNaN(String),
+    /// The GPUs parameter was not set.
+    GpusNoValue,
+    /// The GPUs parameter was given, but no GPUs were found.
+    GpusUnavailable,
+    /// The header parameter was not `:`.
+    HeaderInvalid(String),
+    /// The header parameter was not set.
+    HeaderNoValue,
+    /// The header parameter may not be used with the given source of inputs.
+    HeaderUnsupported(&'static str),
+    /// The header parameter was given without the colsep parameter, which separates the named columns.
+    HeaderWithoutColsep,
+    /// The host env parameter was not of the form `LOGIN=NAME=VALUE`.
+    HostEnvInvalid(String),
+    /// The host env parameter was not set.
+    HostEnvNoValue,
+    /// The host setup parameter was not of the form `LOGIN=COMMANDS`.
+    HostSetupInvalid(String),
+    /// The host setup parameter was not set.
+    HostSetupNoValue,
+    /// The host env or host setup parameters named a login which was not given as a host.
+    HostUnknown(String),
+    /// The hostfile watch parameter was not a duration.
+    HostfileWatchInvalid(String),
+    /// The hostfile watch parameter was not set.
+    HostfileWatchNoValue,
+    /// The hostfile watch parameter was given without the sshloginfile parameter, whose hosts it re-reads.
+    HostfileWatchWithoutFile,
     /// The joblog parameter was not set.
     JoblogNoValue,
     /// The jobs number parameter was not set to a number.
     JobsNaN(String),
     /// The jobs number parameter was not set.
     JobsNoValue,
-    /// An invalid argument flag was provided.
-    InvalidArgument(usize),
+    /// An input file given after `::::` does not exist.
+    InputFileNotFound(PathBuf),
+    /// The inner jobs parameter was neither `auto` nor a positive number of jobs.
+    InnerJobsInvalid(String),
+    /// The inner jobs parameter was not set.
+    InnerJobsNoValue,
+    /// An invalid argument flag was provided at the given position.
+    InvalidArgument(usize, String),
+    /// The ionice parameter was not an I/O scheduling class, with an optional level.
+    IoniceInvalid(String),
+    /// The ionice parameter was not set.
+    IoniceNoValue,
+    /// The key parameter was not set.
+    KeyNoValue,
+    /// The label parameter was not set.
+    LabelNoValue,
+    /// The load parameter was not a positive number, or a percentage of the cores.
+    LoadInvalid(String),
+    /// The load parameter was not set.
+    LoadNoValue,
+    /// The local only parameter was not a valid regular expression, for the given reason.
+    LocalOnlyInvalid(String, String),
+    /// The local only parameter was not set.
+    LocalOnlyNoValue,
+    /// The local slots parameter was not a positive number.
+    LocalSlotsInvalid(String),
+    /// The local slots parameter was not set.
+    LocalSlotsNoValue,
+    /// The log commands parameter was given without the joblog parameter.
+    LogCommandsWithoutJoblog,
     /// The value for `max_args` was not set to a number.
-    MaxArgsNaN(usize),
+    MaxArgsNaN(String),
     /// No value was provided for the `max_args` flag.
     MaxArgsNoValue,
+    /// The max cost parameter was not a non-negative number.
+    MaxCostInvalid(String),
+    /// The max cost parameter was not set.
+    MaxCostNoValue,
+    /// The max cost parameter was given without the cost per second parameter.
+    MaxCostWithoutCost,
+    /// The max failures parameter was not set to a number.
+    MaxFailuresNaN(String),
+    /// The max failures parameter was not set.
+    MaxFailuresNoValue,
+    /// The max runtime parameter was not a duration.
+    MaxRuntimeInvalid(String),
+    /// The max runtime parameter was not set.
+    MaxRuntimeNoValue,
+    /// The value for `max_per_key` was not set to a positive number.
+    MaxPerKeyNaN(String),
+    /// No value was provided for the `max_per_key` flag.
+    MaxPerKeyNoValue,
+    /// The max spool parameter was not a size, such as 10G.
+    MaxSpoolInvalid(String),
+    /// The max spool parameter was not set.
+    MaxSpoolNoValue,
     /// The memfree parameter was invalid.
-    MemInvalid(usize),
+    MemInvalid(String),
     /// The memfree parameter was not set.
     MemNoValue,
+    /// The merge logs parameter was not given any job logs.
+    MergeLogsNoValue,
+    /// The needs parameter was not set.
+    NeedsNoValue,
+    /// The needs parameter was given without any pool parameter.
+    NeedsWithoutPool,
+    /// The nice parameter was not a niceness from -20 to 19.
+    NiceInvalid(String),
+    /// The nice parameter was not set.
+    NiceNoValue,
     /// No arguments were given, so no action can be taken.
     NoArguments,
+    /// The not after parameter was not a positive number.
+    NotAfterNaN(String),
+    /// The not after parameter was not set.
+    NotAfterNoValue,
+    /// The not before parameter was not a positive number.
+    NotBeforeNaN(String),
+    /// The not before parameter was not set.
+    NotBeforeNoValue,
+    /// The notify command parameter was not set.
+    NotifyCommandNoValue,
     /// An invalid command was supplied whose quotes aren't terminated.
     NonTerminated(String),
+    /// The only label parameter was not set.
+    OnlyLabelNoValue,
+    /// The only label parameter was given without the label parameter, which labels the jobs.
+    OnlyLabelWithoutLabel,
+    /// The permute order parameter was not one of row-major, column-major, or interleaved.
+    PermuteOrderInvalid(String),
+    /// The permute order parameter was not set.
+    PermuteOrderNoValue,
+    /// The plan parameter was not set.
+    PlanNoValue,
+    /// The pool parameter was not of the form NAME=N, where N is a positive number.
+    PoolInvalid(String),
+    /// The pool parameter was not set.
+    PoolNoValue,
+    /// The file descriptor of the progress fd parameter is not open.
+    ProgressFd(i32, io::Error),
+    /// The progress fd parameter was not a file descriptor.
+    ProgressFdNaN(String),
+    /// The progress fd parameter was not set.
+    ProgressFdNoValue,
+    /// The record parameter was not set.
+    RecordNoValue,
     /// The standard input could not be redirected to the given file
     RedirFile(PathBuf),
+    /// The reduce parameter was not set.
+    ReduceNoValue,
+    /// The reduce order parameter was not one of input or completion.
+    ReduceOrderInvalid(String),
+    /// The reduce order parameter was not set.
+    ReduceOrderNoValue,
+    /// The reduce parameter was given along with the tty parameter, whose output is not captured.
+    ReduceWithTty,
+    /// The ramp up parameter was not set to a duration.
+    RampUpInvalid(String),
+    /// The ramp up parameter was not set.
+    RampUpNoValue,
+    /// The file given to the replay parameter is not a record.
+    ReplayInvalid(PathBuf, String),
+    /// The replay jobs parameter was not a list of job numbers.
+    ReplayJobsInvalid(String),
+    /// The replace parameter was not set.
+    ReplaceNoValue,
+    /// The replay jobs parameter was not set.
+    ReplayJobsNoValue,
+    /// The replay jobs parameter was given without the replay parameter.
+    ReplayJobsWithoutReplay,
+    /// The replay parameter was not set.
+    ReplayNoValue,
+    /// The replay parameter was given along with a command or inputs.
+    ReplayWithCommand,
+    /// The require input exists parameter was not one of skip, fail, or retry.
+    RequireExistsInvalid(String),
+    /// The require input exists parameter was not set.
+    RequireExistsNoValue,
+    /// The resume or resume failed parameter was given without the joblog parameter.
+    ResumeWithoutJoblog(&'static str),
+    /// The return parameter was not set.
+    ReturnNoValue,
+    /// The return first parameter was not set to a positive number.
+    ReturnFirstNaN(String),
+    /// The return first parameter was not set.
+    ReturnFirstNoValue,
+    /// The retries parameter was not a number.
+    RetriesNaN(String),
+    /// The retries parameter was not set.
+    RetriesNoValue,
+    /// The retry priority parameter was not one of first or last.
+    RetryPriorityInvalid(String),
+    /// The retry priority parameter was not set.
+    RetryPriorityNoValue,
+    /// The run ID parameter was not set.
+    RunIdNoValue,
+    /// A value of the seq parameter was not an integer.
+    SeqInvalid(String),
+    /// The seq parameter was not given both of its values.
+    SeqNoValue,
+    /// The seq parameter was given along with other inputs.
+    SeqWithInputs,
+    /// The shard ID parameter was not of the form i/n.
+    ShardIdInvalid(String),
+    /// The shard ID parameter was not set.
+    ShardIdNoValue,
+    /// The speculative max parameter was not a number.
+    SpeculativeMaxNaN(String),
+    /// The speculative max parameter was not set.
+    SpeculativeMaxNoValue,
+    /// The speculative max parameter was given along with a parameter whose resources a duplicate would not claim.
+    SpeculativeUnsupported(&'static str),
+    /// A host of the sshlogin parameter was empty, or had an invalid number of slots.
+    SshloginInvalid(String),
+    /// The sshlogin parameter was not set.
+    SshloginNoValue,
+    /// The sshloginfile parameter was not set.
+    SshloginfileNoValue,
+    /// The non-blocking stdin parameter was given, and the inputs would have been read from a terminal.
+    StdinIsTerminal,
+    /// Under the strict parameter, a `:::+` list was linked to a list of a different length.
+    StrictLinkedLengths(usize, usize),
+    /// Under the strict parameter, inputs were skipped because they could not be read.
+    StrictSkipped(usize),
+    /// The systemd property parameter was not in the form of `NAME=VALUE`.
+    SystemdPropertyInvalid(String),
+    /// The systemd property parameter was not set.
+    SystemdPropertyNoValue,
+    /// The tty parameter was given, but the terminal could not be opened.
+    TtyUnavailable(io::Error),
+    /// The tag sep parameter was not set.
+    TagSepNoValue,
+    /// The tag sep parameter was given without the tag or ctag parameters.
+    TagSepWithoutTag,
+    /// The tag or ctag parameter was given along with ungroup, whose output is not read by the program.
+    TagWithUngroup,
+    /// The value of the test deterministic parameter is not set to a number.
+    TestDeterministicNaN(String),
+    /// The test deterministic parameter was not set.
+    TestDeterministicNoValue,
+    /// The test deterministic parameter was given along with retries, whose attempts would wait on their own turn.
+    TestDeterministicWithRetries,
+    /// The termseq parameter was not a list of signals and milliseconds.
+    TermSeqInvalid(String),
+    /// The then jobs parameter was not a positive number of jobs.
+    ThenJobsInvalid(String),
+    /// The then jobs parameter was not set.
+    ThenJobsNoValue,
+    /// The then jobs parameter was given before any then parameter, whose stage it limits.
+    ThenJobsWithoutThen,
+    /// The then parameter was not set.
+    ThenNoValue,
+    /// The then parameter was given along with the pipe parameter, whose inputs are not arguments.
+    ThenWithPipe,
+    /// The then parameter was given without a command, which is the first stage of each job.
+    ThenWithoutCommand,
+    /// The throttle file could not be parsed, for the given reason.
+    ThrottleFileInvalid(String, String),
+    /// The throttle-file parameter was not set.
+    ThrottleFileNoValue,
+    /// The termseq parameter was not set.
+    TermSeqNoValue,
     /// The timeout parameter was not set to a number.
-    TimeoutNaN(usize),
+    TimeoutNaN(String),
     /// The timeout parameter was not set.
     TimeoutNoValue,
+    /// The timeout column parameter was not set to a positive number.
+    TimeoutColNaN(String),
+    /// The timeout column parameter was not set.
+    TimeoutColNoValue,
+    /// The total jobs parameter was not set to a positive number.
+    TotalJobsNaN(String),
+    /// The total jobs parameter was not set.
+    TotalJobsNoValue,
+    /// The transferfile parameter was not set.
+    TransferfileNoValue,
+    /// A parameter which applies to remote hosts was given without the sshlogin parameter.
+    TransferWithoutSshlogin(&'static str),
+    /// The tune parameter was not a list of valid settings, for the given reason.
+    TuneInvalid(String, String),
+    /// The tune parameter was not set.
+    TuneNoValue,
+    /// The ungroup or line buffer parameter was given along with a parameter which requires the output
+    /// of each job to be grouped.
+    Ungrouped(&'static str, &'static str),
+    /// An option at the given position is not supported.
+    UnknownOption(usize, String),
+    /// The webhook parameter was not set.
+    WebhookNoValue,
+    /// The webhook retries parameter was not a number.
+    WebhookRetriesNaN(String),
+    /// The webhook retries parameter was not set.
+    WebhookRetriesNoValue,
+    /// The webhook timeout parameter was not a positive duration.
+    WebhookTimeoutInvalid(String),
+    /// The webhook timeout parameter was not set.
+    WebhookTimeoutNoValue,
+    /// An option of the webhook was given without the webhook parameter.
+    WebhookWithoutUrl(&'static str),
+    /// The weight column parameter was not set to a positive number.
+    WeightColNaN(String),
+    /// The weight column parameter was not set.
+    WeightColNoValue,
+    /// The where parameter refers to a list beyond the number of input lists.
+    WhereColumn(usize, usize),
+    /// The where parameter is not a valid expression, for the given reason.
+    WhereInvalid(String, String),
+    /// The where parameter was not set.
+    WhereNoValue,
+    /// The where parameter was given without any lists of inputs to filter.
+    WhereWithoutLists,
+    /// The workdir could not be created.
+    WorkDir(PathBuf, io::Error),
     /// The workdir parameter was not set.
     WorkDirNoValue,
 }
@@ -64,68 +424,358 @@ impl From<FileErr> for ParseErr {
     fn from(input: FileErr) -> ParseErr { ParseErr::File(input) }
 }
 
-impl ParseErr {
-    pub fn handle(self, arguments: &[String]) -> ! {
-        // Always lock an output buffer before using it.
-        let stderr = stderr();
-        let stdout = stdout();
-        let mut stderr = stderr.lock();
-        let stdout = &mut stdout.lock();
-        let _ = stderr.write(b"parallel: parsing error: ");
-        match self {
-            ParseErr::File(file_err) => {
-                let _ = writeln!(stderr, "{}", file_err);
-            }
-            ParseErr::DelayNaN(index) => {
-                let _ = write!(stderr, "delay parameter, '{}', is not a number.\n", arguments[index]);
-            },
-            ParseErr::DelayNoValue => {
-                let _ = stderr.write(b"no delay parameter was defined.\n");
-            },
-            ParseErr::JoblogNoValue => {
-                let _ = stderr.write(b"no joblog parameter was defined.\n");
-            },
-            ParseErr::JobsNaN(value) => {
-                let _ = write!(stderr, "jobs parameter, '{}', is not a number.\n", value);
-            },
-            ParseErr::JobsNoValue => {
-                let _ = stderr.write(b"no jobs parameter was defined.\n");
-            },
-            ParseErr::MaxArgsNaN(index) => {
-                let _ = write!(stderr, "groups parameter, '{}', is not a number.\n", arguments[index]);
-            },
-            ParseErr::MaxArgsNoValue => {
-                let _ = stderr.write(b"no groups parameter was defined.\n");
-            },
-            ParseErr::MemNoValue => {
-                let _ = stderr.write(b"no memory parameter was defined.\n");
-            },
-            ParseErr::MemInvalid(index) => {
-                let _ = write!(stderr, "invalid memory value: {}\n", arguments[index]);
-            }
-            ParseErr::InvalidArgument(index) => {
-                let _ = write!(stderr, "invalid argument: {}\n", arguments[index]);
-            },
-            ParseErr::NoArguments => {
-                let _ = write!(stderr, "no input arguments were given.\n");
-            },
-            ParseErr::NonTerminated(command) => {
-                let _ = write!(stderr, "command is not properly terminated:\n  $ {}\nTip: Try using the --quote parameter to escape your command\n", command);
-            },
-            ParseErr::RedirFile(path) => {
-                let _ = write!(stderr, "an error occurred while redirecting file: {:?}\n", path);
-            },
-            ParseErr::TimeoutNaN(index) => {
-                let _ = write!(stderr, "invalid timeout value: {}\n", arguments[index]);
-            },
-            ParseErr::TimeoutNoValue => {
-                let _ = stderr.write(b"no timeout parameter was defined.\n");
-            },
-            ParseErr::WorkDirNoValue => {
-                let _ = stderr.write(b"no workdir parameter was defined.\n");
-            }
-        };
-        let _ = stdout.write(b"For help on command-line usage, execute `parallel -h`\n");
-        exit(1);
+impl fmt::Display for ParseErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseErr::File(ref file_err) => write!(f, "{}", file_err),
+            ParseErr::AllowOverlapWithoutEvery => write!(f, "allow overlap parameter requires the every parameter."),
+            ParseErr::ArgFileNoValue => write!(f, "no arg file parameter was defined."),
+            ParseErr::ArgFileSepNoValue => write!(f, "no arg file separator parameter was defined."),
+            ParseErr::ArgSepNoValue => write!(f, "no arg separator parameter was defined."),
+            ParseErr::BasefileNoValue => write!(f, "no basefile parameter was defined."),
+            ParseErr::ChunkFileInvalid(ref value) => write!(f, "chunk file parameter, '{}', is not a size.", value),
+            ParseErr::ChunkFileNoValue => write!(f, "no chunk file parameter was defined."),
+            ParseErr::ChunkFileUnsupported(inputs) => write!(f, "chunk file parameter may not be used with {}.", inputs),
+            ParseErr::ClassInvalid(ref value) => {
+                write!(f, "class parameter, '{}', is not of the form PATTERN=N, where N is a positive number.", value)
+            },
+            ParseErr::ClassNoValue => write!(f, "no class parameter was defined."),
+            ParseErr::CmdFileNoValue => write!(f, "no command file parameter was defined."),
+            ParseErr::CollectNoValue => write!(f, "no collect parameter was defined."),
+            ParseErr::ColsepInvalid(ref value, ref why) => {
+                write!(f, "colsep parameter, '{}', is not a valid regular expression: {}", value, why)
+            },
+            ParseErr::ColsepNoValue => write!(f, "no colsep parameter was defined."),
+            ParseErr::CostInvalid(ref value) => {
+                write!(f, "cost per second parameter, '{}', is not a non-negative number.", value)
+            },
+            ParseErr::CostNoValue => write!(f, "no cost per second parameter was defined."),
+            ParseErr::DelayInvalid(ref value) => {
+                write!(f, "delay parameter, '{}', is not a duration, such as 0.5 or 500ms.", value)
+            },
+            ParseErr::DelayNoValue => write!(f, "no delay parameter was defined."),
+            ParseErr::DiscardOutputWith(parameter) => {
+                write!(f, "discard output parameter may not be combined with the {} parameter.", parameter)
+            },
+            ParseErr::EnvNoValue => write!(f, "no env parameter was defined."),
+            ParseErr::EnvWithoutHome => write!(f, "the ignored variables of record env require HOME to be set."),
+            ParseErr::EpipeStatusNaN(ref value) => write!(f, "epipe status parameter, '{}', is not a number.", value),
+            ParseErr::EpipeStatusNoValue => write!(f, "no epipe status parameter was defined."),
+            ParseErr::EveryInvalid(ref value) => write!(f, "every parameter, '{}', is not a positive duration.", value),
+            ParseErr::EveryNoValue => write!(f, "no every parameter was defined."),
+            ParseErr::EveryWithStdin => {
+                write!(f, "every parameter requires inputs which are not read from the standard input.")
+            },
+            ParseErr::ExecutePlanInvalid(ref path, ref why) => {
+                write!(f, "execute plan parameter, {:?}, is not a plan: {}.", path, why)
+            },
+            ParseErr::ExecutePlanNoValue => write!(f, "no execute plan parameter was defined."),
+            ParseErr::ExecutePlanWithCommand => {
+                write!(f, "execute plan parameter may not be combined with a command or inputs, which are planned.")
+            },
+            ParseErr::ExpiredInvalid(ref value) => {
+                write!(f, "expired parameter, '{}', is not one of skip or fail.", value)
+            },
+            ParseErr::ExpiredNoValue => write!(f, "no expired parameter was defined."),
+            ParseErr::ExportInvalid(ref value) => {
+                write!(f, "export parameter, '{}', is not one of slurm, pbs, or lsf.", value)
+            },
+            ParseErr::ExportNoValue => write!(f, "no export parameter was defined."),
+            ParseErr::GnuConflict(option, gnu, ours) => {
+                write!(f, "'-{}' denotes {} in GNU Parallel, which is not implemented. Use '{}' for the \
+                    meaning of '-{}' here.", option, gnu, ours, option)
+            },
+            ParseErr::GnuUnsupported(ref name, equivalent) => {
+                let dashes = if name.len() == 1 { "-" } else { "--" };
+                write!(f, "'{}{}' is an option of GNU Parallel which is not implemented.", dashes, name)?;
+                match equivalent {
+                    Some(equivalent) => write!(f, " The nearest equivalent is {}.", equivalent),
+                    None             => Ok(())
+                }
+            },
+            ParseErr::GpusNaN(ref value) => write!(f, "gpus parameter, '{}', is not a positive number.", value),
+            ParseErr::GpusNoValue => write!(f, "no gpus parameter was defined."),
+            ParseErr::GpusUnavailable => {
+                write!(f, "gpus parameter requires GPUs, but none were found by nvidia-smi or in CUDA_VISIBLE_DEVICES.")
+            },
+            ParseErr::HeaderInvalid(ref value) => {
+                write!(f, "header parameter, '{}', is not ':', which takes the first input as the header.", value)
+            },
+            ParseErr::HeaderNoValue => write!(f, "no header parameter was defined."),
+            ParseErr::HeaderUnsupported(inputs) => write!(f, "header parameter may not be used with {}.", inputs),
+            ParseErr::HeaderWithoutColsep => write!(f, "header parameter requires the colsep parameter."),
+            ParseErr::HostEnvInvalid(ref value) => {
+                write!(f, "host env parameter, '{}', is not of the form LOGIN=NAME=VALUE.", value)
+            },
+            ParseErr::HostEnvNoValue => write!(f, "no host env parameter was defined."),
+            ParseErr::HostSetupInvalid(ref value) => {
+                write!(f, "host setup parameter, '{}', is not of the form LOGIN=COMMANDS.", value)
+            },
+            ParseErr::HostSetupNoValue => write!(f, "no host setup parameter was defined."),
+            ParseErr::HostUnknown(ref login) => write!(f, "'{}' is not one of the sshlogin hosts.", login),
+            ParseErr::HostfileWatchInvalid(ref value) => {
+                write!(f, "hostfile watch parameter, '{}', is not a duration, such as 30s or 5m.", value)
+            },
+            ParseErr::HostfileWatchNoValue => write!(f, "no hostfile watch parameter was defined."),
+            ParseErr::HostfileWatchWithoutFile => write!(f, "hostfile watch parameter requires the sshloginfile parameter."),
+            ParseErr::JoblogNoValue => write!(f, "no joblog parameter was defined."),
+            ParseErr::JobsNaN(ref value) => write!(f, "jobs parameter, '{}', is not a number.", value),
+            ParseErr::JobsNoValue => write!(f, "no jobs parameter was defined."),
+            ParseErr::LoadInvalid(ref value) => {
+                write!(f, "load parameter, '{}', is not a load average, such as 8 or 150%.", value)
+            },
+            ParseErr::LoadNoValue => write!(f, "no load parameter was defined."),
+            ParseErr::LocalOnlyInvalid(ref value, ref why) => {
+                write!(f, "local only parameter, '{}', is not a valid regular expression: {}", value, why)
+            },
+            ParseErr::LocalOnlyNoValue => write!(f, "no local only parameter was defined."),
+            ParseErr::LocalSlotsInvalid(ref value) => {
+                write!(f, "local slots parameter, '{}', is not a positive number.", value)
+            },
+            ParseErr::LocalSlotsNoValue => write!(f, "no local slots parameter was defined."),
+            ParseErr::LogCommandsWithoutJoblog => {
+                write!(f, "log commands parameter requires the joblog parameter, which the commands are logged to.")
+            },
+            ParseErr::MaxArgsNaN(ref value) => write!(f, "groups parameter, '{}', is not a number.", value),
+            ParseErr::MaxArgsNoValue => write!(f, "no groups parameter was defined."),
+            ParseErr::KeyNoValue => write!(f, "no key parameter was defined."),
+            ParseErr::LabelNoValue => write!(f, "no label parameter was defined."),
+            ParseErr::MaxCostInvalid(ref value) => {
+                write!(f, "max cost parameter, '{}', is not a non-negative number.", value)
+            },
+            ParseErr::MaxCostNoValue => write!(f, "no max cost parameter was defined."),
+            ParseErr::MaxCostWithoutCost => write!(f, "max cost parameter requires the cost per second parameter."),
+            ParseErr::MaxFailuresNaN(ref value) => {
+                write!(f, "max failures parameter, '{}', is not a number.", value)
+            },
+            ParseErr::MaxFailuresNoValue => write!(f, "no max failures parameter was defined."),
+            ParseErr::MaxRuntimeInvalid(ref value) => {
+                write!(f, "max runtime parameter, '{}', is not a duration.", value)
+            },
+            ParseErr::MaxRuntimeNoValue => write!(f, "no max runtime parameter was defined."),
+            ParseErr::MaxPerKeyNaN(ref value) => {
+                write!(f, "max per key parameter, '{}', is not a positive number.", value)
+            },
+            ParseErr::MaxPerKeyNoValue => write!(f, "no max per key parameter was defined."),
+            ParseErr::MaxSpoolInvalid(ref value) => {
+                write!(f, "max spool parameter, '{}', is not a size, such as 10G.", value)
+            },
+            ParseErr::MaxSpoolNoValue => write!(f, "no max spool parameter was defined."),
+            ParseErr::MemNoValue => write!(f, "no memory parameter was defined."),
+            ParseErr::MergeLogsNoValue => write!(f, "no job logs were given to the merge logs parameter."),
+            ParseErr::MemInvalid(ref value) => write!(f, "invalid memory value: {}", value),
+            ParseErr::InnerJobsInvalid(ref value) => {
+                write!(f, "inner jobs parameter, '{}', is neither auto nor a number of jobs.", value)
+            },
+            ParseErr::InnerJobsNoValue => write!(f, "no inner jobs parameter was defined."),
+            ParseErr::InputFileNotFound(ref path) => {
+                write!(f, "input file, {:?}, does not exist.\nTip: inputs following '::::' are read from \
+                    files, whereas inputs following ':::' are used as they are given.", path)
+            },
+            ParseErr::InvalidArgument(position, ref value) => {
+                write!(f, "invalid argument at position {}: '{}'", position, value)
+            },
+            ParseErr::IoniceInvalid(ref value) => {
+                write!(f, "ionice parameter, '{}', is not an I/O scheduling class, such as idle or best-effort:7.", value)
+            },
+            ParseErr::IoniceNoValue => write!(f, "no ionice parameter was defined."),
+            ParseErr::NeedsNoValue => write!(f, "no needs parameter was defined."),
+            ParseErr::NiceInvalid(ref value) => {
+                write!(f, "nice parameter, '{}', is not a niceness from -20 to 19.", value)
+            },
+            ParseErr::NiceNoValue => write!(f, "no nice parameter was defined."),
+            ParseErr::NeedsWithoutPool => write!(f, "needs parameter requires the pool parameter."),
+            ParseErr::NoArguments => write!(f, "no input arguments were given."),
+            ParseErr::NotAfterNaN(ref value) => write!(f, "not after parameter, '{}', is not a positive number.", value),
+            ParseErr::NotAfterNoValue => write!(f, "no not after parameter was defined."),
+            ParseErr::NotBeforeNaN(ref value) => {
+                write!(f, "not before parameter, '{}', is not a positive number.", value)
+            },
+            ParseErr::NotBeforeNoValue => write!(f, "no not before parameter was defined."),
+            ParseErr::NotifyCommandNoValue => write!(f, "no notify command parameter was defined."),
+            ParseErr::NonTerminated(ref command) => {
+                write!(f, "command is not properly terminated:\n  $ {}\nTip: Try using the --quote parameter to escape your command", command)
+            },
+            ParseErr::OnlyLabelNoValue => write!(f, "no only label parameter was defined."),
+            ParseErr::OnlyLabelWithoutLabel => write!(f, "only label parameter requires the label parameter."),
+            ParseErr::PermuteOrderInvalid(ref value) => {
+                write!(f, "permute order parameter, '{}', is not one of row-major, column-major, or interleaved.", value)
+            },
+            ParseErr::PermuteOrderNoValue => write!(f, "no permute order parameter was defined."),
+            ParseErr::PlanNoValue => write!(f, "no plan parameter was defined."),
+            ParseErr::PoolInvalid(ref value) => {
+                write!(f, "pool parameter, '{}', is not of the form NAME=N, where N is a positive number.", value)
+            },
+            ParseErr::PoolNoValue => write!(f, "no pool parameter was defined."),
+            ParseErr::ProgressFd(fd, ref why) => write!(f, "progress fd parameter, {}, is not open: {}", fd, why),
+            ParseErr::ProgressFdNaN(ref value) => {
+                write!(f, "progress fd parameter, '{}', is not a file descriptor.", value)
+            },
+            ParseErr::ProgressFdNoValue => write!(f, "no progress fd parameter was defined."),
+            ParseErr::RecordNoValue => write!(f, "no record parameter was defined."),
+            ParseErr::RedirFile(ref path) => write!(f, "an error occurred while redirecting file: {:?}", path),
+            ParseErr::ReduceNoValue => write!(f, "no reduce parameter was defined."),
+            ParseErr::ReduceOrderInvalid(ref value) => {
+                write!(f, "reduce order parameter, '{}', is not one of input or completion.", value)
+            },
+            ParseErr::ReduceOrderNoValue => write!(f, "no reduce order parameter was defined."),
+            ParseErr::ReduceWithTty => write!(f, "reduce parameter may not be combined with the tty parameter."),
+            ParseErr::RampUpInvalid(ref value) => write!(f, "ramp up parameter, '{}', is not a duration.", value),
+            ParseErr::RampUpNoValue => write!(f, "no ramp up parameter was defined."),
+            ParseErr::ReplayInvalid(ref path, ref why) => {
+                write!(f, "replay parameter, {:?}, is not a record: {}.", path, why)
+            },
+            ParseErr::ReplayJobsInvalid(ref value) => {
+                write!(f, "replay jobs parameter, '{}', is not a list of job numbers, such as 1,4-6.", value)
+            },
+            ParseErr::ReplaceNoValue => write!(f, "no replace parameter was defined."),
+            ParseErr::ReplayJobsNoValue => write!(f, "no replay jobs parameter was defined."),
+            ParseErr::ReplayJobsWithoutReplay => write!(f, "replay jobs parameter requires the replay parameter."),
+            ParseErr::ReplayNoValue => write!(f, "no replay parameter was defined."),
+            ParseErr::ReplayWithCommand => {
+                write!(f, "replay parameter may not be combined with a command or inputs, which are recorded.")
+            },
+            ParseErr::RequireExistsInvalid(ref value) => {
+                write!(f, "require input exists parameter, '{}', is not one of skip, fail, or retry.", value)
+            },
+            ParseErr::RequireExistsNoValue => write!(f, "no require input exists parameter was defined."),
+            ParseErr::ResumeWithoutJoblog(parameter) => {
+                write!(f, "{} parameter requires the joblog parameter, which logs the jobs to resume.", parameter)
+            },
+            ParseErr::ReturnNoValue => write!(f, "no return parameter was defined."),
+            ParseErr::ReturnFirstNaN(ref value) => {
+                write!(f, "return first parameter, '{}', is not a positive number.", value)
+            },
+            ParseErr::ReturnFirstNoValue => write!(f, "no return first parameter was defined."),
+            ParseErr::RetriesNaN(ref value) => write!(f, "retries parameter, '{}', is not a number.", value),
+            ParseErr::RetriesNoValue => write!(f, "no retries parameter was defined."),
+            ParseErr::RetryPriorityInvalid(ref value) => {
+                write!(f, "retry priority parameter, '{}', is not one of first or last.", value)
+            },
+            ParseErr::RetryPriorityNoValue => write!(f, "no retry priority parameter was defined."),
+            ParseErr::RunIdNoValue => write!(f, "no run ID parameter was defined."),
+            ParseErr::SeqInvalid(ref value) => write!(f, "seq parameter, '{}', is not an integer.", value),
+            ParseErr::SeqNoValue => write!(f, "seq parameter requires the first and last integers."),
+            ParseErr::SeqWithInputs => write!(f, "seq parameter may not be combined with other inputs."),
+            ParseErr::ShardIdInvalid(ref value) => {
+                write!(f, "shard id parameter, '{}', is not of the form i/n, where i is from 1 to n.", value)
+            },
+            ParseErr::ShardIdNoValue => write!(f, "no shard id parameter was defined."),
+            ParseErr::SpeculativeMaxNaN(ref value) => {
+                write!(f, "speculative max parameter, '{}', is not a number.", value)
+            },
+            ParseErr::SpeculativeMaxNoValue => write!(f, "no speculative max parameter was defined."),
+            ParseErr::SpeculativeUnsupported(parameter) => {
+                write!(f, "speculative max parameter may not be combined with the {} parameter.", parameter)
+            },
+            ParseErr::SshloginInvalid(ref value) => {
+                write!(f, "sshlogin parameter, '{}', is not a host, such as 4/user@server, or : for this machine.", value)
+            },
+            ParseErr::SshloginNoValue => write!(f, "no sshlogin parameter was defined."),
+            ParseErr::SshloginfileNoValue => write!(f, "no sshloginfile parameter was defined."),
+            ParseErr::StdinIsTerminal => {
+                write!(f, "no inputs were given, and the standard input is a terminal, which --non-blocking-stdin does not read.")
+            },
+            ParseErr::StrictLinkedLengths(original, append) => {
+                write!(f, "a list of {} inputs was linked with `:::+` to a list of {} inputs.", original, append)
+            },
+            ParseErr::StrictSkipped(sources) => write!(f, "{} input sources were skipped under --strict.", sources),
+            ParseErr::SystemdPropertyInvalid(ref value) => {
+                write!(f, "systemd property parameter, '{}', is not in the form of NAME=VALUE.", value)
+            },
+            ParseErr::SystemdPropertyNoValue => write!(f, "no systemd property parameter was defined."),
+            ParseErr::TtyUnavailable(ref why) => write!(f, "tty parameter requires a terminal: {}", why),
+            ParseErr::TagSepNoValue => write!(f, "no tag sep parameter was defined."),
+            ParseErr::TagSepWithoutTag => write!(f, "tag sep parameter requires the tag or ctag parameter."),
+            ParseErr::TagWithUngroup => write!(f, "tag parameter may not be combined with the ungroup parameter."),
+            ParseErr::TestDeterministicNaN(ref value) => {
+                write!(f, "test deterministic parameter, '{}', is not a number.", value)
+            },
+            ParseErr::TestDeterministicNoValue => write!(f, "no test deterministic parameter was defined."),
+            ParseErr::TestDeterministicWithRetries => {
+                write!(f, "test deterministic parameter may not be combined with the retries parameter.")
+            },
+            ParseErr::TermSeqInvalid(ref value) => {
+                write!(f, "termseq parameter, '{}', is not a list of signals and milliseconds, such as TERM,200,KILL,25.", value)
+            },
+            ParseErr::TermSeqNoValue => write!(f, "no termseq parameter was defined."),
+            ParseErr::ThenJobsInvalid(ref value) => write!(f, "invalid then jobs value: {}", value),
+            ParseErr::ThenJobsNoValue => write!(f, "no then jobs parameter was defined."),
+            ParseErr::ThenJobsWithoutThen => write!(f, "then jobs parameter must follow the then parameter."),
+            ParseErr::ThenNoValue => write!(f, "no then parameter was defined."),
+            ParseErr::ThenWithPipe => write!(f, "then parameter may not be combined with the pipe parameter."),
+            ParseErr::ThenWithoutCommand => write!(f, "then parameter requires a command."),
+            ParseErr::ThrottleFileInvalid(ref path, ref why) => write!(f, "throttle file, '{}', is invalid: {}.", path, why),
+            ParseErr::ThrottleFileNoValue => write!(f, "no throttle-file parameter was defined."),
+            ParseErr::TimeoutNaN(ref value) => write!(f, "invalid timeout value: {}", value),
+            ParseErr::TimeoutNoValue => write!(f, "no timeout parameter was defined."),
+            ParseErr::TimeoutColNaN(ref value) => {
+                write!(f, "timeout column parameter, '{}', is not a column number.", value)
+            },
+            ParseErr::TimeoutColNoValue => write!(f, "no timeout column parameter was defined."),
+            ParseErr::TotalJobsNaN(ref value) => {
+                write!(f, "total jobs parameter, '{}', is not a positive number.", value)
+            },
+            ParseErr::TotalJobsNoValue => write!(f, "no total jobs parameter was defined."),
+            ParseErr::TransferfileNoValue => write!(f, "no transferfile parameter was defined."),
+            ParseErr::TransferWithoutSshlogin(parameter) => {
+                write!(f, "{} parameter requires the sshlogin or sshloginfile parameters.", parameter)
+            },
+            ParseErr::TuneInvalid(ref value, ref why) => write!(f, "tune parameter, '{}', is invalid: {}.", value, why),
+            ParseErr::TuneNoValue => write!(f, "no tune parameter was defined."),
+            ParseErr::Ungrouped(mode, parameter) => {
+                write!(f, "{} parameter may not be combined with the {} parameter, which requires grouped output.",
+                    mode, parameter)
+            },
+            ParseErr::UnknownOption(position, ref option) => {
+                write!(f, "unknown option at position {}: '{}'.", position, option)?;
+                let name = option.trim_start_matches('-');
+                if let Some((name, value)) = suggestions::split_value(name) {
+                    write!(f, "\nTip: values are given as the next argument, as in '--{} {}'.", name, value)
+                } else if let Some(name) = suggestions::nearest(name) {
+                    write!(f, " Did you mean '--{}'?", name)
+                } else {
+                    Ok(())
+                }
+            },
+            ParseErr::WebhookNoValue => write!(f, "no webhook parameter was defined."),
+            ParseErr::WebhookRetriesNaN(ref value) => {
+                write!(f, "webhook retries parameter, '{}', is not a number.", value)
+            },
+            ParseErr::WebhookRetriesNoValue => write!(f, "no webhook retries parameter was defined."),
+            ParseErr::WebhookTimeoutInvalid(ref value) => {
+                write!(f, "webhook timeout parameter, '{}', is not a positive duration.", value)
+            },
+            ParseErr::WebhookTimeoutNoValue => write!(f, "no webhook timeout parameter was defined."),
+            ParseErr::WebhookWithoutUrl(parameter) => {
+                write!(f, "webhook {} parameter requires the webhook parameter.", parameter)
+            },
+            ParseErr::WeightColNaN(ref value) => {
+                write!(f, "weight column parameter, '{}', is not a column number.", value)
+            },
+            ParseErr::WeightColNoValue => write!(f, "no weight column parameter was defined."),
+            ParseErr::WhereColumn(column, lists) => {
+                write!(f, "where parameter refers to {{{}}}, but only {} input lists were given.", column, lists)
+            },
+            ParseErr::WhereInvalid(ref value, ref why) => {
+                write!(f, "where parameter, '{}', is not a valid expression: {}.", value, why)
+            },
+            ParseErr::WhereNoValue => write!(f, "no where parameter was defined."),
+            ParseErr::WhereWithoutLists => {
+                write!(f, "where parameter requires inputs to be given with ':::' or '::::'.")
+            },
+            ParseErr::WorkDir(ref path, ref why) => write!(f, "unable to create tempdir {:?}: {}", path, why),
+            ParseErr::WorkDirNoValue => write!(f, "no workdir parameter was defined."),
+        }
+    }
+}
+
+impl Error for ParseErr {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            ParseErr::File(ref file_err)  => Some(file_err),
+            ParseErr::WorkDir(_, ref why) => Some(why),
+            _                             => None
+        }
     }
 }
@@ -0,0 +1,59 @@
+/// Options of GNU Parallel which are not implemented, paired with the nearest equivalent, if any.
+const UNSUPPORTED: &[(&str, Option<&str>)] = &[
+    ("0", None),
+    ("E", None),
+    ("L", Some("--max-args")),
+    ("N", Some("--max-args")),
+    ("X", None),
+    ("block", None),
+    ("d", None),
+    ("delimiter", None),
+    ("halt", None),
+    ("halt-on-error", None),
+    ("i", Some("-I")),
+    ("link", Some(":::+")),
+    ("m", None),
+    ("max-lines", Some("--max-args")),
+    ("null", None),
+    ("pipepart", Some("--pipe")),
+    ("replace", Some("-I")),
+    ("results", None),
+    ("semaphore", None),
+    ("t", Some("--verbose")),
+    ("tagstring", None),
+    ("workdir", None),
+    ("xapply", Some(":::+")),
+];
+
+/// Short options which have a different meaning in GNU Parallel, paired with the GNU Parallel
+/// option which they denote, and the option which has the meaning of the short option here.
+const CONFLICTING: &[(char, &str, &str)] = &[
+    ('p', "--interactive", "--pipe"),
+    ('s', "--max-chars", "--silent"),
+];
+
+/// If `name` is an option of GNU Parallel which is not implemented, returns the nearest
+/// equivalent, if there is one.
+pub fn unsupported(name: &str) -> Option<Option<&'static str>> {
+    UNSUPPORTED.iter().find(|&&(option, _)| option == name).map(|&(_, equivalent)| equivalent)
+}
+
+/// Under `--gnu-compat`, short options which denote a different option in GNU Parallel are
+/// rejected, returning the GNU Parallel option and the long form of the option here.
+pub fn conflicting(option: char) -> Option<(&'static str, &'static str)> {
+    CONFLICTING.iter().find(|&&(short, _, _)| short == option).map(|&(_, gnu, ours)| (gnu, ours))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gnu_options() {
+        assert_eq!(unsupported("pipepart"), Some(Some("--pipe")));
+        assert_eq!(unsupported("tagstring"), Some(None));
+        assert_eq!(unsupported("jobs"), None);
+        assert_eq!(conflicting('p'), Some(("--interactive", "--pipe")));
+        assert_eq!(conflicting('q'), None);
+    }
+}
@@ -1,15 +1,36 @@
 use super::errors::ParseErr;
-use num_cpus;
+use super::cores;
+use std::env;
+
+/// The number of job slots of every instance which encloses a job, multiplied together, is exported
+/// to each job in this variable, so that an instance executed within a job may divide its share of
+/// the cores with `--inner-jobs`, rather than claiming every core for itself.
+pub const OUTER_SLOTS: &str = "PARALLEL_OUTER_SLOTS";
+
+/// The number of job slots of the instances which enclose this one, if it was executed by a job.
+pub fn outer_slots() -> Option<usize> {
+    env::var(OUTER_SLOTS).ok().and_then(|slots| slots.parse::<usize>().ok()).filter(|&slots| slots != 0)
+}
+
+/// With `--inner-jobs`, the number of job slots of an instance which was executed by a job with
+/// `outer` slots around it. With `auto`, the `ncores` are divided among the outer slots, and
+/// otherwise, the number of `inner` slots given is used.
+pub fn inner(ncores: usize, inner: Option<usize>, outer: usize) -> usize {
+    match inner {
+        Some(inner) => inner,
+        None        => (ncores / outer).max(1),
+    }
+}
 
 /// Receives an input that is either an integer, or percent. If the string ends with `%`, it will
-/// be calculated as a percent of the total number of CPU cores. Otherwise, the number provided
-/// will be considered the number of jobs to run in parallel.
+/// be calculated as a percent of the number of CPU cores which jobs may use. Otherwise, the number
+/// provided will be considered the number of jobs to run in parallel.
 pub fn parse(value: &str) -> Result<usize, ParseErr> {
     match (value.bytes().next().unwrap(), value.bytes().last().unwrap()) {
         (b'+', b'%') => {
             value[1..value.bytes().count()-1].parse::<usize>()
             .map(|percent| {
-                let ncpus = num_cpus::get();
+                let ncpus = cores::get();
                 ncpus + ((ncpus * percent) / 100)
             })
             .map_err(|_| ParseErr::JobsNaN(value.to_owned()))
@@ -17,7 +38,7 @@ pub fn parse(value: &str) -> Result<usize, ParseErr> {
         (b'-', b'%') => {
             value[1..value.bytes().count()-1].parse::<usize>()
                 .map(|percent| {
-                    let ncpus = num_cpus::get();
+                    let ncpus = cores::get();
                     let modifier = (ncpus * percent) / 100;
                     if modifier > ncpus { 1 } else { ncpus - modifier }
                 })
@@ -25,18 +46,18 @@ pub fn parse(value: &str) -> Result<usize, ParseErr> {
         },
         (_, b'%') => {
             value[0..value.bytes().count()-1].parse::<usize>()
-                .map(|percent| (num_cpus::get() * percent) / 100)
+                .map(|percent| (cores::get() * percent) / 100)
                 .map_err(|_| ParseErr::JobsNaN(value.to_owned()))
         },
         (b'+', _) => {
             value[1..value.bytes().count()].parse::<usize>()
-                .map(|value| num_cpus::get() + value)
+                .map(|value| cores::get() + value)
                 .map_err(|_| ParseErr::JobsNaN(value.to_owned()))
         },
         (b'-', _) => {
             value[1..value.bytes().count()].parse::<usize>()
             .map(|value| {
-                let ncpus = num_cpus::get();
+                let ncpus = cores::get();
                 if value > ncpus { 1 } else { ncpus - value }
             })
             .map_err(|_| ParseErr::JobsNaN(value.to_owned()))
@@ -49,13 +70,20 @@ pub fn parse(value: &str) -> Result<usize, ParseErr> {
 
 #[test]
 fn job_parsing() {
-    let ncores = num_cpus::get();
+    let ncores = cores::get();
     assert_eq!((ncores * 50) / 100,  parse("50%" ).unwrap());
     assert_eq!((ncores * 100) / 100, parse("100%").unwrap());
     assert_eq!((ncores * 150) / 100, parse("150%").unwrap());
     assert_eq!(4,                    parse("4"   ).unwrap());
     assert_eq!((ncores * 150) / 100, parse("+50%").unwrap());
-    assert_eq!((ncores * 50) / 100,  parse("-50%").unwrap());
-    assert_eq!(ncores - 2,           parse("-2"  ).unwrap());
+    assert_eq!(ncores - (ncores * 50) / 100, parse("-50%").unwrap());
+    assert_eq!(if ncores < 2 { 1 } else { ncores - 2 }, parse("-2").unwrap());
     assert_eq!(ncores + 2,           parse("+2"  ).unwrap());
 }
+
+#[test]
+fn inner_jobs() {
+    assert_eq!(inner(16, None, 4), 4);
+    assert_eq!(inner(16, None, 32), 1);
+    assert_eq!(inner(16, Some(3), 4), 3);
+}
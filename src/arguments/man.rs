@@ -17,7 +17,9 @@ DESCRIPTION
        that all following arguments will be used as INPUTS for the command.
 
     2. If no COMMAND is provided, then the INPUTS will be interpreted as
-       COMMANDS.
+       COMMANDS. Each command may begin with NAME=value environment variables,
+       and a command which begins with #!SHELL, such as #!bash, is executed
+       within that shell.
 
     3. If no INPUTS are provided, then standard input will be read for INPUTS.
 
@@ -68,6 +70,9 @@ INPUT TOKENS
     -  {/^abc...}: Displays the base name with a custom suffix removed.
     -         {#}: Displays the current job ID as a number counting from 1.
     -        {##}: Displays the total number of jobs to be processed.
+    -     {runid}: Displays the ID of this run, which is shared by all of its jobs.
+    -   {scratch}: Displays the path of the scratch directory of the job's slot.
+    -      {host}: Displays the --sshlogin host of the job, or : for the local machine.
     -         {%}: Displays the thread's ID number.
     -         {N}: Where N is a number, display the associated job number.
     -        {N.}: Removes the extension from the Nth job.
@@ -76,34 +81,361 @@ INPUT TOKENS
     -       {N//}: Displays the directory name of the Nth job.
     -       {N/.}: Displays the base name of the Nth job with the extension removed.
     - {N/^abc...}: Displays the base name of the Nth job with a custom suffix removed.
+    - {now:FORMAT}: Displays the time at which the job started, formatted as
+                   with strftime, such as {now:%Y%m%d}.
+    -  {env:NAME}: Displays the value of the environment variable NAME.
+    -       {src}: Displays the source of the input: the path of its input file,
+                   ::: for inputs given as arguments, or - for standard input.
+    -   {srcline}: Displays the line of the input within its source, or its
+                   position among the arguments. Both are empty for inputs
+                   generated by --seq or by permutating multiple lists.
+    -     {start}: With --chunk-file, displays the offset of the first byte of
+                   the input file that the job covers.
+    -       {end}: With --chunk-file, displays the offset just past the last byte
+                   of the input file that the job covers.
+    -   {=expr=}: Evaluates an expression, as described below.
+
+    Expressions consist of a source value, followed by functions which transform
+    it, separated by spaces or pipes, as in {=1 basename | trim_ext | upper=}.
+    The source may be empty or _ for the input, N for the Nth column of the
+    input, # for the job ID, % for the slot ID, or integer arithmetic of these
+    using +, -, *, /, %, and parentheses, where $N refers to the Nth column, as
+    in {=#*2=}. The available functions are basename, dirname, trim_ext, upper,
+    lower, trim, len, pad(WIDTH), and replace(FROM,TO).
+
+    Before any job is launched, a warning is printed for patterns such as {file}
+    which are not placeholders, for {N} tokens in a command whose inputs will be
+    appended because no other placeholder varies with each input, and for columns
+    which the first input does not have.
 
 
 OPTIONS
     Options may also be supplied to the program to change how the program
     operates:
 
-    --delay:
-        Delays starting the next job for N amount of seconds, where the seconds can be fractional.
+    --:
+        Ends the options, so that the following argument is the command, even if it
+        begins with -. As with --cmd-file, the command is passed to the shell intact.
+
+    -a, --arg-file FILE:
+        Reads inputs from FILE instead of the standard input. May be given
+        multiple times.
+
+    --allow-overlap:
+        With --every, starts each cycle when it is due, even if the previous cycle is
+        still running.
+
+    --arg-file-sep SEPARATOR:
+        Uses SEPARATOR in place of ::::, and SEPARATOR+ in place of ::::+, so that
+        :::: may be given to the command as an argument.
+
+    --arg-sep SEPARATOR:
+        Uses SEPARATOR in place of :::, and SEPARATOR+ in place of :::+, so that :::
+        may be given to the command as an argument.
+
+    --bar:
+        Draws the progress of the run on standard error as a bar across the width of
+        the terminal, with the percentage of jobs which have completed, the time that
+        has elapsed, and the estimated time remaining. If standard error is not a
+        terminal, the progress is displayed as with --eta instead.
+
+    --bf FILE, --basefile FILE:
+        Copies FILE to every host of --sshlogin with rsync once, before any job is
+        executed, such as a program or a data set which every job needs. Relative
+        paths are kept under the home directory on each host. With --cleanup, FILE is
+        removed from each host once every job has completed. May be given more than once.
+
+    --binary:
+        Passes the output of jobs through exactly as it was written, such as tar streams
+        or images, in the order of the inputs, rather than splitting it into lines. This
+        overrides --line-buffer.
+
+    -C REGEX, --colsep REGEX:
+        Splits each input into columns at each match of the regular expression REGEX,
+        such as ',' or '\t', rather than at whitespace. Within the command, {N} then
+        denotes the Nth column of each input, rather than the Nth input, as do N and
+        $N within expressions, and the columns of every other option.
+
+    --chunk-file SIZE:
+        Divides each input file into chunks of roughly SIZE bytes, such as 100M, with
+        a job for each chunk. Each chunk is extended to the end of the line that it
+        ends within, so that no line is divided between jobs. The byte range of each
+        chunk is available as {start} and {end}, and {} remains the path of the file.
+        Inputs must be given with :::, ::::, or -a, as a single list.
+
+    --class PATTERN=N:
+        Runs at most N jobs at a time whose command matches PATTERN, regardless of the
+        number of job slots, where * matches any sequence of characters and ? matches
+        any single character. May be given multiple times, in which case each job
+        belongs to the first class whose pattern matches its command. Each class is a
+        pool of N units, of which a job of the class needs one, as with --pool.
+
+    --cleanup:
+        Removes the files of --transferfile and --return from the host of a remote job
+        once the files have been returned, and the files of --basefile from every host
+        once every job has completed.
+
+    --cmd-file FILE, --block-cmd FILE:
+        Reads the command from FILE, which may span multiple lines and contain
+        here-docs. The command is passed to the shell intact, with placeholders
+        replaced, and without requiring its quotes to be terminated.
+
+    --collect FILE:
+        Each job writes its result to {#}.part within the current directory, and the
+        parts are appended to FILE in the order of the inputs, as soon as every job
+        before them has completed. Each part is removed once it has been collected.
+        A job which did not write its part is reported.
+
+    --cost-per-second RATE:
+        Accumulates the cost of the run as the runtime of each job, in seconds,
+        multiplied by RATE. The cost is reported once all jobs have completed, and is
+        included in --metrics.
+
+    --ctag:
+        The same as --tag, but each tag is colored by job, and is followed by a single
+        space, rather than a tab, unless --tag-sep was given.
+
+    --delay DURATION:
+        Starts each job at least DURATION after the job before it was started, such as
+        0.5, 500ms, or 2s, regardless of when the jobs before it completed. Useful for
+        jobs which would otherwise all access a shared resource at the same moment.
+
+    --discard-output:
+        Discards the standard output and error of every job, which are opened on
+        /dev/null rather than collected, for runs where only the exit status of each
+        job matters. This avoids the cost of collecting the output of each job,
+        which is noticeable when many short jobs are executed.
 
     --dry-run:
         Prints the jobs that will be run to standard output, without running them.
 
-    --eta:
-        Prints the estimated time to complete based on average runtime of running processes.
+    --env NAME:
+        Gives the variable NAME, or the bash function NAME which was exported with
+        `export -f`, to each job of --sshlogin, whose environment is otherwise that of
+        its login. Commands which are given a function are executed by bash on the
+        host. Local jobs already inherit every variable. May be given more than once.
+        With `--env _`, every variable is given, other than those which were recorded
+        with --record-env.
+
+    --epipe-status N:
+        Once the standard output has been closed by its reader, such as with
+        `parallel ... | head`, no further jobs are executed, the jobs which are running
+        are sent SIGTERM, and the program exits with a status of N. The default is 0.
 
-    -j, --jobs:
+    --eta:
+        Displays the progress of the run on standard error, refreshed every second: the
+        number of jobs which have completed, failed, are running, and remain, along with
+        the average runtime of a job, and the estimated time until the run completes.
+
+    --every INTERVAL:
+        Runs the jobs again every INTERVAL, such as 5m, until the program is killed.
+        Each cycle executes the program again with the same options and inputs, and
+        a summary of the cycle is printed to standard error once it completes. A cycle
+        which is due while the previous cycle is still running is skipped, unless
+        --allow-overlap was given. Inputs may not be read from the standard input.
+
+    --execute-plan FILE:
+        Executes the jobs that were planned in FILE with --plan, in the order that they
+        were planned, exactly as they were expanded. A command or inputs may not be
+        given. Jobs of --pipe cannot be executed from a plan.
+
+    --expired MODE:
+        Determines how jobs which are past their --not-after deadline when they are
+        about to be executed will be handled:
+            skip: the job is not executed, and the number of skipped jobs is reported.
+            fail: the job is not executed, and is reported as an error.
+        Defaults to skip.
+
+    --export:
+        Instead of executing jobs, prints a job array script for a cluster scheduler,
+        where each element of the array executes the job of the same number ({#}).
+        Supported schedulers are slurm, pbs, and lsf.
+
+    --fail-if-empty:
+        Exits with an error when there are no inputs, even along with --no-run-if-empty,
+        or in xargs mode, where the command would otherwise be executed once.
+
+    --filter-hosts:
+        Passes over the --sshlogin hosts which can't be logged into, with a warning,
+        rather than stopping before any job is executed. Each host is logged into
+        once before any job is dispatched, with ssh -o BatchMode=yes, so that a host
+        which refuses the key, has an unknown host key, or doesn't answer within ten
+        seconds, is reported with the reason.
+
+    --gnu-compat:
+        Follows the conventions of GNU Parallel for the exit status, and reports options
+        of GNU Parallel which are not implemented, along with their nearest equivalent.
+        The short options -p and -s are rejected, as they differ in GNU Parallel. Only
+        the exit status and the handling of options are affected: output is still
+        printed in the order of the inputs, and {N} still denotes the Nth input, rather
+        than the Nth input source as in GNU Parallel.
+
+    --gpus N:
+        Assigns N GPUs to each job, which are given to the job as CUDA_VISIBLE_DEVICES.
+        Jobs wait until enough GPUs are no longer assigned to other jobs. The GPUs are
+        those of CUDA_VISIBLE_DEVICES, if it is already set, or are otherwise detected
+        with nvidia-smi.
+
+    --group:
+        Prints the output of each job only once the job has completed, in the order of
+        the inputs, so that the output of jobs is never mixed together. This is the
+        default, and overrides an earlier --ungroup or --line-buffer.
+
+    --hardware-cores:
+        Counts every CPU core of the machine for --jobs, --load, and --num-cpu-cores,
+        rather than only the cores which the program may use, which may be fewer under
+        taskset or within a container, by the CPU quota of its cgroup.
+
+    --header ::
+        Takes the first input as a header, which names the columns of the inputs that
+        follow it, rather than as an input. With --colsep, {name} then denotes the
+        column which the header named name, as does {N} for the Nth column. Names
+        which are also placeholders, such as src, refer to those placeholders.
+
+    --host-env LOGIN=NAME=VALUE:
+        Gives the variable NAME, with VALUE, to each job which is executed on the
+        --sshlogin host of LOGIN, such as user@server. May be given more than once.
+
+    --host-setup LOGIN=COMMANDS:
+        Executes COMMANDS, such as `module load gcc`, before the command of each job
+        which is executed on the --sshlogin host of LOGIN, within the same shell. The
+        job fails if the COMMANDS do. May be given more than once.
+
+    --hostfile-watch DURATION:
+        Re-reads the --sshloginfile files every DURATION, such as 30s, and whenever
+        SIGHUP is received. A host which was removed from the files is drained: its
+        running jobs finish, but it is given no further jobs. A host which was added
+        is reported, as the hosts of a run are fixed once its jobs have started. The
+        local machine is never drained, nor is every host at once.
+
+    -I STRING:
+        Replaces occurrences of STRING within the command with the input, as
+        an alternative to {}.
+
+    --inner-jobs auto|N:
+        When executed by a job of another run, divides the job slots among the slots
+        of the runs around it, rather than running as many jobs as there are cores in
+        each of their slots. The product of the slots of the runs around each job is
+        exported to it as PARALLEL_OUTER_SLOTS. With auto, the number of --jobs is
+        divided by it, and otherwise, N jobs are run. Has no effect when the run was not
+        executed by a job. Regardless, a run executed by a job has a tempdir of its own,
+        within the tempdir, unless another was given with --tmpdir.
+
+    --ionice CLASS[:LEVEL]:
+        Executes each job within the I/O scheduling CLASS, which is one of realtime,
+        best-effort, or idle, with an optional LEVEL from 0 to 7 for the first two,
+        as with `ionice -c CLASS -n LEVEL`, on Linux. A job fails if its I/O
+        priority could not be set, such as when realtime is given without privileges.
+
+    -j, -P, --jobs, --max-procs:
         Defines the number of tasks to process in parallel.
         Values may be written as a number (12) or as a percent (150%).
-        The default value is the number of CPU cores in the system.
+        The default value is the number of CPU cores which the program may use, which
+        is limited by its CPU affinity and the CPU quota of its cgroup.
 
     --joblog:
-        Logs job statistics to a designated file as they are completed.
+        Logs job statistics to a designated file as they are completed, in the order of
+        their inputs, with the tab-separated columns Sequence, Host, StartTime, Runtime,
+        ExitVal, Signal, Source, and Command. The Host is `:` for the local machine, and
+        the Source records the source and line of each job's input, as with
+        {src}:{srcline}. With --label, a Label column precedes the Command. The first
+        line records the run ID, as in `# RunID: 3f5a07e9ff859c54`.
 
     --joblog-8601:
         Writes the start time in the ISO 8601 format: `YYYY-MM-DD hh:mm:ss`
 
-    --memfree:
-        Defines the minimum amount of memory available before starting the next job.
+    -k, --keep-order:
+        Accepted for compatibility with GNU Parallel. The output of each job is always
+        kept within the tempdir until the jobs before it have been printed, so output
+        is printed in the order of the inputs regardless.
+
+    --keep-going-on-input-errors:
+        Rather than aborting when an input file cannot be read, a warning is printed
+        and the file is skipped, along with lines that are not valid UTF-8. Skipped
+        inputs are listed again once all jobs have completed.
+
+    --key:
+        Defines a template that generates a key for each job, such as '{1}' or '{//}'.
+        Within this template, {N} denotes the Nth whitespace-separated column of
+        the input. Used in conjunction with --max-per-key.
+
+    --kill-stragglers:
+        With --max-runtime, jobs which are still running once the maximum runtime has
+        elapsed are killed, rather than being allowed to complete.
+
+    --label TEMPLATE:
+        Defines a template that generates a label for each job from its input, such as
+        '{2}', which is recorded in the Label column of the --joblog, so that a single
+        file of inputs may hold several stages of a workflow.
+
+    --line-buffer:
+        Prints the output of jobs as it is written, one complete line at a time, so that
+        lines from jobs which are running at the same time are interleaved, but never
+        mixed together. Output is no longer printed in the order of the inputs, and the
+        standard error of a job follows its standard output.
+
+    --load MAX:
+        Waits to start the next job until the load average of the last minute is below
+        MAX, which is either a number, such as 8, or a percentage of the number of
+        cores, such as 150%. Unlike --jobs, this accounts for the other programs which
+        are running on the system.
+
+    --local-only REGEX:
+        With --sshlogin, the jobs whose input matches REGEX, such as ^/scratch/, are
+        executed by the job slots of the local machine, while any of them remain, so
+        that their files needn't be transferred. Other jobs run on any host.
+
+    --local-slots N:
+        With --sshlogin, reserves N job slots for the local machine, in addition to
+        the slots of the hosts, as would be given with N/:.
+
+    --log-commands:
+        With --joblog, the Command column records each command exactly as it was
+        executed, including the shell and any launcher, such as that of --systemd,
+        with each word quoted as it would be for a shell. The commands are recorded
+        without being printed, unlike with --verbose.
+
+    --max-cost COST:
+        With --cost-per-second, stops executing new jobs once the cost of the run has
+        exceeded COST. Jobs which are already running are allowed to finish.
+
+    --max-failures N:
+        Stops executing new jobs once more than N jobs have failed, regardless of how
+        many jobs there are. A job fails if it exits with a non-zero status, is killed,
+        or could not be executed. Jobs which are already running are allowed to finish.
+
+    --max-per-key:
+        Limits how many jobs sharing the same key may run at the same time. Inputs
+        whose key is at the limit are set aside until a job with that key completes,
+        while other inputs continue to run. Without --key, the key is the input.
+
+    --max-runtime DURATION:
+        Stops executing new jobs once the run has taken longer than DURATION, given
+        in seconds or with a unit of s, m, h, or d, as in 30m. Jobs which are already
+        running are allowed to finish, unless --kill-stragglers is given.
+
+    --max-spool:
+        Limits the amount of output that is buffered within the tempdir, such as 10G.
+        Once the limit is reached, the output of running jobs is no longer read until
+        buffered output has been printed, so those jobs will block on their output
+        rather than filling the disk. The job whose output is being printed is never
+        paused, so its output may exceed the limit.
+
+    --memfree SIZE:
+        Waits to start the next job until at least SIZE of memory is available, such
+        as 2G. On Linux, the memory available is read from /proc/meminfo, and is
+        checked every tenth of a second while waiting.
+
+    --merge-logs FILE...:
+        Merges the job logs given after it, such as those of the shards of a run, or of
+        a run and the runs which resumed it, into a single job log which is printed to
+        the standard output, ordered by sequence number, and exits. Where a job was
+        logged more than once, its last successful attempt is kept, or otherwise its
+        last attempt, in the order that the job logs were given.
+
+    --metrics:
+        Once all jobs have finished, reports the peak occupancy of the internal
+        queues and buffers to the standard error, to aid in choosing --tune values.
 
     -n, --max-args:
         Groups up to a certain number of arguments together in the same
@@ -112,20 +444,233 @@ OPTIONS
         first argument, arguments three and four will become the second
         argument, and argument five will become the third argument.
 
+    --needs TEMPLATE:
+        Declares the units of the pools of --pool that each job needs, as generated
+        from TEMPLATE, such as {=2=} for the second column of the input. The template
+        expands to a comma-separated list of pool names, each of which may be preceded
+        by a number of units, as in 2=gpu,license. A job waits until all of its units
+        are available, and returns them once it completes.
+
+    --nice N:
+        Executes each job with a niceness of N, from -20 to 19, as with `nice -n N`.
+        A job fails if its niceness could not be set, such as when a negative niceness
+        is given without privileges.
+
+    --no-append:
+        Runs the command as given for each input, rather than appending the input to
+        commands which contain no placeholder. When a command is run within a shell,
+        each word of an appended input is quoted, so that it is not interpreted.
+
+    --non-blocking-stdin:
+        Exits with an error, rather than waiting for inputs to be typed, when no inputs
+        were given and the standard input is a terminal. Otherwise, a warning is printed
+        before the terminal is read.
+
+    --not-after N:
+        Reads the deadline of each job from the Nth whitespace-separated column of its
+        input, as seconds since the Unix epoch or as a UTC timestamp of the form
+        2024-01-31T12:00:00Z, where - denotes that there is no deadline. Jobs which
+        have not been executed by their deadline are handled according to --expired.
+
+    --not-before N:
+        Reads the earliest start time of each job from the Nth whitespace-separated
+        column of its input, in the same form as --not-after. Jobs whose start time
+        has not arrived are set aside until it has, while the following inputs are
+        executed.
+
+    --notify:
+        Sends a desktop notification once the run has completed, through notify-send,
+        or osascript on macOS, summarizing how many jobs succeeded, failed, and were
+        skipped. If no desktop is available, the --notify-command is executed instead.
+
+    --notify-command COMMAND:
+        Executes the command within the shell once the run has completed, or only
+        when a desktop notification could not be sent if --notify was also given.
+        The summary is given by the PARALLEL_TOTAL, PARALLEL_SUCCEEDED,
+        PARALLEL_FAILED, PARALLEL_SKIPPED, PARALLEL_ELAPSED, PARALLEL_STATUS, and
+        PARALLEL_SUMMARY environment variables.
+        For example, parallel --notify-command 'mail -s "$PARALLEL_SUMMARY" me </dev/null' ...
+
     --num-cpu-cores:
-        A convenience command that will print the number of CPU cores in the system.
+        A convenience command that will print the number of CPU cores which the program
+        may use, or those of the system, with --hardware-cores.
+
+    --only-label LABEL:
+        With --label, only executes the jobs whose label is LABEL, passing over the
+        others without changing their sequence numbers. May be given more than once.
+        Along with --resume, a single stage may be resumed, or executed again.
+        For example, parallel --colsep , --label {1} --only-label fetch ... :::: jobs.csv
 
     -p, --pipe:
         Instead of supplying arguments as arguments to child processes, apply
         them to the standard input of each child process.
 
+    --permute-order:
+        Determines the order in which the permutations of multiple input lists are
+        executed:
+            row-major: the last list varies the fastest, as with nested loops (default).
+            column-major: the first list varies the fastest.
+            interleaved: permutations are ordered by the sum of their positions within
+                each list, so that the early values of every list are covered first.
+
+    --plain:
+        Passes the command through byte-for-byte, without interpreting any
+        placeholders, and appends the input as the last argument. Useful for
+        commands which contain braces of their own, such as jq filters.
+
+    --plan FILE:
+        Instead of executing jobs, writes each job to FILE as JSON, after it has been
+        fully expanded: its number (seq), command, argv, and environment variables,
+        along with the host assignment policy, which is always local, and the number
+        of slots. The plan may be reviewed, and later executed with --execute-plan.
+        Commands containing {%}, {scratch}, or {host} cannot be planned.
+
+    --pool NAME=N:
+        Defines a pool named NAME of N units, such as GPUs, license seats, or database
+        connections, which jobs claim according to --needs. May be given multiple times.
+
+    --progress:
+        The same as --eta.
+
+    --progress-fd N:
+        Writes progress events to the open file descriptor N as JSON lines, so that
+        wrappers may display their own progress without parsing standard error. Each
+        event has an event name and a time, in seconds since the Unix epoch:
+            run_started: the total number of jobs, before any job is executed.
+            job_started: the job, slot, and input of a job which was executed.
+            job_finished: the job, slot, exit, signal, and runtime of a job which exited.
+            job_skipped: the job and slot of an input which was not executed.
+        For example, parallel --progress-fd 3 gzip ::: *.log 3>progress.jsonl
+
     -q, --quote:
         Escapes the command argument supplied so that spaces, quotes, and slashes
         are retained.
 
+    -r, --no-run-if-empty:
+        Exits successfully, without executing anything, when there are no inputs.
+
+    --ramp-up:
+        Rather than starting all job slots at once, the number of jobs running at the
+        same time is increased linearly from one to the value of --jobs over the given
+        duration, such as 30s or 5m. Unlike --delay, which spaces out every job, this
+        only affects the start of the run.
+
+    --record FILE:
+        Records the options, the command, and the inputs of each job, after they have
+        been expanded, into FILE as JSON before any job is executed, so that the run
+        may be reproduced later with --replay.
+
+    --record-env:
+        Records the names of the variables of the current environment in
+        ~/.parallel/ignored_vars, and exits. When executed within a fresh login, `--env _`
+        then gives only the variables which were defined since.
+
+    --reduce COMMAND:
+        Rather than printing the standard output of each job, keeps it as a file within
+        the tempdir, and once every job has completed, executes COMMAND within a shell,
+        where {outputs} is replaced with the paths of the files. If COMMAND does not
+        contain {outputs}, the paths are appended to it. The standard error of jobs is
+        still printed. If COMMAND fails, the run exits with its exit status.
+
+    --reduce-order:
+        Determines the order of the files given to --reduce: either input, the order
+        of the inputs, or completion, the order in which the jobs completed. Defaults
+        to input.
+
+    --replay FILE:
+        Executes the command and inputs that were recorded in FILE with --record, using
+        the recorded options. Options given alongside --replay apply unless they were
+        also recorded. A command or inputs may not be given.
+
+    --replay-jobs LIST:
+        Replays only the jobs in LIST, a comma-separated list of job numbers and ranges
+        of job numbers from the record, such as 1,4-6. The jobs are numbered in the
+        order that they are replayed.
+
+    --require-input-exists:
+        Checks that the input of each job exists as a file when the job is about to
+        be executed, handling missing inputs as specified:
+            skip: the job is not executed, and the number of skipped jobs is reported.
+            fail: the job is not executed, and is reported as an error.
+            retry: the job is attempted again later, up to 5 times, before failing.
+
+    --resume:
+        With --joblog, jobs which are already logged in the job log are not executed
+        again, so that an interrupted run may be resumed. Jobs are identified by their
+        sequence number, so the inputs must be given in the same order. The jobs which
+        are executed are logged after the jobs of the previous runs.
+
+    --resume-failed:
+        As with --resume, but jobs which failed, or were killed by a signal, according
+        to the job log are executed again.
+
+    --retries N:
+        Attempts a job which failed up to N more times before it is recorded as a
+        failure. Only the output and the job log entry of the last attempt are kept.
+
+    --retry-priority:
+        Determines when jobs that are to be attempted again, such as with --retries or
+        the retry mode of --require-input-exists, will be executed: either first, as
+        soon as they are ready, or last, once all of the remaining inputs have been
+        dispatched. Defaults to first.
+
+    --return FILE:
+        Copies FILE back from the host of a remote job with rsync once the job has
+        completed, where FILE is a template such as {.}.out, relative to the home
+        directory on the host. May be given more than once. Jobs whose templates
+        expand to the same path would overwrite each other, so they are reported
+        with a warning before any job is executed, or with an error under --strict.
+
+    --return-first N:
+        Stops executing new jobs once N jobs have succeeded, and prints the output of
+        only those jobs. The output of jobs which failed, or which succeeded after the
+        Nth success, is discarded, and the remaining inputs are reported as skipped.
+
+    --runid ID:
+        Uses ID as the run ID, rather than generating a random one. The run ID is
+        available as {runid}, is exported to each job as PARALLEL_RUNID, and is
+        written to the job log and to records. Replayed runs keep the recorded ID.
+
     -s, --silent, --quiet:
         Disables printing the standard output of running processes.
 
+    -S, --sshlogin [N/]LOGIN[,...]:
+        Distributes jobs across the hosts given, executing the jobs of N job slots on
+        each host with ssh, or as many as --jobs if N is not given. The local machine
+        is written as `:`. A LOGIN, such as user@server, may also be given with the
+        command which logs into it, as in `ssh -p 2222 server`. The command of each
+        job is executed by sh on its host, with PARALLEL_SEQ, PARALLEL_SLOT,
+        PARALLEL_TOTAL, PARALLEL_HOST, and PARALLEL_HOST_NCPU, and its output is
+        collected as that of a local job. With --nice, --ionice, and --timeout, the
+        command is wrapped within nice, ionice, and timeout on its host, each of which
+        is left out if the host lacks it. The job log records the host of each job,
+        which is also given by {host}. May be given more than once.
+
+    --sandbox:
+        Runs each job within fresh mount and PID namespaces, where the root filesystem
+        is read-only, except for the working directory of the job and a private /tmp,
+        which remain writable. Uses bubblewrap (bwrap) if it is installed, or unshare
+        otherwise.
+
+    --scratch:
+        Creates a private scratch directory for each job slot within the tempdir,
+        which is available to jobs as {scratch} and PARALLEL_SCRATCH. The directory is
+        kept for the jobs which run in that slot, and is removed along with its
+        contents once the slot has no more jobs to run. Implied by {scratch}.
+
+    --seq FIRST LAST:
+        Uses each integer from FIRST to LAST, inclusively, as the inputs, counting down
+        if LAST is less than FIRST. The integers are generated as they are needed, and
+        may be written in scientific notation, such as 1e6. May not be combined with
+        other inputs.
+
+    --shard-id i/n:
+        Executes only the ith of n shards of the jobs, where each shard consists of
+        every nth job, starting from the ith, so that the jobs may be divided across
+        several invocations, such as one on each of n machines, which are given the same
+        inputs. Jobs keep their sequence numbers, as in {#} and the job log, so that
+        the job logs of every shard may be combined, or used with --resume.
+
     --shebang:
         Grants ability to utilize the parallel command as an interpreter via
         calling it within a shebang line.
@@ -133,12 +678,145 @@ OPTIONS
     --shellquote:
         Prints commands that will be executed, with the commands quoted.
 
+    --slf FILE, --sshloginfile FILE:
+        Distributes jobs across the hosts of FILE, one on each line, as they would be
+        given to --sshlogin. Blank lines and lines which begin with # are ignored.
+        May be given more than once, and along with --sshlogin.
+
+    --speculative-max N:
+        Once there are no more inputs, idle job slots start a duplicate of any job which
+        has been running for more than twice the median runtime of the completed jobs.
+        Whichever run of the job completes first has its output printed, and the other run
+        is killed. At most N duplicates are started. Jobs must be safe to run twice. May
+        not be combined with --class, --gpus, --max-spool, --pool, --retries, --tty, or
+        --weight-col.
+
+    --spool-idle:
+        Performs the I/O of Parallel itself, such as buffering the output of jobs
+        within the tempdir, in the idle I/O scheduling class, as with ionice -c3, so
+        that it doesn't compete with the disk traffic of the jobs. The jobs retain
+        their usual I/O priority. Only supported on Linux.
+
+    --strict:
+        Turns situations which may silently lose data into errors, before any job is
+        executed: warnings about the command template, such as an unknown placeholder,
+        an empty set of inputs, even with --no-run-if-empty, inputs which are skipped
+        because they could not be read or are not valid UTF-8, a `:::+` list whose
+        length differs from that of the list it is linked to, and --return paths
+        which more than one job expands to.
+
+    --systemd:
+        Launches each job within a transient systemd scope via `systemd-run --scope`,
+        so that each job is tracked, cleaned up, and logged as its own unit. Scopes
+        are created within the user's own service manager unless running as root.
+
+    --systemd-property:
+        Sets a property, such as MemoryMax=1G, on the scope of each job. May be
+        given multiple times, and implies --systemd.
+
+    --tag:
+        Prefixes each line of the output of a job with its input, followed by a tab.
+        Backslashes, tabs, and line endings within the input are escaped as \\, \t,
+        \n, and \r, so that tagged output may be split at the first tab, such as with
+        cut -f2-. May not be combined with --ungroup, and is disabled by --binary.
+
+    --tag-sep SEPARATOR:
+        Separates the tag of --tag or --ctag from the output with SEPARATOR, rather than
+        a tab. The first character of SEPARATOR is escaped within tags as its code
+        point, such as \u{3a} for a colon, so that a tag never contains the separator.
+
+    --termseq SEQUENCE:
+        The signals which are sent to a job that exceeds its timeout, each followed by
+        the number of milliseconds to wait for the job to exit before the next signal
+        is sent, such as TERM,1000,KILL,25. Signals are given by name or by number.
+        The job is killed if it is still running at the end of the sequence. Defaults
+        to TERM,1000, so that a job is given a second to exit before it is killed. The
+        sequence of GNU Parallel may be given as TERM,200,TERM,100,TERM,50,KILL,25.
+
+    --test-deterministic SEED:
+        Intended for testing. Jobs complete in an order which is shuffled by SEED
+        within each batch of as many jobs as there are slots, and are recorded with
+        start times and runtimes derived from SEED, so that the order of their output
+        is reproducible. May not be combined with --retries.
+
+    --tf FILE, --transferfile FILE:
+        Copies FILE, a template such as {}, to the host of each remote job with rsync
+        before the job is executed. Relative paths are kept, under the home directory
+        on the host, where remote jobs are executed. May be given more than once.
+
+    --then TEMPLATE:
+        Adds a further stage to each job, which is executed as soon as the stage
+        before it has succeeded, without waiting for the stages of other jobs. Within
+        the template, {prev} is replaced by the standard output of the previous stage,
+        without its final line ending, as a single quoted argument, and other
+        placeholders refer to the input of the job. Without {prev}, the output is
+        appended, as inputs are. The output of the last stage is the output of the
+        job, and the standard error of earlier stages is printed as they complete. If
+        a stage fails, the job fails without executing the rest. May be given more
+        than once. May not be combined with --pipe.
+        For example, parallel --then 'gzip {prev}' 'convert {} {.}.png && echo {.}.png' ::: *.svg
+
+    --then-jobs N:
+        Limits how many jobs may be executed at once within the stage of the --then
+        which precedes it, as with --jobs, which limits the first stage.
+
+    --throttle-file FILE:
+        Reads the throttles from FILE, with a NAME=VALUE per line, where the names are
+        load, memfree, delay, and nice, whose values are given as with their options,
+        and lines which begin with # are ignored. The file is read again each time
+        that SIGHUP is received, applying to the jobs which have not started yet, so
+        that a long run can be tuned without restarting it. A throttle which the file
+        doesn't name keeps the value of its option, and if the file can't be read
+        again, the throttles are left as they were. Remote jobs keep their niceness.
+
+    --timeout DURATION:
+        If a command runs for longer than DURATION, such as 90 or 5m, where a number
+        without a unit is in seconds, it will be sent SIGTERM, and then SIGKILL if it
+        is still running a second later, or the signals of --termseq. The job log
+        records the signal which terminated the job.
+
+    --timeout-col:
+        Reads the timeout of each job, in seconds, from the Nth whitespace-separated
+        column of its input. Jobs whose column is missing or invalid will fall back
+        to the value of --timeout.
+
     --tmpdir:
         Defines the directory to use for temporary files.
 
-    --timeout:
-        If a command runs for longer than a specified number of seconds, it will be
-        killed with a SIGKILL.
+    --total-jobs N:
+        Executes jobs while the inputs are still being read from the standard input,
+        rather than once all of them have been read. Until the standard input ends,
+        N stands in for the total number of jobs within {##}, PARALLEL_TOTAL, and
+        --eta, unless more inputs have already been read. Once it ends, the number
+        of inputs that were read replaces N. The inputs are read up front as before
+        along with --header, --record, --dry-run, --plan, --export, --only-label,
+        or --test-deterministic, which need every input before the first job.
+
+    --transfer:
+        Copies the input file of each remote job, {}, to its host with rsync before
+        the job is executed. The same as --transferfile {}.
+
+    --trc FILE:
+        The same as --transfer --return FILE --cleanup.
+
+    --tty:
+        Attaches each job's standard input, output, and error to the terminal, so that
+        commands which require a terminal, such as password prompts, will work. Only
+        one job may hold the terminal at a time, so jobs are executed one at a time,
+        in the foreground, and their output is not buffered. Implies --jobs 1.
+
+    --tune KEY=VALUE,...:
+        Adjusts the sizes of internal queues and buffers for unusual workloads:
+            queue      signals from finished jobs that may await printing (1024)
+            output     bytes copied at a time from each job's output (8192)
+            readahead  kilobytes of the unprocessed file read at a time (8)
+
+    -u, --ungroup:
+        Passes the standard output and error of each job directly to those of parallel,
+        so that output is printed as it is written, without being copied. The output of
+        jobs which are running at the same time may be mixed together, even within a
+        line. --reduce, --return-first, --speculative-max, and --retries cannot be
+        combined with --ungroup or --line-buffer, as they require grouped output.
 
     -v, --verbose:
         Print information about running processes.
@@ -146,6 +824,66 @@ OPTIONS
     --version:
         Print version information.
 
+    --webhook URL:
+        Posts a JSON summary of the run to the URL with curl once the run has completed,
+        with the total, succeeded, failed, and skipped jobs, the elapsed seconds, and
+        the exit status, as in:
+            {"event":"run_completed","total":3,"succeeded":2,"failed":1,...}
+
+    --webhook-failures:
+        Also posts each job which failed to the --webhook as it fails, with its job
+        number, input, exit value, and signal, as in:
+            {"event":"job_failed","job":2,"input":"b.mkv","exit":1,"signal":0}
+
+    --webhook-retries N:
+        Attempts a request to the --webhook which failed up to N more times, waiting a
+        second longer before each attempt. Defaults to 2.
+
+    --webhook-timeout DURATION:
+        Abandons a request to the --webhook which takes longer than the duration, in
+        seconds or with an s, m, h, or d suffix. Defaults to 10s.
+
+    --weight-col:
+        Reads the weight of each job from the Nth whitespace-separated column of its
+        input. A job with a weight of N will occupy N of the job slots defined by
+        --jobs while it is running, which is useful for jobs that are multi-threaded.
+
+    --where:
+        Skips the permutations of the input lists which do not match an expression,
+        where {N} refers to the value from the Nth list. Values may be compared with
+        <, <=, >, >=, ==, and !=, numerically if both are numbers, and as strings
+        otherwise. Comparisons may be combined with and (&&), or (||), not (!), and
+        parentheses, as in '{1} < {2} && {3} != debug'.
+
+    --xargs:
+        Follows the conventions of xargs, so that scripts written for xargs may use
+        Parallel in its place. Inputs are items separated by blanks and newlines,
+        which are appended to the command up to 5000 at a time, or -n at a time.
+        With -I, each line is an input to its own command. Commands are executed
+        directly, rather than with a shell, the default command is echo, and the
+        command is executed once when there are no inputs, unless -r is given.
+        The exit status is 123 if any command failed, 124 if a command exited with
+        255, 125 if a command was killed, 126 if a command could not be executed,
+        and 127 if a command was not found.
+
+ENVIRONMENT
+    Each job is executed with the following environment variables, so that it
+    may identify itself without placeholders on its command line:
+
+    PARALLEL_SEQ     The job ID, as with {#}.
+    PARALLEL_SLOT    The job slot, as with {%}.
+    PARALLEL_TOTAL   The total number of jobs, as with {##}.
+    PARALLEL_RUNID   The run ID, as with {runid}.
+    PARALLEL_SCRATCH The scratch directory of the slot, as with {scratch}, if
+                     --scratch was given.
+    PARALLEL_CYCLE   The number of the cycle, if --every was given.
+    PARALLEL_HOST    The login of the host, as with {host}, for jobs executed on
+                     an --sshlogin host.
+    PARALLEL_HOST_NCPU
+                     The number of processors of the --sshlogin host of the job.
+    CUDA_VISIBLE_DEVICES
+                     The GPUs assigned to the job, if --gpus was given.
+
 EXAMPLES
     # Command followed by inputs
     parallel -vun 'ffmpeg -i "{}" -c:a libopus -b:a 128k "{.}.opus"' ::: $(find -type f -name "*.flac")
@@ -156,6 +894,9 @@ EXAMPLES
     # Inputs are used as commands
     parallel ::: "echo 1" "echo 2" "echo 3" "echo 4"
 
+    # Commands with environment variables, or within a given shell
+    parallel ::: 'LANG=C sort data' '#!bash echo {1..4}'
+
     # Placeholder values automatically inferred
     parallel -j2 wget ::: URL1 URL2 URL3 URL4
 
@@ -167,12 +908,37 @@ EXAMPLES
     1
     2
 
+    # Counting the lines of a large file, 100M at a time
+    parallel --chunk-file 100M 'tail -c +$(({start}+1)) {} | head -c $(({end}-{start})) | wc -l' ::: big.log
+
+    # Downloading a file in segments of 10M, and joining the segments
+    seq 0 9 | parallel --collect file.iso 'curl -s -r {}0000000-{}9999999 -o {#}.part $URL'
+
+    # Sorting each file in parallel, and merging the sorted outputs
+    parallel --reduce 'sort -m {outputs}' sort ::: *.txt
+
+EXIT STATUS
+    0        All jobs were executed.
+    1-249    The number of jobs which could not be executed, up to 249.
+    250      The run was stopped early by --max-runtime, --max-failures, or --max-cost.
+    251      Inputs could not be read from a file or the standard input.
+    252      The tempdir, joblog, or another file could not be created.
+    253      The command contains an invalid placeholder token.
+    254      The arguments supplied to the program were invalid.
+
+    With --reduce, the exit status is that of the reduce command, if it failed.
+    Under --xargs, the exit status instead follows the conventions of xargs.
+    Under --gnu-compat, the exit status is the number of jobs which failed, up to 100,
+    or 101 if more failed, and 255 for any other error, as with GNU Parallel.
+
 HOW IT WORKS
     The Parallel command consists of three phases: parsing, threading, and execution.
 
     1. Parsing Phase
         A. Arguments are read into a write-only in-memory disk buffer which
            stores inputs into an unprocessed file when the disk buffer is full.
+           When multiple lists of inputs are given, only the lists are stored,
+           and their permutations are generated as they are needed.
 
         B. Flags are parsed from the command-line along with the command
            argument.
@@ -197,7 +963,10 @@ HOW IT WORKS
            the current input argument being processed.
 
         B. Commands are then executed in a sub-process, with their standard
-           output and error piped to the main process.
+           output and error piped to the main process. If the tempdir runs
+           out of space while output is being buffered, the thread waits up to
+           five minutes for space to be freed, pausing the dispatch of further
+           jobs, before the job is marked as an error.
 
         C. Messages from processes are sorted and printed in the order that
            inputs were given, as if each command was executed serially.
@@ -1,29 +1,75 @@
 /// Contains all functionality pertaining to parsing, tokenizing, and generating input arguments.
 pub mod errors;
+mod cores;
+mod gnu;
 mod jobs;
 mod man;
 mod redirection;
+mod suggestions;
+pub mod throttles;
+mod tune;
 
+use std::collections::HashMap;
 use std::env;
-use std::fs::{self, create_dir_all};
-use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::fs::{self, create_dir_all, File};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Write};
 use std::num::ParseIntError;
 use std::path::{Path, PathBuf};
 use std::process::exit;
+use std::thread;
 use std::time::Duration;
 
 use arrayvec::ArrayVec;
-use permutate::Permutator;
+use disk_buffer::SpaceWriter;
+use errors as exit_status;
+use execute::{environment, finished_jobs, gpus, ionice, merge_logs, plan, ExitPolicy, Expired, Launcher, MissingInput, Pools, ReduceOrder, Sandbox, Scheduler, ProgressEvents, Remote, Tag, TermSeq};
+use input_iterator::{chunks, stream, Excluded, Filter, Generator, PermutatedInputs, PermuteOrder, Provenance, Resources, RetryPriority, Sequence};
 use tokenizer::Token;
-use num_cpus;
+use record::{self, LoadErr};
+use regex::Regex;
+use supervisor;
+use shell;
 use self::errors::ParseErr;
 
 // Re-export key items from internal modules.
 pub use self::errors::FileErr;
+pub use self::jobs::{outer_slots, OUTER_SLOTS};
+pub use self::throttles::Throttles;
+pub use self::tune::Tune;
 
 #[derive(PartialEq)]
 enum Mode { Arguments, Command, Inputs, InputsAppend, Files, FilesAppend }
 
+/// The arguments which separate the lists of inputs from the command and from each other, which are
+/// `:::` and `::::` unless others were given with `--arg-sep` and `--arg-file-sep`.
+struct Separators {
+    arg:  String,
+    file: String,
+}
+
+impl Default for Separators {
+    fn default() -> Separators { Separators { arg: String::from(":::"), file: String::from("::::") } }
+}
+
+impl Separators {
+    /// The mode which follows the `argument`, if it is a separator. A separator which is followed by
+    /// `+` links the list which follows it to the list before it.
+    fn mode(&self, argument: &str) -> Option<Mode> {
+        let linked = argument.strip_suffix('+').unwrap_or("");
+        if argument == self.arg {
+            Some(Mode::Inputs)
+        } else if argument == self.file {
+            Some(Mode::Files)
+        } else if linked == self.arg {
+            Some(Mode::InputsAppend)
+        } else if linked == self.file {
+            Some(Mode::FilesAppend)
+        } else {
+            None
+        }
+    }
+}
+
 pub const INPUTS_ARE_COMMANDS: u16 = 1;
 pub const PIPE_IS_ENABLED:     u16 = 2;
 pub const SHELL_ENABLED:       u16 = 4;
@@ -36,39 +82,200 @@ pub const ETA:                 u16 = 256;
 pub const JOBLOG:              u16 = 512;
 pub const JOBLOG_8601:         u16 = 1024;
 pub const ION_EXISTS:          u16 = 2048;
+pub const TTY:                 u16 = 4096;
+pub const NO_APPEND:           u16 = 8192;
+pub const UNGROUP:             u16 = 16384;
+pub const LINE_BUFFER:         u16 = 32768;
+
+/// Under `--xargs`, items are grouped into commands of up to this many items, unless `-n` is given.
+const XARGS_MAX_ARGS: usize = 5000;
 
 /// `Args` is a collection of critical options and arguments that were collected at
 /// startup of the application.
 pub struct Args {
-    pub flags:     u16,
-    pub ncores:    usize,
-    pub ninputs:   usize,
-    pub memory:    u64,
-    pub delay:     Duration,
-    pub timeout:   Duration,
-    pub arguments: ArrayVec<[Token; 128]>,
-    pub joblog:    Option<String>,
-    pub tempdir:   Option<PathBuf>,
+    pub flags:          u16,
+    pub ncores:         usize,
+    pub ninputs:        usize,
+    pub resources:      Resources,
+    pub max_spool:      u64,
+    pub metrics:        bool,
+    pub plain:          bool,
+    pub spool_idle:     bool,
+    pub nice:           Option<i32>,
+    pub ionice:         Option<i32>,
+    pub scratch:        bool,
+    pub run_id:         String,
+    pub xargs:          bool,
+    pub exit_policy:    Option<ExitPolicy>,
+    pub tune:           Tune,
+    pub delay:          Duration,
+    pub throttles:      Option<(String, Throttles)>,
+    pub timeout:        Duration,
+    pub termseq:        TermSeq,
+    pub timeout_col:    usize,
+    pub weight_col:     usize,
+    pub not_before:     usize,
+    pub not_after:      usize,
+    pub expired:        Expired,
+    pub max_per_key:    usize,
+    pub speculative:    usize,
+    pub classes:        Vec<(String, usize)>,
+    pub pools:          Vec<(String, usize)>,
+    pub needs:          Option<String>,
+    pub gpus:           usize,
+    pub gpu_devices:    Vec<String>,
+    pub return_first:   usize,
+    pub max_runtime:    Option<Duration>,
+    pub max_failures:   Option<usize>,
+    pub kill_overrun:   bool,
+    pub cost_rate:      Option<f64>,
+    pub max_cost:       Option<f64>,
+    pub reduce:         Option<String>,
+    pub reduce_order:   ReduceOrder,
+    pub key:            Option<String>,
+    pub label:          Option<String>,
+    pub only_labels:    Vec<String>,
+    pub stages:         Vec<(String, usize)>,
+    pub export:         Option<Scheduler>,
+    pub plan:           Option<PathBuf>,
+    pub launcher:       Launcher,
+    pub skipped:        Option<Vec<String>>,
+    pub missing:        Option<MissingInput>,
+    pub ramp_up:        Duration,
+    pub every:          Option<Duration>,
+    pub epipe_status:   i32,
+    pub bar:            bool,
+    pub deterministic:  Option<u64>,
+    pub tag:            Option<Tag>,
+    pub discard:        bool,
+    pub log_argv:       bool,
+    pub remote:         Remote,
+    pub strict:         bool,
+    pub allow_overlap:  bool,
+    pub notify:         bool,
+    pub notify_command: Option<String>,
+    pub webhook:        Option<String>,
+    pub hook_failures:  bool,
+    pub hook_timeout:   Option<Duration>,
+    pub hook_retries:   Option<usize>,
+    pub retries:        usize,
+    pub retry_priority: RetryPriority,
+    pub permute_order:  PermuteOrder,
+    pub generator:      Option<Generator>,
+    pub record:         Option<PathBuf>,
+    pub recorded:       Vec<String>,
+    pub provenance:     Provenance,
+    pub arguments:      ArrayVec<[Token; 128]>,
+    pub joblog:         Option<String>,
+    pub resume:         bool,
+    pub excluded:       Excluded,
+    pub progress:       Option<File>,
+    pub collect:        Option<String>,
+    pub colsep:         Option<Regex>,
+    pub header:         Option<HashMap<String, usize>>,
+}
+
+impl Default for Args {
+    fn default() -> Args { Args::new() }
 }
 
 impl Args {
     pub fn new() -> Args {
         Args {
-            ncores:    num_cpus::get(),
-            flags:     0,
-            arguments: ArrayVec::new(),
-            ninputs:   0,
-            memory:    0,
-            delay:     Duration::from_millis(0),
-            timeout:   Duration::from_millis(0),
-            joblog:    None,
-            tempdir:   None,
+            ncores:         cores::get(),
+            flags:          0,
+            arguments:      ArrayVec::new(),
+            ninputs:        0,
+            resources:      Resources::default(),
+            max_spool:      0,
+            metrics:        false,
+            plain:          false,
+            spool_idle:     false,
+            nice:           None,
+            ionice:         None,
+            scratch:        false,
+            run_id:         String::new(),
+            xargs:          false,
+            exit_policy:    None,
+            tune:           Tune::default(),
+            delay:          Duration::from_millis(0),
+            throttles:      None,
+            timeout:        Duration::from_millis(0),
+            termseq:        TermSeq::default(),
+            timeout_col:    0,
+            weight_col:     0,
+            not_before:     0,
+            not_after:      0,
+            expired:        Expired::Skip,
+            max_per_key:    0,
+            speculative:    0,
+            classes:        Vec::new(),
+            pools:          Vec::new(),
+            needs:          None,
+            gpus:           0,
+            gpu_devices:    Vec::new(),
+            return_first:   0,
+            max_runtime:    None,
+            max_failures:   None,
+            kill_overrun:   false,
+            cost_rate:      None,
+            max_cost:       None,
+            reduce:         None,
+            reduce_order:   ReduceOrder::Input,
+            key:            None,
+            label:          None,
+            only_labels:    Vec::new(),
+            stages:         Vec::new(),
+            export:         None,
+            plan:           None,
+            launcher:       Launcher::default(),
+            skipped:        None,
+            missing:        None,
+            ramp_up:        Duration::from_millis(0),
+            every:          None,
+            epipe_status:   0,
+            bar:            false,
+            deterministic:  None,
+            tag:            None,
+            discard:        false,
+            log_argv:       false,
+            remote:         Remote::default(),
+            strict:         false,
+            allow_overlap:  false,
+            notify:         false,
+            notify_command: None,
+            webhook:        None,
+            hook_failures:  false,
+            hook_timeout:   None,
+            hook_retries:   None,
+            retries:        0,
+            retry_priority: RetryPriority::First,
+            permute_order:  PermuteOrder::RowMajor,
+            generator:      None,
+            record:         None,
+            recorded:       Vec::new(),
+            provenance:     Provenance::new(),
+            joblog:         None,
+            resume:         false,
+            excluded:       Excluded::default(),
+            progress:       None,
+            collect:        None,
+            colsep:         None,
+            header:         None,
         }
     }
 
     /// Performs all the work related to parsing program arguments
     pub fn parse(&mut self, comm: &mut String, arguments: &[String], base_path: &mut PathBuf)
         -> Result<usize, ParseErr>
+    {
+        self.parse_arguments(comm, arguments, base_path, None)
+    }
+
+    /// Parses the program arguments, where `replayed` contains the recorded inputs of a run which
+    /// is being replayed, in which case `arguments` are the options and command that were recorded.
+    fn parse_arguments(&mut self, comm: &mut String, arguments: &[String], base_path: &mut PathBuf,
+        replayed: Option<Vec<String>>) -> Result<usize, ParseErr>
     {
         // Each list will consist of a series of input arguments
         let mut lists: Vec<Vec<String>>     = Vec::new();
@@ -76,19 +283,74 @@ impl Args {
         let mut current_inputs: Vec<String> = Vec::with_capacity(1024);
         // If this value is set, input arguments will be grouped into pairs defined by `max_args` value.
         let mut max_args = 0;
+        // If the `--chunk-file` parameter was passed, each input file is divided into chunks of this size.
+        let mut chunk_size = 0;
         // It is important for the custom `InputIterator` to know how many input arguments are to be processed.
         let mut number_of_arguments = 0;
         // If the `--quote` parameter was passed, this will quote the command argument.
         let mut quote_enabled = false;
+        // If the command was read from `--cmd-file` or follows `--`, it is passed to the shell intact.
+        let mut block = false;
+        // If the `-I` parameter was passed, this string will be replaced with `{}` within the command.
+        let mut replace: Option<String> = None;
+        // If the `-a` parameter was passed, inputs will be read from these files.
+        let mut arg_files: Vec<String> = Vec::new();
+        // If the `-r` parameter was passed, nothing will be executed when there are no inputs.
+        let mut no_run_if_empty = false;
+        let mut fail_if_empty = false;
+        let mut non_blocking_stdin = false;
+        // The lists of inputs are separated by `:::` and `::::`, unless other separators were given.
+        let mut separators = Separators::default();
+        // With `--inner-jobs`, the number of slots when executed by another instance, where `auto` is `None`.
+        let mut inner_jobs = None;
+        // With `--header :`, the first input is the header, which names the columns of the inputs.
+        let mut header = false;
+        let mut header_line = String::new();
+        // With `--total-jobs`, the standard input is read while jobs are executed, and this is the expected total.
+        let mut total_jobs = 0;
+        // With `--throttle-file`, the file whose throttles are applied over those of the command line.
+        let mut throttle_file = None;
+        // With `--env`, the names of the variables which are given to remote jobs.
+        let mut env_names: Vec<String> = Vec::new();
+        // If the `--where` parameter was passed, permutations which do not match this filter are skipped.
+        let mut filter = None;
+        // If the `--seq` parameter was passed, this will contain the first and last integers to generate.
+        let mut sequence = None;
+        // If the `--replay` parameter was passed, this will contain the record and the jobs to replay.
+        let mut replay = None;
+        let mut replay_jobs = None;
+        // If the `--execute-plan` parameter was passed, this will contain the plan to execute.
+        let mut execute_plan = None;
+        // With `--resume`, this is whether only the jobs which succeeded are not executed again.
+        let mut resume = None;
+        let mut planned = None;
+        // With `--binary`, the output of each job is never split into lines.
+        let mut binary = false;
+        // With `--tag` or `--ctag`, each line of output is prefixed with its input and the `--tag-sep`.
+        let mut tag = false;
+        let mut ctag = false;
+        let mut tag_sep = None;
+        // Recorded runs are replayed from their own inputs, rather than from the standard input.
+        let replaying = replayed.is_some();
+        let mut redirected = if replaying { None } else { redirection::input_was_redirected() };
+
+        // The options and command are recorded with `--record`, excluding the record option itself.
+        self.recorded = record_arguments(arguments);
+
+        // Options which are relative to the number of cores, such as `--jobs 50%`, may precede `--hardware-cores`.
+        if self.recorded.iter().any(|argument| argument == "--hardware-cores") {
+            cores::use_hardware();
+            self.ncores = cores::get();
+        }
 
         // If no arguments were passed, we can assume that the standard input will be parsing commands.
         // Otherwise, we will parse all the arguments and take actions based on these inputs.
-        if env::args().len() > 1 {
+        if arguments.len() > 1 {
             // The first argument defines which `mode` to shift into and which argument `index` to start from.
-            let (mut mode, mut index) = match arguments[1].as_str() {
-                ":::"  | ":::+"  => (Mode::Inputs, 2),
-                "::::" | "::::+" => (Mode::Files, 2),
-                _                => (Mode::Arguments, 1)
+            let (mut mode, mut index) = match separators.mode(&arguments[1]) {
+                Some(Mode::Inputs) | Some(Mode::InputsAppend) => (Mode::Inputs, 2),
+                Some(_)                                       => (Mode::Files, 2),
+                None                                          => (Mode::Arguments, 1)
             };
 
             // If the `--shebang` parameter was passed, this will be set to `true`.
@@ -106,44 +368,243 @@ impl Args {
                     // We can guarantee that there will always be at least one character.
                     if char_iter.next().unwrap() == b'-' {
                         // If the second character exists, everything's OK.
-                        let character = char_iter.next().ok_or_else(|| ParseErr::InvalidArgument(index-1))?;
+                        let character = char_iter.next().ok_or_else(|| ParseErr::InvalidArgument(index-1, argument.to_owned()))?;
                         if character == b'j' {
                             let val = parse_jobs(argument, arguments.get(index), &mut index)?;
                             if val != 0 { self.ncores = val; }
                         } else if character == b'n' {
                             max_args = parse_max_args(argument, arguments.get(index), &mut index)?;
+                        } else if character == b'P' {
+                            let val = parse_jobs(argument, arguments.get(index), &mut index)?;
+                            if val != 0 { self.ncores = val; }
+                        } else if character == b'S' {
+                            let val = parse_value(argument, arguments.get(index), &mut index)
+                                .ok_or(ParseErr::SshloginNoValue)?;
+                            self.remote.add(&val).map_err(|login| ParseErr::SshloginInvalid(login.to_owned()))?;
+                        } else if character == b'C' {
+                            let val = parse_value(argument, arguments.get(index), &mut index)
+                                .ok_or(ParseErr::ColsepNoValue)?;
+                            self.colsep = Some(parse_colsep(&val)?);
+                        } else if character == b'I' {
+                            replace = Some(parse_value(argument, arguments.get(index), &mut index)
+                                .ok_or(ParseErr::ReplaceNoValue)?);
+                        } else if character == b'a' {
+                            arg_files.push(parse_value(argument, arguments.get(index), &mut index)
+                                .ok_or(ParseErr::ArgFileNoValue)?);
                         } else if character != b'-' {
                             for character in argument[1..].bytes() {
+                                if self.exit_policy == Some(ExitPolicy::Gnu) {
+                                    if let Some((gnu, ours)) = gnu::conflicting(character as char) {
+                                        return Err(ParseErr::GnuConflict(character as char, gnu, ours));
+                                    }
+                                }
                                 match character {
                                     b'h' => {
                                         println!("{}", man::MAN_PAGE);
                                         exit(0);
                                     },
+                                    // Output is always printed in the order of the inputs.
+                                    b'k' => (),
                                     b'p' => self.flags |= PIPE_IS_ENABLED,
                                     b'q' => quote_enabled = true,
+                                    b'r' => no_run_if_empty = true,
                                     b's' => self.flags |= QUIET_MODE,
+                                    b'u' => self.flags = self.flags & !LINE_BUFFER | UNGROUP,
                                     b'v' => self.flags |= VERBOSE_MODE,
                                     _ => {
-                                        let stderr = io::stderr();
-                                        let _ = writeln!(stderr.lock(), "parallel: unsupported argument: '-{}'", character as char);
+                                        let name = (character as char).to_string();
+                                        return Err(unsupported_option(index-1, argument, &name));
                                     }
                                 }
                             }
                         } else {
                             // NOTE: Long mode versions of arguments
                             match &argument[2..] {
+                                "" => {
+                                    // `--` ends the options, so that the command may begin with `-`.
+                                    block = true;
+                                    if let Some(argument) = arguments.get(index) {
+                                        index += 1;
+                                        match separators.mode(argument) {
+                                            Some(Mode::Inputs) => mode = Mode::Inputs,
+                                            Some(Mode::Files)  => mode = Mode::Files,
+                                            _ => {
+                                                comm.push_str(argument);
+                                                mode = Mode::Command;
+                                            }
+                                        }
+                                    }
+                                    break
+                                },
+                                "allow-overlap" => self.allow_overlap = true,
+                                "arg-file-sep" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ArgFileSepNoValue)?;
+                                    if val.is_empty() { return Err(ParseErr::ArgFileSepNoValue); }
+                                    separators.file = val.to_owned();
+                                    index += 1;
+                                },
+                                "arg-sep" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ArgSepNoValue)?;
+                                    if val.is_empty() { return Err(ParseErr::ArgSepNoValue); }
+                                    separators.arg = val.to_owned();
+                                    index += 1;
+                                },
+                                "arg-file" => {
+                                    arg_files.push(arguments.get(index).ok_or(ParseErr::ArgFileNoValue)?.to_owned());
+                                    index += 1;
+                                },
+                                "basefile" | "bf" => {
+                                    let file = arguments.get(index).ok_or(ParseErr::BasefileNoValue)?;
+                                    self.remote.basefiles.push(file.to_owned());
+                                    index += 1;
+                                },
+                                "bar" => {
+                                    self.flags |= ETA;
+                                    self.bar = true;
+                                },
+                                "binary" => binary = true,
+                                "chunk-file" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ChunkFileNoValue)?;
+                                    chunk_size = match parse_memory(val) {
+                                        Ok(size) if size != 0 => size,
+                                        _ => return Err(ParseErr::ChunkFileInvalid(val.to_owned()))
+                                    };
+                                    index += 1;
+                                },
+                                "cleanup" => self.remote.cleanup = true,
+                                "class" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ClassNoValue)?;
+                                    let class = Pools::parse(val).ok_or_else(|| ParseErr::ClassInvalid(val.to_owned()))?;
+                                    self.classes.push(class);
+                                    index += 1;
+                                },
+                                "collect" => {
+                                    let file = arguments.get(index).ok_or(ParseErr::CollectNoValue)?;
+                                    self.collect = Some(file.to_owned());
+                                    index += 1;
+                                },
+                                "colsep" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ColsepNoValue)?;
+                                    self.colsep = Some(parse_colsep(val)?);
+                                    index += 1;
+                                },
+                                "cmd-file" | "block-cmd" => {
+                                    let path = arguments.get(index).ok_or(ParseErr::CmdFileNoValue)?;
+                                    let command = fs::read_to_string(path)
+                                        .map_err(|why| ParseErr::File(FileErr::Read(PathBuf::from(path), why)))?;
+                                    comm.push_str(command.trim_end_matches('\n'));
+                                    block = true;
+                                    index += 1;
+                                },
+                                "ctag" => ctag = true,
+                                "cost-per-second" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::CostNoValue)?;
+                                    self.cost_rate = Some(parse_cost(val).ok_or_else(|| ParseErr::CostInvalid(val.to_owned()))?);
+                                    index += 1;
+                                },
                                 "delay" => {
                                     let val = arguments.get(index).ok_or(ParseErr::DelayNoValue)?;
-                                    let seconds = val.parse::<f64>().map_err(|_| ParseErr::DelayNaN(index))?;
-                                    self.delay = Duration::from_millis((seconds * 1000f64) as u64);
+                                    self.delay = parse_duration(val).ok_or_else(|| ParseErr::DelayInvalid(val.to_owned()))?;
                                     index += 1;
                                 },
+                                "discard-output" => self.discard = true,
                                 "dry-run" => self.flags |= DRY_RUN,
-                                "eta" => self.flags |= ETA + QUIET_MODE,
+                                "env" => {
+                                    let name = arguments.get(index).ok_or(ParseErr::EnvNoValue)?;
+                                    env_names.push(name.to_owned());
+                                    index += 1;
+                                },
+                                "epipe-status" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::EpipeStatusNoValue)?;
+                                    self.epipe_status = val.parse::<i32>().map_err(|_| ParseErr::EpipeStatusNaN(val.to_owned()))?;
+                                    index += 1;
+                                },
+                                "eta" | "progress" => self.flags |= ETA,
+                                "every" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::EveryNoValue)?;
+                                    self.every = match parse_duration(val) {
+                                        Some(interval) if interval != Duration::from_millis(0) => Some(interval),
+                                        _ => return Err(ParseErr::EveryInvalid(val.to_owned()))
+                                    };
+                                    index += 1;
+                                },
+                                "execute-plan" => {
+                                    let path = arguments.get(index).ok_or(ParseErr::ExecutePlanNoValue)?;
+                                    execute_plan = Some(PathBuf::from(path));
+                                    index += 1;
+                                },
+                                "expired" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ExpiredNoValue)?;
+                                    self.expired = Expired::parse(val).ok_or_else(|| ParseErr::ExpiredInvalid(val.to_owned()))?;
+                                    index += 1;
+                                },
+                                "export" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ExportNoValue)?;
+                                    self.export = Some(Scheduler::parse(val).ok_or(ParseErr::ExportInvalid(val.to_owned()))?);
+                                    index += 1;
+                                },
+                                "fail-if-empty" => fail_if_empty = true,
+                                "filter-hosts" => self.remote.filter = true,
+                                // Output is grouped unless the last output mode given was another.
+                                "group" => self.flags &= !(UNGROUP | LINE_BUFFER),
+                                "gpus" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::GpusNoValue)?;
+                                    self.gpus = match val.parse::<usize>() {
+                                        Ok(gpus) if gpus != 0 => gpus,
+                                        _ => return Err(ParseErr::GpusNaN(val.to_owned()))
+                                    };
+                                    // Ensure that GPUs are available before any job is executed.
+                                    self.gpu_devices = gpus::detect();
+                                    if self.gpu_devices.is_empty() { return Err(ParseErr::GpusUnavailable); }
+                                    index += 1;
+                                },
+                                "gnu-compat" => {
+                                    self.exit_policy = Some(ExitPolicy::Gnu);
+                                    exit_status::use_gnu_exit_status();
+                                },
+                                "hardware-cores" => (),
+                                "header" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::HeaderNoValue)?;
+                                    if val != ":" { return Err(ParseErr::HeaderInvalid(val.to_owned())); }
+                                    header = true;
+                                    index += 1;
+                                },
                                 "help" => {
                                     println!("{}", man::MAN_PAGE);
                                     exit(0);
                                 },
+                                "host-env" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::HostEnvNoValue)?;
+                                    if !self.remote.add_env(val) { return Err(ParseErr::HostEnvInvalid(val.to_owned())); }
+                                    index += 1;
+                                },
+                                "host-setup" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::HostSetupNoValue)?;
+                                    if !self.remote.add_setup(val) { return Err(ParseErr::HostSetupInvalid(val.to_owned())); }
+                                    index += 1;
+                                },
+                                "hostfile-watch" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::HostfileWatchNoValue)?;
+                                    self.remote.watch = parse_duration(val).filter(|interval| !interval.is_zero())
+                                        .map(Some).ok_or_else(|| ParseErr::HostfileWatchInvalid(val.to_owned()))?;
+                                    index += 1;
+                                },
+                                "inner-jobs" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::InnerJobsNoValue)?;
+                                    inner_jobs = Some(match val.as_str() {
+                                        "auto" => None,
+                                        _ => match jobs::parse(val) {
+                                            Ok(slots) if slots != 0 => Some(slots),
+                                            _ => return Err(ParseErr::InnerJobsInvalid(val.to_owned()))
+                                        }
+                                    });
+                                    index += 1;
+                                },
+                                "ionice" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::IoniceNoValue)?;
+                                    self.ionice = Some(ionice::parse(val).ok_or_else(|| ParseErr::IoniceInvalid(val.to_owned()))?);
+                                    index += 1;
+                                },
                                 "joblog" => {
                                     let file = arguments.get(index).ok_or(ParseErr::JoblogNoValue)?;
                                     self.joblog = Some(file.to_owned());
@@ -151,69 +612,439 @@ impl Args {
                                     self.flags |= JOBLOG;
                                 },
                                 "joblog-8601" => self.flags |= JOBLOG_8601,
-                                "jobs" => {
+                                "jobs" | "max-procs" => {
                                     let val = jobs::parse(arguments.get(index).ok_or(ParseErr::JobsNoValue)?)?;
                                     if val != 0 { self.ncores = val; }
                                     index += 1;
                                 },
+                                "kill-stragglers" => self.kill_overrun = true,
+                                "keep-order" => (),
+                                "keep-going-on-input-errors" => if self.skipped.is_none() {
+                                    self.skipped = Some(Vec::new());
+                                },
+                                "key" => {
+                                    let template = arguments.get(index).ok_or(ParseErr::KeyNoValue)?;
+                                    self.key = Some(template.to_owned());
+                                    index += 1;
+                                },
+                                "merge-logs" => {
+                                    // The remaining arguments are the job logs to merge.
+                                    let paths = arguments[index..].iter().map(PathBuf::from).collect::<Vec<PathBuf>>();
+                                    if paths.is_empty() { return Err(ParseErr::MergeLogsNoValue); }
+                                    let stdout = io::stdout();
+                                    merge_logs(&paths, &mut BufWriter::new(stdout.lock())).map_err(ParseErr::File)?;
+                                    exit(0);
+                                },
                                 "num-cpu-cores" => {
-                                    println!("{}", num_cpus::get());
+                                    println!("{}", cores::get());
                                     exit(0);
                                 },
+                                "label" => {
+                                    let template = arguments.get(index).ok_or(ParseErr::LabelNoValue)?;
+                                    self.label = Some(template.to_owned());
+                                    index += 1;
+                                },
+                                "line-buffer" => self.flags = self.flags & !UNGROUP | LINE_BUFFER,
+                                "load" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::LoadNoValue)?;
+                                    self.resources.load = parse_load(val).ok_or_else(|| ParseErr::LoadInvalid(val.to_owned()))?;
+                                    index += 1;
+                                },
+                                "local-only" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::LocalOnlyNoValue)?;
+                                    self.remote.local_only = Some(Regex::new(val)
+                                        .map_err(|why| ParseErr::LocalOnlyInvalid(val.to_owned(), why.to_string()))?);
+                                    index += 1;
+                                },
+                                "local-slots" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::LocalSlotsNoValue)?;
+                                    self.remote.local = val.parse::<usize>().ok().filter(|&slots| slots != 0)
+                                        .ok_or_else(|| ParseErr::LocalSlotsInvalid(val.to_owned()))?;
+                                    index += 1;
+                                },
+                                "log-commands" => self.log_argv = true,
                                 "max-args" => {
                                     let val = arguments.get(index).ok_or(ParseErr::MaxArgsNoValue)?;
-                                    max_args = val.parse::<usize>().map_err(|_| ParseErr::MaxArgsNaN(index))?;
+                                    max_args = val.parse::<usize>().map_err(|_| ParseErr::MaxArgsNaN(val.to_owned()))?;
+                                    index += 1;
+                                },
+                                "metrics" => self.metrics = true,
+                                "max-spool" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::MaxSpoolNoValue)?;
+                                    self.max_spool = match parse_memory(val) {
+                                        Ok(limit) if limit != 0 => limit,
+                                        _ => return Err(ParseErr::MaxSpoolInvalid(val.to_owned()))
+                                    };
                                     index += 1;
                                 },
-                                "mem-free" => {
+                                "max-cost" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::MaxCostNoValue)?;
+                                    self.max_cost = Some(parse_cost(val).ok_or_else(|| ParseErr::MaxCostInvalid(val.to_owned()))?);
+                                    index += 1;
+                                },
+                                "max-failures" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::MaxFailuresNoValue)?;
+                                    let max = val.parse::<usize>().map_err(|_| ParseErr::MaxFailuresNaN(val.to_owned()))?;
+                                    self.max_failures = Some(max);
+                                    index += 1;
+                                },
+                                "max-runtime" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::MaxRuntimeNoValue)?;
+                                    let runtime = parse_duration(val).ok_or_else(|| ParseErr::MaxRuntimeInvalid(val.to_owned()))?;
+                                    self.max_runtime = Some(runtime);
+                                    index += 1;
+                                },
+                                "max-per-key" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::MaxPerKeyNoValue)?;
+                                    self.max_per_key = match val.parse::<usize>() {
+                                        Ok(limit) if limit != 0 => limit,
+                                        _ => return Err(ParseErr::MaxPerKeyNaN(val.to_owned()))
+                                    };
+                                    index += 1;
+                                },
+                                "mem-free" | "memfree" => {
                                     let val = arguments.get(index).ok_or(ParseErr::MemNoValue)?;
-                                    self.memory = parse_memory(val).map_err(|_| ParseErr::MemInvalid(index))?;
+                                    self.resources.memfree = parse_memory(val).map_err(|_| ParseErr::MemInvalid(val.to_owned()))?;
+                                    index += 1;
+                                },
+                                "needs" => {
+                                    let template = arguments.get(index).ok_or(ParseErr::NeedsNoValue)?;
+                                    self.needs = Some(template.to_owned());
+                                    index += 1;
+                                },
+                                "nice" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::NiceNoValue)?;
+                                    let niceness = val.parse::<i32>().ok().filter(|niceness| *niceness >= -20 && *niceness <= 19);
+                                    self.nice = Some(niceness.ok_or_else(|| ParseErr::NiceInvalid(val.to_owned()))?);
+                                    index += 1;
+                                },
+                                "no-append" => self.flags |= NO_APPEND,
+                                "no-run-if-empty" => no_run_if_empty = true,
+                                "non-blocking-stdin" => non_blocking_stdin = true,
+                                "not-after" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::NotAfterNoValue)?;
+                                    self.not_after = match val.parse::<usize>() {
+                                        Ok(column) if column != 0 => column,
+                                        _ => return Err(ParseErr::NotAfterNaN(val.to_owned()))
+                                    };
+                                    index += 1;
+                                },
+                                "not-before" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::NotBeforeNoValue)?;
+                                    self.not_before = match val.parse::<usize>() {
+                                        Ok(column) if column != 0 => column,
+                                        _ => return Err(ParseErr::NotBeforeNaN(val.to_owned()))
+                                    };
+                                    index += 1;
+                                },
+                                "notify" => self.notify = true,
+                                "notify-command" => {
+                                    let command = arguments.get(index).ok_or(ParseErr::NotifyCommandNoValue)?;
+                                    self.notify_command = Some(command.to_owned());
+                                    index += 1;
+                                },
+                                "only-label" => {
+                                    let label = arguments.get(index).ok_or(ParseErr::OnlyLabelNoValue)?;
+                                    self.only_labels.push(label.to_owned());
                                     index += 1;
                                 },
                                 "pipe" => self.flags |= PIPE_IS_ENABLED,
+                                "plain" => self.plain = true,
+                                "pool" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::PoolNoValue)?;
+                                    let pool = Pools::parse(val).ok_or_else(|| ParseErr::PoolInvalid(val.to_owned()))?;
+                                    self.pools.push(pool);
+                                    index += 1;
+                                },
+                                "permute-order" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::PermuteOrderNoValue)?;
+                                    self.permute_order = PermuteOrder::parse(val)
+                                        .ok_or_else(|| ParseErr::PermuteOrderInvalid(val.to_owned()))?;
+                                    index += 1;
+                                },
                                 "quiet" | "silent" => self.flags |= QUIET_MODE,
                                 "quote" => quote_enabled = true,
+                                "ramp-up" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::RampUpNoValue)?;
+                                    self.ramp_up = parse_duration(val).ok_or_else(|| ParseErr::RampUpInvalid(val.to_owned()))?;
+                                    index += 1;
+                                },
+                                "require-input-exists" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::RequireExistsNoValue)?;
+                                    self.missing = Some(MissingInput::parse(val)
+                                        .ok_or_else(|| ParseErr::RequireExistsInvalid(val.to_owned()))?);
+                                    index += 1;
+                                },
+                                "plan" => {
+                                    let path = arguments.get(index).ok_or(ParseErr::PlanNoValue)?;
+                                    self.plan = Some(PathBuf::from(path));
+                                    index += 1;
+                                },
+                                "progress-fd" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ProgressFdNoValue)?;
+                                    let fd = val.parse::<i32>().ok().filter(|&fd| fd >= 0)
+                                        .ok_or_else(|| ParseErr::ProgressFdNaN(val.to_owned()))?;
+                                    self.progress = Some(ProgressEvents::open(fd).map_err(|why| ParseErr::ProgressFd(fd, why))?);
+                                    index += 1;
+                                },
+                                "record-env" => {
+                                    let path = environment::ignored_vars().ok_or(ParseErr::EnvWithoutHome)?;
+                                    environment::record(&path).map_err(|why| ParseErr::File(FileErr::Write(path, why)))?;
+                                    exit(0);
+                                },
+                                "record" => {
+                                    let path = arguments.get(index).ok_or(ParseErr::RecordNoValue)?;
+                                    self.record = Some(PathBuf::from(path));
+                                    index += 1;
+                                },
+                                "replay" => {
+                                    let path = arguments.get(index).ok_or(ParseErr::ReplayNoValue)?;
+                                    replay = Some(PathBuf::from(path));
+                                    index += 1;
+                                },
+                                "replay-jobs" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ReplayJobsNoValue)?;
+                                    replay_jobs = Some(record::parse_jobs(val)
+                                        .ok_or_else(|| ParseErr::ReplayJobsInvalid(val.to_owned()))?);
+                                    index += 1;
+                                },
+                                "return" => {
+                                    let template = arguments.get(index).ok_or(ParseErr::ReturnNoValue)?;
+                                    self.remote.returns.push(template.to_owned());
+                                    index += 1;
+                                },
+                                "return-first" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ReturnFirstNoValue)?;
+                                    self.return_first = match val.parse::<usize>() {
+                                        Ok(wanted) if wanted != 0 => wanted,
+                                        _ => return Err(ParseErr::ReturnFirstNaN(val.to_owned()))
+                                    };
+                                    index += 1;
+                                },
+                                "reduce" => {
+                                    let command = arguments.get(index).ok_or(ParseErr::ReduceNoValue)?;
+                                    self.reduce = Some(command.to_owned());
+                                    index += 1;
+                                },
+                                "reduce-order" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ReduceOrderNoValue)?;
+                                    self.reduce_order = ReduceOrder::parse(val)
+                                        .ok_or_else(|| ParseErr::ReduceOrderInvalid(val.to_owned()))?;
+                                    index += 1;
+                                },
+                                "resume" => if resume.is_none() { resume = Some(false) },
+                                "resume-failed" => resume = Some(true),
+                                "retries" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::RetriesNoValue)?;
+                                    self.retries = val.parse::<usize>().map_err(|_| ParseErr::RetriesNaN(val.to_owned()))?;
+                                    index += 1;
+                                },
+                                "retry-priority" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::RetryPriorityNoValue)?;
+                                    self.retry_priority = RetryPriority::parse(val)
+                                        .ok_or_else(|| ParseErr::RetryPriorityInvalid(val.to_owned()))?;
+                                    index += 1;
+                                },
+                                "sandbox" => self.launcher.sandbox = Some(Sandbox::detect()),
+                                "scratch" => self.scratch = true,
+                                "runid" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::RunIdNoValue)?;
+                                    if val.is_empty() { return Err(ParseErr::RunIdNoValue); }
+                                    self.run_id = val.to_owned();
+                                    index += 1;
+                                },
+                                "seq" => {
+                                    let first = arguments.get(index).ok_or(ParseErr::SeqNoValue)?;
+                                    let last = arguments.get(index+1).ok_or(ParseErr::SeqNoValue)?;
+                                    let first_val = parse_integer(first).ok_or_else(|| ParseErr::SeqInvalid(first.to_owned()))?;
+                                    let last_val = parse_integer(last).ok_or_else(|| ParseErr::SeqInvalid(last.to_owned()))?;
+                                    sequence = Some((first_val, last_val));
+                                    index += 2;
+                                },
+                                "shard-id" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ShardIdNoValue)?;
+                                    self.excluded.shard = Some(Excluded::parse_shard(val)
+                                        .ok_or_else(|| ParseErr::ShardIdInvalid(val.to_owned()))?);
+                                    index += 1;
+                                },
                                 "shellquote" => self.flags |= DRY_RUN + SHELL_QUOTE,
+                                "speculative-max" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::SpeculativeMaxNoValue)?;
+                                    self.speculative = val.parse::<usize>()
+                                        .map_err(|_| ParseErr::SpeculativeMaxNaN(val.to_owned()))?;
+                                    index += 1;
+                                },
+                                "spool-idle" => self.spool_idle = true,
+                                "sshlogin" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::SshloginNoValue)?;
+                                    self.remote.add(val).map_err(|login| ParseErr::SshloginInvalid(login.to_owned()))?;
+                                    index += 1;
+                                },
+                                "sshloginfile" | "slf" => {
+                                    let path = arguments.get(index).ok_or(ParseErr::SshloginfileNoValue)?;
+                                    let logins = fs::read_to_string(path)
+                                        .map_err(|why| ParseErr::File(FileErr::Read(PathBuf::from(path), why)))?;
+                                    self.remote.add_file(path, &logins).map_err(|login| ParseErr::SshloginInvalid(login.to_owned()))?;
+                                    index += 1;
+                                },
+                                "strict" => self.strict = true,
+                                "systemd" => if self.launcher.systemd.is_none() {
+                                    self.launcher.systemd = Some(Vec::new());
+                                },
+                                "systemd-property" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::SystemdPropertyNoValue)?;
+                                    if !val.contains('=') { return Err(ParseErr::SystemdPropertyInvalid(val.to_owned())); }
+                                    self.launcher.systemd.get_or_insert_with(Vec::new).push(val.to_owned());
+                                    index += 1;
+                                },
+                                "tag" => tag = true,
+                                "tag-sep" => {
+                                    tag_sep = Some(arguments.get(index).ok_or(ParseErr::TagSepNoValue)?.to_owned());
+                                    index += 1;
+                                },
+                                "test-deterministic" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::TestDeterministicNoValue)?;
+                                    self.deterministic = Some(val.parse::<u64>()
+                                        .map_err(|_| ParseErr::TestDeterministicNaN(val.to_owned()))?);
+                                    index += 1;
+                                },
+                                "termseq" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::TermSeqNoValue)?;
+                                    self.termseq = TermSeq::parse(val).ok_or_else(|| ParseErr::TermSeqInvalid(val.to_owned()))?;
+                                    index += 1;
+                                },
+                                "then" => {
+                                    let template = arguments.get(index).ok_or(ParseErr::ThenNoValue)?;
+                                    self.stages.push((template.to_owned(), 0));
+                                    index += 1;
+                                },
+                                "then-jobs" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::ThenJobsNoValue)?;
+                                    let stage = self.stages.last_mut().ok_or(ParseErr::ThenJobsWithoutThen)?;
+                                    stage.1 = match jobs::parse(val) {
+                                        Ok(slots) if slots != 0 => slots,
+                                        _ => return Err(ParseErr::ThenJobsInvalid(val.to_owned()))
+                                    };
+                                    index += 1;
+                                },
+                                "throttle-file" => {
+                                    let path = arguments.get(index).ok_or(ParseErr::ThrottleFileNoValue)?;
+                                    throttle_file = Some(path.to_owned());
+                                    index += 1;
+                                },
                                 "timeout" => {
                                     let val = arguments.get(index).ok_or(ParseErr::TimeoutNoValue)?;
-                                    let seconds = val.parse::<f64>().map_err(|_| ParseErr::TimeoutNaN(index))?;
-                                    self.timeout = Duration::from_millis((seconds * 1000f64) as u64);
+                                    self.timeout = parse_duration(val).ok_or_else(|| ParseErr::TimeoutNaN(val.to_owned()))?;
+                                    index += 1;
+                                },
+                                "timeout-col" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::TimeoutColNoValue)?;
+                                    self.timeout_col = match val.parse::<usize>() {
+                                        Ok(column) if column != 0 => column,
+                                        _ => return Err(ParseErr::TimeoutColNaN(val.to_owned()))
+                                    };
+                                    index += 1;
+                                },
+                                "total-jobs" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::TotalJobsNoValue)?;
+                                    total_jobs = match val.parse::<usize>() {
+                                        Ok(total) if total != 0 => total,
+                                        _ => return Err(ParseErr::TotalJobsNaN(val.to_owned()))
+                                    };
                                     index += 1;
                                 },
+                                "ungroup" => self.flags = self.flags & !LINE_BUFFER | UNGROUP,
                                 "verbose" => self.flags |= VERBOSE_MODE,
+                                "webhook" => {
+                                    let url = arguments.get(index).ok_or(ParseErr::WebhookNoValue)?;
+                                    self.webhook = Some(url.to_owned());
+                                    index += 1;
+                                },
+                                "webhook-failures" => self.hook_failures = true,
+                                "webhook-retries" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::WebhookRetriesNoValue)?;
+                                    let retries = val.parse::<usize>().map_err(|_| ParseErr::WebhookRetriesNaN(val.to_owned()))?;
+                                    self.hook_retries = Some(retries);
+                                    index += 1;
+                                },
+                                "webhook-timeout" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::WebhookTimeoutNoValue)?;
+                                    self.hook_timeout = match parse_duration(val) {
+                                        Some(timeout) if timeout != Duration::from_millis(0) => Some(timeout),
+                                        _ => return Err(ParseErr::WebhookTimeoutInvalid(val.to_owned()))
+                                    };
+                                    index += 1;
+                                },
+                                "weight-col" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::WeightColNoValue)?;
+                                    self.weight_col = match val.parse::<usize>() {
+                                        Ok(column) if column != 0 => column,
+                                        _ => return Err(ParseErr::WeightColNaN(val.to_owned()))
+                                    };
+                                    index += 1;
+                                },
                                 "version" => {
                                     println!("MIT/Rust Parallel {}", env!("CARGO_PKG_VERSION"));
                                     exit(0);
                                 },
+                                "tty" => {
+                                    // Ensure that a terminal is available before any job is executed.
+                                    fs::OpenOptions::new().read(true).write(true).open("/dev/tty")
+                                        .map_err(ParseErr::TtyUnavailable)?;
+                                    self.flags |= TTY;
+                                },
+                                "tune" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::TuneNoValue)?;
+                                    self.tune.parse(val)?;
+                                    index += 1;
+                                },
+                                "transfer" => self.remote.transfers.push(String::from("{}")),
+                                "transferfile" | "tf" => {
+                                    let template = arguments.get(index).ok_or(ParseErr::TransferfileNoValue)?;
+                                    self.remote.transfers.push(template.to_owned());
+                                    index += 1;
+                                },
+                                "trc" => {
+                                    let template = arguments.get(index).ok_or(ParseErr::ReturnNoValue)?;
+                                    self.remote.transfers.push(String::from("{}"));
+                                    self.remote.returns.push(template.to_owned());
+                                    self.remote.cleanup = true;
+                                    index += 1;
+                                },
                                 "tmpdir" | "tempdir" => {
                                     *base_path = PathBuf::from(arguments.get(index).ok_or(ParseErr::WorkDirNoValue)?);
                                     index += 1;
 
                                     // Create the base directory if it does not exist
-                                    if let Err(why) = create_dir_all(base_path.as_path()) {
-                                        let stderr = io::stderr();
-                                        let stderr = &mut stderr.lock();
-                                        let _ = writeln!(stderr, "parallel: unable to create tempdir {:?}: {}", base_path.as_path(), why);
-                                        exit(1);
-                                    }
+                                    create_dir_all(base_path.as_path())
+                                        .map_err(|why| ParseErr::WorkDir(base_path.clone(), why))?;
                                 }
-                                _ if &argument[2..9] == "shebang" => {
+                                "xargs" => {
+                                    self.xargs = true;
+                                    self.exit_policy = Some(ExitPolicy::Xargs);
+                                },
+                                "where" => {
+                                    let val = arguments.get(index).ok_or(ParseErr::WhereNoValue)?;
+                                    filter = Some(Filter::parse(val)
+                                        .map_err(|why| ParseErr::WhereInvalid(val.to_owned(), why))?);
+                                    index += 1;
+                                },
+                                _ if argument[2..].starts_with("shebang") => {
                                     shebang = true;
                                     comm.push_str(&argument[10..]);
                                     break
                                 },
-                                _ => {
-                                    let stderr = io::stderr();
-                                    let _ = writeln!(stderr.lock(), "parallel: unsupported argument: '{}'", argument);
-                                }
+                                _ => return Err(unsupported_option(index-1, argument, &argument[2..]))
                             }
                         }
                     } else {
-                        match argument.as_str() {
-                            ":::"  => mode = Mode::Inputs,
-                            "::::" => mode = Mode::Files,
+                        match separators.mode(argument) {
+                            Some(Mode::Inputs) => mode = Mode::Inputs,
+                            Some(Mode::Files)  => mode = Mode::Files,
                             _ => {
                                 // The command has been supplied, and argument parsing is over.
+                                // Arguments which follow a `--cmd-file` command are appended to it.
+                                if !comm.is_empty() { comm.push(' '); }
                                 if quote_enabled {
                                     comm.push_str(&quote_command(argument));
                                 } else {
@@ -227,27 +1058,286 @@ impl Args {
                 }
             }
 
+            if replaying && chunk_size != 0 {
+                // The recorded inputs were already divided, and their ranges were not recorded.
+                return Err(ParseErr::ChunkFileUnsupported("replayed runs"));
+            }
+
+            if let Some(path) = replay {
+                // The command and inputs are obtained from the record.
+                if mode != Mode::Arguments || index < arguments.len() || shebang {
+                    return Err(ParseErr::ReplayWithCommand);
+                }
+
+                let record = record::load(&path).map_err(|why| match why {
+                    LoadErr::IO(why)          => ParseErr::File(FileErr::Open(path.clone(), why)),
+                    LoadErr::Invalid(message) => ParseErr::ReplayInvalid(path.clone(), message),
+                })?;
+                let inputs = match replay_jobs {
+                    Some(jobs) => record::select(record.inputs, &jobs),
+                    None       => record.inputs
+                };
+
+                // A replayed run keeps the run ID of the recorded run, unless another was given.
+                if self.run_id.is_empty() { self.run_id = record.run_id; }
+
+                let mut recorded = vec![arguments[0].clone()];
+                recorded.extend(record.arguments);
+                return self.parse_arguments(comm, &recorded, base_path, Some(inputs));
+            } else if replay_jobs.is_some() {
+                return Err(ParseErr::ReplayJobsWithoutReplay);
+            }
+
+            if let Some(path) = execute_plan.filter(|_| !replaying) {
+                // The planned commands are executed as they are, in place of a command and inputs.
+                if mode != Mode::Arguments || index < arguments.len() || shebang {
+                    return Err(ParseErr::ExecutePlanWithCommand);
+                }
+
+                let plan = plan::load(&path).map_err(|why| match why {
+                    LoadErr::IO(why)          => ParseErr::File(FileErr::Open(path.clone(), why)),
+                    LoadErr::Invalid(message) => ParseErr::ExecutePlanInvalid(path.clone(), message),
+                })?;
+
+                if self.run_id.is_empty() { self.run_id = plan.run_id; }
+                redirected = None;
+                planned = Some(plan.commands);
+            }
+
+            if replaying || planned.is_some() {
+                // The recorded inputs were already generated and filtered.
+                sequence = None;
+                filter = None;
+            }
+
+            if self.max_cost.is_some() && self.cost_rate.is_none() {
+                return Err(ParseErr::MaxCostWithoutCost);
+            }
+
+            if self.needs.is_some() && self.pools.is_empty() {
+                return Err(ParseErr::NeedsWithoutPool);
+            }
+
+            if self.reduce.is_some() && self.flags & TTY != 0 {
+                return Err(ParseErr::ReduceWithTty);
+            }
+
+            if tag || ctag {
+                let separator = tag_sep.unwrap_or_else(|| if ctag { " " } else { "\t" }.to_owned());
+                self.tag = Some(Tag { separator: separator, color: ctag });
+            } else if tag_sep.is_some() {
+                return Err(ParseErr::TagSepWithoutTag);
+            }
+
+            // Binary output is passed through in the order of the jobs, exactly as it was written.
+            if binary {
+                self.flags &= !LINE_BUFFER;
+                self.tag = None;
+            }
+
+            // Output which is discarded is never collected, so there is nothing to print as it is
+            // written, to tag, or to keep.
+            if self.discard {
+                self.flags &= !(UNGROUP | LINE_BUFFER);
+                self.tag = None;
+                let kept = if self.reduce.is_some() {
+                    Some("reduce")
+                } else if self.collect.is_some() {
+                    Some("collect")
+                } else if self.flags & TTY != 0 {
+                    Some("tty")
+                } else {
+                    None
+                };
+                if let Some(parameter) = kept { return Err(ParseErr::DiscardOutputWith(parameter)); }
+            }
+
+            // Output which is printed directly by the jobs cannot be tagged.
+            if self.tag.is_some() && self.flags & UNGROUP != 0 {
+                return Err(ParseErr::TagWithUngroup);
+            }
+
+            // Output which is printed as the job runs cannot be kept, discarded, or replaced afterwards.
+            if self.flags & (UNGROUP | LINE_BUFFER) != 0 {
+                let mode = if self.flags & UNGROUP != 0 { "ungroup" } else { "line buffer" };
+                let grouped = if self.reduce.is_some() {
+                    Some("reduce")
+                } else if self.return_first != 0 {
+                    Some("return first")
+                } else if self.speculative != 0 {
+                    Some("speculative max")
+                } else if self.retries != 0 {
+                    Some("retries")
+                } else {
+                    None
+                };
+                if let Some(parameter) = grouped { return Err(ParseErr::Ungrouped(mode, parameter)); }
+            }
+
+            // Duplicates do not claim the resources of the job that they duplicate.
+            if self.speculative != 0 {
+                let unsupported = if self.max_spool != 0 {
+                    Some("max spool")
+                } else if self.gpus != 0 {
+                    Some("gpus")
+                } else if !self.classes.is_empty() || !self.pools.is_empty() {
+                    Some("class or pool")
+                } else if self.weight_col != 0 {
+                    Some("weight col")
+                } else if self.flags & TTY != 0 {
+                    Some("tty")
+                } else if self.retries != 0 {
+                    Some("retries")
+                } else {
+                    None
+                };
+                if let Some(parameter) = unsupported { return Err(ParseErr::SpeculativeUnsupported(parameter)); }
+            }
+
+            if self.webhook.is_none() {
+                let without = if self.hook_failures {
+                    Some("failures")
+                } else if self.hook_retries.is_some() {
+                    Some("retries")
+                } else if self.hook_timeout.is_some() {
+                    Some("timeout")
+                } else {
+                    None
+                };
+                if let Some(parameter) = without { return Err(ParseErr::WebhookWithoutUrl(parameter)); }
+            }
+
+            if let Some(succeeded) = resume {
+                // Jobs which were already logged are skipped, and are logged again only if executed.
+                let path = match self.joblog {
+                    Some(ref path) => PathBuf::from(path),
+                    None => return Err(ParseErr::ResumeWithoutJoblog(if succeeded { "resume failed" } else { "resume" }))
+                };
+                self.excluded.resumed = finished_jobs(&path, succeeded).map_err(|why| ParseErr::File(FileErr::Read(path, why)))?;
+                self.resume = true;
+            }
+
+            // An instance which was executed by a job shares the cores with the other slots around it.
+            if let (Some(inner), Some(outer)) = (inner_jobs, jobs::outer_slots()) {
+                self.ncores = jobs::inner(self.ncores, inner, outer);
+            }
+
+            // With `--env _`, every variable is given, other than those which were recorded with `--record-env`.
+            if !env_names.is_empty() {
+                let ignored = if env_names.iter().any(|name| name == environment::EVERYTHING) {
+                    let path = environment::ignored_vars().ok_or(ParseErr::EnvWithoutHome)?;
+                    let names = fs::read_to_string(&path).map_err(|why| ParseErr::File(FileErr::Read(path, why)))?;
+                    names.lines().map(str::to_owned).collect()
+                } else {
+                    Vec::new()
+                };
+                self.remote.env = environment::select(&env_names, &ignored);
+            }
+
+            if self.remote.watch.is_some() && !self.remote.watched() { return Err(ParseErr::HostfileWatchWithoutFile); }
+
+            // The throttles of the file override those of the command line, which are kept for when it is reloaded.
+            if let Some(path) = throttle_file {
+                let base = Throttles { resources: self.resources.clone(), delay: self.delay, nice: self.nice };
+                let contents = fs::read_to_string(&path).map_err(|why| ParseErr::File(FileErr::Read(PathBuf::from(&path), why)))?;
+                let throttles = base.parse(&contents).map_err(|why| ParseErr::ThrottleFileInvalid(path.clone(), why))?;
+                self.resources = throttles.resources;
+                self.delay = throttles.delay;
+                self.nice = throttles.nice;
+                self.throttles = Some((path, base));
+            }
+
+            // With `--sshlogin`, each host has its own job slots, which are `--jobs` unless given.
+            if !self.remote.is_empty() {
+                self.remote.configure().map_err(ParseErr::HostUnknown)?;
+                self.ncores = self.remote.assign(self.ncores);
+            } else if !self.remote.transfers.is_empty() {
+                return Err(ParseErr::TransferWithoutSshlogin("transferfile"));
+            } else if !self.remote.returns.is_empty() {
+                return Err(ParseErr::TransferWithoutSshlogin("return"));
+            } else if !self.remote.basefiles.is_empty() {
+                return Err(ParseErr::TransferWithoutSshlogin("basefile"));
+            } else if self.remote.cleanup {
+                return Err(ParseErr::TransferWithoutSshlogin("cleanup"));
+            } else if self.remote.filter {
+                return Err(ParseErr::TransferWithoutSshlogin("filter-hosts"));
+            } else if self.remote.local != 0 {
+                return Err(ParseErr::TransferWithoutSshlogin("local-slots"));
+            } else if self.remote.local_only.is_some() {
+                return Err(ParseErr::TransferWithoutSshlogin("local-only"));
+            } else if self.remote.configure().is_err() {
+                return Err(ParseErr::TransferWithoutSshlogin("host-env or host-setup"));
+            }
+
+            if header && self.colsep.is_none() { return Err(ParseErr::HeaderWithoutColsep); }
+            if !self.only_labels.is_empty() && self.label.is_none() { return Err(ParseErr::OnlyLabelWithoutLabel); }
+            if !self.stages.is_empty() && self.flags & PIPE_IS_ENABLED != 0 { return Err(ParseErr::ThenWithPipe); }
+
+            // Under `--strict`, inputs which would be skipped are recorded, so that they may be reported as errors.
+            if self.strict && self.skipped.is_none() { self.skipped = Some(Vec::new()); }
+
+            if self.log_argv && self.joblog.is_none() {
+                return Err(ParseErr::LogCommandsWithoutJoblog);
+            }
+
+            if self.deterministic.is_some() && self.retries != 0 {
+                return Err(ParseErr::TestDeterministicWithRetries);
+            }
+
+            if self.allow_overlap && self.every.is_none() {
+                return Err(ParseErr::AllowOverlapWithoutEvery);
+            }
+
+            // Each cycle of `--every` is executed by the supervisor with its own tempdir, so that
+            // overlapping cycles do not share their inputs and outputs.
+            if self.every.is_some() {
+                if let Ok(cycle) = env::var(supervisor::CYCLE_VAR) {
+                    *base_path = PathBuf::from(supervisor::cycle_dir(base_path, &cycle));
+                    create_dir_all(base_path.as_path()).map_err(|why| ParseErr::WorkDir(base_path.clone(), why))?;
+                    self.every = None;
+                }
+            }
+
+            if self.xargs {
+                // Like xargs, `echo` is the default command, and `-I` executes one input per command.
+                if comm.is_empty() { comm.push_str("echo"); }
+                if replace.is_some() {
+                    max_args = 1;
+                } else if max_args == 0 {
+                    max_args = XARGS_MAX_ARGS;
+                }
+            }
+
             // If no command was supplied, then inputs are the commands
             if comm.is_empty() {
                 self.flags |= INPUTS_ARE_COMMANDS;
             } else {
                 // Ensure that the command has each possible quote terminated
-                if !quote_enabled { check_command(comm.as_str())?; }
+                if !quote_enabled && !block { check_command(comm.as_str())?; }
+            }
+
+            // Inputs from the `-a` files take the place of the standard input.
+            if !arg_files.is_empty() && !replaying {
+                redirected = None;
+                for path in &arg_files {
+                    file_parse(&mut current_inputs, path, self.flags & INPUTS_ARE_COMMANDS != 0,
+                        &mut self.skipped, Some(&mut self.provenance))?;
+                }
             }
 
-            if let Some(path) = redirection::input_was_redirected() {
+            if let Some(ref path) = redirected {
                 file_parse(&mut current_inputs, path.to_str().ok_or_else(|| ParseErr::RedirFile(path.clone()))?,
-                    self.flags & INPUTS_ARE_COMMANDS != 0)?;
+                    self.flags & INPUTS_ARE_COMMANDS != 0, &mut self.skipped, Some(&mut self.provenance))?;
             } else if let Mode::Command = mode {
                 while let Some(argument) = arguments.get(index) {
                     index += 1;
-                    match argument.as_str() {
+                    match separators.mode(argument) {
                         // Arguments after `:::` are input values.
-                        ":::" | ":::+"   => mode = Mode::Inputs,
+                        Some(Mode::Inputs) | Some(Mode::InputsAppend) => mode = Mode::Inputs,
                         // Arguments after `::::` are files with inputs.
-                        "::::" | "::::+" => mode = Mode::Files,
+                        Some(_) => mode = Mode::Files,
                         // All other arguments are command arguments.
-                        _ => {
+                        None => {
                             comm.push(' ');
                             if quote_enabled {
                                 comm.push_str(&quote_inputs(argument));
@@ -264,49 +1354,185 @@ impl Args {
                 if comm.is_empty() {
                     self.flags |= INPUTS_ARE_COMMANDS;
                 } else {
-                    // Ensure that the command has each possible quote terminated
-                    if !quote_enabled { check_command(comm.as_str())?; }
+                    // Ensure that the command has each possible quote terminated. Block commands may
+                    // contain here-docs, whose quotes need not be terminated.
+                    if !quote_enabled && !block { check_command(comm.as_str())?; }
                 }
 
-                if shebang {
-                    file_parse(&mut current_inputs, &arguments.last().unwrap(),
-                        self.flags & INPUTS_ARE_COMMANDS != 0)?;
+                if shebang && !replaying {
+                    file_parse(&mut current_inputs, arguments.last().unwrap(),
+                        self.flags & INPUTS_ARE_COMMANDS != 0, &mut self.skipped, Some(&mut self.provenance))?;
                 } else {
                     parse_inputs(arguments, index, &mut current_inputs, &mut lists, &mut mode,
-                        self.flags & INPUTS_ARE_COMMANDS != 0)?;
+                        self.flags & INPUTS_ARE_COMMANDS != 0, &mut self.skipped, &mut self.provenance, self.strict, &separators)?;
                 }
             } else {
                 parse_inputs(arguments, index, &mut current_inputs, &mut lists, &mut mode,
-                    self.flags & INPUTS_ARE_COMMANDS != 0)?;
+                    self.flags & INPUTS_ARE_COMMANDS != 0, &mut self.skipped, &mut self.provenance, self.strict, &separators)?;
+            }
+
+            if self.xargs && replace.is_none() {
+                // Like xargs, inputs are items that are separated by blanks and newlines.
+                current_inputs = split_items(current_inputs);
+                self.provenance.clear();
             }
 
-            number_of_arguments = write_inputs_to_disk(lists, current_inputs, max_args, base_path.clone())?;
-        } else if let Some(path) = redirection::input_was_redirected() {
+            if lists.len() > 1 || (filter.is_some() && !lists.is_empty()) {
+                if chunk_size != 0 { return Err(ParseErr::ChunkFileUnsupported("permutations of input lists")); }
+                if header { return Err(ParseErr::HeaderUnsupported("permutations of input lists")); }
+                if let Some(ref filter) = filter {
+                    if filter.columns() > lists.len() {
+                        return Err(ParseErr::WhereColumn(filter.columns(), lists.len()));
+                    }
+                }
+
+                // Permutations are generated as they are needed, so the unprocessed file remains empty.
+                let permutations = PermutatedInputs::new(lists, self.permute_order, max_args, filter.take());
+                let generator = Generator::Permutations(permutations);
+                write_inputs_to_disk(Vec::new(), max_args, base_path.clone())?;
+                self.provenance.clear();
+                number_of_arguments = generator.len();
+                if number_of_arguments != 0 { self.generator = Some(generator); }
+            } else {
+                if header { header_line = take_header(&mut current_inputs, &mut self.provenance); }
+                if chunk_size != 0 {
+                    current_inputs = chunk_inputs(current_inputs, chunk_size, &mut self.provenance)?;
+                }
+                number_of_arguments = write_inputs_to_disk(current_inputs, max_args, base_path.clone())?;
+                self.provenance.group(max_args);
+            }
+
+            if let Some((first, last)) = sequence {
+                if number_of_arguments != 0 { return Err(ParseErr::SeqWithInputs); }
+                if chunk_size != 0 { return Err(ParseErr::ChunkFileUnsupported("--seq")); }
+                let generator = Generator::Sequence(Sequence::new(first, last, max_args));
+                number_of_arguments = generator.len();
+                self.generator = Some(generator);
+            }
+        } else if let Some(path) = redirected {
             // Read inputs as commands
             let path = path.to_str().ok_or_else(|| ParseErr::RedirFile(path.clone()))?;
-            file_parse(&mut current_inputs, path, true)?;
-            number_of_arguments = write_inputs_to_disk(lists, current_inputs, max_args, base_path.clone())?;
+            file_parse(&mut current_inputs, path, true, &mut self.skipped, Some(&mut self.provenance))?;
+            if header { header_line = take_header(&mut current_inputs, &mut self.provenance); }
+            number_of_arguments = write_inputs_to_disk(current_inputs, max_args, base_path.clone())?;
+            self.provenance.group(max_args);
+        }
+
+        let replaying = replaying || planned.is_some();
+        if let Some(inputs) = replayed.or(planned) {
+            // The recorded inputs were grouped before they were recorded, and the planned commands were
+            // expanded before they were planned, so they are written as they are.
+            if comm.is_empty() { self.flags |= INPUTS_ARE_COMMANDS; }
+            number_of_arguments = write_inputs_to_disk(inputs, 1, base_path.clone())?;
         }
 
-        if number_of_arguments == 0 {
+        if filter.is_some() { return Err(ParseErr::WhereWithoutLists); }
+
+        if number_of_arguments == 0 && !replaying {
+            if chunk_size != 0 { return Err(ParseErr::ChunkFileUnsupported("inputs from the standard input")); }
+            if self.every.is_some() { return Err(ParseErr::EveryWithStdin); }
+
             // If no command was supplied, then inputs are the commands
             if comm.is_empty() {
                 self.flags |= INPUTS_ARE_COMMANDS;
             } else {
                 // Ensure that the command has each possible quote terminated
-                if !quote_enabled { check_command(comm.as_str())?; }
+                if !quote_enabled && !block { check_command(comm.as_str())?; }
             }
 
-            number_of_arguments = write_stdin_to_disk(max_args, base_path.clone(),
-                self.flags & INPUTS_ARE_COMMANDS != 0, quote_enabled)?;
+            // Without inputs, a terminal would be read until Ctrl-D, which looks like a hang.
+            if io::stdin().is_terminal() {
+                if non_blocking_stdin { return Err(ParseErr::StdinIsTerminal); }
+                let stderr = io::stderr();
+                let _ = stderr.lock().write(b"parallel: warning: no inputs were given, so they are read from the \
+                    terminal until Ctrl-D\n");
+            }
+
+            // Inputs are only streamed when nothing needs every input before the first job is executed.
+            let streamed = total_jobs != 0 && !header && self.record.is_none() && self.plan.is_none()
+                && self.export.is_none() && self.flags & DRY_RUN == 0 && self.deterministic.is_none()
+                && self.only_labels.is_empty();
+            let split = self.xargs && replace.is_none();
+            number_of_arguments = if streamed {
+                stream_stdin_to_disk(total_jobs, max_args, base_path.clone(), self.flags & INPUTS_ARE_COMMANDS != 0,
+                    quote_enabled, split)?
+            } else {
+                write_stdin_to_disk(max_args, base_path.clone(), self.flags & INPUTS_ARE_COMMANDS != 0,
+                    quote_enabled, split, Some(&mut self.provenance), if header { Some(&mut header_line) } else { None },
+                    false)?
+            };
+        }
+
+        if let (true, Some(separator)) = (header, self.colsep.as_ref()) {
+            self.header = Some(parse_header(&header_line, separator));
+        }
+
+        if self.strict {
+            match self.skipped {
+                Some(ref skipped) if !skipped.is_empty() => return Err(ParseErr::StrictSkipped(skipped.len())),
+                _ => (),
+            }
+        }
+
+        // An empty set of inputs is an error with `--fail-if-empty`, even along with `-r` or `--xargs`.
+        if number_of_arguments == 0 && !replaying && (fail_if_empty || self.strict) {
+            return Err(ParseErr::NoArguments);
+        }
+
+        if number_of_arguments == 0 && no_run_if_empty { exit(0); }
+
+        if number_of_arguments == 0 && self.xargs {
+            // Like xargs, the command is executed once when there are no inputs.
+            number_of_arguments = write_inputs_to_disk(vec![String::new()], 1, base_path.clone())?;
         }
 
         if number_of_arguments == 0 { return Err(ParseErr::NoArguments); }
 
+        // Each stage of `--then` is executed after the command, so there must be one.
+        if !self.stages.is_empty() && self.flags & INPUTS_ARE_COMMANDS != 0 { return Err(ParseErr::ThenWithoutCommand); }
+
+        // The `-I` string is an alternative to the `{}` placeholder.
+        if let Some(replace) = replace {
+            *comm = comm.replace(replace.as_str(), "{}");
+            for &mut (ref mut template, _) in &mut self.stages {
+                *template = template.replace(replace.as_str(), "{}");
+            }
+        }
+
+        // Only one job may be attached to the terminal at a time.
+        if self.flags & TTY != 0 { self.ncores = 1; }
+
         Ok(number_of_arguments)
     }
 }
 
+/// Collects the options and command, which precede the inputs, without the `--record` option.
+/// The inputs begin at the first separator, which may have been changed by an earlier option.
+fn record_arguments(arguments: &[String]) -> Vec<String> {
+    let mut recorded = Vec::new();
+    let mut separators = Separators::default();
+    let mut arguments = arguments.iter().skip(1);
+    while let Some(argument) = arguments.next() {
+        if separators.mode(argument).is_some() { break }
+        match argument.as_str() {
+            "--record" => { arguments.next(); },
+            "--arg-sep" | "--arg-file-sep" => {
+                recorded.push(argument.clone());
+                if let Some(separator) = arguments.next() {
+                    if argument == "--arg-sep" {
+                        separators.arg = separator.clone();
+                    } else {
+                        separators.file = separator.clone();
+                    }
+                    recorded.push(separator.clone());
+                }
+            },
+            _ => recorded.push(argument.clone())
+        }
+    }
+    recorded
+}
+
 /// Ensures that the command supplied is properly terminated
 fn check_command(input: &str) -> Result<(), ParseErr> {
     let (mut single, mut double, mut back) = (false, false, false);
@@ -323,25 +1549,24 @@ fn check_command(input: &str) -> Result<(), ParseErr> {
     if double || single { Err(ParseErr::NonTerminated(String::from(input))) } else { Ok(()) }
 }
 
-// Performs the same operation as `quote_inputs`, but doesn't escape the first word found
+// Performs the same operation as `quote_inputs`, but doesn't escape the first word found, nor the
+// `NAME=value` environment assignments which precede it. Commands which begin with `#!SHELL` are
+// left as is, as they are interpreted by that shell.
 fn quote_command(input: &str) -> String {
-    let mut output = Vec::with_capacity(input.len());
-    let mut bytes = input.bytes();
-    while let Some(byte) = bytes.next() {
-        output.push(byte);
-        if byte == b' ' { break }
-    }
+    if shell::shebang(input).is_some() { return input.to_owned() }
 
-    for byte in bytes {
-        match byte {
-            b'"' | b'\\' | b'\'' | b' ' => output.push(b'\\'),
-            _ => ()
-        }
-        output.push(byte);
+    let mut start = 0;
+    loop {
+        let end = input[start..].find(' ').map_or(input.len(), |position| start + position + 1);
+        let word = input[start..end].trim_end();
+        start = end;
+        if end == input.len() || !shell::is_assignment(word) { break }
     }
 
-    // Don't worry, this is safe!
-    unsafe { String::from_utf8_unchecked(output) }
+    let mut output = String::with_capacity(input.len());
+    output.push_str(&input[..start]);
+    output.push_str(&quote_inputs(&input[start..]));
+    output
 }
 
 // Escapes space, backslash, and quote characters
@@ -359,23 +1584,52 @@ fn quote_inputs(input: &str) -> String {
     unsafe { String::from_utf8_unchecked(output) }
 }
 
+/// With `--total-jobs`, writes the arguments from standard input to the disk on a thread of its own,
+/// so that jobs may be executed while the standard input is still being read. The hint is returned as
+/// the number of arguments, and stands in for it until the real number is known.
+fn stream_stdin_to_disk(hint: usize, max_args: usize, unprocessed_path: PathBuf, inputs_are_commands: bool,
+    quote_enabled: bool, split: bool) -> Result<usize, ParseErr>
+{
+    // The unprocessed file is opened for reading before the first input has been written to it.
+    let path = unprocessed_path.join("unprocessed");
+    fs::OpenOptions::new().truncate(true).write(true).create(true).open(&path)
+        .map_err(|why| ParseErr::File(FileErr::Open(path, why)))?;
+
+    stream::start(hint);
+    thread::spawn(move || {
+        match write_stdin_to_disk(max_args, unprocessed_path, inputs_are_commands, quote_enabled, split, None, None, true) {
+            Ok(number_of_arguments) => stream::publish(number_of_arguments, true),
+            Err(why) => {
+                let stderr = io::stderr();
+                let _ = writeln!(stderr.lock(), "parallel: parsing error: {}", why);
+                exit_status::exit_with(exit_status::EXIT_INPUT);
+            }
+        }
+    });
+    Ok(hint)
+}
+
 /// Write all arguments from standard input to the disk, recording the number of arguments that were read.
+/// When the inputs are `streamed`, each argument is flushed to the disk once it has been written, and the
+/// number of arguments so far is published to the jobs which are waiting on it. The origins of streamed
+/// arguments are not recorded.
+#[allow(clippy::too_many_arguments)]
 fn write_stdin_to_disk(max_args: usize, mut unprocessed_path: PathBuf, inputs_are_commands: bool,
-    quote_enabled: bool) -> Result<usize, ParseErr>
+    quote_enabled: bool, split: bool, mut provenance: Option<&mut Provenance>, mut header: Option<&mut String>,
+    streamed: bool) -> Result<usize, ParseErr>
 {
     // Write a message to standard error that inputs are being read from standard input
-    let stderr = io::stderr();
-    let mut stderr = stderr.lock();
-    let _ = stderr.write(b"parallel: reading inputs from standard input\n");
+    // The standard error is not kept locked, as jobs print to it while the inputs are streamed.
+    let _ = io::stderr().lock().write(b"parallel: reading inputs from standard input\n");
 
     unprocessed_path.push("unprocessed");
     let disk_buffer = fs::OpenOptions::new().truncate(true).write(true).create(true).open(&unprocessed_path)
         .map_err(|why| ParseErr::File(FileErr::Open(unprocessed_path.clone(), why)))?;
-    let mut disk_buffer = BufWriter::new(disk_buffer);
+    let mut disk_buffer = BufWriter::new(SpaceWriter::new(disk_buffer, &unprocessed_path));
     let mut number_of_arguments = 0;
 
     // If inputs are commands, then inputs should be command escaped, else inputs escaped.
-    let parse_line: Box<Fn(io::Result<String>) -> io::Result<String>> =
+    let parse_line: Box<dyn Fn(io::Result<String>) -> io::Result<String>> =
         if inputs_are_commands && quote_enabled
     {
         Box::new(|input: io::Result<String>| -> io::Result<String> {
@@ -386,37 +1640,52 @@ fn write_stdin_to_disk(max_args: usize, mut unprocessed_path: PathBuf, inputs_ar
     };
 
     let stdin = io::stdin();
+    let source = provenance.as_mut().map_or(0, |provenance| provenance.add_source("-"));
+    let lines = BufReader::new(stdin.lock()).lines().enumerate()
+        .filter_map(|(id, line)| parse_line(line).ok().map(|line| (id, line)));
+
+    // If `split` is set, each line is split into the items that are separated by whitespace.
+    let inputs: Box<dyn Iterator<Item = (usize, String)>> = if split {
+        Box::new(lines.flat_map(|(id, line)| {
+            line.split_whitespace().map(|item| (id, item.to_owned())).collect::<Vec<_>>()
+        }))
+    } else {
+        Box::new(lines)
+    };
+
     if max_args < 2 {
-        for line in BufReader::new(stdin.lock()).lines() {
-            if let Ok(line) = parse_line(line) {
-                if line.is_empty() { continue }
-                disk_buffer.write(line.as_bytes()).and_then(|_| disk_buffer.write(b"\n"))
-                    .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-                number_of_arguments += 1;
-            }
+        for (id, line) in inputs {
+            if line.is_empty() { continue }
+            if let Some(header) = header.take() { *header = line; continue }
+            if let Some(ref mut provenance) = provenance { provenance.push(source, id + 1); }
+            disk_buffer.write(line.as_bytes()).and_then(|_| disk_buffer.write(b"\n"))
+                .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
+            number_of_arguments += 1;
+            if streamed { publish(&mut disk_buffer, &unprocessed_path, number_of_arguments)?; }
         }
     } else {
         let mut max_args_index = max_args;
-        for line in BufReader::new(stdin.lock()).lines() {
-            if let Ok(line) = parse_line(line) {
-                if line.is_empty() { continue }
-                if max_args_index == max_args {
-                    max_args_index -= 1;
-                    number_of_arguments += 1;
-                    disk_buffer.write(line.as_bytes())
-                        .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-                } else if max_args_index == 1 {
-                    max_args_index = max_args;
-                    disk_buffer.write(b" ")
-                        .and_then(|_| disk_buffer.write(line.as_bytes()))
-                        .and_then(|_| disk_buffer.write(b"\n"))
-                        .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-                } else {
-                    max_args_index -= 1;
-                    disk_buffer.write(b" ")
-                        .and_then(|_| disk_buffer.write(line.as_bytes()))
-                        .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-                }
+        for (id, line) in inputs {
+            if line.is_empty() { continue }
+            if let Some(header) = header.take() { *header = line; continue }
+            if max_args_index == max_args {
+                max_args_index -= 1;
+                number_of_arguments += 1;
+                if let Some(ref mut provenance) = provenance { provenance.push(source, id + 1); }
+                disk_buffer.write(line.as_bytes())
+                    .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
+            } else if max_args_index == 1 {
+                max_args_index = max_args;
+                disk_buffer.write(b" ")
+                    .and_then(|_| disk_buffer.write(line.as_bytes()))
+                    .and_then(|_| disk_buffer.write(b"\n"))
+                    .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
+                if streamed { publish(&mut disk_buffer, &unprocessed_path, number_of_arguments)?; }
+            } else {
+                max_args_index -= 1;
+                disk_buffer.write(b" ")
+                    .and_then(|_| disk_buffer.write(line.as_bytes()))
+                    .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
             }
         }
         if max_args_index != max_args {
@@ -425,101 +1694,28 @@ fn write_stdin_to_disk(max_args: usize, mut unprocessed_path: PathBuf, inputs_ar
         }
     }
 
+    disk_buffer.flush().map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
     Ok(number_of_arguments)
 }
 
+/// Flushes the arguments which were streamed so far to the disk, and publishes their number.
+fn publish<W: Write>(disk_buffer: &mut W, unprocessed_path: &Path, number_of_arguments: usize) -> Result<(), ParseErr> {
+    disk_buffer.flush().map_err(|why| FileErr::Write(unprocessed_path.to_owned(), why))?;
+    stream::publish(number_of_arguments, false);
+    Ok(())
+}
+
 /// Write all input arguments buffered in memory to the disk, recording the number of arguments that were read.
-fn write_inputs_to_disk(lists: Vec<Vec<String>>, current_inputs: Vec<String>, max_args: usize,
-    mut unprocessed_path: PathBuf) -> Result<usize, ParseErr>
+fn write_inputs_to_disk(current_inputs: Vec<String>, max_args: usize, mut unprocessed_path: PathBuf)
+    -> Result<usize, ParseErr>
 {
     unprocessed_path.push("unprocessed");
     let disk_buffer = fs::OpenOptions::new().truncate(true).write(true).create(true).open(&unprocessed_path)
         .map_err(|why| ParseErr::File(FileErr::Open(unprocessed_path.to_owned(), why)))?;
-    let mut disk_buffer = BufWriter::new(disk_buffer);
+    let mut disk_buffer = BufWriter::new(SpaceWriter::new(disk_buffer, &unprocessed_path));
     let mut number_of_arguments = 0;
 
-    if lists.len() > 1 {
-        // Convert the Vec<Vec<String>> into a Vec<Vec<&str>>
-        let tmp: Vec<Vec<&str>> = lists.iter()
-            .map(|list| list.iter().map(AsRef::as_ref).collect::<Vec<&str>>())
-            .collect();
-
-        // Convert the Vec<Vec<&str>> into a Vec<&[&str]>
-        let list_array: Vec<&[&str]> = tmp.iter().map(AsRef::as_ref).collect();
-
-        // Create a `Permutator` with the &[&[&str]] as the input.
-        let mut permutator = Permutator::new(&list_array[..]);
-
-        // Generate the first permutation's buffer
-        let mut permutation_buffer = permutator.next().unwrap();
-        {
-            let mut iter = permutation_buffer.iter();
-            disk_buffer.write(iter.next().unwrap().as_bytes())
-                .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-            for element in iter {
-                disk_buffer.write(b" ").and_then(|_| disk_buffer.write(element.as_bytes()))
-                    .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-            }
-
-            number_of_arguments += 1;
-        }
-
-        if max_args < 2 {
-            disk_buffer.write(b"\n").map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-            while permutator.next_with_buffer(&mut permutation_buffer) {
-                let mut iter = permutation_buffer.iter();
-                disk_buffer.write(iter.next().unwrap().as_bytes())
-                    .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-                for element in iter {
-                    disk_buffer.write(b" ").and_then(|_| disk_buffer.write(element.as_bytes()))
-                        .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-                }
-                disk_buffer.write(b"\n")
-                    .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-                number_of_arguments += 1;
-            }
-        } else {
-            let mut max_args_index = max_args - 1;
-            while permutator.next_with_buffer(&mut permutation_buffer) {
-                let mut iter = permutation_buffer.iter();
-                if max_args_index == max_args {
-                    max_args_index -= 1;
-                    number_of_arguments += 1;
-
-                    disk_buffer.write(iter.next().unwrap().as_bytes())
-                        .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-
-                    for element in iter {
-                        disk_buffer.write(b" ").and_then(|_| disk_buffer.write(element.as_bytes()))
-                            .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-                    }
-                } else if max_args_index == 1 {
-                    max_args_index = max_args;
-                    disk_buffer.write(b" ")
-                        .and_then(|_| disk_buffer.write(iter.next().unwrap().as_bytes()))
-                        .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-
-                    for element in iter {
-                        disk_buffer.write(b" ").and_then(|_| disk_buffer.write(element.as_bytes()))
-                            .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-                    }
-
-                    disk_buffer.write(b"\n")
-                        .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-                } else {
-                    max_args_index -= 1;
-                    disk_buffer.write(b" ")
-                        .and_then(|_| disk_buffer.write(iter.next().unwrap().as_bytes()))
-                        .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-
-                    for element in iter {
-                        disk_buffer.write(b" ").and_then(|_| disk_buffer.write(element.as_bytes()))
-                            .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
-                    }
-                }
-            }
-        }
-    } else if max_args < 2 {
+    if max_args < 2 {
         for input in current_inputs {
             disk_buffer.write(input.as_bytes())
                 .and_then(|_| disk_buffer.write(b"\n"))
@@ -543,30 +1739,43 @@ fn write_inputs_to_disk(lists: Vec<Vec<String>>, current_inputs: Vec<String>, ma
                 .map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
         }
     }
+
+    disk_buffer.flush().map_err(|why| FileErr::Write(unprocessed_path.clone(), why))?;
     Ok(number_of_arguments)
 }
 
 /// Collects all the provided inputs that were passed as command line arguments into the program.
+/// The origin of each input within the current list is recorded in `provenance`, where the line
+/// of an input given as an argument is its position among the program's arguments.
 fn parse_inputs(arguments: &[String], mut index: usize, current_inputs: &mut Vec<String>,
-    lists: &mut Vec<Vec<String>>, mode: &mut Mode, inputs_are_commands: bool) -> Result<(), ParseErr>
+    lists: &mut Vec<Vec<String>>, mode: &mut Mode, inputs_are_commands: bool, skipped: &mut Option<Vec<String>>,
+    provenance: &mut Provenance, strict: bool, separators: &Separators) -> Result<(), ParseErr>
 {
-    let mut append_list = &mut Vec::new();
+    let append_list = &mut Vec::new();
+    let source = provenance.add_source(":::");
 
     macro_rules! switch_mode {
         ($mode:expr) => {{
             match *mode {
-                Mode::InputsAppend | Mode::FilesAppend => merge_lists(current_inputs, append_list),
+                Mode::InputsAppend | Mode::FilesAppend => {
+                    merge_lists(current_inputs, append_list, strict)?;
+                    provenance.truncate(current_inputs.len());
+                },
                 _ => (),
             }
             *mode = $mode;
             if !current_inputs.is_empty() {
                 lists.push(current_inputs.clone());
                 current_inputs.clear();
+                provenance.clear();
             }
         }};
         (append $mode:expr) => {{
             match *mode {
-                Mode::InputsAppend | Mode::FilesAppend => merge_lists(current_inputs, append_list),
+                Mode::InputsAppend | Mode::FilesAppend => {
+                    merge_lists(current_inputs, append_list, strict)?;
+                    provenance.truncate(current_inputs.len());
+                },
                 _ => (),
             }
             *mode = $mode;
@@ -576,23 +1785,30 @@ fn parse_inputs(arguments: &[String], mut index: usize, current_inputs: &mut Vec
     // Parse each and every input argument supplied to the program.
     while let Some(argument) = arguments.get(index) {
         index += 1;
-        match argument.as_str() {
+        match separators.mode(argument) {
             // `:::` denotes that the next set of inputs will be added to a new list.
-            ":::"   => switch_mode!(Mode::Inputs),
+            Some(Mode::Inputs)       => switch_mode!(Mode::Inputs),
             // `:::+` denotes that the next set of inputs will be added to the current list.
-            ":::+"  => switch_mode!(append Mode::InputsAppend),
+            Some(Mode::InputsAppend) => switch_mode!(append Mode::InputsAppend),
             // `::::` denotes that the next set of inputs will be added to a new list.
-            "::::"  => switch_mode!(Mode::Files),
-            // `:::+` denotes that the next set of inputs will be added to the current list.
-            "::::+" => switch_mode!(append Mode::FilesAppend),
+            Some(Mode::Files)        => switch_mode!(Mode::Files),
+            // `::::+` denotes that the next set of inputs will be added to the current list.
+            Some(_)                  => switch_mode!(append Mode::FilesAppend),
             // All other arguments will be added to the current list.
-            _ => match *mode {
-                Mode::Inputs if inputs_are_commands       => current_inputs.push(quote_command(&argument)),
-                Mode::InputsAppend if inputs_are_commands => append_list.push(quote_command(&argument)),
-                Mode::Inputs       => current_inputs.push(argument.clone()),
+            None => match *mode {
+                Mode::Inputs if inputs_are_commands       => {
+                    current_inputs.push(quote_command(argument));
+                    provenance.push(source, index - 1);
+                },
+                Mode::InputsAppend if inputs_are_commands => append_list.push(quote_command(argument)),
+                Mode::Inputs       => {
+                    current_inputs.push(argument.clone());
+                    provenance.push(source, index - 1);
+                },
                 Mode::InputsAppend => append_list.push(argument.clone()),
-                Mode::Files        => file_parse(current_inputs, argument, inputs_are_commands)?,
-                Mode::FilesAppend  => file_parse(append_list, argument, inputs_are_commands)?,
+                Mode::Files        => file_parse(current_inputs, argument, inputs_are_commands, skipped,
+                    Some(provenance))?,
+                Mode::FilesAppend  => file_parse(append_list, argument, inputs_are_commands, skipped, None)?,
                 _                  => unreachable!()
             }
         }
@@ -600,7 +1816,10 @@ fn parse_inputs(arguments: &[String], mut index: usize, current_inputs: &mut Vec
 
     if !append_list.is_empty() {
         match *mode {
-            Mode::InputsAppend | Mode::FilesAppend => merge_lists(current_inputs, append_list),
+            Mode::InputsAppend | Mode::FilesAppend => {
+                merge_lists(current_inputs, append_list, strict)?;
+                provenance.truncate(current_inputs.len());
+            },
             _ => (),
         }
     }
@@ -615,17 +1834,20 @@ fn parse_inputs(arguments: &[String], mut index: usize, current_inputs: &mut Vec
 /// Parses the `max_args` value, `-n3` or `-n 3`, and optionally increments the index if necessary.
 fn parse_max_args(argument: &str, next_argument: Option<&String>,index: &mut usize) -> Result<usize, ParseErr> {
     if argument.len() > 2 {
-        Ok(argument[2..].parse::<usize>().map_err(|_| ParseErr::MaxArgsNaN(*index))?)
+        Ok(argument[2..].parse::<usize>().map_err(|_| ParseErr::MaxArgsNaN(argument[2..].to_owned()))?)
     } else {
         *index += 1;
         let argument = next_argument.ok_or(ParseErr::MaxArgsNoValue)?;
-        Ok(argument.parse::<usize>().map_err(|_| ParseErr::MaxArgsNaN(*index))?)
+        Ok(argument.parse::<usize>().map_err(|_| ParseErr::MaxArgsNaN(argument.to_owned()))?)
     }
 }
 
 /// Merges an `append` list to the `original` list, draining the `append` list in the process.
-/// Excess arguments will be truncated, and therefore lost.
-fn merge_lists(original: &mut Vec<String>, append: &mut Vec<String>) {
+/// Excess arguments will be truncated, and therefore lost, unless the lists are `strict`ly linked.
+fn merge_lists(original: &mut Vec<String>, append: &mut Vec<String>, strict: bool) -> Result<(), ParseErr> {
+    if strict && original.len() != append.len() {
+        return Err(ParseErr::StrictLinkedLengths(original.len(), append.len()));
+    }
     if original.len() > append.len() {
         original.truncate(append.len());
     }
@@ -633,6 +1855,7 @@ fn merge_lists(original: &mut Vec<String>, append: &mut Vec<String>) {
         input.push(' ');
         input.push_str(&element);
     }
+    Ok(())
 }
 
 /// When the `--memfree` option has been selected, this will attempt to parse the unit's value, multiplying
@@ -654,6 +1877,93 @@ fn parse_memory(input: &str) -> Result<u64, ParseIntError> {
     Ok(result)
 }
 
+/// Parses an integer, which may be written in scientific notation, such as `1e6`.
+fn parse_integer(value: &str) -> Option<i64> {
+    value.parse::<i64>().ok().or_else(|| {
+        let (mantissa, exponent) = value.split_at(value.find(['e', 'E'])?);
+        let exponent = exponent[1..].parse::<u32>().ok()?;
+        mantissa.parse::<i64>().ok()?.checked_mul(10i64.checked_pow(exponent)?)
+    })
+}
+
+/// Parses a duration given in seconds, which may be suffixed with a unit of `ms`, `s`, `m`, `h`, or `d`.
+fn parse_duration(input: &str) -> Option<Duration> {
+    let (value, multiplier) = match input.bytes().last()? {
+        _ if input.ends_with("ms") => (&input[..input.len()-2], 0.001f64),
+        b's' => (&input[..input.len()-1], 1f64),
+        b'm' => (&input[..input.len()-1], 60f64),
+        b'h' => (&input[..input.len()-1], 3_600f64),
+        b'd' => (&input[..input.len()-1], 86_400f64),
+        _    => (input, 1f64)
+    };
+    match value.parse::<f64>() {
+        Ok(seconds) if seconds >= 0f64 => Some(Duration::from_millis((seconds * multiplier * 1000f64) as u64)),
+        _ => None
+    }
+}
+
+/// Parses a cost, or a cost per second, which must be a non-negative number.
+fn parse_cost(input: &str) -> Option<f64> {
+    input.parse::<f64>().ok().filter(|cost| cost.is_finite() && *cost >= 0f64)
+}
+
+/// With `--header :`, the first input is the header, rather than an input.
+fn take_header(inputs: &mut Vec<String>, provenance: &mut Provenance) -> String {
+    if inputs.is_empty() { return String::new() }
+    provenance.remove_first();
+    inputs.remove(0)
+}
+
+/// Maps the name of each column of the `header` to its number, counting from one. If a name is
+/// given to more than one column, it refers to the first.
+fn parse_header(header: &str, separator: &Regex) -> HashMap<String, usize> {
+    let mut names = HashMap::new();
+    for (index, name) in separator.split(header).enumerate().filter(|&(_, name)| !name.is_empty()) {
+        names.entry(name.to_owned()).or_insert(index + 1);
+    }
+    names
+}
+
+/// Parses the regular expression which separates the columns of each input.
+fn parse_colsep(input: &str) -> Result<Regex, ParseErr> {
+    Regex::new(input).map_err(|why| ParseErr::ColsepInvalid(input.to_owned(), why.to_string()))
+}
+
+/// Parses a maximum load average, which is either a positive number, or a percentage of the number
+/// of cores, such as `150%`.
+fn parse_load(input: &str) -> Option<f64> {
+    let load = match input.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok()? * cores::get() as f64 / 100f64,
+        None          => input.parse::<f64>().ok()?
+    };
+    Some(load).filter(|load| load.is_finite() && *load > 0f64)
+}
+
+/// An option which is not supported, noting whether it is an option of GNU Parallel which is not
+/// implemented, and what its nearest equivalent is.
+fn unsupported_option(position: usize, argument: &str, name: &str) -> ParseErr {
+    match gnu::unsupported(name) {
+        Some(equivalent) => ParseErr::GnuUnsupported(name.to_owned(), equivalent),
+        None             => ParseErr::UnknownOption(position, argument.to_owned())
+    }
+}
+
+/// Obtains the value of a short option, which is either attached to the option, as in `-aFILE`,
+/// or is the next argument.
+fn parse_value(argument: &str, next_argument: Option<&String>, index: &mut usize) -> Option<String> {
+    if argument.len() > 2 {
+        Some(argument[2..].to_owned())
+    } else {
+        *index += 1;
+        next_argument.cloned()
+    }
+}
+
+/// Splits each input into the items that are separated by whitespace, as xargs does.
+fn split_items(inputs: Vec<String>) -> Vec<String> {
+    inputs.iter().flat_map(|input| input.split_whitespace()).map(String::from).collect()
+}
+
 /// Parses the jobs value, and optionally increments the index if necessary.
 fn parse_jobs(argument: &str, next_argument: Option<&String>, index: &mut usize) -> Result<usize, ParseErr> {
     let ncores = if argument.len() > 2 {
@@ -666,22 +1976,86 @@ fn parse_jobs(argument: &str, next_argument: Option<&String>, index: &mut usize)
     Ok(ncores)
 }
 
-/// Attempts to open an input argument and adds each line to the `inputs` list.
-fn file_parse<P: AsRef<Path>>(inputs: &mut Vec<String>, path: P, inputs_are_commands: bool)
-    -> Result<(), ParseErr>
+/// Attempts to open an input argument and adds each line to the `inputs` list. If `skipped` is set,
+/// unreadable files and lines which are not valid UTF-8 will be recorded there instead of aborting.
+/// If `provenance` is set, the line number of each input will be recorded there.
+fn file_parse<P: AsRef<Path>>(inputs: &mut Vec<String>, path: P, inputs_are_commands: bool,
+    skipped: &mut Option<Vec<String>>, mut provenance: Option<&mut Provenance>) -> Result<(), ParseErr>
 {
     let path = path.as_ref();
-    let file = fs::File::open(path).map_err(|err| ParseErr::File(FileErr::Open(path.to_owned(), err)))?;
-    for line in BufReader::new(file).lines() {
-        if let Ok(line) = line {
-            if !line.is_empty() && !line.starts_with("#") {
+    let file = match fs::File::open(path) {
+        Ok(file) => file,
+        Err(why) => {
+            if let Some(ref mut skipped) = *skipped {
+                skip_input(skipped, format!("{:?}: {}", path, why));
+                return Ok(());
+            }
+            return Err(match why.kind() {
+                io::ErrorKind::NotFound => ParseErr::InputFileNotFound(path.to_owned()),
+                _                       => ParseErr::File(FileErr::Open(path.to_owned(), why))
+            });
+        }
+    };
+
+    let source = match provenance {
+        Some(ref mut provenance) => provenance.add_source(&path.to_string_lossy()),
+        None => 0
+    };
+
+    let mut invalid_lines = 0;
+    for (id, line) in BufReader::new(file).lines().enumerate() {
+        match line {
+            // Lines which begin with `#` are comments, except for commands that select a shell
+            // with `#!SHELL`, other than on the first line, where it is the file's own interpreter.
+            Ok(line) => if !line.is_empty() && (!line.starts_with("#")
+                || (inputs_are_commands && id != 0 && shell::shebang(&line).is_some()))
+            {
+                if let Some(ref mut provenance) = provenance { provenance.push(source, id + 1); }
                 if inputs_are_commands {
                     inputs.push(quote_command(&line));
                 } else {
                     inputs.push(line);
                 }
+            },
+            Err(ref why) if why.kind() == io::ErrorKind::InvalidData => invalid_lines += 1,
+            Err(why) => match *skipped {
+                Some(ref mut skipped) => {
+                    skip_input(skipped, format!("{:?}: {}", path, why));
+                    break
+                },
+                None => return Err(ParseErr::File(FileErr::Read(path.to_owned(), why)))
             }
         }
     }
+
+    if invalid_lines != 0 {
+        if let Some(ref mut skipped) = *skipped {
+            skip_input(skipped, format!("{:?}: {} of its lines are not valid UTF-8", path, invalid_lines));
+        }
+    }
     Ok(())
 }
+
+/// With `--chunk-file`, replaces each input file with one input for each of its byte ranges of roughly
+/// `size` bytes, recording the ranges within `provenance` for the `{start}` and `{end}` tokens.
+fn chunk_inputs(inputs: Vec<String>, size: u64, provenance: &mut Provenance) -> Result<Vec<String>, ParseErr> {
+    let mut chunked = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let ranges = fs::File::open(&input)
+            .and_then(|file| {
+                let length = file.metadata()?.len();
+                chunks::ranges(file, length, size)
+            })
+            .map_err(|why| ParseErr::File(FileErr::Read(PathBuf::from(&input), why)))?;
+        provenance.chunk(chunked.len(), &ranges);
+        for _ in 0..ranges.len() { chunked.push(input.clone()); }
+    }
+    Ok(chunked)
+}
+
+/// Warns that an input source is being skipped, and records it for the summary printed at exit.
+fn skip_input(skipped: &mut Vec<String>, source: String) {
+    let stderr = io::stderr();
+    let _ = writeln!(stderr.lock(), "parallel: skipping input: {}", source);
+    skipped.push(source);
+}
@@ -0,0 +1,76 @@
+use std::cmp;
+
+/// All of the supported long options, which are used to suggest corrections for mistyped options.
+/// New options must also be added here.
+pub const LONG_OPTIONS: &'static [&'static str] = &[
+    "allow-overlap", "arg-file", "arg-file-sep", "arg-sep", "bar", "basefile", "bf", "binary",
+    "block-cmd", "chunk-file", "class", "cleanup", "cmd-file", "collect", "colsep",
+    "cost-per-second", "ctag", "delay", "discard-output", "dry-run", "env", "epipe-status", "eta",
+    "every", "execute-plan", "expired", "export", "fail-if-empty", "filter-hosts", "gnu-compat", "gpus",
+    "group", "hardware-cores", "header", "help", "host-env", "host-setup", "hostfile-watch", "inner-jobs",
+    "ionice", "joblog", "joblog-8601", "jobs", "keep-going-on-input-errors", "keep-order", "key",
+    "kill-stragglers", "label", "line-buffer", "load", "local-only", "local-slots", "log-commands",
+    "max-args", "max-cost", "max-failures", "max-per-key", "max-procs", "max-runtime", "max-spool", "mem-free",
+    "memfree", "merge-logs", "metrics", "needs", "nice", "no-append", "no-run-if-empty",
+    "non-blocking-stdin", "not-after", "not-before", "notify", "notify-command", "num-cpu-cores", "only-label",
+    "permute-order", "pipe", "plain", "plan", "pool", "progress", "progress-fd", "quiet", "quote",
+    "ramp-up", "record", "record-env", "reduce", "reduce-order", "replay", "replay-jobs",
+    "require-input-exists", "resume", "resume-failed", "retries", "retry-priority", "return", "return-first",
+    "runid", "sandbox", "scratch", "seq", "shard-id", "shebang", "shellquote", "silent", "slf",
+    "speculative-max", "spool-idle", "sshlogin", "sshloginfile", "strict", "systemd", "systemd-property",
+    "tag", "tag-sep", "tempdir", "termseq", "test-deterministic", "tf", "then", "then-jobs",
+    "throttle-file", "timeout", "timeout-col", "tmpdir", "total-jobs", "transfer", "transferfile", "trc", "tty",
+    "tune", "ungroup", "verbose", "version", "webhook", "webhook-failures", "webhook-retries",
+    "webhook-timeout", "weight-col", "where", "xargs",
+];
+
+/// Finds the long option that is nearest to the unknown `option`, if one is similar enough.
+pub fn nearest(option: &str) -> Option<&'static str> {
+    let threshold = cmp::max(2, option.len() / 3);
+    LONG_OPTIONS.iter()
+        .map(|&candidate| (distance(option, candidate), candidate))
+        .filter(|&(distance, _)| distance <= threshold)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// Options are commonly mistaken to accept their value after an `=`, as in `--jobs=4`.
+pub fn split_value(option: &str) -> Option<(&'static str, &str)> {
+    let position = option.find('=')?;
+    LONG_OPTIONS.iter()
+        .find(|&&candidate| candidate == &option[..position])
+        .map(|&candidate| (candidate, &option[position+1..]))
+}
+
+/// The Levenshtein distance, which is the number of single-character edits between two strings.
+fn distance(source: &str, target: &str) -> usize {
+    let target = target.chars().collect::<Vec<char>>();
+    let mut previous = (0..target.len()+1).collect::<Vec<usize>>();
+    let mut current = vec![0; target.len()+1];
+
+    for (i, source_char) in source.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, &target_char) in target.iter().enumerate() {
+            let substitution = previous[j] + if source_char == target_char { 0 } else { 1 };
+            current[j+1] = cmp::min(substitution, cmp::min(previous[j+1], current[j]) + 1);
+        }
+        previous.copy_from_slice(&current);
+    }
+
+    previous[target.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggestions() {
+        assert_eq!(distance("timout", "timeout"), 1);
+        assert_eq!(nearest("timout"), Some("timeout"));
+        assert_eq!(nearest("dryrun"), Some("dry-run"));
+        assert_eq!(nearest("frobnicate"), None);
+        assert_eq!(split_value("jobs=4"), Some(("jobs", "4")));
+        assert_eq!(split_value("jbos=4"), None);
+    }
+}
@@ -0,0 +1,120 @@
+//! With `--throttle-file`, the throttles of a run are read from a file of `NAME=VALUE` lines, once
+//! before the first job, and again each time that SIGHUP is received, so that a long run can be tuned
+//! without restarting it. The names are `load`, `memfree`, `delay`, and `nice`, whose values are given
+//! as they would be on the command line. A throttle which is not named within the file keeps the value
+//! that was given on the command line, so removing its line from the file reverts it. A reload applies
+//! to the jobs which have not started yet, while the jobs which are running are left as they are.
+
+use execute::{nice, signals};
+use input_iterator::Resources;
+use std::fs;
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+use std::thread;
+use std::time::Duration;
+use super::{parse_duration, parse_load, parse_memory};
+
+/// The settings which hold back the launch of the next job.
+#[derive(Clone, Default)]
+pub struct Throttles {
+    pub resources: Resources,
+    pub delay:     Duration,
+    pub nice:      Option<i32>,
+}
+
+/// The throttles which were last reloaded, and the number of times that they have been reloaded.
+static RELOADED: RwLock<Option<Throttles>> = RwLock::new(None);
+static RELOADS: AtomicUsize = AtomicUsize::new(0);
+
+impl Throttles {
+    /// Applies the settings of the throttle file over these throttles, which are those of the command line.
+    pub fn parse(&self, contents: &str) -> Result<Throttles, String> {
+        let mut throttles = self.clone();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') { continue }
+            let invalid = |reason: &str| format!("line {}: {}", number + 1, reason);
+
+            let mut pair = line.splitn(2, '=');
+            let name = pair.next().unwrap().trim();
+            let value = pair.next().ok_or_else(|| invalid(&format!("'{}' has no value", name)))?.trim();
+            match name {
+                "load" => throttles.resources.load = parse_load(value)
+                    .ok_or_else(|| invalid(&format!("'{}' is not a load average", value)))?,
+                "memfree" => throttles.resources.memfree = parse_memory(value)
+                    .map_err(|_| invalid(&format!("'{}' is not an amount of memory", value)))?,
+                "delay" => throttles.delay = parse_duration(value)
+                    .ok_or_else(|| invalid(&format!("'{}' is not a duration", value)))?,
+                "nice" => throttles.nice = Some(value.parse::<i32>().ok()
+                    .filter(|niceness| *niceness >= -20 && *niceness <= 19)
+                    .ok_or_else(|| invalid(&format!("'{}' is not a niceness from -20 to 19", value)))?),
+                _ => return Err(invalid(&format!("'{}' is not one of load, memfree, delay, or nice", name)))
+            }
+        }
+        Ok(throttles)
+    }
+
+    /// Re-reads the throttle file at `path` each time that SIGHUP is received, applying its settings over
+    /// these throttles. If the file can't be read or parsed, the throttles are left as they were.
+    pub fn watch(self, path: String) {
+        signals::catch_hangups();
+        thread::spawn(move || {
+            let mut seen = signals::hangups();
+            loop {
+                thread::sleep(Duration::from_millis(100));
+                let hangups = signals::hangups();
+                if hangups == seen { continue }
+                seen = hangups;
+
+                let stderr = io::stderr();
+                let throttles = match fs::read_to_string(&path).map_err(|why| why.to_string())
+                    .and_then(|contents| self.parse(&contents))
+                {
+                    Ok(throttles) => throttles,
+                    Err(why) => {
+                        let _ = writeln!(stderr.lock(), "parallel: unable to reload the throttles from '{}': {}", path, why);
+                        continue
+                    }
+                };
+
+                match throttles.nice {
+                    Some(niceness) => nice::enable(niceness),
+                    None => nice::disable()
+                }
+                *RELOADED.write().unwrap() = Some(throttles);
+                RELOADS.fetch_add(1, Ordering::SeqCst);
+                let _ = writeln!(stderr.lock(), "parallel: reloaded the throttles from '{}'", path);
+            }
+        });
+    }
+}
+
+/// Obtains the throttles which were reloaded since the `seen` reload, if any, recording this reload as seen.
+pub fn reloaded(seen: &mut usize) -> Option<Throttles> {
+    let reloads = RELOADS.load(Ordering::SeqCst);
+    if reloads == *seen { return None }
+    *seen = reloads;
+    RELOADED.read().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn throttle_file() {
+        let base = Throttles { delay: Duration::from_secs(2), nice: Some(5), ..Throttles::default() };
+        let throttles = base.parse("# tuned for the night\nload = 4\n\nmemfree=1G\ndelay=500ms\n").unwrap();
+        assert_eq!(throttles.resources.load, 4f64);
+        assert_eq!(throttles.resources.memfree, 1_073_741_824);
+        assert_eq!(throttles.delay, Duration::from_millis(500));
+        assert_eq!(throttles.nice, Some(5));
+
+        // Removing a line from the file reverts the throttle to its value from the command line.
+        assert_eq!(base.parse("nice=10\n").unwrap().delay, Duration::from_secs(2));
+        assert_eq!(base.parse("nice=20\n").err().unwrap(), "line 1: '20' is not a niceness from -20 to 19");
+        assert_eq!(base.parse("\nrate=4\n").err().unwrap(), "line 2: 'rate' is not one of load, memfree, delay, or nice");
+        assert_eq!(base.parse("load\n").err().unwrap(), "line 1: 'load' has no value");
+    }
+}
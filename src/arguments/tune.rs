@@ -0,0 +1,60 @@
+use super::errors::ParseErr;
+use disk_buffer::BUFFER_SIZE;
+
+/// The sizes of the internal queues and buffers, which may be adjusted with `--tune` to squeeze
+/// more throughput out of unusual workloads.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tune {
+    /// The number of job signals that may be waiting on the receiver before the job threads block.
+    pub queue:     usize,
+    /// The size, in bytes, of the buffers which copy each job's output to the spool and terminal.
+    pub output:    usize,
+    /// The number of kilobytes of the unprocessed file that are read ahead at a time.
+    pub readahead: usize,
+}
+
+impl Default for Tune {
+    fn default() -> Tune {
+        Tune { queue: 1024, output: BUFFER_SIZE, readahead: BUFFER_SIZE / 1024 }
+    }
+}
+
+impl Tune {
+    /// Parses a comma-separated list of `KEY=VALUE` pairs, such as `queue=1024,readahead=64`,
+    /// where keys which are not given retain their current value.
+    pub fn parse(&mut self, value: &str) -> Result<(), ParseErr> {
+        let invalid = |reason: &str| ParseErr::TuneInvalid(value.to_owned(), reason.to_owned());
+        for pair in value.split(',') {
+            let mut pair = pair.splitn(2, '=');
+            let key = pair.next().unwrap().trim();
+            let setting = pair.next().ok_or_else(|| invalid(&format!("'{}' has no value", key)))?;
+            let setting = match setting.trim().parse::<usize>() {
+                Ok(setting) if setting != 0 => setting,
+                _ => return Err(invalid(&format!("'{}' is not a positive number", setting)))
+            };
+
+            match key {
+                "queue"     => self.queue = setting,
+                "output"    => self.output = setting,
+                "readahead" => self.readahead = setting,
+                _ => return Err(invalid(&format!("'{}' is not one of queue, output, or readahead", key)))
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tune_parsing() {
+        let mut tune = Tune::default();
+        tune.parse("queue=64, readahead=128").unwrap();
+        assert_eq!(tune, Tune { queue: 64, output: BUFFER_SIZE, readahead: 128 });
+        assert!(tune.parse("queue=0").is_err());
+        assert!(tune.parse("ready=1").is_err());
+        assert!(tune.parse("output").is_err());
+    }
+}
@@ -1,38 +1,26 @@
+mod space;
+
+pub use self::space::SpaceWriter;
+
 use std::path::{Path, PathBuf};
 use std::io::{Error, Read};
 
-/// Controls the size of the buffers for reading/writing to files.
+/// Controls the default size of the buffers for reading/writing to files.
 pub const BUFFER_SIZE: usize = 8 * 1024; // 8K seems to be the best buffer size.
 
-pub trait DiskBufferTrait {
-    /// Clears the buffered memory and resets the capacity.
-    fn clear(&mut self);
-
-    /// Obtain a slice of only the useful information.
-    fn get_ref(&self) -> &[u8];
-
-    /// Returns true if the buffer does not contain any contents.
-    fn is_empty(&self) -> bool;
-}
-
 /// A `DiskBufferReader` contains the `buffer` method.
 pub struct DiskBufferReader<IO: Read> {
-    pub data:     [u8; BUFFER_SIZE],
+    pub data:     Vec<u8>,
     pub capacity: usize,
     pub file:     IO,
     pub path:     PathBuf,
 }
 
-impl<IO: Read> DiskBufferTrait for DiskBufferReader<IO> {
-    fn clear(&mut self) { self.capacity = 0; }
-    fn get_ref(&self) -> &[u8] { &self.data[0..self.capacity] }
-    fn is_empty(&self) -> bool { self.capacity == 0 }
-}
-
 impl<IO: Read> DiskBufferReader<IO> {
-    pub fn new<P: AsRef<Path>>(path: P, file: IO) -> DiskBufferReader<IO> {
+    /// Creates a reader which buffers up to `size` bytes of the file at a time.
+    pub fn new<P: AsRef<Path>>(path: P, file: IO, size: usize) -> DiskBufferReader<IO> {
         DiskBufferReader {
-            data:     [b'\0'; BUFFER_SIZE],
+            data:     vec![b'\0'; size],
             capacity: 0,
             file:     file,
             path:     path.as_ref().to_owned(),
@@ -65,7 +53,7 @@ mod tests {
     fn test_disk_buffer_reader_simple() {
         let file = include_bytes!("../../tests/buffer.dat");
         let mut disk_buffer_reader = DiskBufferReader::new(Path::new("tests/buffer.dat"),
-            File::open("tests/buffer.dat").expect("unable to open test data"));
+            File::open("tests/buffer.dat").expect("unable to open test data"), BUFFER_SIZE);
         let _ = disk_buffer_reader.buffer(0);
         assert_eq!(&file[0..BUFFER_SIZE], &disk_buffer_reader.data[..]);
         let _ = disk_buffer_reader.buffer(0);
@@ -78,7 +66,7 @@ mod tests {
     fn test_disk_buffer_reader_byte_shifting() {
         let file = include_bytes!("../../tests/buffer.dat");
         let mut disk_buffer_reader = DiskBufferReader::new(Path::new("tests/buffer.dat"),
-            File::open("tests/buffer.dat").expect("unable to open test data"));
+            File::open("tests/buffer.dat").expect("unable to open test data"), BUFFER_SIZE);
         let _ = disk_buffer_reader.buffer(0);
         assert_eq!(&file[0..BUFFER_SIZE], &disk_buffer_reader.data[..]);
         let _ = disk_buffer_reader.buffer(BUFFER_SIZE/2);
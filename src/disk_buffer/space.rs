@@ -0,0 +1,93 @@
+use std::io::{self, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// The `errno` which signals that the filesystem has no space left.
+const ENOSPC: i32 = 28;
+
+/// How long to wait for space to be freed before the write fails.
+pub const SPACE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Wraps a file which is written to within the tempdir, so that writes will wait for space to be
+/// freed when the filesystem is full, rather than failing mid-write. The thread that is writing
+/// will not dispatch further jobs while it waits, so a full tempdir pauses the run until either
+/// space is freed or `SPACE_TIMEOUT` elapses, upon which the original error is returned.
+pub struct SpaceWriter<'a, W: Write> {
+    inner: W,
+    path:  &'a Path,
+}
+
+impl<'a, W: Write> SpaceWriter<'a, W> {
+    pub fn new(inner: W, path: &'a Path) -> SpaceWriter<'a, W> {
+        SpaceWriter { inner, path }
+    }
+
+    fn retry<T, F: FnMut(&mut W) -> io::Result<T>>(&mut self, needed: usize, mut action: F) -> io::Result<T> {
+        let mut waiting: Option<Instant> = None;
+        loop {
+            match action(&mut self.inner) {
+                Err(ref why) if why.raw_os_error() == Some(ENOSPC) => {
+                    let path = self.path;
+                    let started = *waiting.get_or_insert_with(|| {
+                        let stderr = io::stderr();
+                        let _ = writeln!(stderr.lock(), "parallel: no space left on device while writing to {:?}: \
+                            {} more bytes are needed. Waiting up to {} seconds for space to be freed.",
+                            path, needed, SPACE_TIMEOUT.as_secs());
+                        Instant::now()
+                    });
+                    if started.elapsed() >= SPACE_TIMEOUT {
+                        return action(&mut self.inner);
+                    }
+                    thread::sleep(Duration::from_secs(1));
+                },
+                result => {
+                    if waiting.is_some() && result.is_ok() {
+                        let stderr = io::stderr();
+                        let _ = writeln!(stderr.lock(), "parallel: space was freed for {:?}, resuming", self.path);
+                    }
+                    return result;
+                }
+            }
+        }
+    }
+}
+
+impl<'a, W: Write> Write for SpaceWriter<'a, W> {
+    fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+        self.retry(buffer.len(), |inner| inner.write(buffer))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.retry(0, |inner| inner.flush())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    /// Fails with `ENOSPC` for the first `failures` writes.
+    struct Full { failures: usize, written: Vec<u8> }
+
+    impl Write for Full {
+        fn write(&mut self, buffer: &[u8]) -> io::Result<usize> {
+            if self.failures != 0 {
+                self.failures -= 1;
+                return Err(io::Error::from_raw_os_error(ENOSPC));
+            }
+            self.written.extend_from_slice(buffer);
+            Ok(buffer.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> { Ok(()) }
+    }
+
+    #[test]
+    fn waits_for_space() {
+        let mut writer = SpaceWriter::new(Full { failures: 1, written: Vec::new() }, Path::new("test"));
+        writer.write_all(b"input\n").unwrap();
+        assert_eq!(writer.inner.written, b"input\n");
+    }
+}
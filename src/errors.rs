@@ -0,0 +1,157 @@
+use arguments::FileErr;
+use arguments::errors::ParseErr;
+use tokenizer::TokenErr;
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// The exit status will be the number of jobs that failed, up to this limit.
+pub const MAX_FAILED_JOBS: i32 = 249;
+
+/// The run was stopped early by `--max-runtime` or `--max-failures`.
+pub const EXIT_BUDGET: i32 = 250;
+
+/// Inputs could not be read from a file or the standard input.
+pub const EXIT_INPUT: i32 = 251;
+/// A file which the program writes to, such as the tempdir or joblog, could not be created.
+pub const EXIT_FILES: i32 = 252;
+/// The command template contains an invalid placeholder.
+pub const EXIT_TEMPLATE: i32 = 253;
+/// The arguments supplied to the program were invalid.
+pub const EXIT_USAGE: i32 = 254;
+
+/// Under `--gnu-compat`, every error exits with this status, as with GNU Parallel.
+pub const EXIT_GNU_ERROR: i32 = 255;
+
+/// Set by `--gnu-compat`, once it has been parsed.
+static GNU_EXIT_STATUS: AtomicBool = AtomicBool::new(false);
+
+/// Causes errors to exit with `EXIT_GNU_ERROR`, rather than with a status specific to the error.
+pub fn use_gnu_exit_status() {
+    GNU_EXIT_STATUS.store(true, Ordering::Relaxed);
+}
+
+/// The status of an error, unless GNU Parallel's exit status was requested.
+pub fn status_of(code: i32) -> i32 {
+    if GNU_EXIT_STATUS.load(Ordering::Relaxed) { EXIT_GNU_ERROR } else { code }
+}
+
+/// Exits with the status of an error, unless GNU Parallel's exit status was requested.
+pub fn exit_with(code: i32) -> ! {
+    exit(status_of(code))
+}
+
+/// Opens a file which the program writes to, exiting with `EXIT_FILES` if it could not be opened.
+pub fn open_or_exit(options: &fs::OpenOptions, path: &Path) -> fs::File {
+    options.open(path).unwrap_or_else(|why| {
+        let stderr = io::stderr();
+        let _ = writeln!(stderr.lock(), "parallel: {}", FileErr::Open(path.to_owned(), why));
+        exit_with(EXIT_FILES);
+    })
+}
+
+/// All of the errors which prevent the program from executing jobs.
+#[derive(Debug)]
+pub enum Error {
+    /// The program's arguments could not be parsed.
+    Parse(ParseErr),
+    /// The files of `--basefile` could not be copied to the given host.
+    Basefile(String, io::Error),
+    /// The home directory, which contains the default tempdir, could not be found.
+    NoHome,
+    /// The plan of the run could not be written.
+    Plan(PathBuf, io::Error),
+    /// The record of the run could not be written.
+    Record(PathBuf, io::Error),
+    /// The tempdir could not be created.
+    Tempdir(PathBuf, io::Error),
+    /// The tempdir's path is not valid UTF-8.
+    TempdirPath(PathBuf),
+    /// The file containing the unprocessed inputs could not be opened or read.
+    Unprocessed(FileErr),
+    /// The command template could not be tokenized.
+    Token(TokenErr),
+}
+
+impl Error {
+    /// The exit status that the program will exit with when this error occurs.
+    pub fn exit_code(&self) -> i32 {
+        match *self {
+            Error::Parse(ParseErr::File(_)) | Error::Parse(ParseErr::InputFileNotFound(_)) |
+                Error::Parse(ParseErr::RedirFile(_)) | Error::Parse(ParseErr::StrictSkipped(_)) => EXIT_INPUT,
+            Error::Parse(ParseErr::WorkDir(..)) => EXIT_FILES,
+            Error::Parse(_) => EXIT_USAGE,
+            Error::Basefile(..) | Error::NoHome | Error::Plan(..) | Error::Record(..) | Error::Tempdir(..) | Error::TempdirPath(_) | Error::Unprocessed(_) => EXIT_FILES,
+            Error::Token(_) => EXIT_TEMPLATE,
+        }
+    }
+
+    /// Prints the error to the standard error and exits with the error's exit status.
+    pub fn handle(self) -> ! {
+        let stderr = io::stderr();
+        let _ = writeln!(stderr.lock(), "parallel: {}", self);
+        if self.exit_code() == EXIT_USAGE {
+            let stdout = io::stdout();
+            let _ = stdout.lock().write(b"For help on command-line usage, execute `parallel -h`\n");
+        }
+        exit_with(self.exit_code());
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Parse(ref why)             => write!(f, "parsing error: {}", why),
+            Error::Basefile(ref host, ref why) => write!(f, "unable to copy basefiles to {}: {}", host, why),
+            Error::NoHome                     => write!(f, "unable to open home directory"),
+            Error::Plan(ref path, ref why)    => write!(f, "unable to write plan to {:?}: {}", path, why),
+            Error::Record(ref path, ref why)  => write!(f, "unable to write record to {:?}: {}", path, why),
+            Error::Tempdir(ref path, ref why) => write!(f, "unable to create tempdir {:?}: {}", path, why),
+            Error::TempdirPath(ref path)      => write!(f, "tempdir path, {:?}, is invalid", path),
+            Error::Unprocessed(ref why)       => write!(f, "unprocessed file: {}", why),
+            Error::Token(ref why)             => write!(f, "{}", why),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match *self {
+            Error::Parse(ref why)       => Some(why),
+            Error::Basefile(_, ref why) => Some(why),
+            Error::Plan(_, ref why)     => Some(why),
+            Error::Record(_, ref why)   => Some(why),
+            Error::Tempdir(_, ref why)  => Some(why),
+            Error::Unprocessed(ref why) => Some(why),
+            Error::Token(ref why)       => Some(why),
+            _                           => None
+        }
+    }
+}
+
+impl From<ParseErr> for Error {
+    fn from(why: ParseErr) -> Error { Error::Parse(why) }
+}
+
+impl From<TokenErr> for Error {
+    fn from(why: TokenErr) -> Error { Error::Token(why) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[allow(clippy::assertions_on_constants)]
+    fn exit_codes() {
+        assert_eq!(Error::Parse(ParseErr::NoArguments).exit_code(), EXIT_USAGE);
+        assert_eq!(Error::Parse(ParseErr::RedirFile(PathBuf::from("a"))).exit_code(), EXIT_INPUT);
+        assert_eq!(Error::Token(TokenErr::OutOfBounds).exit_code(), EXIT_TEMPLATE);
+        assert!(MAX_FAILED_JOBS < EXIT_BUDGET && EXIT_BUDGET < EXIT_INPUT);
+    }
+}
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 const DOUBLE: u8 = 1;
 const SINGLE: u8 = 2;
 const BACK:   u8 = 4;
@@ -81,6 +83,27 @@ impl<'a> Iterator for ArgumentSplitter<'a> {
     }
 }
 
+/// Removes the quotes and backslashes from a word, as a shell would, for commands which are executed
+/// without one. Backslashes are retained within single quotes.
+pub fn unquote(word: &str) -> Cow<'_, str> {
+    if !word.bytes().any(|byte| byte == b'\\' || byte == b'"' || byte == b'\'') {
+        return Cow::Borrowed(word);
+    }
+
+    let mut output = String::with_capacity(word.len());
+    let (mut single, mut double, mut back) = (false, false, false);
+    for character in word.chars() {
+        match character {
+            _ if back                  => { back = false; output.push(character); },
+            '\\' if !single           => back = true,
+            '\'' if !double            => single = !single,
+            '"' if !single             => double = !double,
+            _                          => output.push(character),
+        }
+    }
+    Cow::Owned(output)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -121,4 +144,13 @@ mod tests {
         let expected = vec!["echo", "'one two \"three four\"'", "\"five six 'seven eight'\""];
         compare(input, expected);
     }
+
+    #[test]
+    fn unquoting() {
+        assert_eq!(unquote("plain"), "plain");
+        assert_eq!(unquote("a\\ b"), "a b");
+        assert_eq!(unquote("'one \\ \"two\"'"), "one \\ \"two\"");
+        assert_eq!(unquote("\"it's\""), "it's");
+        assert_eq!(unquote("FOO=\"a b\""), "FOO=a b");
+    }
 }
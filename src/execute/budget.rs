@@ -0,0 +1,163 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+use time;
+
+const WITHIN_BUDGET: usize = 0;
+const RUNTIME_EXCEEDED: usize = 1;
+const FAILURES_EXCEEDED: usize = 2;
+const COST_EXCEEDED: usize = 3;
+
+/// The limit of a `Budget` which stopped the run.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Exceeded {
+    /// The run exceeded the `--max-runtime` duration.
+    Runtime(Duration),
+    /// More jobs failed than `--max-failures` permits.
+    Failures(usize),
+    /// The cost of the jobs exceeded `--max-cost`.
+    Cost(f64),
+}
+
+impl fmt::Display for Exceeded {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Exceeded::Runtime(runtime) => {
+                let runtime = runtime.as_secs() as f64 + runtime.subsec_nanos() as f64 / 1_000_000_000f64;
+                write!(f, "the maximum runtime of {}s was exceeded", runtime)
+            },
+            Exceeded::Failures(failures) => {
+                write!(f, "more than the maximum of {} jobs failed", failures)
+            },
+            Exceeded::Cost(cost) => write!(f, "the maximum cost of {} was exceeded", cost),
+        }
+    }
+}
+
+/// The limits of the whole run, set by `--max-runtime`, `--max-failures`, and `--max-cost`. Once a
+/// limit has been exceeded, no further jobs are executed, and with `--kill-stragglers`, jobs which are
+/// still running at the end of the maximum runtime are killed. With `--cost-per-second`, the cost of
+/// the run is accumulated from the runtime of each job.
+pub struct Budget {
+    runtime:      Option<Duration>,
+    deadline:     Option<Instant>,
+    kill:         bool,
+    max_failures: Option<usize>,
+    rate:         Option<f64>,
+    max_cost:     Option<f64>,
+    failures:     AtomicUsize,
+    /// The total runtime of the jobs, in microseconds.
+    job_runtime:  AtomicU64,
+    exceeded:     AtomicUsize,
+}
+
+impl Budget {
+    pub fn new(runtime: Option<Duration>, kill: bool, max_failures: Option<usize>, rate: Option<f64>,
+        max_cost: Option<f64>) -> Budget
+    {
+        Budget {
+            runtime,
+            deadline: runtime.map(|runtime| Instant::now() + runtime),
+            kill,
+            max_failures,
+            rate,
+            max_cost,
+            failures: AtomicUsize::new(0),
+            job_runtime: AtomicU64::new(0),
+            exceeded: AtomicUsize::new(WITHIN_BUDGET),
+        }
+    }
+
+    /// Whether a limit has been exceeded, in which case the remaining inputs are skipped.
+    pub fn exhausted(&self) -> bool {
+        if self.exceeded.load(Ordering::SeqCst) != WITHIN_BUDGET { return true }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                self.exceed(RUNTIME_EXCEEDED);
+                true
+            },
+            _ => false
+        }
+    }
+
+    /// Records the outcome and runtime of a job, where a job which could not be executed is also a failure.
+    pub fn record(&self, failed: bool, runtime: time::Duration) {
+        let runtime = runtime.num_microseconds().unwrap_or(0).max(0) as u64;
+        self.job_runtime.fetch_add(runtime, Ordering::SeqCst);
+        if let (Some(cost), Some(max)) = (self.cost(), self.max_cost) {
+            if cost > max { self.exceed(COST_EXCEEDED); }
+        }
+
+        if !failed { return }
+        let failures = self.failures.fetch_add(1, Ordering::SeqCst) + 1;
+        if self.max_failures.map_or(false, |max| failures > max) {
+            self.exceed(FAILURES_EXCEEDED);
+        }
+    }
+
+    /// The total runtime of the jobs that have completed, in seconds.
+    pub fn job_runtime(&self) -> f64 {
+        self.job_runtime.load(Ordering::SeqCst) as f64 / 1_000_000f64
+    }
+
+    /// The cost of the jobs that have completed, if `--cost-per-second` was given.
+    pub fn cost(&self) -> Option<f64> {
+        self.rate.map(|rate| self.job_runtime() * rate)
+    }
+
+    /// With `--kill-stragglers`, shortens the timeout of a job so that it is killed at the end of
+    /// the maximum runtime. A timeout of zero denotes that the job has no timeout.
+    pub fn timeout(&self, timeout: Duration) -> Duration {
+        match self.deadline {
+            Some(deadline) if self.kill => {
+                let now = Instant::now();
+                let remaining = if deadline > now { deadline - now } else { Duration::from_millis(1) };
+                if timeout == Duration::from_millis(0) || remaining < timeout { remaining } else { timeout }
+            },
+            _ => timeout
+        }
+    }
+
+    /// The limit which stopped the run, if any. The first limit to be exceeded is reported.
+    pub fn exceeded(&self) -> Option<Exceeded> {
+        match self.exceeded.load(Ordering::SeqCst) {
+            RUNTIME_EXCEEDED  => self.runtime.map(Exceeded::Runtime),
+            FAILURES_EXCEEDED => self.max_failures.map(Exceeded::Failures),
+            COST_EXCEEDED     => self.max_cost.map(Exceeded::Cost),
+            _                 => None
+        }
+    }
+
+    fn exceed(&self, limit: usize) {
+        let _ = self.exceeded.compare_exchange(WITHIN_BUDGET, limit, Ordering::SeqCst, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_limits() {
+        let budget = Budget::new(None, false, Some(1), None, None);
+        budget.record(true, time::Duration::zero());
+        budget.record(false, time::Duration::zero());
+        assert!(!budget.exhausted());
+        budget.record(true, time::Duration::zero());
+        assert!(budget.exhausted());
+        assert_eq!(budget.exceeded(), Some(Exceeded::Failures(1)));
+
+        let budget = Budget::new(None, false, None, Some(0.5), Some(2.0));
+        budget.record(false, time::Duration::seconds(4));
+        assert_eq!(budget.cost(), Some(2.0));
+        assert!(!budget.exhausted());
+        budget.record(false, time::Duration::milliseconds(500));
+        assert!(budget.exhausted());
+        assert_eq!(budget.exceeded(), Some(Exceeded::Cost(2.0)));
+
+        let budget = Budget::new(Some(Duration::from_millis(0)), true, None, None, None);
+        assert!(budget.exhausted());
+        assert_eq!(budget.exceeded(), Some(Exceeded::Runtime(Duration::from_millis(0))));
+        assert_eq!(budget.timeout(Duration::from_secs(5)), Duration::from_millis(1));
+    }
+}
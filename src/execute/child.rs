@@ -1,31 +1,104 @@
-use arguments::QUIET_MODE;
+use arguments::{LINE_BUFFER, QUIET_MODE, TTY, UNGROUP};
+use std::io::{self, Write};
 use std::process::Child;
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::SyncSender;
 use std::time::Duration;
 use wait_timeout::ChildExt;
 use time::{get_time, Timespec};
-use super::signals;
-use super::pipe::disk::output as pipe_output;
-use super::pipe::disk::State;
+use super::deterministic;
+use super::signals::{self, TermSeq};
+use super::pipe::disk::{self, State};
+use super::return_first::ReturnFirst;
+use super::speculate::Speculation;
+use super::spool::Spool;
+use super::tag::Tag;
+use tokenizer::column;
 
-/// Receives a `Child` and handles the child according. If a `timeout` is specified then the process will be killed
-/// if it exceeds the `timeout` value. Job stats are also gathered in case the `--joblog` parameter was supplied.
-pub fn handle_child(mut child: Child, output: &Sender<State>, flags: u16, job_id: usize, input: String,
-    has_timeout: bool, timeout: Duration, base: &str, buffer: &mut [u8]) -> (Timespec, Timespec, i32, i32)
+/// Obtains the timeout of the current job. If a `column` was designated with `--timeout-col`, the
+/// number of seconds stored within that column of the input will be used, falling back to the
+/// global `timeout` when the column is missing or invalid.
+pub fn job_timeout(input: &str, job_id: usize, column_no: usize, timeout: Duration) -> Duration {
+    if column_no == 0 { return timeout }
+    match column(input, column_no).map(|value| (value, value.parse::<f64>())) {
+        Some((_, Ok(seconds))) if seconds >= 0f64 => Duration::from_millis((seconds * 1000f64) as u64),
+        Some((value, _)) => {
+            let stderr = io::stderr();
+            let _ = writeln!(stderr.lock(), "parallel: job {}: invalid timeout column value, '{}', using default",
+                job_id + 1, value);
+            timeout
+        },
+        None => timeout
+    }
+}
+
+/// Receives a `Child` and handles the child according. If a `timeout` is specified then the process will be
+/// terminated with the signals of the `termseq` if it exceeds the `timeout` value. Job stats are also
+/// gathered in case the `--joblog` parameter was supplied.
+/// The completion of the job is signaled once the child has exited, and with `--return-first`, the output of
+/// the job is discarded unless it was one of the first jobs to succeed. With `--speculative-max`, the
+/// `speculation` is given whether this run is a duplicate, and `None` is returned without signaling
+/// completion if the other run of the job completed first. If the job may be attempted again with
+/// `--retries`, a failed job does not signal its completion, as its output will be replaced. With `--tag`,
+/// each line of the output is prefixed with the `tag` of the job.
+pub fn handle_child(mut child: Child, output: &SyncSender<State>, flags: u16, job_id: usize, input: String,
+    has_timeout: bool, timeout: Duration, termseq: &TermSeq, base: &str, buffer: &mut [u8], membuffer: &mut [u8],
+    spool: Option<&Spool>, first: Option<&ReturnFirst>, retry: bool, speculation: Option<(&Speculation, bool)>,
+    tag: Option<&Tag>) -> Option<(Timespec, Timespec, i32, i32)>
 {
     let start_time = get_time();
-    if has_timeout && child.wait_timeout(timeout).unwrap().is_none() {
-        let _ = child.kill();
-        pipe_output(&mut child, job_id, input, output, flags & QUIET_MODE != 0, base, buffer);
-        (start_time, get_time(), -1, 15)
+    let (state, result) = if has_timeout && child.wait_timeout(timeout).unwrap().is_none() {
+        let signal = signals::terminate(&mut child, termseq);
+        deterministic::wait(job_id);
+        let state = pipe_output(&mut child, job_id, input, flags, base, buffer, membuffer, spool, tag);
+        (state, (start_time, get_time(), -1, signal))
     } else {
-        pipe_output(&mut child, job_id, input, output, flags & QUIET_MODE != 0, base, buffer);
-        match child.wait() {
+        deterministic::wait(job_id);
+        let state = pipe_output(&mut child, job_id, input, flags, base, buffer, membuffer, spool, tag);
+        let result = match child.wait() {
             Ok(status) => match status.code() {
                 Some(exit) => (start_time, get_time(), exit, 0),
                 None       => (start_time, get_time(), -1, signals::get(status))
             },
             Err(_) => (start_time, get_time(), -1, 0),
-        }
+        };
+        (state, result)
+    };
+    let result = match deterministic::times(job_id) {
+        Some((start_time, end_time)) => (start_time, end_time, result.2, result.3),
+        None => result
+    };
+
+    if retry && (result.2 != 0 || result.3 != 0) { return Some(result) }
+
+    if let Some((speculation, duplicate)) = speculation {
+        if !speculation.finish(job_id, duplicate, result.1 - result.0) { return None }
+    }
+
+    let state = match (state, first) {
+        (State::Completed(id, name), Some(first)) if !first.record(result.2, result.3) => State::Discarded(id, name),
+        (state, _) => state
+    };
+    let _ = output.send(state);
+    deterministic::complete(job_id);
+    Some(result)
+}
+
+/// Collects the output of the child, unless the child is attached to the terminal, or its output
+/// is printed as it is written, with `--ungroup` or `--line-buffer`. With `--tag`, each line of the
+/// output is prefixed with the input of the job. With `--discard-output`, neither output of the
+/// child was captured, so the job has no output files, and is signaled as discarded.
+fn pipe_output(child: &mut Child, job_id: usize, input: String, flags: u16, base: &str, buffer: &mut [u8],
+    membuffer: &mut [u8], spool: Option<&Spool>, tag: Option<&Tag>) -> State
+{
+    let prefix = tag.map(|tag| tag.prefix(job_id, &input));
+    let prefix = prefix.as_ref().map(|prefix| &prefix[..]);
+    if flags & (TTY | UNGROUP) != 0 {
+        disk::attached(job_id, input, base, buffer)
+    } else if child.stdout.is_none() && child.stderr.is_none() {
+        State::Discarded(job_id, input)
+    } else if flags & LINE_BUFFER != 0 {
+        disk::line_buffered(child, job_id, input, flags & QUIET_MODE != 0, base, buffer, membuffer, prefix)
+    } else {
+        disk::output(child, job_id, input, flags & QUIET_MODE != 0, base, buffer, membuffer, spool, prefix)
     }
 }
@@ -0,0 +1,38 @@
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+
+/// With `--collect`, each job writes its result to `{#}.part` within the current directory, and
+/// the parts are appended to a single file in the order of the inputs, as soon as every job before
+/// them has completed. Each part is removed once it has been collected.
+pub struct Collector {
+    file: BufWriter<File>,
+}
+
+impl Collector {
+    pub fn new(file: File) -> Collector {
+        Collector { file: BufWriter::new(file) }
+    }
+
+    /// The path of the part that is written by the job `id`, which is numbered from one as with `{#}`.
+    pub fn part(id: usize) -> String { format!("{}.part", id + 1) }
+
+    /// Appends the part of the job `id` to the file, and removes the part. Returns `false` if the
+    /// job did not write a part.
+    pub fn collect(&mut self, id: usize) -> io::Result<bool> {
+        let path = Collector::part(id);
+        let mut part = match File::open(&path) {
+            Ok(part) => part,
+            Err(ref why) if why.kind() == io::ErrorKind::NotFound => return Ok(false),
+            Err(why) => return Err(why)
+        };
+        io::copy(&mut part, &mut self.file)?;
+        self.file.flush()?;
+        fs::remove_file(&path)?;
+        Ok(true)
+    }
+
+    /// Removes the part of a job whose output was discarded, if it wrote one.
+    pub fn discard(&self, id: usize) {
+        let _ = fs::remove_file(Collector::part(id));
+    }
+}
@@ -0,0 +1,84 @@
+//! With `--return`, the files of each remote job are copied back into the working directory under the
+//! path that their template expanded to, so two jobs whose templates expand to the same path overwrite
+//! each other, in whichever order they complete. The paths of every job are expanded before any job is
+//! executed, so that such collisions are reported up front, rather than discovered in the results.
+
+use input_iterator::{Excluded, InputIterator, Provenance};
+use numtoa::NumToA;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read};
+use super::command::ParallelCommand;
+use tokenizer::Token;
+
+/// A path which the templates of two different jobs expand to.
+pub struct Collision {
+    pub path:   String,
+    /// The numbers of the job which named the path first, and of the job which named it again.
+    pub first:  usize,
+    pub second: usize,
+}
+
+impl fmt::Display for Collision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "jobs {} and {} both return '{}', so one would overwrite the other", self.first, self.second, self.path)
+    }
+}
+
+/// Expands the `templates` for every input which is to be executed, returning each path which more than
+/// one job expands to. Templates with a placeholder which is only known once the job is executed, such as
+/// `{%}` or `{host}`, are passed over, as their paths can't be known beforehand.
+pub fn find<IO: Read>(templates: &[Vec<Token>], inputs: &InputIterator<IO>, provenance: &Provenance,
+    excluded: &Excluded) -> io::Result<Vec<Collision>>
+{
+    let templates = templates.iter().filter(|template| planned(template)).collect::<Vec<_>>();
+    let mut collisions = Vec::new();
+    if templates.is_empty() { return Ok(collisions) }
+
+    let mut paths: HashMap<String, usize> = HashMap::new();
+    let mut total_buffer = [0u8; 20];
+    let mut job_buffer = [0u8; 20];
+    let total_indice = inputs.total_arguments.numtoa(10, &mut total_buffer);
+    inputs.each(|job_id, input| {
+        if excluded.contains(&job_id) { return }
+        let job_indice = (job_id + 1).numtoa(10, &mut job_buffer);
+        for template in &templates {
+            let mut path = String::new();
+            ParallelCommand {
+                job_no:    &job_buffer[job_indice..],
+                job_total: &total_buffer[total_indice..],
+                source:    provenance.get(job_id),
+                range:     provenance.range(job_id),
+                ..ParallelCommand::template(template, input, 0)
+            }.build_arguments(&mut path);
+            if path.is_empty() { continue }
+
+            match paths.entry(path) {
+                Entry::Occupied(entry) => if *entry.get() != job_id {
+                    collisions.push(Collision { path: entry.key().clone(), first: entry.get() + 1, second: job_id + 1 });
+                },
+                Entry::Vacant(entry) => { entry.insert(job_id); }
+            }
+        }
+    })?;
+    Ok(collisions)
+}
+
+/// Whether every placeholder of the template is known before the job is executed.
+fn planned(template: &[Token]) -> bool {
+    !template.iter().any(|token| matches!(*token, Token::Host | Token::Now(_) | Token::Scratch | Token::Slot))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    #[test]
+    fn planned_templates() {
+        assert!(planned(&[Token::BaseAndExt, Token::Argument(Cow::Borrowed(".out"))]));
+        assert!(!planned(&[Token::Slot, Token::Argument(Cow::Borrowed(".out"))]));
+        assert!(!planned(&[Token::Host]));
+    }
+}
@@ -1,29 +1,153 @@
+use std::borrow::Cow;
 use std::convert::AsRef;
 use std::ffi::OsStr;
+use std::fs::{File, OpenOptions};
 use std::io::{self, Write};
-use std::process::{Child, Command, Stdio};
+use std::process::{Child, Command, ExitStatus, Stdio};
 use std::str;
+use std::sync::OnceLock;
 use arguments;
+use numtoa::NumToA;
+use time;
 use tokenizer::*;
-use super::argument_splitter::ArgumentSplitter;
+use shell;
+use super::{environment, ionice, nice, remote};
+use super::argument_splitter::{unquote, ArgumentSplitter};
+use super::remote::Host;
+
+/// The `/dev/null` which the output of jobs is discarded into, which is opened once for the run,
+/// rather than once for each job.
+static DEV_NULL: OnceLock<Option<File>> = OnceLock::new();
 
 pub enum CommandErr {
-    IO(io::Error)
+    IO(io::Error),
+    /// With `--then`, the stage of the given number, counting from one, did not succeed.
+    Stage(usize, ExitStatus),
 }
 
-/// If no placeholder tokens are in use, then the input will be appended at the end of the the command.
-pub fn append_argument(arguments: &mut String, command_template: &[Token], input: &str) {
-    // Check to see if any placeholder tokens are in use.
-    let placeholder_exists = command_template.iter().any(|x| match *x {
-        Token::BaseAndExt | Token::Basename | Token::Dirname | Token::Job | Token::Placeholder |
-        Token::RemoveExtension | Token::RemoveSuffix(_) | Token::Slot => true,
-        _ => false,
-    });
-
+/// If no placeholder tokens are in use, then the input will be appended at the end of the the command,
+/// unless `--no-append` was given. If the command will be interpreted by a shell, each word of the
+/// input is `quote`d, so that the input is split into the same arguments as it would be without one.
+pub fn append_argument(arguments: &mut String, command_template: &[Token], input: &str, flags: u16, quote: bool) {
     // If no placeholder tokens are in use, the user probably wants to infer one.
-    if !placeholder_exists {
-        arguments.push(' ');
-        arguments.push_str(input);
+    if flags & arguments::NO_APPEND == 0 && !lint::has_placeholder(command_template) {
+        if quote {
+            for word in input.split_whitespace() {
+                arguments.push(' ');
+                push_quoted(arguments, word);
+            }
+        } else {
+            // When the inputs are the commands, there is nothing to separate them from.
+            if !arguments.is_empty() { arguments.push(' '); }
+            arguments.push_str(input);
+        }
+    }
+}
+
+/// Appends the word within single quotes, unless it consists only of characters that no shell interprets.
+pub fn push_quoted(arguments: &mut String, word: &str) {
+    let safe = |byte: u8| byte.is_ascii_alphanumeric() || b"_-./:=,+%".contains(&byte);
+    if word.bytes().all(safe) {
+        arguments.push_str(word);
+    } else {
+        arguments.push('\'');
+        arguments.push_str(&word.replace('\'', "'\\''"));
+        arguments.push('\'');
+    }
+}
+
+/// The identity of a job, which is exported to its environment as `PARALLEL_SEQ`, `PARALLEL_SLOT`,
+/// and `PARALLEL_TOTAL`, so that programs may identify themselves without placeholders. The scratch
+/// directory of the slot, if there is one, is exported as `PARALLEL_SCRATCH`, and the GPUs assigned
+/// to the job with `--gpus` as `CUDA_VISIBLE_DEVICES`. With `--sshlogin`, the job may be executed
+/// on a remote `host`, in which case only the first three are exported, within its command line,
+/// along with the variables of `--env` and `--host-env`, and the login and number of processors of
+/// the host, as `PARALLEL_HOST` and `PARALLEL_HOST_NCPU`. Its command follows the `--host-setup` of
+/// the host, and is wrapped within the tools which set its priorities and timeout on the host. The
+/// files of its `input` are transferred to and from the host.
+pub struct JobEnv<'a> {
+    pub seq:     &'a [u8],
+    pub slot:    &'a str,
+    pub total:   &'a [u8],
+    pub scratch: &'a str,
+    pub gpus:    &'a str,
+    pub host:    Option<&'a Host>,
+    pub input:   &'a str,
+}
+
+impl<'a> JobEnv<'a> {
+    fn export(&self, command: &mut Command) {
+        command.env("PARALLEL_SEQ", str::from_utf8(self.seq).unwrap_or(""))
+            .env("PARALLEL_SLOT", self.slot)
+            .env("PARALLEL_TOTAL", str::from_utf8(self.total).unwrap_or(""));
+        if !self.scratch.is_empty() { command.env("PARALLEL_SCRATCH", self.scratch); }
+        if !self.gpus.is_empty() { command.env("CUDA_VISIBLE_DEVICES", self.gpus); }
+    }
+
+    /// The command line which the shell of a remote host executes, which is quoted as it would be
+    /// for a shell, with the launcher `prefix`. The remote shell may not be the one that would be
+    /// used locally, so commands are given to `sh`. The variables of `--env` are given through `env`,
+    /// as the names of exported bash functions are not valid within an assignment, and commands are
+    /// then given to `bash`, as `sh` may be `dash`, which removes those functions from its environment.
+    fn remote_line(&self, command: &str, flags: u16, prefix: &[String]) -> String {
+        let mut line = String::with_capacity(command.len() + 64);
+        // The commands of `--host-setup` are executed first, and the job fails if they do.
+        for commands in self.host.iter().flat_map(|host| &host.setup) {
+            line.push_str("{ ");
+            line.push_str(commands.trim().trim_end_matches(';'));
+            line.push_str("; } && ");
+        }
+        let exported = environment::exported();
+        if !exported.is_empty() {
+            line.push_str("env ");
+            for &(ref name, ref value) in exported {
+                push_quoted(&mut line, &[name, "=", value].concat());
+                line.push(' ');
+            }
+        }
+        line.push_str("PARALLEL_SEQ=");
+        line.push_str(str::from_utf8(self.seq).unwrap_or(""));
+        line.push_str(" PARALLEL_SLOT=");
+        line.push_str(self.slot);
+        line.push_str(" PARALLEL_TOTAL=");
+        line.push_str(str::from_utf8(self.total).unwrap_or(""));
+        if let Some(host) = self.host {
+            line.push_str(" PARALLEL_HOST=");
+            push_quoted(&mut line, &host.login);
+            // The processors are counted by the shell of the host, which expands the line.
+            line.push_str(" PARALLEL_HOST_NCPU=$(nproc 2>/dev/null || getconf _NPROCESSORS_ONLN)");
+            for &(ref name, ref value) in &host.env {
+                line.push(' ');
+                line.push_str(name);
+                line.push('=');
+                push_quoted(&mut line, value);
+            }
+        }
+        line.push(' ');
+        line.push_str(remote::wrapper());
+        let (mut arguments, variables) = argv(command, flags & !(arguments::DASH_EXISTS | arguments::ION_EXISTS));
+        if environment::functions() && arguments.first().map_or(false, |program| program == "sh") {
+            arguments[0] = "bash".to_owned();
+        }
+        line.push_str(&join(&arguments, &variables, prefix));
+        line
+    }
+
+    /// Generates the names of the files which are transferred for the job from their `templates`.
+    /// Templates which name no file for the job are omitted.
+    fn files(&self, templates: &[Vec<Token>]) -> Vec<String> {
+        templates.iter().filter_map(|template| {
+            let mut file = String::new();
+            ParallelCommand {
+                slot_no:   self.slot,
+                job_no:    self.seq,
+                job_total: self.total,
+                scratch:   self.scratch,
+                gpus:      self.gpus,
+                ..ParallelCommand::template(template, self.input, 0)
+            }.build_arguments(&mut file);
+            if file.is_empty() { None } else { Some(file) }
+        }).collect()
     }
 }
 
@@ -35,18 +159,55 @@ pub struct ParallelCommand<'a> {
     pub input:            &'a str,
     pub flags:            u16,
     pub command_template: &'a [Token],
+    pub prefix:           &'a [String],
+    /// The scratch directory of the job slot, if `--scratch` is enabled.
+    pub scratch:          &'a str,
+    /// The GPUs assigned to the job, if `--gpus` is enabled.
+    pub gpus:             &'a str,
+    /// The remote host of the job slot, if `--sshlogin` was given.
+    pub host:             Option<&'a Host>,
+    /// The name of the source of the input, and the line of the input within that source, if known.
+    pub source:           Option<(&'a str, usize)>,
+    /// The byte range of the input file, if inputs were divided with `--chunk-file`.
+    pub range:            Option<(u64, u64)>,
+    /// The output of the previous stage of the job, if this is a later stage of a `--then` pipeline.
+    pub previous:         Option<&'a str>,
 }
 
 impl<'a> ParallelCommand<'a> {
+    /// A command which only expands the `command_template` with the `input`, where the placeholders
+    /// of the job that are not otherwise given, such as `{#}` and `{%}`, are empty. The fields which
+    /// are known are given with the struct update syntax.
+    pub fn template(command_template: &'a [Token], input: &'a str, flags: u16) -> ParallelCommand<'a> {
+        ParallelCommand {
+            slot_no:          "",
+            job_no:           &[],
+            job_total:        &[],
+            input:            input,
+            flags:            flags,
+            command_template: command_template,
+            prefix:           &[],
+            scratch:          "",
+            gpus:             "",
+            host:             None,
+            source:           None,
+            range:            None,
+            previous:         None,
+        }
+    }
+
     /// Builds and execute commands based on given flags, supplied inputs and token arguments.
-    pub fn exec(&self, arguments: &mut String) -> Result<Child, CommandErr> {
+    /// With `--discard-output`, the output of the child is `discard`ed rather than captured.
+    pub fn exec(&self, arguments: &mut String, discard: bool) -> Result<Child, CommandErr> {
         self.build_arguments(arguments);
 
         if self.flags & arguments::PIPE_IS_ENABLED == 0 {
-            append_argument(arguments, self.command_template, self.input);
-            get_command_output(arguments.as_str(), self.flags).map_err(CommandErr::IO)
+            let shell = self.flags & arguments::SHELL_ENABLED != 0;
+            append_argument(arguments, self.command_template, self.input, self.flags, shell);
+            get_command_output(arguments.as_str(), self.flags, discard, self.prefix, &self.env()).map_err(CommandErr::IO)
         } else {
-            let mut child = get_command_output(arguments.as_str(), self.flags).map_err(CommandErr::IO)?;
+            let mut child = get_command_output(arguments.as_str(), self.flags, discard, self.prefix, &self.env())
+                .map_err(CommandErr::IO)?;
 
             {   // Grab a handle to the child's stdin and write the input argument to the child's stdin.
                 let stdin = child.stdin.as_mut().unwrap();
@@ -61,6 +222,28 @@ impl<'a> ParallelCommand<'a> {
         }
     }
 
+    /// Executes a command line which was built from the template and then amended, as with the output
+    /// of an earlier stage of the job.
+    pub fn spawn(&self, arguments: &str, discard: bool) -> Result<Child, CommandErr> {
+        get_command_output(arguments, self.flags, discard, self.prefix, &self.env()).map_err(CommandErr::IO)
+    }
+
+    fn env(&self) -> JobEnv<'_> {
+        JobEnv {
+            seq:     self.job_no,
+            slot:    self.slot_no,
+            total:   self.job_total,
+            scratch: self.scratch,
+            gpus:    self.gpus,
+            host:    self.host,
+            input:   self.input,
+        }
+    }
+
+    fn job(&self) -> &str {
+        str::from_utf8(self.job_no).unwrap_or("")
+    }
+
     /// Builds arguments using the `tokens` template with the current `input` value.
     /// The arguments will be stored within a `Vec<String>`
     pub fn build_arguments(&self, arguments: &mut String) {
@@ -69,7 +252,18 @@ impl<'a> ParallelCommand<'a> {
                 match *arg {
                     Token::Argument(ref arg) => arguments.push_str(arg),
                     Token::Job               => for character in self.job_no { arguments.push(*character as char); },
+                    Token::Host              => arguments.push_str(self.host.map_or(":", |host| &host.login)),
+                    Token::Scratch           => arguments.push_str(self.scratch),
                     Token::Slot              => arguments.push_str(self.slot_no),
+                    Token::Now(format)       => push_time(arguments, format),
+                    Token::RangeEnd          => push_number(arguments, self.range.map(|(_, end)| end)),
+                    Token::RangeStart        => push_number(arguments, self.range.map(|(start, _)| start)),
+                    Token::Expression(ref expression) => {
+                        expression.evaluate(self.input, self.job(), self.slot_no, arguments)
+                    },
+                    Token::Source            => arguments.push_str(self.source.map_or("", |(source, _)| source)),
+                    Token::SourceLine        => push_line(arguments, self.source),
+                    Token::Total             => arguments.push_str(str::from_utf8(self.job_total).unwrap_or("")),
                     _ => ()
                 }
             }
@@ -80,80 +274,281 @@ impl<'a> ParallelCommand<'a> {
                     Token::Basename           => arguments.push_str(basename(self.input)),
                     Token::BaseAndExt         => arguments.push_str(basename(remove_extension(self.input))),
                     Token::BaseAndSuffix(pat) => arguments.push_str(basename(remove_pattern(self.input, pat))),
+                    Token::Column(number)     => arguments.push_str(column(self.input, number).unwrap_or("")),
                     Token::Dirname            => arguments.push_str(dirname(self.input)),
+                    Token::Expression(ref expression) => {
+                        expression.evaluate(self.input, self.job(), self.slot_no, arguments)
+                    },
+                    Token::Host               => arguments.push_str(self.host.map_or(":", |host| &host.login)),
                     Token::Job                => for character in self.job_no { arguments.push(*character as char); },
+                    Token::Now(format)        => push_time(arguments, format),
                     Token::Placeholder        => arguments.push_str(self.input),
+                    Token::Previous           => push_previous(arguments, self.previous),
+                    Token::RangeEnd           => push_number(arguments, self.range.map(|(_, end)| end)),
+                    Token::RangeStart         => push_number(arguments, self.range.map(|(start, _)| start)),
                     Token::RemoveExtension    => arguments.push_str(remove_extension(self.input)),
                     Token::RemoveSuffix(pat)  => arguments.push_str(remove_pattern(self.input, pat)),
-                    Token::Slot               => arguments.push_str(self.slot_no)
+                    Token::Scratch            => arguments.push_str(self.scratch),
+                    Token::Slot               => arguments.push_str(self.slot_no),
+                    Token::Source             => arguments.push_str(self.source.map_or("", |(source, _)| source)),
+                    Token::SourceLine         => push_line(arguments, self.source),
+                    Token::Total              => arguments.push_str(str::from_utf8(self.job_total).unwrap_or("")),
                 }
             }
         }
     }
 }
 
+/// Appends the current time, in the given `strftime` format, which was validated by the tokenizer.
+fn push_time(arguments: &mut String, format: &str) {
+    if let Ok(time) = time::strftime(format, &time::now()) { arguments.push_str(&time); }
+}
+
+/// Appends the output of the previous stage as a single quoted word, so that neither a shell nor the
+/// splitting of a command which is executed without one interprets it. Outside of a later stage of a
+/// `--then` pipeline, there is no previous stage, so `{prev}` is left as it was written.
+fn push_previous(arguments: &mut String, previous: Option<&str>) {
+    match previous {
+        Some(previous) => push_quoted(arguments, previous),
+        None           => arguments.push_str("{prev}"),
+    }
+}
+
+/// Appends the line number of the input within its source, if it is known.
+fn push_line(arguments: &mut String, source: Option<(&str, usize)>) {
+    push_number(arguments, source.map(|(_, line)| line as u64));
+}
+
+/// Appends the number, if there is one.
+fn push_number(arguments: &mut String, number: Option<u64>) {
+    if let Some(number) = number {
+        let mut buffer = [0u8; 20];
+        let start = number.numtoa(10, &mut buffer);
+        arguments.push_str(unsafe { str::from_utf8_unchecked(&buffer[start..]) });
+    }
+}
+
 /// Handles shell execution and returns a handle to the underlying `Child` process.
 /// If the command requires to be executed in a shell, it will be executed within a shell.
 /// Otherwise, the arguments will be split and the command will run without a shell.
-pub fn get_command_output(command: &str, flags: u16) -> io::Result<Child> {
-    if flags & arguments::SHELL_ENABLED != 0 && flags & arguments::PIPE_IS_ENABLED == 0 {
-        shell_output(command, flags)
+/// If a launcher `prefix` was supplied, the command will be executed by the launcher.
+/// The identity of the `job` is exported to the environment of the command. If the output of the
+/// command is to be `discard`ed, its standard output and error are opened on `/dev/null`. If the job
+/// has a remote host, the command is executed on that host by `ssh`, through the launcher, within
+/// a script which transfers the files of the job, if any are to be transferred.
+pub fn get_command_output(command: &str, flags: u16, discard: bool, prefix: &[String], job: &JobEnv)
+    -> io::Result<Child>
+{
+    let mut child = if let Some(host) = job.host {
+        let line = job.remote_line(command, flags, prefix);
+        if let Some(transfer) = remote::transfer() {
+            let script = host.script(&line, &job.files(&transfer.files), &job.files(&transfer.returns), transfer.cleanup);
+            let mut child = Command::new("sh");
+            child.arg("-c").arg(script);
+            child
+        } else {
+            let mut child = Command::new(&host.ssh[0]);
+            child.args(&host.ssh[1..]).arg("--").arg(line);
+            child
+        }
+    } else if let Some((shell, command)) = shell::shebang(command) {
+        // Commands which begin with `#!SHELL` are executed within that shell.
+        let mut child = launch(prefix, shell);
+        child.arg("-c").arg(command);
+        child
+    } else if flags & arguments::SHELL_ENABLED != 0 && flags & arguments::PIPE_IS_ENABLED == 0 {
+        shell_command(command, flags, prefix)
     } else {
-        // Collect each argument into a vector
-        let arguments = ArgumentSplitter::new(command).collect::<Vec<&str>>();
-        match (arguments.len() == 1, flags & arguments::QUIET_MODE != 0, flags & arguments::PIPE_IS_ENABLED != 0) {
-            (true, true, false) => Command::new(&arguments[0])
-                .stdout(Stdio::null()).stderr(Stdio::piped())
-                .spawn(),
-            (true, true, true) => Command::new(&arguments[0])
-                .stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped())
-                .spawn(),
-            (true, false, false) => Command::new(&arguments[0])
-                .stdout(Stdio::piped()).stderr(Stdio::piped())
-                .spawn(),
-            (true, false, true) => Command::new(&arguments[0])
-                .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
-                .spawn(),
-            (false, true, false) => Command::new(&arguments[0]).args(&arguments[1..])
-                .stdout(Stdio::null()).stderr(Stdio::piped())
-                .spawn(),
-            (false, true, true) => Command::new(&arguments[0]).args(&arguments[1..])
-                .stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped())
-                .spawn(),
-            (false, false, false) => Command::new(&arguments[0]).args(&arguments[1..])
-                .stdout(Stdio::piped()).stderr(Stdio::piped())
-                .spawn(),
-            (false, false, true) => Command::new(&arguments[0]).args(&arguments[1..])
-                .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
-                .spawn(),
+        let (arguments, assignments) = split(command);
+        // A command without any words, such as an empty input which is used as a command, has no program.
+        let program = match arguments.get(assignments) {
+            Some(program) => program,
+            None => return Err(io::Error::new(io::ErrorKind::InvalidInput, "the command is empty"))
+        };
+        let mut child = launch(prefix, &**program);
+        child.args(arguments[assignments+1..].iter().map(|argument| &**argument));
+        for assignment in &arguments[..assignments] {
+            let position = assignment.find('=').unwrap();
+            child.env(&assignment[..position], &assignment[position+1..]);
         }
+        child
+    };
+
+    job.export(&mut child);
+    ionice::restore(&mut child);
+    nice::apply(&mut child);
+    if flags & arguments::PIPE_IS_ENABLED != 0 { child.stdin(Stdio::piped()); }
+    if flags & arguments::TTY != 0 {
+        // The job is attached to the terminal, so its output will not be captured.
+        let tty = OpenOptions::new().read(true).write(true).open("/dev/tty")?;
+        if flags & arguments::PIPE_IS_ENABLED == 0 { child.stdin(Stdio::from(tty.try_clone()?)); }
+        child.stdout(Stdio::from(tty.try_clone()?));
+        return child.stderr(Stdio::from(tty)).spawn();
+    }
+    if discard { return child.stdout(null()).stderr(null()).spawn(); }
+    if flags & arguments::QUIET_MODE != 0 {
+        child.stdout(null());
+    } else if flags & arguments::UNGROUP != 0 {
+        child.stdout(Stdio::inherit());
+    } else {
+        child.stdout(Stdio::piped());
+    }
+    // With `--ungroup`, the output of the job is written directly to that of the program.
+    if flags & arguments::UNGROUP != 0 { return child.stderr(Stdio::inherit()).spawn(); }
+    child.stderr(Stdio::piped()).spawn()
+}
+
+/// Discards a stream of a job into a duplicate of the shared `/dev/null`, falling back to opening
+/// `/dev/null` anew if it could not be opened or duplicated.
+fn null() -> Stdio {
+    DEV_NULL.get_or_init(|| OpenOptions::new().write(true).open("/dev/null").ok()).as_ref()
+        .and_then(|null| null.try_clone().ok())
+        .map_or_else(Stdio::null, Stdio::from)
+}
+
+/// Collects each argument of a command which is executed without a shell into a vector, removing the
+/// quotes that a shell would have removed, along with the number of leading `NAME=value` words, which
+/// define environment variables for the command, as with a shell.
+fn split(command: &str) -> (Vec<Cow<'_, str>>, usize) {
+    let arguments = ArgumentSplitter::new(command).map(unquote).collect::<Vec<_>>();
+    let assignments = arguments.iter().take_while(|word| shell::is_assignment(word)).count()
+        .min(arguments.len().saturating_sub(1));
+    (arguments, assignments)
+}
+
+/// The arguments with which `get_command_output()` executes a command, without a launcher, along with
+/// the environment variables which are defined by the leading `NAME=value` words of the command.
+pub fn argv(command: &str, flags: u16) -> (Vec<String>, Vec<(String, String)>) {
+    if let Some((shell, command)) = shell::shebang(command) {
+        (vec![shell.to_owned(), "-c".to_owned(), command.to_owned()], Vec::new())
+    } else if flags & arguments::SHELL_ENABLED != 0 && flags & arguments::PIPE_IS_ENABLED == 0 {
+        let (shell, flag) = shell_program(flags);
+        (vec![shell.to_owned(), flag.to_owned(), command.to_owned()], Vec::new())
+    } else {
+        let (arguments, assignments) = split(command);
+        let variables = arguments[..assignments].iter().map(|assignment| {
+            let position = assignment.find('=').unwrap();
+            (assignment[..position].to_owned(), assignment[position+1..].to_owned())
+        }).collect();
+        (arguments[assignments..].iter().map(|argument| argument.clone().into_owned()).collect(), variables)
+    }
+}
+
+/// The command line with which `get_command_output()` executes a `command` through the launcher
+/// `prefix`, as recorded in the job log with `--log-commands`. Each word is quoted as it would be
+/// for a shell, after the environment variables which are defined for the command.
+pub fn command_line(command: &str, flags: u16, prefix: &[String]) -> String {
+    let (arguments, variables) = argv(command, flags);
+    join(&arguments, &variables, prefix)
+}
+
+fn join(arguments: &[String], variables: &[(String, String)], prefix: &[String]) -> String {
+    let mut line = String::with_capacity(arguments.iter().map(|argument| argument.len() + 3).sum::<usize>() + 16);
+    for &(ref name, ref value) in variables {
+        line.push_str(name);
+        line.push('=');
+        push_quoted(&mut line, value);
+        line.push(' ');
+    }
+    for word in prefix.iter().chain(arguments.iter()) {
+        push_quoted(&mut line, word);
+        line.push(' ');
+    }
+    line.pop();
+    line
+}
+
+/// Creates a command which executes `program` directly, or through the launcher `prefix`.
+fn launch<S: AsRef<OsStr>>(prefix: &[String], program: S) -> Command {
+    match prefix.split_first() {
+        Some((launcher, arguments)) => {
+            let mut command = Command::new(launcher);
+            command.args(arguments).arg(program);
+            command
+        },
+        None => Command::new(program)
     }
 }
 
-/// Executes the command within a shell
-fn shell_output<S: AsRef<OsStr>>(args: S, flags: u16) -> io::Result<Child> {
-    let (cmd, flag) = if cfg!(windows) {
-        ("cmd".to_owned(), "/C")
+/// Creates a command which executes the arguments within a shell
+pub fn shell_command<S: AsRef<OsStr>>(args: S, flags: u16, prefix: &[String]) -> Command {
+    let (cmd, flag) = shell_program(flags);
+    let mut command = launch(prefix, cmd);
+    command.arg(flag).arg(args);
+    command
+}
+
+/// The shell which commands are executed within, and the flag which gives it a command.
+fn shell_program(flags: u16) -> (&'static str, &'static str) {
+    if cfg!(windows) {
+        ("cmd", "/C")
     } else if flags & arguments::ION_EXISTS != 0 {
-        ("ion".to_owned(), "-c")
+        ("ion", "-c")
     } else if flags & arguments::DASH_EXISTS != 0  {
-        ("dash".to_owned(), "-c")
+        ("dash", "-c")
     } else {
-        ("sh".to_owned(), "-c")
-    };
+        ("sh", "-c")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    #[test]
+    fn appended_inputs() {
+        let template = [Token::Argument(Cow::Borrowed("echo"))];
+        let mut arguments = String::from("echo");
+        append_argument(&mut arguments, &template, "a;b it's", 0, true);
+        assert_eq!(arguments, "echo 'a;b' 'it'\\''s'");
+
+        let mut arguments = String::from("echo");
+        append_argument(&mut arguments, &template, "a b", arguments::NO_APPEND, false);
+        assert_eq!(arguments, "echo");
+
+        let mut arguments = String::from("echo ");
+        append_argument(&mut arguments, &[Token::Placeholder], "a", 0, false);
+        assert_eq!(arguments, "echo ");
+    }
+
+    #[test]
+    fn command_lines() {
+        let prefix = ["nice".to_owned(), "-n".to_owned(), "10".to_owned()];
+        assert_eq!(command_line("echo a; b", arguments::SHELL_ENABLED, &prefix), "nice -n 10 sh -c 'echo a; b'");
+        assert_eq!(command_line("A='1 2' echo 'x y' z", 0, &[]), "A='1 2' echo 'x y' z");
+        assert_eq!(command_line("  ", 0, &[]), "");
+
+        // An empty command is an error of the job, rather than a panic of its slot.
+        let job = JobEnv { seq: b"1", slot: "1", total: b"1", scratch: "", gpus: "", host: None, input: "" };
+        let empty = get_command_output("  ", 0, false, &[], &job);
+        assert_eq!(empty.err().map(|why| why.kind()), Some(io::ErrorKind::InvalidInput));
+    }
+
+    #[test]
+    fn remote_lines() {
+        let mut host = Host::new("user@a".to_owned(), vec!["ssh".to_owned(), "user@a".to_owned()]);
+        host.env.push(("PATH".to_owned(), "/opt/bin:/bin".to_owned()));
+        host.setup.push("module load gcc;".to_owned());
+        let job = JobEnv { seq: b"2", slot: "1", total: b"3", scratch: "", gpus: "", host: Some(&host), input: "x" };
+        assert_eq!(job.remote_line("echo x", 0, &[]), "{ module load gcc; } && PARALLEL_SEQ=2 PARALLEL_SLOT=1 \
+            PARALLEL_TOTAL=3 PARALLEL_HOST='user@a' PARALLEL_HOST_NCPU=$(nproc 2>/dev/null || getconf _NPROCESSORS_ONLN) \
+            PATH=/opt/bin:/bin echo x");
 
-    match (flags & arguments::QUIET_MODE != 0, flags & arguments::PIPE_IS_ENABLED != 0) {
-        (true, false) => Command::new(cmd).arg(flag).arg(args)
-            .stdout(Stdio::null()).stderr(Stdio::piped())
-            .spawn(),
-        (true, true) => Command::new(cmd).arg(flag).arg(args)
-            .stdin(Stdio::piped()).stdout(Stdio::null()).stderr(Stdio::piped())
-            .spawn(),
-        (false, false) => Command::new(cmd).arg(flag).arg(args)
-            .stdout(Stdio::piped()).stderr(Stdio::piped())
-            .spawn(),
-        (false, true) => Command::new(cmd).arg(flag).arg(args)
-            .stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped())
-            .spawn()
+        let template = [Token::Argument(Cow::Borrowed("echo ")), Token::Host];
+        let mut command = ParallelCommand {
+            slot_no:   "1",
+            job_no:    b"2",
+            job_total: b"3",
+            host:      Some(&host),
+            ..ParallelCommand::template(&template, "x", 0)
+        };
+        let mut arguments = String::new();
+        command.build_arguments(&mut arguments);
+        assert_eq!(arguments, "echo user@a");
+        command.host = None;
+        arguments.clear();
+        command.build_arguments(&mut arguments);
+        assert_eq!(arguments, "echo :");
     }
 }
@@ -0,0 +1,134 @@
+use arguments::JOBLOG;
+use input_iterator::Provenance;
+use time::Timespec;
+use super::budget::Budget;
+use super::command;
+use super::downstream::Downstream;
+use super::gpus::Gpus;
+use super::groups::KeyGroups;
+use super::labels::Labels;
+use super::job_log::JobLog;
+use super::launcher::Launcher;
+use super::meter::Meter;
+use super::missing::MissingInput;
+use super::pipe::disk::State;
+use super::pools::Pools;
+use super::progress::ProgressEvents;
+use super::remote::Host;
+use super::return_first::ReturnFirst;
+use super::signals::TermSeq;
+use super::slots::Slots;
+use super::speculate::Speculation;
+use super::spool::Spool;
+use super::stages::Stages;
+use super::status::JobStatus;
+use super::tag::Tag;
+use super::webhook::Webhook;
+use super::window::Window;
+
+use std::sync::Arc;
+use std::sync::mpsc::SyncSender;
+use std::time::Duration;
+
+/// The settings and shared state of a run, which every job slot holds, and which the slots only
+/// modify through the synchronization of each of its parts. The state which differs between the
+/// slots, such as their host and scratch directory, is held by the executor of each slot.
+pub struct RunContext {
+    pub num_inputs:  usize,
+    pub timeout:     Duration,
+    pub termseq:     TermSeq,
+    pub timeout_col: usize,
+    pub weight_col:  usize,
+    pub slots:       Option<Arc<Slots>>,
+    pub groups:      Option<Arc<KeyGroups>>,
+    pub labels:      Option<Arc<Labels>>,
+    /// With `--then`, the further stages of each job.
+    pub stages:      Option<Arc<Stages>>,
+    pub pools:       Option<Arc<Pools>>,
+    pub gpus:        Option<Arc<Gpus>>,
+    pub launcher:    Arc<Launcher>,
+    pub provenance:  Arc<Provenance>,
+    pub spool:       Option<Arc<Spool>>,
+    pub output_size: usize,
+    pub missing:     Option<MissingInput>,
+    pub window:      Option<Window>,
+    pub status:      Option<Arc<JobStatus>>,
+    pub first:       Option<Arc<ReturnFirst>>,
+    pub budget:      Option<Arc<Budget>>,
+    pub progress:    Option<Arc<ProgressEvents>>,
+    pub meter:       Option<Arc<Meter>>,
+    pub speculation: Option<Arc<Speculation>>,
+    pub retries:     usize,
+    pub webhook:     Option<Arc<Webhook>>,
+    pub downstream:  Arc<Downstream>,
+    pub tag:         Option<Tag>,
+    pub discard:     bool,
+    /// With `--log-commands`, the job log records the command line as executed, through its launcher.
+    pub log_argv:    bool,
+    pub tempdir:     String,
+}
+
+/// How a job ended, as it is recorded by `RunContext::record()`.
+pub struct Ended {
+    pub start_time: Timespec,
+    pub end_time:   Timespec,
+    pub exit_value: i32,
+    pub signal:     i32,
+    /// Whether the job was executed, rather than skipped or failed before it could be.
+    pub ran:        bool,
+    /// Whether the job lost to its duplicate with `--speculative-max`, which records it instead.
+    pub lost:       bool,
+    /// Whether the job failed and will be attempted again with `--retries`, whose last attempt records it.
+    pub retried:    bool,
+}
+
+impl RunContext {
+    /// Records the end of a job of the `slot` within the progress events, the meter, the webhook, and
+    /// the job log, where `command` was executed with the `flags` through the launcher `prefix`.
+    pub fn record(&self, slot: &str, host: Option<&Host>, output_tx: &SyncSender<State>, flags: u16, job_id: usize,
+        input: &str, command: &str, prefix: &[String], ended: &Ended)
+    {
+        let failed = ended.exit_value != 0 || ended.signal != 0;
+        let recorded = ended.ran && !ended.lost && !ended.retried;
+        let runtime = ended.end_time - ended.start_time;
+
+        if let Some(ref progress) = self.progress {
+            if !ended.ran {
+                progress.skipped(job_id, slot);
+            } else if recorded {
+                progress.finished(job_id, slot, ended.exit_value, ended.signal, runtime);
+            }
+        }
+
+        if let Some(ref meter) = self.meter {
+            if !ended.ran {
+                meter.skipped();
+            } else if !recorded {
+                meter.stopped();
+            } else {
+                meter.finished(failed, runtime);
+            }
+        }
+
+        // With `--webhook-failures`, each job which failed is posted to the webhook.
+        if let Some(ref webhook) = self.webhook {
+            if recorded && failed { webhook.failed(job_id, input, ended.exit_value, ended.signal); }
+        }
+
+        if flags & JOBLOG != 0 && !ended.lost && !ended.retried {
+            let logged = if !self.log_argv { command.to_owned() } else { command::command_line(command, flags, prefix) };
+            let _ = output_tx.send(State::JobLog(JobLog {
+                job_id:     job_id,
+                start_time: ended.start_time,
+                runtime:    runtime.num_nanoseconds().unwrap_or(0) as u64,
+                exit_value: ended.exit_value,
+                signal:     ended.signal,
+                flags:      flags,
+                host:       host.map(|host| host.login.clone()),
+                source:     self.provenance.get(job_id).map(|(source, line)| format!("{}:{}", source, line)),
+                label:      self.labels.as_ref().map(|labels| labels.render(job_id, input)),
+                command:    logged,
+            }));
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! With `--test-deterministic SEED`, the order in which jobs complete, and the times that are
+//! recorded for them, are derived from the seed rather than from how the jobs happened to be
+//! scheduled, so that the features which reorder output may be tested reproducibly.
+//!
+//! The jobs are divided into batches of as many jobs as there are slots, and the jobs of each
+//! batch complete in an order that is shuffled by the seed, after every job of the batches before
+//! it. A job only waits for its turn once it has been executed, so the jobs still run in parallel;
+//! only the collection of their output, and the signal of their completion, take turns.
+
+use input_iterator::Excluded;
+use std::sync::{Condvar, Mutex, OnceLock};
+use time::{Duration, Timespec};
+
+/// The time at which the first job in the order of completion is recorded to have started.
+const EPOCH: i64 = 1_500_000_000;
+
+struct Turns {
+    seed:  u64,
+    slots: usize,
+    total: usize,
+    /// The first position in the order of completion which has not completed, and whether each
+    /// position has completed.
+    state: Mutex<(usize, Vec<bool>)>,
+    turn:  Condvar,
+}
+
+static TURNS: OnceLock<Turns> = OnceLock::new();
+
+/// Enables the deterministic mode for the `total` jobs of the run. Jobs which are `excluded` are
+/// never executed, so their turns are considered to have been taken already.
+pub fn enable(seed: u64, slots: usize, total: usize, excluded: &Excluded) {
+    let mut turns = Turns {
+        seed:  seed,
+        slots: slots.max(1),
+        total: total,
+        state: Mutex::new((0, Vec::new())),
+        turn:  Condvar::new(),
+    };
+    let mut completed = vec![false; total];
+    for job_id in (0..total).filter(|job_id| excluded.contains(job_id)) {
+        completed[turns.position(job_id)] = true;
+    }
+    let next = completed.iter().position(|&completed| !completed).unwrap_or(total);
+    *turns.state.get_mut().unwrap() = (next, completed);
+    let _ = TURNS.set(turns);
+}
+
+/// Waits until every job which precedes the job of `job_id` in the order of completion has completed.
+pub fn wait(job_id: usize) {
+    if let Some(turns) = TURNS.get() {
+        let position = turns.position(job_id);
+        if position >= turns.total { return }
+        let mut state = turns.state.lock().unwrap();
+        while state.0 < position { state = turns.turn.wait(state).unwrap(); }
+    }
+}
+
+/// Records that the job of `job_id` has completed, so that the next job may complete.
+pub fn complete(job_id: usize) {
+    if let Some(turns) = TURNS.get() {
+        let position = turns.position(job_id);
+        if position >= turns.total { return }
+        let mut state = turns.state.lock().unwrap();
+        state.1[position] = true;
+        while state.0 < turns.total && state.1[state.0] { state.0 += 1; }
+        turns.turn.notify_all();
+    }
+}
+
+/// The start and end times which are recorded for the job of `job_id`, if the mode is enabled.
+/// Each job starts a second after the job before it in the order of completion, and runs for
+/// up to a second, according to the seed.
+pub fn times(job_id: usize) -> Option<(Timespec, Timespec)> {
+    TURNS.get().map(|turns| {
+        let start = Timespec::new(EPOCH + turns.position(job_id) as i64, 0);
+        let runtime = random(turns.seed ^ job_id as u64) % 1000;
+        (start, start + Duration::milliseconds(runtime as i64))
+    })
+}
+
+impl Turns {
+    /// The position of a job in the order of completion.
+    fn position(&self, job_id: usize) -> usize {
+        let batch = job_id / self.slots;
+        let start = batch * self.slots;
+        if start >= self.total { return job_id }
+        let size = self.slots.min(self.total - start);
+
+        // The positions within the batch are shuffled with a Fisher-Yates shuffle.
+        let mut order = (0..size).collect::<Vec<usize>>();
+        let mut state = self.seed ^ (batch as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        for index in (1..size).rev() {
+            state = random(state);
+            order.swap(index, state as usize % (index + 1));
+        }
+        start + order[job_id - start]
+    }
+}
+
+/// The next value of a xorshift generator, which is never zero.
+fn random(state: u64) -> u64 {
+    let mut x = if state == 0 { 0x2545_F491_4F6C_DD1D } else { state };
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shuffled_batches() {
+        let turns = Turns { seed: 7, slots: 4, total: 10, state: Mutex::new((0, Vec::new())), turn: Condvar::new() };
+        let positions = (0..10).map(|job_id| turns.position(job_id)).collect::<Vec<usize>>();
+
+        // Each batch keeps the positions of its own jobs, and is shuffled the same way every time.
+        for (batch, range) in [(0, 0..4), (1, 4..8), (2, 8..10)].iter().cloned() {
+            let mut shuffled = positions[range.clone()].to_vec();
+            shuffled.sort();
+            assert_eq!(shuffled, range.collect::<Vec<usize>>(), "batch {}", batch);
+        }
+        assert_eq!(positions, (0..10).map(|job_id| turns.position(job_id)).collect::<Vec<usize>>());
+    }
+}
@@ -0,0 +1,48 @@
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use super::signals::{self, SIGTERM};
+
+/// Tracks whether the reader of the standard output, such as `head`, has closed it before the run
+/// has completed. Once it has, no further jobs are executed, and the jobs which are still running
+/// are asked to terminate, as there is nowhere left for their output to go.
+pub struct Downstream {
+    closed:  AtomicBool,
+    /// The process ID of each job which is running.
+    running: Mutex<Vec<u32>>,
+}
+
+impl Default for Downstream {
+    fn default() -> Downstream { Downstream::new() }
+}
+
+impl Downstream {
+    pub fn new() -> Downstream {
+        Downstream { closed: AtomicBool::new(false), running: Mutex::new(Vec::new()) }
+    }
+
+    /// Whether the standard output has been closed.
+    pub fn closed(&self) -> bool { self.closed.load(Ordering::SeqCst) }
+
+    /// Records that a job is running, terminating it at once if the standard output was closed
+    /// while the job was being started.
+    pub fn start(&self, pid: u32) {
+        let mut running = self.running.lock().unwrap();
+        if self.closed() {
+            signals::send(pid, SIGTERM);
+        } else {
+            running.push(pid);
+        }
+    }
+
+    /// Records that a job is no longer running.
+    pub fn finish(&self, pid: u32) {
+        self.running.lock().unwrap().retain(|&running| running != pid);
+    }
+
+    /// Records that the standard output was closed, and terminates the jobs which are running.
+    pub fn close(&self) {
+        let running = self.running.lock().unwrap();
+        self.closed.store(true, Ordering::SeqCst);
+        for &pid in running.iter() { signals::send(pid, SIGTERM); }
+    }
+}
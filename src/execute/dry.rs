@@ -1,14 +1,17 @@
-use input_iterator::{InputIterator, InputIteratorErr};
+use input_iterator::{InputIterator, InputIteratorErr, Provenance};
 use tokenizer::Token;
 use arguments;
 use execute::command;
 use numtoa::NumToA;
+use shell;
 
 use std::io::{self, StdoutLock, Read, Write};
 
 /// Instead of executing commands in parallel, the commands that would be executed will be printed
 /// directly to the standard output of this application. This also applies to shell quoted arguments.
-pub fn dry_run<IO: Read>(flags: u16, inputs: InputIterator<IO>, arguments: &[Token]) {
+pub fn dry_run<IO: Read>(flags: u16, inputs: InputIterator<IO>, arguments: &[Token],
+    provenance: &Provenance)
+{
     let stdout             = io::stdout();
     let stdout             = &mut stdout.lock();
     let stderr             = io::stderr();
@@ -20,10 +23,11 @@ pub fn dry_run<IO: Read>(flags: u16, inputs: InputIterator<IO>, arguments: &[Tok
     let mut total_buffer   = [0u8; 20];
     let start_indice       = inputs.total_arguments.numtoa(10, &mut total_buffer);
     let job_total          = &total_buffer[start_indice..];
+    let shell              = shell::required(shell::Kind::Tokens(arguments));
 
     // If `SHELL_QUOTE` is enabled then the quoted command will be printed, otherwise the command will be
     // printed unmodified. The correct function to execute will be assigned here in advance.
-    let pipe_action: Box<Fn(&mut StdoutLock, &str)> = if flags & arguments::SHELL_QUOTE != 0 {
+    let pipe_action: Box<dyn Fn(&mut StdoutLock, &str)> = if flags & arguments::SHELL_QUOTE != 0 {
         Box::new(|stdout: &mut StdoutLock, input: &str| {
             if let Some(new_arg) = shell_quote(input) {
                 let _ = stdout.write(new_arg.as_bytes());
@@ -40,19 +44,20 @@ pub fn dry_run<IO: Read>(flags: u16, inputs: InputIterator<IO>, arguments: &[Tok
     for (job_id, input) in inputs.enumerate() {
         match input {
             Ok(input) => {
-                let start_indice = job_id.numtoa(10, &mut id_buffer);
+                let start_indice = (job_id+1).numtoa(10, &mut id_buffer);
                 let command = command::ParallelCommand {
-                    slot_no:          slot,
-                    job_no:           &id_buffer[start_indice..],
-                    job_total:        job_total,
-                    input:            &input,
-                    command_template: arguments,
-                    flags:            flags,
+                    slot_no:   slot,
+                    job_no:    &id_buffer[start_indice..],
+                    job_total: job_total,
+                    scratch:   "{SCRATCH}",
+                    source:    provenance.get(job_id),
+                    range:     provenance.range(job_id),
+                    ..command::ParallelCommand::template(arguments, &input, flags)
                 };
 
                 command.build_arguments(&mut command_buffer);
                 if !pipe {
-                    command::append_argument(&mut command_buffer, command.command_template, command.input);
+                    command::append_argument(&mut command_buffer, command.command_template, command.input, flags, shell);
                 }
                 pipe_action(stdout, &command_buffer);
                 let _ = stdout.write(b"\n");
@@ -0,0 +1,92 @@
+//! Local jobs inherit the whole environment of the program, but remote jobs are executed within the
+//! environment of their login. With `--env NAME`, the variable of that name, or the bash function
+//! which was exported with `export -f NAME`, is given to each remote job. With `--env _`, every
+//! variable is given, other than those which were recorded in `~/.parallel/ignored_vars` with
+//! `--record-env`, which is meant to be executed within a fresh login, so that only the variables
+//! which were defined since are given.
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// The name which selects every variable which was not recorded with `--record-env`.
+pub const EVERYTHING: &str = "_";
+
+static EXPORTED: OnceLock<Vec<(String, String)>> = OnceLock::new();
+
+/// Gives the `variables` to each remote job.
+pub fn export(variables: Vec<(String, String)>) {
+    let _ = EXPORTED.set(variables);
+}
+
+/// The variables which are given to each remote job, if any are.
+pub fn exported() -> &'static [(String, String)] {
+    EXPORTED.get().map_or(&[], |variables| variables.as_slice())
+}
+
+/// Whether any of the variables which are given to each remote job is an exported bash function.
+pub fn functions() -> bool {
+    exported().iter().any(|&(ref name, _)| function(name).is_some())
+}
+
+/// The file which `--record-env` records the names of the variables of the environment in.
+pub fn ignored_vars() -> Option<PathBuf> {
+    env::var_os("HOME").map(|home| Path::new(&home).join(".parallel").join("ignored_vars"))
+}
+
+/// Records the name of each variable of the environment in `path`, one on each line.
+pub fn record(path: &Path) -> io::Result<()> {
+    if let Some(parent) = path.parent() { fs::create_dir_all(parent)?; }
+    let mut names = env::vars_os().map(|(name, _)| name.to_string_lossy().into_owned()).collect::<Vec<String>>();
+    names.sort();
+    let mut file = io::BufWriter::new(fs::File::create(path)?);
+    for name in names { writeln!(file, "{}", name)?; }
+    file.flush()
+}
+
+/// The variables of the environment which were `named`, where `_` names each variable which is
+/// not `ignored`. Variables which are not valid UTF-8 are never given.
+pub fn select(named: &[String], ignored: &[String]) -> Vec<(String, String)> {
+    select_from(env::vars_os().filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?))),
+        named, ignored)
+}
+
+fn select_from<I: Iterator<Item = (String, String)>>(variables: I, named: &[String], ignored: &[String])
+    -> Vec<(String, String)>
+{
+    let everything = named.iter().any(|name| name == EVERYTHING);
+    let mut selected = variables
+        .filter(|&(ref name, _)| {
+            let name = function(name).unwrap_or(name);
+            named.iter().any(|named| named == name)
+        } || (everything && !ignored.iter().any(|ignored| ignored == name)))
+        .collect::<Vec<(String, String)>>();
+    selected.sort();
+    selected
+}
+
+/// The name of the bash function which a variable defines, if it was exported with `export -f`.
+fn function(variable: &str) -> Option<&str> {
+    let name = variable.strip_prefix("BASH_FUNC_")?;
+    name.strip_suffix("%%").or_else(|| name.strip_suffix("()"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selected_variables() {
+        let variables = || vec![("A", "1"), ("B", "2"), ("HOME", "/root"), ("BASH_FUNC_f%%", "() {  echo\n}")]
+            .into_iter().map(|(name, value)| (name.to_owned(), value.to_owned()));
+        let names = |selected: Vec<(String, String)>| selected.into_iter().map(|(name, _)| name).collect::<Vec<_>>();
+
+        let named = vec!["A".to_owned(), "f".to_owned(), "C".to_owned()];
+        assert_eq!(names(select_from(variables(), &named, &[])), vec!["A", "BASH_FUNC_f%%"]);
+
+        let ignored = vec!["HOME".to_owned(), "B".to_owned()];
+        assert_eq!(names(select_from(variables(), &["_".to_owned()], &ignored)), vec!["A", "BASH_FUNC_f%%"]);
+    }
+}
@@ -1,99 +1,305 @@
-use arguments::{VERBOSE_MODE, JOBLOG};
+use arguments::VERBOSE_MODE;
 use execute::command::{self, CommandErr};
-use input_iterator::InputsLock;
+use input_iterator::{stream, InputsLock};
 use numtoa::NumToA;
 use time::{self, Timespec};
 use tokenizer::Token;
 use verbose;
 use super::pipe::disk::State;
-use super::job_log::JobLog;
-use super::child::handle_child;
+use super::child::{handle_child, job_timeout};
+use super::context::{Ended, RunContext};
+use super::ionice;
+use super::missing::{retry_delay, MissingInput, RETRY_ATTEMPTS};
+use super::pipe::disk;
+use super::scratch::Scratch;
+use super::signals;
+use super::speculate;
+use super::slots::job_weight;
+use super::remote::{self, Host};
+use super::window::{Dispatch, Expired};
 
 use std::io::{self, Read, Write};
-use std::sync::mpsc::Sender;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::mpsc::SyncSender;
 use std::time::Duration;
 
 /// Contains all the required data needed for executing commands in parallel.
 /// Commands will be generated based on a template of argument tokens combined
 /// with the current input argument.
 pub struct ExecCommands<IO: Read> {
-    pub slot:       usize,
-    pub num_inputs: usize,
-    pub flags:      u16,
-    pub timeout:    Duration,
-    pub inputs:     InputsLock<IO>,
-    pub output_tx:  Sender<State>,
-    pub arguments:  &'static [Token],
-    pub tempdir:    String,
+    pub slot:        usize,
+    pub flags:       u16,
+    /// The settings and shared state of the run, which every slot holds.
+    pub context:     Arc<RunContext>,
+    /// The remote host which the jobs of this slot are executed on, with `--sshlogin`.
+    pub host:        Option<Arc<Host>>,
+    pub scratch:     Option<Scratch>,
+    pub inputs:      InputsLock<IO>,
+    pub output_tx:   SyncSender<State>,
+    pub arguments:   &'static [Token],
 }
 
 impl<IO: Read> ExecCommands<IO> {
     pub fn run(&mut self) {
+        ionice::lower();
+        let context = self.context.clone();
         let stdout = io::stdout();
         let stderr = io::stderr();
 
         let slot               = &self.slot.to_string();
-        let mut command_buffer = &mut String::with_capacity(64);
-        let has_timeout        = self.timeout != Duration::from_millis(0);
+        let scratch            = self.scratch.as_ref().map_or("", |scratch| scratch.path());
+        let command_buffer     = &mut String::with_capacity(64);
         let mut input          = String::with_capacity(64);
+        let mut key            = String::new();
+        let mut class_buffer   = String::new();
+        let mut needs          = Vec::new();
+        let mut claimed        = Vec::new();
+        let mut visible        = String::new();
+        let mut prefix         = Vec::new();
         let mut id_buffer      = [0u8; 20];
         let mut job_buffer     = [0u8; 20];
         let mut total_buffer   = [0u8; 20];
-        let mut start_indice   = self.num_inputs.numtoa(10, &mut total_buffer);
-        let job_total          = &total_buffer[start_indice..];
+        let mut membuffer      = vec![0u8; context.output_size];
 
 
-        while let Some(job_id) = self.inputs.try_next(&mut input) {
+        loop {
+            // Once the standard output has been closed, the remaining inputs are not needed.
+            if context.downstream.closed() { break }
+            // With `--hostfile-watch`, a host which was removed from the sshloginfile is given no further jobs.
+            if remote::drained(self.host.as_ref().map(|host| &**host)) { break }
+
+            let job_id = match context.groups {
+                Some(ref groups) => groups.next(&mut self.inputs, slot, &mut input, &mut key),
+                None             => self.inputs.try_next(&mut input)
+            };
+            let job_id = match job_id { Some(job_id) => job_id, None => break };
+
+            // With `--total-jobs`, the hint is the total until every input has been read.
+            let total = stream::total(context.num_inputs);
+            let total_indice = total.numtoa(10, &mut total_buffer);
+            let job_total = &total_buffer[total_indice..];
+
             if self.flags & VERBOSE_MODE != 0  {
-                verbose::processing_task(&stdout, job_id+1, self.num_inputs, &input);
+                verbose::processing_task(&stdout, job_id+1, total, &input);
             }
 
-            start_indice = (job_id+1).numtoa(10, &mut id_buffer);
-            let command = command::ParallelCommand {
-                slot_no:          slot,
-                job_no:           &id_buffer[start_indice..],
-                job_total:        job_total,
-                input:            &input,
-                command_template: self.arguments,
-                flags:            self.flags
+            // Inputs whose `--not-before` time has not arrived are set aside until it has, and inputs
+            // past their `--not-after` deadline are skipped or failed.
+            let expired = match context.window.map(|window| (window, window.dispatch(&input, job_id))) {
+                Some((_, Dispatch::Wait(delay))) => {
+                    let attempt = self.inputs.attempt;
+                    self.inputs.inputs.lock().unwrap().requeue(job_id, &input, attempt, delay);
+                    if let Some(ref groups) = context.groups { groups.release(&key); }
+                    continue
+                },
+                Some((window, Dispatch::Expired)) => Some(window.expired),
+                _ => None
             };
 
-            command_buffer.clear();
-            let (start_time, end_time, exit_value, signal) = match command.exec(command_buffer) {
-                Ok(child) => {
-                    handle_child(child, &self.output_tx, self.flags, job_id, input.clone(), has_timeout, self.timeout,
-                        &self.tempdir, &mut job_buffer)
+            // Inputs which no longer exist may be skipped, failed, or attempted again later.
+            let missing = context.missing.filter(|_| expired.is_none() && !Path::new(&input).exists());
+            if missing == Some(MissingInput::Retry) && self.inputs.attempt + 1 < RETRY_ATTEMPTS {
+                let attempt = self.inputs.attempt + 1;
+                self.inputs.inputs.lock().unwrap().requeue(job_id, &input, attempt, retry_delay(attempt));
+                if let Some(ref groups) = context.groups { groups.release(&key); }
+                continue
+            }
+
+            context.launcher.prefix(job_id, &mut prefix);
+            let start_indice = (job_id+1).numtoa(10, &mut id_buffer);
+            let mut command = command::ParallelCommand {
+                slot_no:   slot,
+                job_no:    &id_buffer[start_indice..],
+                job_total: job_total,
+                prefix:    &prefix,
+                scratch:   scratch,
+                host:      self.host.as_ref().map(|host| &**host),
+                source:    context.provenance.get(job_id),
+                range:     context.provenance.range(job_id),
+                ..command::ParallelCommand::template(self.arguments, &input, self.flags)
+            };
+
+            let timeout = job_timeout(&input, job_id, context.timeout_col, context.timeout);
+            let timeout = context.budget.as_ref().map_or(timeout, |budget| budget.timeout(timeout));
+            let has_timeout = timeout != Duration::from_millis(0);
+
+            // The remaining inputs are skipped once enough jobs have succeeded with `--return-first`,
+            // or once a limit of `--max-runtime` or `--max-failures` has been exceeded.
+            let stopped = context.first.as_ref().map_or(false, |first| first.reached())
+                || context.budget.as_ref().map_or(false, |budget| budget.exhausted());
+            let runnable = !stopped && missing.is_none() && expired.is_none();
+
+            // Jobs wait until the units of the pools that they need, including the `--class` of their
+            // command, are available. The units are claimed before any weighted job slots, so that
+            // neither is held while waiting on the other.
+            let pools = match context.pools {
+                Some(ref pools) if runnable => {
+                    class_buffer.clear();
+                    command.build_arguments(&mut class_buffer);
+                    pools.needs(&class_buffer, job_id, slot, &input, &mut needs);
+                    pools.acquire(&needs);
+                    Some(pools)
                 },
-                Err(cmd_err) => {
-                    let mut stderr = stderr.lock();
-                    let _ = stderr.write(b"parallel: command error: ");
-                    let message = match cmd_err {
-                        CommandErr::IO(error) => format!("I/O error: {}\n", error),
-                    };
-
-                    let _ = stderr.write(message.as_bytes());
-                    let message = format!("{}: {}: {}", job_id+1, command.input, message);
-                    let _ = self.output_tx.send(State::Error(job_id, message));
-                    (Timespec::new(0, 0), Timespec::new(0, 0), -1, 0)
+                _ => None
+            };
+
+            // With `--gpus`, jobs wait until enough GPUs are free, and are given them as `CUDA_VISIBLE_DEVICES`.
+            let gpus = match context.gpus {
+                Some(ref gpus) if runnable => {
+                    gpus.acquire(&mut claimed, &mut visible);
+                    command.gpus = &visible;
+                    Some(gpus)
+                },
+                _ => None
+            };
+
+            // If jobs are weighted, wait until enough job slots are free to accommodate this job.
+            let weight = match context.slots {
+                Some(ref slots) if runnable => Some(slots.acquire(job_weight(&input, job_id, context.weight_col))),
+                _                           => None
+            };
+
+            // A job which lost to its duplicate with `--speculative-max` is recorded by the duplicate,
+            // and a failed job which will be attempted again with `--retries` by its last attempt.
+            let mut lost = false;
+            let mut ran = false;
+            let mut retried = false;
+            command_buffer.clear();
+            let (start_time, end_time, exit_value, signal) = if stopped {
+                command.build_arguments(command_buffer);
+                disk::skip(job_id, &self.output_tx, &context.tempdir, &mut job_buffer);
+                (Timespec::new(0, 0), Timespec::new(0, 0), -1, 0)
+            } else if let Some(missing) = missing {
+                command.build_arguments(command_buffer);
+                if missing == MissingInput::Skip {
+                    disk::skip(job_id, &self.output_tx, &context.tempdir, &mut job_buffer);
+                } else {
+                    if let Some(ref budget) = context.budget { budget.record(true, time::Duration::zero()); }
+                    let message = format!("{}: {}: input does not exist\n", job_id+1, input);
+                    disk::error(job_id, message, &self.output_tx, &context.tempdir, &mut job_buffer);
+                }
+                (Timespec::new(0, 0), Timespec::new(0, 0), -1, 0)
+            } else if let Some(expired) = expired {
+                command.build_arguments(command_buffer);
+                if expired == Expired::Skip {
+                    disk::skip(job_id, &self.output_tx, &context.tempdir, &mut job_buffer);
+                } else {
+                    if let Some(ref budget) = context.budget { budget.record(true, time::Duration::zero()); }
+                    let message = format!("{}: {}: input is past its deadline\n", job_id+1, input);
+                    disk::error(job_id, message, &self.output_tx, &context.tempdir, &mut job_buffer);
+                }
+                (Timespec::new(0, 0), Timespec::new(0, 0), -1, 0)
+            } else {
+                let child = match context.stages {
+                    Some(ref stages) => stages.exec(&command, command_buffer, context.discard),
+                    None             => command.exec(command_buffer, context.discard)
+                };
+                match child {
+                    Ok(child) => {
+                        ran = true;
+                        if let Some(ref progress) = context.progress { progress.started(job_id, slot, &input); }
+                        if let Some(ref meter) = context.meter { meter.started(); }
+                        if let Some(ref speculation) = context.speculation { speculation.start(job_id, &input, child.id()); }
+                        let pid = child.id();
+                        context.downstream.start(pid);
+                        let retry = self.inputs.attempt < context.retries;
+                        let result = handle_child(child, &self.output_tx, self.flags, job_id, input.clone(),
+                            has_timeout, timeout, &context.termseq, &context.tempdir, &mut job_buffer, &mut membuffer,
+                            context.spool.as_ref().map(|spool| &**spool), context.first.as_ref().map(|first| &**first),
+                            retry, context.speculation.as_ref().map(|speculation| (&**speculation, false)), context.tag.as_ref());
+                        context.downstream.finish(pid);
+                        if let Some(ref stages) = context.stages { stages.finished(); }
+                        match result {
+                            Some(result) if retry && (result.2 != 0 || result.3 != 0) => {
+                                if let Some(ref budget) = context.budget { budget.record(false, result.1 - result.0); }
+                                retried = true;
+                                result
+                            },
+                            Some(result) => {
+                                if let Some(ref status) = context.status { status.record(result.2, result.3); }
+                                if let Some(ref budget) = context.budget {
+                                    budget.record(result.2 != 0 || result.3 != 0, result.1 - result.0);
+                                }
+                                result
+                            },
+                            None => {
+                                lost = true;
+                                (Timespec::new(0, 0), Timespec::new(0, 0), -1, 0)
+                            }
+                        }
+                    },
+                    Err(cmd_err) => {
+                        if let Some(ref budget) = context.budget { budget.record(true, time::Duration::zero()); }
+                        if let (Some(status), &CommandErr::IO(ref why)) = (context.status.as_ref(), &cmd_err) {
+                            status.record_error(why);
+                        }
+                        let mut stderr = stderr.lock();
+                        let _ = stderr.write(b"parallel: command error: ");
+                        let message = match cmd_err {
+                            CommandErr::IO(error) => format!("I/O error: {}\n", error),
+                            CommandErr::Stage(stage, status) => match status.code() {
+                                Some(exit) => format!("stage {} exited with {}\n", stage, exit),
+                                None       => format!("stage {} was killed by signal {}\n", stage, signals::get(status))
+                            },
+                        };
+
+                        let _ = stderr.write(message.as_bytes());
+                        let message = format!("{}: {}: {}", job_id+1, command.input, message);
+                        disk::error(job_id, message, &self.output_tx, &context.tempdir, &mut job_buffer);
+                        (Timespec::new(0, 0), Timespec::new(0, 0), -1, 0)
+                    }
                 }
             };
 
-            if self.flags & JOBLOG != 0 {
-                let runtime: time::Duration = end_time - start_time;
-                let _ = self.output_tx.send(State::JobLog(JobLog {
-                    job_id:     job_id,
-                    start_time: start_time,
-                    runtime:    runtime.num_nanoseconds().unwrap_or(0) as u64,
-                    exit_value: exit_value,
-                    signal:     signal,
-                    flags:      self.flags,
-                    command:    command_buffer.clone(),
-                }));
+            let ended = Ended { start_time, end_time, exit_value, signal, ran, lost, retried };
+            context.record(slot, self.host.as_ref().map(|host| &**host), &self.output_tx, self.flags, job_id, &input,
+                command_buffer, &prefix, &ended);
+
+            if let (Some(slots), Some(weight)) = (context.slots.as_ref(), weight) {
+                slots.release(weight);
+            }
+
+            if let Some(pools) = pools { pools.release(&needs); }
+
+            if let Some(gpus) = gpus { gpus.release(&claimed); }
+
+            if let Some(ref groups) = context.groups { groups.release(&key); }
+
+            if retried {
+                let attempt = self.inputs.attempt + 1;
+                self.inputs.inputs.lock().unwrap().requeue(job_id, &input, attempt, Duration::from_millis(0));
             }
 
             if self.flags & VERBOSE_MODE != 0 {
-                verbose::task_complete(&stdout, job_id, self.num_inputs, &input);
+                verbose::task_complete(&stdout, job_id, total, &input);
             }
         }
+
+        // Once there are no more inputs, idle slots duplicate jobs which have been running for far longer
+        // than the median runtime, and whichever run of the job completes first is kept.
+        let total_indice = stream::total(context.num_inputs).numtoa(10, &mut total_buffer);
+        let job_total = &total_buffer[total_indice..];
+        let host = self.host.as_ref().map(|host| &**host);
+        let (arguments, flags) = (self.arguments, self.flags);
+        speculate::duplicate_stragglers(&context, slot, host, &self.output_tx, &mut membuffer, |job_id, input, prefix, buffer| {
+            let start_indice = (job_id+1).numtoa(10, &mut id_buffer);
+            let command = command::ParallelCommand {
+                slot_no:   slot,
+                job_no:    &id_buffer[start_indice..],
+                job_total: job_total,
+                prefix:    prefix,
+                scratch:   scratch,
+                host:      host,
+                source:    context.provenance.get(job_id),
+                range:     context.provenance.range(job_id),
+                ..command::ParallelCommand::template(arguments, input, flags)
+            };
+            let child = match context.stages {
+                Some(ref stages) => stages.exec(&command, buffer, context.discard),
+                None             => command.exec(buffer, context.discard)
+            };
+            child.ok().map(|child| (child, flags))
+        });
     }
 }
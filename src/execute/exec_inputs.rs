@@ -1,42 +1,93 @@
-use arguments::{self, JOBLOG};
+use arguments;
 use execute::command;
-use input_iterator::InputsLock;
+use input_iterator::{stream, InputsLock};
+use numtoa::NumToA;
 use shell;
-use time::Timespec;
+use time::{self, Timespec};
 use verbose;
-use super::job_log::JobLog;
-use super::pipe::disk::State;
-use super::child::handle_child;
+use super::pipe::disk::{self, State};
+use super::child::{handle_child, job_timeout};
+use super::context::{Ended, RunContext};
+use super::ionice;
+use super::scratch::Scratch;
+use super::slots::job_weight;
+use super::speculate;
+use super::remote::{self, Host};
+use super::window::{Dispatch, Expired};
 
-use std::u16;
 use std::time::Duration;
 use std::io::{self, Read, Write};
-use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::sync::mpsc::SyncSender;
 
 /// Contains all the required data needed for executing commands in parallel.
 /// The inputs will be executed as commands themselves.
 pub struct ExecInputs<IO: Read> {
-    pub num_inputs: usize,
-    pub timeout:    Duration,
-    pub inputs:     InputsLock<IO>,
-    pub output_tx:  Sender<State>,
-    pub tempdir:    String,
+    pub slot:        usize,
+    /// The settings and shared state of the run, which every slot holds.
+    pub context:     Arc<RunContext>,
+    /// The remote host which the jobs of this slot are executed on, with `--sshlogin`.
+    pub host:        Option<Arc<Host>>,
+    pub scratch:     Option<Scratch>,
+    pub inputs:      InputsLock<IO>,
+    pub output_tx:   SyncSender<State>,
 }
 
 impl<IO: Read> ExecInputs<IO> {
     pub fn run(&mut self, mut flags: u16) {
+        ionice::lower();
+        let context = self.context.clone();
         let stdout = io::stdout();
         let stderr = io::stderr();
 
-        let has_timeout   = self.timeout != Duration::from_millis(0);
         let mut input     = String::with_capacity(64);
+        let mut key       = String::new();
+        let mut prefix    = Vec::new();
+        let mut needs     = Vec::new();
+        let mut claimed   = Vec::new();
+        let mut visible   = String::new();
         let mut id_buffer = [0u8; 20];
+        let mut membuffer = vec![0u8; context.output_size];
+
+        let slot             = &self.slot.to_string();
+        let scratch          = self.scratch.as_ref().map_or("", |scratch| scratch.path());
+        let mut seq_buffer   = [0u8; 20];
+        let mut total_buffer = [0u8; 20];
+
+        loop {
+            // Once the standard output has been closed, the remaining inputs are not needed.
+            if context.downstream.closed() { break }
+            // With `--hostfile-watch`, a host which was removed from the sshloginfile is given no further jobs.
+            if remote::drained(self.host.as_ref().map(|host| &**host)) { break }
+
+            let job_id = match context.groups {
+                Some(ref groups) => groups.next(&mut self.inputs, "", &mut input, &mut key),
+                None             => self.inputs.try_next(&mut input)
+            };
+            let job_id = match job_id { Some(job_id) => job_id, None => break };
+
+            // With `--total-jobs`, the hint is the total until every input has been read.
+            let total = stream::total(context.num_inputs);
+            let total_indice = total.numtoa(10, &mut total_buffer);
+            let job_total = &total_buffer[total_indice..];
 
-        while let Some(job_id) = self.inputs.try_next(&mut input) {
             if flags & arguments::VERBOSE_MODE != 0 {
-                verbose::processing_task(&stdout, job_id+1, self.num_inputs, &input);
+                verbose::processing_task(&stdout, job_id+1, total, &input);
             }
 
+            // Inputs whose `--not-before` time has not arrived are set aside until it has, and inputs
+            // past their `--not-after` deadline are skipped or failed.
+            let expired = match context.window.map(|window| (window, window.dispatch(&input, job_id))) {
+                Some((_, Dispatch::Wait(delay))) => {
+                    let attempt = self.inputs.attempt;
+                    self.inputs.inputs.lock().unwrap().requeue(job_id, &input, attempt, delay);
+                    if let Some(ref groups) = context.groups { groups.release(&key); }
+                    continue
+                },
+                Some((window, Dispatch::Expired)) => Some(window.expired),
+                _ => None
+            };
+
             // Checks the current command to determine if a shell will be required.
             if shell::required(shell::Kind::Input(&input)) {
                 flags |= arguments::SHELL_ENABLED;
@@ -44,36 +95,143 @@ impl<IO: Read> ExecInputs<IO> {
                 flags &= u16::MAX ^ arguments::SHELL_ENABLED;
             }
 
-            let (start_time, end_time, exit_value, signal) = match command::get_command_output(&input, flags) {
-                Ok(child) => {
-                    handle_child(child, &self.output_tx, flags, job_id, input.clone(), has_timeout, self.timeout,
-                        &self.tempdir, &mut id_buffer)
+            context.launcher.prefix(job_id, &mut prefix);
+            let timeout = job_timeout(&input, job_id, context.timeout_col, context.timeout);
+            let timeout = context.budget.as_ref().map_or(timeout, |budget| budget.timeout(timeout));
+            let has_timeout = timeout != Duration::from_millis(0);
+
+            // The remaining inputs are skipped once enough jobs have succeeded with `--return-first`,
+            // or once a limit of `--max-runtime` or `--max-failures` has been exceeded.
+            let stopped = context.first.as_ref().map_or(false, |first| first.reached())
+                || context.budget.as_ref().map_or(false, |budget| budget.exhausted());
+            let runnable = !stopped && expired.is_none();
+
+            // Jobs wait until the units of the pools that they need, including the `--class` of their
+            // command, are available, before claiming any weighted job slots.
+            let pools = context.pools.as_ref().filter(|_| runnable).inspect(|pools| {
+                pools.needs(&input, job_id, slot, &input, &mut needs);
+                pools.acquire(&needs);
+            });
+
+            // With `--gpus`, jobs wait until enough GPUs are free, and are given them as `CUDA_VISIBLE_DEVICES`.
+            let gpus = context.gpus.as_ref().filter(|_| runnable).inspect(|gpus| {
+                gpus.acquire(&mut claimed, &mut visible);
+            });
+
+            // If jobs are weighted, wait until enough job slots are free to accommodate this job.
+            let weight = context.slots.as_ref().filter(|_| runnable)
+                .map(|slots| slots.acquire(job_weight(&input, job_id, context.weight_col)));
+
+            let start_indice = (job_id+1).numtoa(10, &mut seq_buffer);
+            let env = command::JobEnv {
+                seq: &seq_buffer[start_indice..], slot: slot, total: job_total, scratch: scratch, gpus: &visible,
+                host: self.host.as_ref().map(|host| &**host), input: ""
+            };
+            let output = if !runnable { None } else {
+                Some(command::get_command_output(&input, flags, context.discard, &prefix, &env))
+            };
+            // A job which lost to its duplicate with `--speculative-max` is recorded by the duplicate,
+            // and a failed job which will be attempted again with `--retries` by its last attempt.
+            let mut lost = false;
+            let mut ran = false;
+            let mut retried = false;
+            let (start_time, end_time, exit_value, signal) = match output {
+                None if !stopped && expired == Some(Expired::Fail) => {
+                    if let Some(ref budget) = context.budget { budget.record(true, time::Duration::zero()); }
+                    let message = format!("{}: {}: input is past its deadline\n", job_id+1, input);
+                    disk::error(job_id, message, &self.output_tx, &context.tempdir, &mut id_buffer);
+                    (Timespec::new(0, 0), Timespec::new(0, 0), -1, 0)
+                },
+                None => {
+                    disk::skip(job_id, &self.output_tx, &context.tempdir, &mut id_buffer);
+                    (Timespec::new(0, 0), Timespec::new(0, 0), -1, 0)
+                },
+                Some(Ok(child)) => {
+                    ran = true;
+                    if let Some(ref progress) = context.progress { progress.started(job_id, slot, &input); }
+                    if let Some(ref meter) = context.meter { meter.started(); }
+                    if let Some(ref speculation) = context.speculation { speculation.start(job_id, &input, child.id()); }
+                    let pid = child.id();
+                    context.downstream.start(pid);
+                    let retry = self.inputs.attempt < context.retries;
+                    let result = handle_child(child, &self.output_tx, flags, job_id, input.clone(), has_timeout,
+                        timeout, &context.termseq, &context.tempdir, &mut id_buffer, &mut membuffer,
+                        context.spool.as_ref().map(|spool| &**spool), context.first.as_ref().map(|first| &**first),
+                        retry, context.speculation.as_ref().map(|speculation| (&**speculation, false)), context.tag.as_ref());
+                    context.downstream.finish(pid);
+                    match result {
+                        Some(result) if retry && (result.2 != 0 || result.3 != 0) => {
+                            if let Some(ref budget) = context.budget { budget.record(false, result.1 - result.0); }
+                            retried = true;
+                            result
+                        },
+                        Some(result) => {
+                            if let Some(ref status) = context.status { status.record(result.2, result.3); }
+                            if let Some(ref budget) = context.budget {
+                                budget.record(result.2 != 0 || result.3 != 0, result.1 - result.0);
+                            }
+                            result
+                        },
+                        None => {
+                            lost = true;
+                            (Timespec::new(0, 0), Timespec::new(0, 0), -1, 0)
+                        }
+                    }
                 },
-                Err(why) => {
+                Some(Err(why)) => {
+                    if let Some(ref status) = context.status { status.record_error(&why); }
+                    if let Some(ref budget) = context.budget { budget.record(true, time::Duration::zero()); }
                     let mut stderr = stderr.lock();
                     let _ = write!(&mut stderr, "parallel: command error: {}: {}\n", input, why);
                     let message = format!("{}: {}: {}\n", job_id, input, why);
-                    let _ = self.output_tx.send(State::Error(job_id, message));
+                    disk::error(job_id, message, &self.output_tx, &context.tempdir, &mut id_buffer);
                     (Timespec::new(0, 0), Timespec::new(0, 0), -1, 0)
                 }
             };
 
-            if flags & JOBLOG != 0 {
-                let runtime = end_time - start_time;
-                let _ = self.output_tx.send(State::JobLog(JobLog {
-                    job_id:     job_id,
-                    start_time: start_time,
-                    runtime:    runtime.num_nanoseconds().unwrap_or(0) as u64,
-                    exit_value: exit_value,
-                    signal:     signal,
-                    flags:      flags,
-                    command:    input.clone(),
-                }));
+            let ended = Ended { start_time, end_time, exit_value, signal, ran, lost, retried };
+            context.record(slot, self.host.as_ref().map(|host| &**host), &self.output_tx, flags, job_id, &input, &input,
+                &prefix, &ended);
+
+            if let (Some(slots), Some(weight)) = (context.slots.as_ref(), weight) {
+                slots.release(weight);
+            }
+
+            if let Some(pools) = pools { pools.release(&needs); }
+
+            if let Some(gpus) = gpus { gpus.release(&claimed); }
+
+            if let Some(ref groups) = context.groups { groups.release(&key); }
+
+            if retried {
+                let attempt = self.inputs.attempt + 1;
+                self.inputs.inputs.lock().unwrap().requeue(job_id, &input, attempt, Duration::from_millis(0));
             }
 
             if flags & arguments::VERBOSE_MODE != 0 {
-                verbose::task_complete(&stdout, job_id, self.num_inputs, &input);
+                verbose::task_complete(&stdout, job_id, total, &input);
             }
         }
+
+        // Once there are no more inputs, idle slots duplicate jobs which have been running for far longer
+        // than the median runtime, and whichever run of the job completes first is kept.
+        let total_indice = stream::total(context.num_inputs).numtoa(10, &mut total_buffer);
+        let job_total = &total_buffer[total_indice..];
+        let host = self.host.as_ref().map(|host| &**host);
+        speculate::duplicate_stragglers(&context, slot, host, &self.output_tx, &mut membuffer, |job_id, input, prefix, buffer| {
+            let flags = if shell::required(shell::Kind::Input(input)) {
+                flags | arguments::SHELL_ENABLED
+            } else {
+                flags & (u16::MAX ^ arguments::SHELL_ENABLED)
+            };
+
+            let start_indice = (job_id+1).numtoa(10, &mut seq_buffer);
+            let env = command::JobEnv {
+                seq: &seq_buffer[start_indice..], slot: slot, total: job_total, scratch: scratch, gpus: "",
+                host: host, input: ""
+            };
+            buffer.push_str(input);
+            command::get_command_output(input, flags, context.discard, prefix, &env).ok().map(|child| (child, flags))
+        });
     }
 }
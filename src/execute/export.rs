@@ -0,0 +1,130 @@
+use input_iterator::{InputIterator, InputIteratorErr, Provenance};
+use tokenizer::Token;
+use arguments;
+use execute::command;
+use numtoa::NumToA;
+
+use std::io::{self, Read, Write};
+
+/// The cluster schedulers whose job array scripts may be generated with `--export`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scheduler { Slurm, Pbs, Lsf }
+
+impl Scheduler {
+    pub fn parse(name: &str) -> Option<Scheduler> {
+        match name {
+            "slurm" => Some(Scheduler::Slurm),
+            "pbs"   => Some(Scheduler::Pbs),
+            "lsf"   => Some(Scheduler::Lsf),
+            _       => None
+        }
+    }
+
+    /// The directives that define a job array of `total` jobs, numbered from 1.
+    fn header(self, total: usize) -> String {
+        match self {
+            Scheduler::Slurm => format!("#SBATCH --job-name=parallel\n#SBATCH --array=1-{}\n", total),
+            Scheduler::Pbs   => format!("#PBS -N parallel\n#PBS -J 1-{}\n", total),
+            Scheduler::Lsf   => format!("#BSUB -J \"parallel[1-{}]\"\n", total),
+        }
+    }
+
+    /// The environment variable which contains the array index of the running job.
+    fn index_variable(self) -> &'static str {
+        match self {
+            Scheduler::Slurm => "$SLURM_ARRAY_TASK_ID",
+            Scheduler::Pbs   => "${PBS_ARRAY_INDEX:-$PBS_ARRAYID}",
+            Scheduler::Lsf   => "$LSB_JOBINDEX",
+        }
+    }
+}
+
+/// Instead of executing commands, a job array script for the given cluster `scheduler` will be written
+/// to the standard output, where each element of the array executes the command of the same job number.
+pub fn export<IO: Read>(scheduler: Scheduler, flags: u16, inputs: InputIterator<IO>, arguments: &[Token],
+    provenance: &Provenance)
+{
+    let stdout             = io::stdout();
+    let stdout             = &mut stdout.lock();
+    let stderr             = io::stderr();
+    let stderr             = &mut stderr.lock();
+    let mut command_buffer = String::new();
+    let pipe               = flags & arguments::PIPE_IS_ENABLED != 0;
+    let mut id_buffer      = [0u8; 20];
+    let mut total_buffer   = [0u8; 20];
+    let start_indice       = inputs.total_arguments.numtoa(10, &mut total_buffer);
+    let job_total          = &total_buffer[start_indice..];
+
+    let _ = stdout.write(b"#!/bin/sh\n");
+    let _ = stdout.write(scheduler.header(inputs.total_arguments).as_bytes());
+    let _ = write!(stdout, "\ncase \"{}\" in\n", scheduler.index_variable());
+
+    for (job_id, input) in inputs.enumerate() {
+        match input {
+            Ok(input) => {
+                let start_indice = (job_id+1).numtoa(10, &mut id_buffer);
+                let command = command::ParallelCommand {
+                    slot_no:   "1",
+                    job_no:    &id_buffer[start_indice..],
+                    job_total: job_total,
+                    scratch:   "${TMPDIR:-/tmp}",
+                    source:    provenance.get(job_id),
+                    range:     provenance.range(job_id),
+                    ..command::ParallelCommand::template(arguments, &input, flags)
+                };
+
+                command.build_arguments(&mut command_buffer);
+                if !pipe {
+                    // The job array script is interpreted by a shell, although inputs which are
+                    // commands are meant to be interpreted by it.
+                    let quote = flags & arguments::INPUTS_ARE_COMMANDS == 0;
+                    command::append_argument(&mut command_buffer, command.command_template, command.input, flags, quote);
+                }
+
+                let _ = stdout.write(b"    ");
+                let _ = stdout.write(&id_buffer[start_indice..]);
+                let _ = stdout.write(b") ");
+                if pipe {
+                    let _ = write!(stdout, "printf '%s\\n' {} | ", single_quote(&input));
+                }
+                let _ = write!(stdout, "exec sh -c {} ;;\n", single_quote(command_buffer.trim()));
+                command_buffer.clear();
+            },
+            Err(why) => {
+                match why {
+                    InputIteratorErr::FileRead(path, why) => {
+                        let _ = write!(stderr, "parallel: input file read error: {:?}: {}\n", path, why);
+                    },
+                }
+            }
+        }
+    }
+
+    let _ = stdout.write(b"esac\n");
+}
+
+/// Wraps the input within single quotes, so that the shell will interpret it literally.
+fn single_quote(input: &str) -> String {
+    let mut output = String::with_capacity(input.len() + 2);
+    output.push('\'');
+    for character in input.chars() {
+        if character == '\'' {
+            output.push_str("'\\''");
+        } else {
+            output.push(character);
+        }
+    }
+    output.push('\'');
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoting() {
+        assert_eq!(single_quote("echo 'a b'"), "'echo '\\''a b'\\'''");
+        assert_eq!(single_quote("ls $HOME"), "'ls $HOME'");
+    }
+}
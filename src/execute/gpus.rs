@@ -0,0 +1,91 @@
+use std::env;
+use std::fs;
+use std::process::Command;
+use std::sync::{Condvar, Mutex};
+
+/// Obtains the GPUs which jobs may be assigned to. If `CUDA_VISIBLE_DEVICES` is already set, only
+/// those devices are used. Otherwise, the devices are listed by `nvidia-smi`, or are counted from
+/// the driver's entries within `/proc` when `nvidia-smi` is not available.
+pub fn detect() -> Vec<String> {
+    if let Ok(visible) = env::var("CUDA_VISIBLE_DEVICES") {
+        return visible.split(',').map(str::trim).filter(|device| !device.is_empty()).map(String::from).collect();
+    }
+
+    let listed = Command::new("nvidia-smi").args(["--query-gpu=index", "--format=csv,noheader"]).output();
+    if let Ok(output) = listed {
+        if output.status.success() {
+            return String::from_utf8_lossy(&output.stdout).lines().map(str::trim)
+                .filter(|device| !device.is_empty()).map(String::from).collect();
+        }
+    }
+
+    fs::read_dir("/proc/driver/nvidia/gpus")
+        .map(|entries| (0..entries.count()).map(|device| device.to_string()).collect())
+        .unwrap_or_default()
+}
+
+/// With `--gpus N`, assigns `N` of the available GPUs to each job, which are given to the job as
+/// `CUDA_VISIBLE_DEVICES`. Jobs wait until enough devices are no longer assigned to other jobs.
+pub struct Gpus {
+    devices: Vec<String>,
+    per_job: usize,
+    free:    Mutex<Vec<bool>>,
+    condvar: Condvar,
+}
+
+impl Gpus {
+    /// Jobs which need more GPUs than there are may still run, with every GPU.
+    pub fn new(devices: Vec<String>, per_job: usize) -> Gpus {
+        let free = vec![true; devices.len()];
+        Gpus { per_job: per_job.min(devices.len()), devices, free: Mutex::new(free), condvar: Condvar::new() }
+    }
+
+    /// Blocks until enough GPUs are free and claims them, recording their positions in `claimed`,
+    /// and writing their comma-separated devices into `visible`.
+    pub fn acquire(&self, claimed: &mut Vec<usize>, visible: &mut String) {
+        claimed.clear();
+        visible.clear();
+        let mut free = self.free.lock().unwrap();
+        while free.iter().filter(|&&free| free).count() < self.per_job {
+            free = self.condvar.wait(free).unwrap();
+        }
+
+        for (position, free) in free.iter_mut().enumerate().filter(|&(_, ref free)| **free).take(self.per_job) {
+            *free = false;
+            claimed.push(position);
+            if !visible.is_empty() { visible.push(','); }
+            visible.push_str(&self.devices[position]);
+        }
+    }
+
+    /// Returns the GPUs of a completed job, waking up any jobs waiting on them.
+    pub fn release(&self, claimed: &[usize]) {
+        {
+            let mut free = self.free.lock().unwrap();
+            for &position in claimed { free[position] = true; }
+        }
+        self.condvar.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gpu_assignment() {
+        let gpus = Gpus::new(vec!["0".to_owned(), "1".to_owned(), "3".to_owned()], 2);
+        let (mut first, mut second) = (Vec::new(), Vec::new());
+        let mut visible = String::new();
+        gpus.acquire(&mut first, &mut visible);
+        assert_eq!(visible, "0,1");
+        gpus.release(&first[..1]);
+        gpus.acquire(&mut second, &mut visible);
+        assert_eq!(visible, "0,3");
+        assert_eq!(second, vec![0, 2]);
+
+        let gpus = Gpus::new(vec!["0".to_owned()], 4);
+        gpus.acquire(&mut first, &mut visible);
+        assert_eq!(visible, "0");
+    }
+}
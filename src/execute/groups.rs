@@ -0,0 +1,130 @@
+use execute::command::ParallelCommand;
+use input_iterator::InputsLock;
+use numtoa::NumToA;
+use tokenizer::Token;
+
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+use std::sync::{Condvar, Mutex};
+
+/// The number of over-limit inputs that may be set aside before waiting on a running job to finish.
+const MAX_DEFERRED: usize = 1024;
+
+/// An input that was set aside because its key had already reached the limit of running jobs.
+struct Deferred {
+    job_id:  usize,
+    attempt: usize,
+    input:   String,
+    key:     String,
+}
+
+struct GroupState {
+    running:  HashMap<String, usize>,
+    deferred: VecDeque<Deferred>,
+}
+
+impl GroupState {
+    fn has_room(&self, key: &str, limit: usize) -> bool {
+        self.running.get(key).map_or(true, |&running| running < limit)
+    }
+
+    fn claim(&mut self, key: &str) {
+        *self.running.entry(key.to_owned()).or_insert(0) += 1;
+    }
+}
+
+/// Limits how many jobs that share the same key, as generated from the `--key` template, may run at
+/// the same time. Inputs whose key has reached the limit are set aside and dispatched as soon as a
+/// job with the same key completes, so that job slots are not left idle in the meantime.
+pub struct KeyGroups {
+    state:    Mutex<GroupState>,
+    condvar:  Condvar,
+    limit:    usize,
+    template: &'static [Token],
+}
+
+impl KeyGroups {
+    pub fn new(limit: usize, template: &'static [Token]) -> KeyGroups {
+        KeyGroups {
+            state:    Mutex::new(GroupState { running: HashMap::new(), deferred: VecDeque::new() }),
+            condvar:  Condvar::new(),
+            limit:    limit,
+            template: template,
+        }
+    }
+
+    /// Obtains the next job whose key has not reached its limit, writing the job's input and key
+    /// into the supplied buffers. Inputs that were set aside earlier take priority over new inputs.
+    pub fn next<IO: Read>(&self, inputs: &mut InputsLock<IO>, slot: &str, input: &mut String,
+        key: &mut String) -> Option<usize>
+    {
+        let mut fresh = inputs.try_next(input).map(|job_id| (job_id, self.render(job_id, slot, input)));
+        let mut exhausted = fresh.is_none();
+        let mut state = self.state.lock().unwrap();
+
+        loop {
+            let position = state.deferred.iter().position(|job| state.has_room(&job.key, self.limit));
+            if let Some(position) = position {
+                if let Some((job_id, job_key)) = fresh.take() {
+                    state.deferred.push_back(Deferred {
+                        job_id: job_id, attempt: inputs.attempt, input: input.clone(), key: job_key
+                    });
+                }
+                let job = state.deferred.remove(position).unwrap();
+                state.claim(&job.key);
+                inputs.attempt = job.attempt;
+                *input = job.input;
+                *key = job.key;
+                return Some(job.job_id);
+            }
+
+            if let Some((job_id, job_key)) = fresh.take() {
+                if state.has_room(&job_key, self.limit) {
+                    state.claim(&job_key);
+                    *key = job_key;
+                    return Some(job_id);
+                }
+                state.deferred.push_back(Deferred {
+                    job_id: job_id, attempt: inputs.attempt, input: input.clone(), key: job_key
+                });
+            }
+
+            if !exhausted && state.deferred.len() < MAX_DEFERRED {
+                drop(state);
+                match inputs.next_input(input) {
+                    Some(job_id) => fresh = Some((job_id, self.render(job_id, slot, input))),
+                    None         => exhausted = true
+                }
+                state = self.state.lock().unwrap();
+            } else if exhausted && state.deferred.is_empty() {
+                return None
+            } else {
+                state = self.condvar.wait(state).unwrap();
+            }
+        }
+    }
+
+    /// Marks a job with the given key as completed, waking up any threads waiting on that key.
+    pub fn release(&self, key: &str) {
+        let mut state = self.state.lock().unwrap();
+        let finished = match state.running.get_mut(key) {
+            Some(running) => { *running -= 1; *running == 0 },
+            None          => false
+        };
+        if finished { state.running.remove(key); }
+        self.condvar.notify_all();
+    }
+
+    /// Generates the key of an input from the `--key` template.
+    fn render(&self, job_id: usize, slot: &str, input: &str) -> String {
+        let mut id_buffer = [0u8; 20];
+        let start_indice = (job_id+1).numtoa(10, &mut id_buffer);
+        let mut key = String::new();
+        ParallelCommand {
+            slot_no: slot,
+            job_no:  &id_buffer[start_indice..],
+            ..ParallelCommand::template(self.template, input, 0)
+        }.build_arguments(&mut key);
+        key
+    }
+}
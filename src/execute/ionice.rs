@@ -0,0 +1,152 @@
+//! With `--spool-idle`, the threads of Parallel perform their I/O within the idle scheduling class,
+//! as with `ionice -c3`, so that spooling the output of jobs doesn't compete with the disk traffic
+//! of the jobs themselves. The I/O priority is inherited by child processes, so each job has its
+//! priority restored to that of Parallel before it is executed.
+//!
+//! With `--ionice`, each job is instead executed with the I/O priority that was given, which is
+//! set within the job after it has been forked, so that Parallel itself keeps its own.
+
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+
+/// Whether `--spool-idle` was given, and the I/O priority of Parallel before it was lowered.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static ORIGINAL: AtomicI32 = AtomicI32::new(0);
+/// The I/O priority of each job, if one was given with `--ionice`, or otherwise -1.
+static JOBS: AtomicI32 = AtomicI32::new(-1);
+
+/// Records the current I/O priority, so that it may be restored for jobs, and lowers the priority
+/// of the calling thread. Returns `false` if the I/O priority could not be obtained.
+pub fn enable() -> bool {
+    match sys::get() {
+        Some(priority) => {
+            ORIGINAL.store(priority, Ordering::SeqCst);
+            ENABLED.store(true, Ordering::SeqCst);
+            lower();
+            true
+        },
+        None => false
+    }
+}
+
+/// Moves the I/O of the calling thread into the idle class, if `--spool-idle` was given.
+pub fn lower() {
+    if ENABLED.load(Ordering::Relaxed) { sys::set(sys::IDLE); }
+}
+
+/// Sets the I/O priority which each job is executed with, as obtained from `parse()`.
+pub fn set_jobs(priority: i32) { JOBS.store(priority, Ordering::SeqCst); }
+
+/// Restores the original I/O priority within the job, before it is executed, or sets the priority
+/// that was given with `--ionice`, in which case the job fails if the priority could not be set.
+pub fn restore(command: &mut Command) {
+    let jobs = JOBS.load(Ordering::Relaxed);
+    if jobs >= 0 {
+        sys::apply(command, jobs);
+    } else if ENABLED.load(Ordering::Relaxed) {
+        sys::restore(command, ORIGINAL.load(Ordering::Relaxed));
+    }
+}
+
+/// Parses an I/O scheduling class, as with `ionice -c`, which is one of `realtime`, `best-effort`,
+/// or `idle`, or their numbers 1 to 3, optionally followed by a level from 0 to 7, as with
+/// `ionice -n`, such as `best-effort:7`. The level defaults to 4, and the idle class has none.
+pub fn parse(value: &str) -> Option<i32> {
+    let (class, level) = match value.find(':') {
+        Some(position) => (&value[..position], Some(&value[position+1..])),
+        None => (value, None)
+    };
+    let class = match class {
+        "realtime" | "1"    => 1,
+        "best-effort" | "2" => 2,
+        "idle" | "3"        => 3,
+        _                   => return None
+    };
+    let level = match level {
+        Some(_) if class == 3 => return None,
+        Some(level) => level.parse::<i32>().ok().filter(|level| *level >= 0 && *level <= 7)?,
+        None => if class == 3 { 0 } else { 4 }
+    };
+    Some(class << 13 | level)
+}
+
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::io;
+    use std::os::raw::{c_int, c_long};
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+
+    #[cfg(target_arch = "x86_64")]
+    const SYS_IOPRIO_SET: c_long = 251;
+    #[cfg(target_arch = "x86_64")]
+    const SYS_IOPRIO_GET: c_long = 252;
+    #[cfg(target_arch = "x86")]
+    const SYS_IOPRIO_SET: c_long = 289;
+    #[cfg(target_arch = "x86")]
+    const SYS_IOPRIO_GET: c_long = 290;
+    #[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+    const SYS_IOPRIO_SET: c_long = 30;
+    #[cfg(any(target_arch = "aarch64", target_arch = "riscv64"))]
+    const SYS_IOPRIO_GET: c_long = 31;
+    #[cfg(target_arch = "arm")]
+    const SYS_IOPRIO_SET: c_long = 314;
+    #[cfg(target_arch = "arm")]
+    const SYS_IOPRIO_GET: c_long = 315;
+
+    /// `IOPRIO_WHO_PROCESS`, which with an ID of zero refers to the calling thread.
+    const WHO_PROCESS: c_int = 1;
+    /// `IOPRIO_CLASS_IDLE`, shifted into the class bits of an I/O priority.
+    pub const IDLE: c_int = 3 << 13;
+
+    extern "C" {
+        fn syscall(number: c_long, ...) -> c_long;
+    }
+
+    pub fn get() -> Option<c_int> {
+        let priority = unsafe { syscall(SYS_IOPRIO_GET, WHO_PROCESS, 0 as c_int) };
+        if priority < 0 { None } else { Some(priority as c_int) }
+    }
+
+    pub fn set(priority: c_int) -> bool {
+        unsafe { syscall(SYS_IOPRIO_SET, WHO_PROCESS, 0 as c_int, priority) == 0 }
+    }
+
+    pub fn restore(command: &mut Command, priority: c_int) {
+        // Only the system call is made between fork and exec, which is async-signal-safe.
+        unsafe { command.pre_exec(move || { set(priority); Ok(()) }); }
+    }
+
+    pub fn apply(command: &mut Command, priority: c_int) {
+        unsafe {
+            command.pre_exec(move || if set(priority) { Ok(()) } else { Err(io::Error::last_os_error()) });
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod sys {
+    use std::process::Command;
+
+    pub const IDLE: i32 = 0;
+
+    pub fn get() -> Option<i32> { None }
+    pub fn set(_priority: i32) -> bool { false }
+    pub fn restore(_command: &mut Command, _priority: i32) {}
+    pub fn apply(_command: &mut Command, _priority: i32) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn io_classes() {
+        assert_eq!(parse("idle"), Some(3 << 13));
+        assert_eq!(parse("best-effort"), Some(2 << 13 | 4));
+        assert_eq!(parse("2:7"), Some(2 << 13 | 7));
+        assert_eq!(parse("idle:7"), None);
+        assert_eq!(parse("best-effort:8"), None);
+        assert_eq!(parse("low"), None);
+    }
+}
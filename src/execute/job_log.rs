@@ -1,7 +1,10 @@
 use arguments::JOBLOG_8601;
+use arguments::errors::FileErr;
 use numtoa::NumToA;
+use std::collections::{BTreeMap, HashSet};
 use std::fs::File;
-use std::io::{Write, BufWriter};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
 use time::{at, Timespec};
 
 // Each `JobLog` consists of a single job's statistics ready to be written to the job log file.
@@ -18,105 +21,244 @@ pub struct JobLog {
     pub signal:     i32,
     /// Contains the configuration parameters for the joblog
     pub flags:      u16,
+    /// The remote host that the job was executed on, or `None` for the local machine
+    pub host:       Option<String>,
+    /// The source and line of the input that produced this job, in the form of `source:line`
+    pub source:     Option<String>,
+    /// The label of the job, with `--label`
+    pub label:      Option<String>,
     /// The actual `command` that was executed for this job
     pub command:    String
 }
 
 impl JobLog {
-    /// Writes an individual job log to the job log file, efficiently.
-    pub fn write_entry(&self, joblog: &mut File, id_buffer: &mut [u8], pad: usize) {
+    /// Writes an individual job log to the job log file, efficiently, as a tab-separated line. The
+    /// `joblog` is expected to be buffered, so that consecutive entries are written to the file together.
+    pub fn write_entry<W: Write>(&self, joblog: &mut W, id_buffer: &mut [u8]) {
         // 1: JobID
-        let mut joblog = BufWriter::new(joblog);
         let mut index = (self.job_id + 1).numtoa(10, id_buffer);
         let _ = joblog.write(&id_buffer[index..]);
-        for _ in 0..pad - (20 - index) {
-            let _ = joblog.write(b" ");
-        }
+        let _ = joblog.write(b"\t");
+
+        // 2: Host, which is `:` for the local machine, as with GNU Parallel.
+        let _ = joblog.write(self.host.as_ref().map_or(":", |host| host.as_str()).as_bytes());
+        let _ = joblog.write(b"\t");
 
-        // 2: StartTime
+        // 3: StartTime
         if self.flags & JOBLOG_8601 != 0 {
             // ISO 8601 representation of the time
             let tm = at(self.start_time);
-            let _ = write!(joblog, "{}-{:02}-{:02} {:02}:{:02}:{:02}  ", 1900+tm.tm_year, 1+tm.tm_mon,
+            let _ = write!(joblog, "{}-{:02}-{:02} {:02}:{:02}:{:02}\t", 1900+tm.tm_year, 1+tm.tm_mon,
                 tm.tm_mday, tm.tm_hour, tm.tm_min, tm.tm_sec);
 
         } else {
-            // Represented in seconds, with two decimal places
+            // Represented in seconds, with three decimal places
             index = self.start_time.sec.numtoa(10, id_buffer);
             let _ = joblog.write(&id_buffer[index..]);
             let _ = joblog.write(b".");
-            let decimal = (self.start_time.nsec % 1_000_000_000) / 1_000_000;
-            if decimal == 0 {
-                let _ = joblog.write(b"000");
-            } else {
-                index = decimal.numtoa(10, id_buffer);
-                match 20 - index {
-                    1 => { let _ = joblog.write(b"00"); },
-                    2 => { let _ = joblog.write(b"0"); },
-                    _ => (),
-                };
-                let _ = joblog.write(&id_buffer[index..]);
-            }
-            let _ = joblog.write(b"  ");
+            write_millis(joblog, (self.start_time.nsec % 1_000_000_000) as u64 / 1_000_000, id_buffer);
+            let _ = joblog.write(b"\t");
         }
 
-        // 3: Runtime in seconds, with up to three decimal places.
+        // 4: Runtime in seconds, with three decimal places.
         index = (self.runtime / 1_000_000_000).numtoa(10, id_buffer);
-        for _ in 0..6 - (20 - index) {
-            let _ = joblog.write(b" ");
-        }
         let _ = joblog.write(&id_buffer[index..]);
         let _ = joblog.write(b".");
-        let decimal = (self.runtime % 1_000_000_000) / 1_000_000;
-        if decimal == 0 {
-            let _ = joblog.write(b"000");
-        } else {
-            index = decimal.numtoa(10, id_buffer);
-            match 20 - index {
-                1 => { let _ = joblog.write(b"00"); },
-                2 => { let _ = joblog.write(b"0"); },
-                _ => (),
-            };
-            let _ = joblog.write(&id_buffer[index..]);
-        }
-        let _ = joblog.write(b"  ");
+        write_millis(joblog, (self.runtime % 1_000_000_000) / 1_000_000, id_buffer);
+        let _ = joblog.write(b"\t");
 
-        // 4: Exit Value
+        // 5: Exit Value
         index = self.exit_value.numtoa(10, id_buffer);
         let _ = joblog.write(&id_buffer[index..]);
-        for _ in 0..9 - (20 - index) {
-            let _ = joblog.write(b" ");
-        }
+        let _ = joblog.write(b"\t");
 
-        // 5: Signal
+        // 6: Signal
         index = self.signal.numtoa(10, id_buffer);
         let _ = joblog.write(&id_buffer[index..]);
-        for _ in 0..8 - (20 - index) {
-            let _ = joblog.write(b" ");
+        let _ = joblog.write(b"\t");
+
+        // 7: Source
+        let _ = joblog.write(self.source.as_ref().map_or("-", |source| source.as_str()).as_bytes());
+        let _ = joblog.write(b"\t");
+
+        // 8: Label, with `--label`
+        if let Some(ref label) = self.label {
+            let _ = joblog.write(label.as_bytes());
+            let _ = joblog.write(b"\t");
         }
 
-        // 5: Command
+        // 9: Command
         let _ = joblog.write(self.command.as_bytes());
         let _ = joblog.write(b"\n");
     }
 }
 
-/// Creates the column headers in the first line of the job log file
-pub fn create(file: &mut File, padding: usize, flags: u16) {
-    let mut joblog = BufWriter::new(file);
+/// Writes the milliseconds of a time as three digits.
+fn write_millis<W: Write>(joblog: &mut W, millis: u64, id_buffer: &mut [u8]) {
+    let index = millis.numtoa(10, id_buffer);
+    match 20 - index {
+        1 => { let _ = joblog.write(b"00"); },
+        2 => { let _ = joblog.write(b"0"); },
+        _ => (),
+    };
+    let _ = joblog.write(&id_buffer[index..]);
+}
+
+/// Creates the tab-separated column headers of the job log file, preceded by the run ID of the jobs
+/// within it. The jobs are `labeled` with `--label`.
+pub fn create<W: Write>(joblog: &mut W, flags: u16, run_id: &str, labeled: bool) {
+    let _ = writeln!(joblog, "# RunID: {}", run_id);
+
+    let start_time = if flags & JOBLOG_8601 != 0 { "StartTime(ISO-8601)" } else { "StartTime(s)" };
+    let label = if labeled { "Label\t" } else { "" };
+    let _ = writeln!(joblog, "Sequence\tHost\t{}\tRuntime(s)\tExitVal\tSignal\tSource\t{}Command", start_time, label);
+}
+
+/// Reads the job IDs of the jobs in an existing job log which are not to be executed again when
+/// resuming, which are every job that was logged, or with `succeeded`, only the jobs which exited
+/// successfully. A job log that does not exist has no jobs to resume.
+pub fn finished_jobs(path: &Path, succeeded: bool) -> io::Result<HashSet<usize>> {
+    let mut jobs = HashSet::new();
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(ref why) if why.kind() == io::ErrorKind::NotFound => return Ok(jobs),
+        Err(why) => return Err(why)
+    };
+
+    for line in BufReader::new(file).lines() {
+        if let Some((sequence, success)) = entry(&line?) {
+            if !succeeded || success { jobs.insert(sequence - 1); }
+        }
+    }
+    Ok(jobs)
+}
+
+/// Merges the job logs of several runs, such as the shards of a run, or a run and the runs which
+/// resumed it, into a single job log which is ordered by sequence number. Where a job was logged
+/// more than once, its last successful attempt is kept, or otherwise its last attempt, in the order
+/// of the `paths`.
+pub fn merge<W: Write>(paths: &[PathBuf], merged: &mut W) -> Result<(), FileErr> {
+    let mut run_ids = Vec::new();
+    let mut header = None;
+    let mut jobs = BTreeMap::<usize, (bool, String)>::new();
 
-    // Sequence column is at least 10 chars long, counting space separator.
-    let id_column_resize = if padding < 10 { 0 } else { padding - 10 };
-    let _ = joblog.write(b"Sequence  ");
-    for _ in 0..id_column_resize { let _ = joblog.write(b" "); }
+    for path in paths {
+        let file = File::open(path).map_err(|why| FileErr::Open(path.clone(), why))?;
+        for line in BufReader::new(file).lines() {
+            let line = line.map_err(|why| FileErr::Read(path.clone(), why))?;
+            if let Some(run_id) = line.strip_prefix("# RunID: ") {
+                let run_id = run_id.to_owned();
+                if !run_ids.contains(&run_id) { run_ids.push(run_id); }
+            } else if line.starts_with("Sequence\t") {
+                if header.is_none() { header = Some(line); }
+            } else if let Some((sequence, succeeded)) = entry(&line) {
+                let replace = jobs.get(&sequence).map_or(true, |&(kept, _)| succeeded || !kept);
+                if replace { jobs.insert(sequence, (succeeded, line)); }
+            }
+        }
+    }
 
-    if flags & JOBLOG_8601 != 0 {
-        let _ = joblog.write(b"StartTime(ISO-8601)  ");
-    } else {
-        let _ = joblog.write(b"StartTime(s)    ");
+    // The merged job log is only written once every job log has been read.
+    for run_id in &run_ids {
+        let _ = writeln!(merged, "# RunID: {}", run_id);
+    }
+    let header = header.unwrap_or_else(|| {
+        "Sequence\tHost\tStartTime(s)\tRuntime(s)\tExitVal\tSignal\tSource\tCommand".to_owned()
+    });
+    let _ = writeln!(merged, "{}", header);
+    for &(_, ref line) in jobs.values() {
+        let _ = writeln!(merged, "{}", line);
     }
+    let _ = merged.flush();
+    Ok(())
+}
 
+/// Parses the sequence number of an entry of a job log, and whether the job succeeded. Lines which
+/// are not entries, such as the run ID and the column headers, are passed over.
+fn entry(line: &str) -> Option<(usize, bool)> {
+    let columns = line.split('\t').collect::<Vec<&str>>();
+    if columns.len() < 8 { return None }
+    match columns[0].parse::<usize>() {
+        Ok(sequence) if sequence != 0 => Some((sequence, columns[4] == "0" && columns[5] == "0")),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn joblog_entry() {
+        let entry = JobLog {
+            job_id:     41,
+            start_time: Timespec::new(1_500_000_000, 7_000_000),
+            runtime:    2_050_000_000,
+            exit_value: 1,
+            signal:     0,
+            flags:      0,
+            host:       None,
+            source:     Some("inputs.txt:42".to_owned()),
+            label:      None,
+            command:    "gzip a b".to_owned(),
+        };
+        let mut joblog = Vec::new();
+        entry.write_entry(&mut joblog, &mut [0u8; 20]);
+        assert_eq!(String::from_utf8(joblog).unwrap(), "42\t:\t1500000000.007\t2.050\t1\t0\tinputs.txt:42\tgzip a b\n");
+
+        let entry = JobLog { label: Some("compress".to_owned()), ..entry };
+        let mut joblog = Vec::new();
+        entry.write_entry(&mut joblog, &mut [0u8; 20]);
+        assert_eq!(String::from_utf8(joblog).unwrap(),
+            "42\t:\t1500000000.007\t2.050\t1\t0\tinputs.txt:42\tcompress\tgzip a b\n");
+    }
+
+    #[test]
+    fn joblog_finished_jobs() {
+        let path = env::temp_dir().join("parallel_joblog_test.log");
+        fs::write(&path, concat!(
+            "# RunID: 0123abcd\n",
+            "Sequence\tHost\tStartTime(s)\tRuntime(s)\tExitVal\tSignal\tSource\tCommand\n",
+            "1\t:\t1500000000.007\t2.050\t0\t0\t-\techo a\n",
+            "2\t:\t1500000000.007\t2.050\t1\t0\t-\techo b\n",
+            "3\t:\t1500000000.007\t2.050\t-1\t9\t-\techo c\n",
+            "# RunID: 4567efab\n",
+            "Sequence\tHost\tStartTime(s)\tRuntime(s)\tExitVal\tSignal\tSource\tCommand\n",
+            "2\t:\t1500000010.007\t2.050\t0\t0\t-\techo b\n")).unwrap();
+        let logged = finished_jobs(&path, false).unwrap();
+        let succeeded = finished_jobs(&path, true).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(logged, [0, 1, 2].iter().cloned().collect());
+        assert_eq!(succeeded, [0, 1].iter().cloned().collect());
+    }
 
-    // Remaining columns, with the runtim column left-padded.
-    let _ = joblog.write(b"Runtime(s)  ExitVal  Signal  Command\n");
+    #[test]
+    fn joblog_merge() {
+        let (first, second) = (env::temp_dir().join("parallel_merge_1.log"), env::temp_dir().join("parallel_merge_2.log"));
+        fs::write(&first, concat!(
+            "# RunID: 0123abcd\n",
+            "Sequence\tHost\tStartTime(s)\tRuntime(s)\tExitVal\tSignal\tSource\tCommand\n",
+            "1\t:\t1500000000.007\t2.050\t0\t0\t-\techo a\n",
+            "3\t:\t1500000000.007\t2.050\t1\t0\t-\techo c\n")).unwrap();
+        fs::write(&second, concat!(
+            "# RunID: 4567efab\n",
+            "Sequence\tHost\tStartTime(s)\tRuntime(s)\tExitVal\tSignal\tSource\tCommand\n",
+            "2\t:\t1500000010.007\t2.050\t1\t0\t-\techo b\n",
+            "1\t:\t1500000010.007\t2.050\t1\t0\t-\techo a\n",
+            "3\t:\t1500000010.007\t2.050\t0\t0\t-\techo c\n")).unwrap();
+        let mut merged = Vec::new();
+        let result = merge(&[first.clone(), second.clone()], &mut merged);
+        let _ = fs::remove_file(&first);
+        let _ = fs::remove_file(&second);
+        result.unwrap();
+        assert_eq!(String::from_utf8(merged).unwrap(), concat!(
+            "# RunID: 0123abcd\n",
+            "# RunID: 4567efab\n",
+            "Sequence\tHost\tStartTime(s)\tRuntime(s)\tExitVal\tSignal\tSource\tCommand\n",
+            "1\t:\t1500000000.007\t2.050\t0\t0\t-\techo a\n",
+            "2\t:\t1500000010.007\t2.050\t1\t0\t-\techo b\n",
+            "3\t:\t1500000010.007\t2.050\t0\t0\t-\techo c\n"));
+    }
 }
@@ -0,0 +1,40 @@
+//! With `--label`, each job is given a label, which is generated from its input by a template, such
+//! as `{2}` for its second column, and recorded in the job log, so that a single file of inputs may
+//! mix several logical stages. With `--only-label`, only the jobs with one of the given labels are
+//! executed, and along with `--resume`, a single stage may be resumed or executed again.
+
+use numtoa::NumToA;
+use tokenizer::Token;
+use super::command::ParallelCommand;
+
+pub struct Labels {
+    template: &'static [Token],
+    only:     Vec<String>,
+}
+
+impl Labels {
+    pub fn new(template: &'static [Token], only: Vec<String>) -> Labels {
+        Labels { template, only }
+    }
+
+    /// Whether jobs are only executed if they have one of the labels of `--only-label`.
+    pub fn filters(&self) -> bool { !self.only.is_empty() }
+
+    /// Whether the job with the given `label` is to be executed.
+    pub fn selected(&self, label: &str) -> bool {
+        self.only.is_empty() || self.only.iter().any(|only| only == label)
+    }
+
+    /// Generates the label of a job from its input. Tabs and line endings are replaced by spaces,
+    /// so that the label occupies a single column of the job log.
+    pub fn render(&self, job_id: usize, input: &str) -> String {
+        let mut id_buffer = [0u8; 20];
+        let start_indice = (job_id+1).numtoa(10, &mut id_buffer);
+        let mut label = String::new();
+        ParallelCommand {
+            job_no: &id_buffer[start_indice..],
+            ..ParallelCommand::template(self.template, input, 0)
+        }.build_arguments(&mut label);
+        label.replace(['\t', '\n', '\r'], " ")
+    }
+}
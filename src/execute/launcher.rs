@@ -0,0 +1,114 @@
+use shell;
+use std::env;
+use std::process;
+
+/// Makes the root filesystem read-only from within a new mount namespace, except for the job's
+/// working directory, which is bound onto itself beforehand so that it stays writable, and mounts
+/// a fresh `/tmp`, before executing the job's command.
+const UNSHARE_SCRIPT: &'static str = "mount --rbind / / && mount --bind \"$PWD\" \"$PWD\" && cd \"$PWD\" && \
+    for m in $(cut -d' ' -f2 /proc/self/mounts); do \
+    [ \"$m\" = \"$PWD\" ] || mount -o remount,bind,ro \"$m\" 2>/dev/null; done; \
+    mount -t tmpfs tmpfs /tmp && exec \"$@\"";
+
+/// The programs which are capable of running jobs within their own namespaces.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Sandbox { Bubblewrap, Unshare }
+
+impl Sandbox {
+    /// Bubblewrap is preferred when it is installed, as it does not require user namespaces
+    /// to be permitted for unprivileged users.
+    pub fn detect() -> Sandbox {
+        if shell::program_exists("bwrap") { Sandbox::Bubblewrap } else { Sandbox::Unshare }
+    }
+}
+
+/// Describes the programs that each job's command will be launched within, such as a transient
+/// systemd scope, so that each job may be managed as a separate unit by the host.
+#[derive(Default)]
+pub struct Launcher {
+    /// Properties, such as `MemoryMax=1G`, to set on each job's scope when launching with systemd.
+    pub systemd: Option<Vec<String>>,
+    /// Isolates each job within fresh mount and PID namespaces, with a read-only root, where only
+    /// the working directory of the job and a fresh `/tmp` may be written to.
+    pub sandbox: Option<Sandbox>,
+}
+
+impl Launcher {
+    /// Writes the program and arguments that the job's command will be appended to. The prefix
+    /// will be empty when jobs are to be executed directly.
+    pub fn prefix(&self, job_id: usize, prefix: &mut Vec<String>) {
+        prefix.clear();
+        if let Some(ref properties) = self.systemd {
+            prefix.push("systemd-run".to_owned());
+            if !is_root() { prefix.push("--user".to_owned()); }
+            prefix.push("--scope".to_owned());
+            prefix.push("--quiet".to_owned());
+            prefix.push(format!("--unit=parallel-{}-{}", process::id(), job_id + 1));
+            for property in properties {
+                prefix.push("-p".to_owned());
+                prefix.push(property.clone());
+            }
+            prefix.push("--".to_owned());
+        }
+
+        match self.sandbox {
+            Some(Sandbox::Bubblewrap) => {
+                let arguments = ["bwrap", "--ro-bind", "/", "/", "--dev", "/dev", "--proc", "/proc",
+                    "--tmpfs", "/tmp"];
+                prefix.extend(arguments.iter().map(|&argument| argument.to_owned()));
+                // The working directory is bound after `/tmp`, so that it is not hidden if it lies within.
+                if let Ok(workdir) = env::current_dir() {
+                    let workdir = workdir.to_string_lossy().into_owned();
+                    prefix.extend(vec!["--bind".to_owned(), workdir.clone(), workdir.clone(), "--chdir".to_owned(), workdir]);
+                }
+                let arguments = ["--unshare-pid", "--unshare-ipc", "--die-with-parent", "--"];
+                prefix.extend(arguments.iter().map(|&argument| argument.to_owned()));
+            },
+            Some(Sandbox::Unshare) => {
+                let arguments = ["unshare", "--map-root-user", "--mount", "--pid", "--ipc", "--fork",
+                    "--mount-proc", "sh", "-c", UNSHARE_SCRIPT, "sh"];
+                prefix.extend(arguments.iter().map(|&argument| argument.to_owned()));
+            },
+            None => ()
+        }
+    }
+}
+
+/// Unprivileged users must launch their scopes within their own user manager.
+#[cfg(unix)]
+fn is_root() -> bool {
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata("/proc/self").map(|metadata| metadata.uid() == 0).unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_root() -> bool { false }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn systemd_prefix() {
+        let mut prefix = vec!["stale".to_owned()];
+        Launcher::default().prefix(0, &mut prefix);
+        assert!(prefix.is_empty());
+
+        let launcher = Launcher { systemd: Some(vec!["MemoryMax=1G".to_owned()]), sandbox: None };
+        launcher.prefix(4, &mut prefix);
+        assert_eq!(prefix[0], "systemd-run");
+        assert!(prefix.contains(&format!("--unit=parallel-{}-5", process::id())));
+        assert_eq!(&prefix[prefix.len()-3..], &["-p", "MemoryMax=1G", "--"]);
+    }
+
+    #[test]
+    fn sandbox_within_systemd() {
+        let mut prefix = Vec::new();
+        let launcher = Launcher { systemd: Some(Vec::new()), sandbox: Some(Sandbox::Unshare) };
+        launcher.prefix(0, &mut prefix);
+        let position = prefix.iter().position(|argument| argument == "unshare").unwrap();
+        assert_eq!(prefix[position-1], "--");
+        assert_eq!(prefix.last().unwrap(), "sh");
+    }
+}
@@ -0,0 +1,158 @@
+use std::io::{self, Write};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use input_iterator::stream;
+use time;
+
+/// With `--eta` or `--progress`, the progress of the run is displayed on the standard error, and
+/// refreshed every second by a thread of its own, from counters that the jobs update as they start
+/// and finish. With `--bar`, it is instead drawn as a bar across the width of the terminal.
+pub struct Meter {
+    /// The number of jobs which this invocation will execute.
+    total:     usize,
+    bar:       bool,
+    started:   Instant,
+    running:   AtomicUsize,
+    completed: AtomicUsize,
+    failed:    AtomicUsize,
+    /// Jobs which were not executed, such as those whose command could not be found.
+    skipped:   AtomicUsize,
+    /// The sum of the runtimes of the completed and failed jobs, in milliseconds.
+    runtime:   AtomicU64,
+    finished:  AtomicBool,
+}
+
+impl Meter {
+    pub fn new(total: usize, bar: bool) -> Meter {
+        Meter {
+            total:     total,
+            bar:       bar,
+            started:   Instant::now(),
+            running:   AtomicUsize::new(0),
+            completed: AtomicUsize::new(0),
+            failed:    AtomicUsize::new(0),
+            skipped:   AtomicUsize::new(0),
+            runtime:   AtomicU64::new(0),
+            finished:  AtomicBool::new(false),
+        }
+    }
+
+    /// Records that a job has started running.
+    pub fn started(&self) { self.running.fetch_add(1, Ordering::Relaxed); }
+
+    /// Records that a job has exited.
+    pub fn finished(&self, failed: bool, runtime: time::Duration) {
+        self.running.fetch_sub(1, Ordering::Relaxed);
+        self.runtime.fetch_add(runtime.num_milliseconds().max(0) as u64, Ordering::Relaxed);
+        let counter = if failed { &self.failed } else { &self.completed };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records that a job has stopped running without an outcome, as it will be attempted again, or
+    /// a duplicate of it completed first.
+    pub fn stopped(&self) { self.running.fetch_sub(1, Ordering::Relaxed); }
+
+    /// Records that a job was not executed.
+    pub fn skipped(&self) { self.skipped.fetch_add(1, Ordering::Relaxed); }
+
+    /// Spawns the thread which refreshes the display until `finish()` is called.
+    pub fn display(meter: Arc<Meter>) -> JoinHandle<()> {
+        thread::spawn(move || {
+            while !meter.finished.load(Ordering::SeqCst) {
+                meter.write(false);
+                thread::park_timeout(Duration::from_secs(1));
+            }
+            meter.write(true);
+        })
+    }
+
+    /// Stops the display once the run has completed, leaving the final counts on the standard error.
+    pub fn finish(&self, display: JoinHandle<()>) {
+        self.finished.store(true, Ordering::SeqCst);
+        display.thread().unpark();
+        let _ = display.join();
+    }
+
+    fn write(&self, last: bool) {
+        let running   = self.running.load(Ordering::Relaxed);
+        let completed = self.completed.load(Ordering::Relaxed);
+        let failed    = self.failed.load(Ordering::Relaxed);
+        let skipped   = self.skipped.load(Ordering::Relaxed);
+        let done      = completed + failed;
+        // With `--total-jobs`, the hint is the total until every input has been read.
+        let total     = stream::total(self.total);
+        let left      = total.saturating_sub(done + skipped + running);
+
+        // The time remaining is estimated from the rate at which jobs have been completing so far.
+        let elapsed = self.started.elapsed();
+        let elapsed = elapsed.as_secs() as f64 + elapsed.subsec_nanos() as f64 / 1e9;
+        let eta = if done == 0 { None } else { Some(elapsed / done as f64 * (left + running) as f64) };
+
+        let mut line = Vec::with_capacity(128);
+        line.push(b'\r');
+        // The bar is only drawn on a terminal, so that logs of the standard error remain readable.
+        match terminal_width().filter(|_| self.bar) {
+            Some(width) => {
+                let finished = done + skipped;
+                let percent = (finished * 100).checked_div(total).unwrap_or(100);
+                let prefix = format!("{:3}% ", percent);
+                let suffix = match eta {
+                    Some(eta) => format!(" {}/{} {:.0}s ETA {:.0}s", finished, total, elapsed, eta),
+                    None      => format!(" {}/{} {:.0}s", finished, total, elapsed),
+                };
+                line.extend_from_slice(prefix.as_bytes());
+                // The bar is left out if the terminal is too narrow to draw it.
+                let inner = width.saturating_sub(prefix.len() + suffix.len() + 3);
+                if inner >= 10 {
+                    let filled = (inner * finished).checked_div(total).unwrap_or(inner);
+                    line.push(b'[');
+                    line.extend((0..inner).map(|column| if column < filled { b'=' } else { b' ' }));
+                    line.push(b']');
+                }
+                line.extend_from_slice(suffix.as_bytes());
+            },
+            None => {
+                let _ = write!(line, "parallel: {} completed, {} failed, {} running, {} remaining", completed,
+                    failed, running, left);
+                if skipped != 0 { let _ = write!(line, ", {} not executed", skipped); }
+                if let Some(eta) = eta {
+                    let average = self.runtime.load(Ordering::Relaxed) as f64 / done as f64 / 1000f64;
+                    let _ = write!(line, ", {:.2}s average, ETA {:.0}s", average, eta);
+                }
+            }
+        }
+        // Clears the remainder of a longer line that was previously displayed.
+        line.extend_from_slice(b"\x1b[K");
+        if last { line.push(b'\n'); }
+
+        let stderr = io::stderr();
+        let _ = stderr.lock().write_all(&line);
+    }
+}
+
+/// The width of the terminal which the standard error is attached to, if it is attached to one.
+#[cfg(unix)]
+fn terminal_width() -> Option<usize> {
+    use std::os::raw::{c_int, c_ulong, c_ushort};
+
+    #[repr(C)]
+    struct WindowSize { rows: c_ushort, columns: c_ushort, width: c_ushort, height: c_ushort }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    const TIOCGWINSZ: c_ulong = 0x5413;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    const TIOCGWINSZ: c_ulong = 0x4008_7468;
+    extern "C" {
+        fn ioctl(fd: c_int, request: c_ulong, ...) -> c_int;
+    }
+
+    // The request fails if the standard error is not a terminal.
+    let mut size = WindowSize { rows: 0, columns: 0, width: 0, height: 0 };
+    if unsafe { ioctl(2, TIOCGWINSZ, &mut size) } == -1 || size.columns == 0 { return None }
+    Some(size.columns as usize)
+}
+
+#[cfg(not(unix))]
+fn terminal_width() -> Option<usize> { None }
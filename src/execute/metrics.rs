@@ -0,0 +1,41 @@
+use arguments::Tune;
+use std::io::{self, Write};
+
+/// The peak occupancy of the internal queues and buffers over the course of a run, which is
+/// reported with `--metrics` so that the sizes given to `--tune` may be chosen with evidence.
+#[derive(Default)]
+pub struct Metrics {
+    /// The most signals that the receiver received in succession without waiting on the queue. As
+    /// signals may arrive while the receiver drains the queue, this may exceed the queue's capacity.
+    pub queue:   usize,
+    /// The most finished jobs that were waiting on the output of an earlier job to be printed.
+    pub reorder: usize,
+    /// The most job log entries that were waiting on the entry of an earlier job to be written.
+    pub joblog:  usize,
+    /// The number of times that the unprocessed file was read ahead.
+    pub refills: usize,
+    /// The most bytes of output that were buffered at once, if `--max-spool` was set.
+    pub spool:   Option<u64>,
+    /// The cost of the run, if `--cost-per-second` was set.
+    pub cost:    Option<f64>,
+}
+
+impl Metrics {
+    pub fn write_to_stderr(&self, tune: &Tune) {
+        let stderr = io::stderr();
+        let mut stderr = stderr.lock();
+        let _ = writeln!(stderr, "parallel: metrics:");
+        let _ = writeln!(stderr, "  queue:     peak of {} signals received without waiting (capacity of {})",
+            self.queue, tune.queue);
+        let _ = writeln!(stderr, "  reorder:   peak of {} jobs waiting on an earlier job's output", self.reorder);
+        let _ = writeln!(stderr, "  joblog:    peak of {} entries waiting on an earlier job's entry", self.joblog);
+        let _ = writeln!(stderr, "  output:    {} byte buffers", tune.output);
+        let _ = writeln!(stderr, "  readahead: {} KiB, refilled {} times", tune.readahead, self.refills);
+        if let Some(spool) = self.spool {
+            let _ = writeln!(stderr, "  spool:     peak of {} bytes buffered", spool);
+        }
+        if let Some(cost) = self.cost {
+            let _ = writeln!(stderr, "  cost:      {:.2} for the runtime of the jobs", cost);
+        }
+    }
+}
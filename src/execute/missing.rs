@@ -0,0 +1,24 @@
+use std::time::Duration;
+
+/// The number of times that a job whose input is missing will be attempted before it fails.
+pub const RETRY_ATTEMPTS: usize = 5;
+
+/// Determines how jobs whose input file no longer exists at the time of dispatch will be handled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MissingInput { Skip, Fail, Retry }
+
+impl MissingInput {
+    pub fn parse(name: &str) -> Option<MissingInput> {
+        match name {
+            "skip"  => Some(MissingInput::Skip),
+            "fail"  => Some(MissingInput::Fail),
+            "retry" => Some(MissingInput::Retry),
+            _       => None
+        }
+    }
+}
+
+/// Missing inputs wait one second longer with each attempt, giving the input time to reappear.
+pub fn retry_delay(attempt: usize) -> Duration {
+    Duration::from_secs(attempt as u64)
+}
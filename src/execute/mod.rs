@@ -1,16 +1,79 @@
 mod argument_splitter;
+mod budget;
 mod child;
+mod collect;
+mod collisions;
+mod context;
+mod deterministic;
+mod downstream;
 mod dry;
 mod exec_commands;
 mod exec_inputs;
+mod export;
+mod groups;
 mod job_log;
-mod signals;
+mod labels;
+mod launcher;
+mod meter;
+mod metrics;
+mod missing;
+mod notify;
+mod pools;
+mod progress;
+mod reduce;
+mod return_first;
+mod scratch;
+mod slots;
+mod speculate;
+mod spool;
+mod stages;
+mod status;
+mod tag;
 mod receive;
+mod remote;
+mod webhook;
+mod window;
 
 pub mod command;
+pub mod environment;
+pub mod gpus;
+pub mod ionice;
+pub mod nice;
 pub mod pipe;
+pub mod plan;
+pub mod signals;
 
+pub use self::budget::Budget;
+pub use self::collisions::find as find_collisions;
+pub use self::context::RunContext;
+pub use self::deterministic::enable as enable_deterministic;
+pub use self::downstream::Downstream;
 pub use self::dry::dry_run;
 pub use self::exec_commands::ExecCommands;
 pub use self::exec_inputs::ExecInputs;
+pub use self::export::{export, Scheduler};
+pub use self::gpus::Gpus;
+pub use self::groups::KeyGroups;
+pub use self::job_log::{finished_jobs, merge as merge_logs};
+pub use self::labels::Labels;
+pub use self::launcher::{Launcher, Sandbox};
+pub use self::meter::Meter;
+pub use self::metrics::Metrics;
+pub use self::missing::MissingInput;
+pub use self::notify::{Notifiers, Summary};
+pub use self::pools::Pools;
+pub use self::progress::ProgressEvents;
 pub use self::receive::receive_messages;
+pub use self::remote::{enable as enable_transfer, wrap as wrap_remote, Remote, Transfer};
+pub use self::reduce::{ReduceOrder, Reduction};
+pub use self::return_first::ReturnFirst;
+pub use self::signals::TermSeq;
+pub use self::scratch::Scratch;
+pub use self::slots::Slots;
+pub use self::speculate::Speculation;
+pub use self::spool::Spool;
+pub use self::stages::Stages;
+pub use self::status::{ExitPolicy, JobStatus};
+pub use self::tag::Tag;
+pub use self::webhook::Webhook;
+pub use self::window::{Expired, Window};
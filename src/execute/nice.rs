@@ -0,0 +1,57 @@
+//! With `--nice N`, each job is executed with a niceness of N, as with `nice -n N`. The niceness
+//! is set within the job after it has been forked, before it is executed, so that Parallel itself
+//! keeps its own priority, and a job fails if its niceness could not be set.
+
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+
+/// Whether `--nice` was given, and the niceness that was given with it.
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static NICENESS: AtomicI32 = AtomicI32::new(0);
+
+/// Sets the niceness which each job is executed with.
+pub fn enable(niceness: i32) {
+    NICENESS.store(niceness, Ordering::SeqCst);
+    ENABLED.store(true, Ordering::SeqCst);
+}
+
+/// Executes each job with the niceness of Parallel itself, once a reloaded throttle file no longer sets one.
+pub fn disable() {
+    ENABLED.store(false, Ordering::SeqCst);
+}
+
+/// Sets the niceness within the job, before it is executed, if `--nice` was given.
+pub fn apply(command: &mut Command) {
+    if ENABLED.load(Ordering::Relaxed) { sys::apply(command, NICENESS.load(Ordering::Relaxed)); }
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::io;
+    use std::os::raw::{c_int, c_uint};
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+
+    /// `PRIO_PROCESS`, which with an ID of zero refers to the calling process.
+    const PRIO_PROCESS: c_int = 0;
+
+    extern "C" {
+        fn setpriority(which: c_int, who: c_uint, priority: c_int) -> c_int;
+    }
+
+    pub fn apply(command: &mut Command, niceness: c_int) {
+        // Only the system call is made between fork and exec, which is async-signal-safe.
+        unsafe {
+            command.pre_exec(move || {
+                if setpriority(PRIO_PROCESS, 0, niceness) == 0 { Ok(()) } else { Err(io::Error::last_os_error()) }
+            });
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod sys {
+    use std::process::Command;
+
+    pub fn apply(_command: &mut Command, _niceness: i32) {}
+}
@@ -0,0 +1,152 @@
+use super::command;
+use std::fmt;
+use std::io::{self, Write};
+use std::process::{Command, ExitStatus, Stdio};
+use std::time::Duration;
+
+/// The outcome of a run, which is announced once every job has completed.
+pub struct Summary {
+    pub total:     usize,
+    pub succeeded: usize,
+    pub failed:    usize,
+    pub elapsed:   Duration,
+    /// The exit status of the run.
+    pub status:    i32,
+}
+
+impl Summary {
+    /// Inputs which were neither succeeded nor failed were not executed.
+    pub fn skipped(&self) -> usize {
+        self.total.saturating_sub(self.succeeded + self.failed)
+    }
+
+    fn title(&self) -> &'static str {
+        if self.failed == 0 && self.status == 0 { "parallel: run completed" } else { "parallel: run failed" }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} of {} jobs succeeded, {} failed, {} skipped, in {}.{:02}s", self.succeeded, self.total,
+            self.failed, self.skipped(), self.elapsed.as_secs(), self.elapsed.subsec_nanos() / 10_000_000)
+    }
+}
+
+/// A means of announcing the summary of a run, which fails if it is unavailable.
+pub trait Notifier {
+    fn notify(&self, summary: &Summary) -> io::Result<()>;
+}
+
+/// Sends a desktop notification, through `notify-send` on Linux and the BSDs, or through
+/// `osascript` on macOS.
+pub struct Desktop;
+
+impl Notifier for Desktop {
+    #[cfg(target_os = "macos")]
+    fn notify(&self, summary: &Summary) -> io::Result<()> {
+        let script = format!("display notification {} with title {}", apple_quote(&summary.to_string()),
+            apple_quote(summary.title()));
+        succeeded(Command::new("osascript").arg("-e").arg(script).stdin(Stdio::null()).stdout(Stdio::null())
+            .stderr(Stdio::null()).status()?)
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    fn notify(&self, summary: &Summary) -> io::Result<()> {
+        use std::env;
+
+        // Without a session bus or display, such as over SSH, there is no desktop to notify.
+        let session = ["DBUS_SESSION_BUS_ADDRESS", "DISPLAY", "WAYLAND_DISPLAY"].iter()
+            .any(|variable| env::var_os(variable).map_or(false, |value| !value.is_empty()));
+        if !session {
+            return Err(io::Error::new(io::ErrorKind::NotFound, "no desktop session is available"));
+        }
+
+        succeeded(Command::new("notify-send").arg("--app-name=parallel").arg(summary.title())
+            .arg(summary.to_string()).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).status()?)
+    }
+}
+
+/// Executes a command within the shell, with the summary given by the `PARALLEL_TOTAL`,
+/// `PARALLEL_SUCCEEDED`, `PARALLEL_FAILED`, `PARALLEL_SKIPPED`, `PARALLEL_ELAPSED`,
+/// `PARALLEL_STATUS`, and `PARALLEL_SUMMARY` environment variables.
+pub struct Hook {
+    command: String,
+    flags:   u16,
+}
+
+impl Hook {
+    pub fn new(command: String, flags: u16) -> Hook { Hook { command, flags } }
+}
+
+impl Notifier for Hook {
+    fn notify(&self, summary: &Summary) -> io::Result<()> {
+        let elapsed = summary.elapsed.as_secs() as f64 + f64::from(summary.elapsed.subsec_nanos()) / 1e9;
+        succeeded(command::shell_command(&self.command, self.flags, &[])
+            .env("PARALLEL_TOTAL", summary.total.to_string())
+            .env("PARALLEL_SUCCEEDED", summary.succeeded.to_string())
+            .env("PARALLEL_FAILED", summary.failed.to_string())
+            .env("PARALLEL_SKIPPED", summary.skipped().to_string())
+            .env("PARALLEL_ELAPSED", format!("{:.3}", elapsed))
+            .env("PARALLEL_STATUS", summary.status.to_string())
+            .env("PARALLEL_SUMMARY", summary.to_string())
+            .stdin(Stdio::null())
+            .status()?)
+    }
+}
+
+/// With `--notify` and `--notify-command`, the end of the run is announced by the first of the
+/// notifiers which succeeds, so that the command serves as a fallback where there is no desktop.
+pub struct Notifiers(Vec<Box<dyn Notifier>>);
+
+impl Notifiers {
+    pub fn new(desktop: bool, command: Option<String>, flags: u16) -> Notifiers {
+        let mut notifiers: Vec<Box<dyn Notifier>> = Vec::new();
+        if desktop { notifiers.push(Box::new(Desktop)); }
+        if let Some(command) = command { notifiers.push(Box::new(Hook::new(command, flags))); }
+        Notifiers(notifiers)
+    }
+
+    pub fn send(&self, summary: &Summary) {
+        let mut failure = None;
+        for notifier in &self.0 {
+            match notifier.notify(summary) {
+                Ok(()) => return,
+                Err(why) => failure = Some(why),
+            }
+        }
+
+        if let Some(why) = failure {
+            let stderr = io::stderr();
+            let _ = writeln!(stderr.lock(), "parallel: unable to send notification: {}", why);
+        }
+    }
+}
+
+fn succeeded(status: ExitStatus) -> io::Result<()> {
+    if status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("notifier exited with {}", status)))
+    }
+}
+
+/// Quotes a string for AppleScript.
+#[cfg(target_os = "macos")]
+fn apple_quote(string: &str) -> String {
+    format!("\"{}\"", string.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let summary = Summary {
+            total: 12, succeeded: 9, failed: 2, elapsed: Duration::from_millis(3205), status: 0
+        };
+        assert_eq!(summary.skipped(), 1);
+        assert_eq!(summary.title(), "parallel: run failed");
+        assert_eq!(summary.to_string(), "9 of 12 jobs succeeded, 2 failed, 1 skipped, in 3.20s");
+    }
+}
@@ -1,10 +1,15 @@
 pub mod disk {
     use std::fs::File;
-    use std::io::{Read, Write};
+    use std::io::{self, Read, Write};
+    use std::path::Path;
     use std::process::Child;
-    use std::sync::mpsc::Sender;
+    use std::sync::mpsc::SyncSender;
+    use disk_buffer::SpaceWriter;
     use filepaths;
+    use super::super::deterministic;
     use super::super::job_log::JobLog;
+    use super::super::spool::Spool;
+    use super::super::tag::Tagger;
 
     /// When using grouped mode, the `State` will tell the program whether the program is still
     /// processing, or if it has completed.
@@ -13,48 +18,72 @@ pub mod disk {
         Completed(usize, String),
         /// An error occurred, so the error will be marked.
         Error(usize, String),
+        /// The job was not executed, so its output files will be empty.
+        Skipped(usize),
+        /// The job was executed, but its output is not to be printed, as with the failed jobs of
+        /// `--return-first`.
+        Discarded(usize, String),
         /// (job_id, start_time, runtime, exit_value, signal, command)
         JobLog(JobLog),
     }
 
-    /// Sends messages received by a `Child` process's standard output and error and sends them
-    /// to be handled by the grouped output channel.
-    pub fn output(child: &mut Child, job_id: usize, name: String, output_tx: &Sender<State>, quiet: bool,
-        base: &str, buffer: &mut [u8])
+    /// Writes the messages received by a `Child` process's standard output and error to the job's
+    /// output files, returning the signal to be sent to the grouped output channel once the child
+    /// has exited. If the output could not be written, such as when the tempdir remains full, the
+    /// output is discarded and the job is marked as an error. If a `spool` limit was set, reading
+    /// from the child will pause while the limit is exceeded. Output is read from the child
+    /// `membuffer.len()` bytes at a time. With `--tag`, each line is written after the `tag`.
+    pub fn output(child: &mut Child, job_id: usize, name: String, quiet: bool, base: &str, buffer: &mut [u8],
+        membuffer: &mut [u8], spool: Option<&Spool>, tag: Option<&[u8]>) -> State
     {
         let (_, stdout_path, stderr_path) = filepaths::new_job(base, job_id, buffer);
-        let mut stdout_file = File::create(stdout_path).expect("unable to create job stdout file");
-        let mut stderr_file = File::create(stderr_path).expect("unable to create job stderr file");
+        let stdout_file = File::create(&stdout_path).expect("unable to create job stdout file");
+        let stderr_file = File::create(&stderr_path).expect("unable to create job stderr file");
+        let mut stdout_file = SpaceWriter::new(stdout_file, Path::new(&stdout_path));
+        let mut stderr_file = SpaceWriter::new(stderr_file, Path::new(&stderr_path));
+        let mut failure = None;
+        let mut stdout_tagger = tag.map(Tagger::new);
+        let mut stderr_tagger = tag.map(Tagger::new);
+
+        // Output continues to be read after a failure, so that the child will not block on a full pipe.
+        macro_rules! record {
+            ($file:expr, $tagger:expr, $bytes:expr) => {
+                if let Some(spool) = spool { spool.reserve(job_id, $bytes.len() as u64); }
+                if failure.is_none() {
+                    let result = match $tagger { Some(ref mut tagger) => tagger.write(&mut $file, $bytes), None => $file.write_all($bytes) };
+                    if let Err(why) = result { failure = Some(why); }
+                }
+            }
+        }
 
         let stderr = child.stderr.as_mut().expect("unable to open stderr of child");
-        let mut membuffer = [0u8; 8 * 1024];
         if quiet {
             // Only pipe messages from standard error when quiet mode is enabled.
             while let Ok(bytes_read) = stderr.read(&mut membuffer[..]) {
                 if bytes_read != 0 {
-                    let _ = stderr_file.write(&membuffer[0..bytes_read]);
+                    record!(stderr_file, stderr_tagger, &membuffer[0..bytes_read]);
                 } else {
                     break
                 }
             }
         } else {
-            let mut stdout = child.stdout.as_mut().expect("unable to open stdout of child");
+            let stdout = child.stdout.as_mut().expect("unable to open stdout of child");
 
             // Attempt to read from stdout and stderr simultaneously until both are exhausted of messages.
             loop {
                 if let Ok(bytes_read) = stdout.read(&mut membuffer[..]) {
                     if bytes_read != 0 {
-                        let _ = stdout_file.write(&membuffer[0..bytes_read]);
+                        record!(stdout_file, stdout_tagger, &membuffer[0..bytes_read]);
                     } else if let Ok(bytes_read) = stderr.read(&mut membuffer[..]) {
                         if bytes_read != 0 {
-                            let _ = stderr_file.write(&membuffer[0..bytes_read]);
+                            record!(stderr_file, stderr_tagger, &membuffer[0..bytes_read]);
                         } else {
                             break
                         }
                     }
                 } else if let Ok(bytes_read) = stderr.read(&mut membuffer[..]) {
                     if bytes_read != 0 {
-                        let _ = stderr_file.write(&membuffer[0..bytes_read]);
+                        record!(stderr_file, stderr_tagger, &membuffer[0..bytes_read]);
                     } else {
                         break
                     }
@@ -64,7 +93,86 @@ pub mod disk {
             }
         }
 
-        // Signal to the channel that the job has completed.
-        let _ = output_tx.send(State::Completed(job_id, name));
+        match failure {
+            Some(why) => {
+                let stderr = io::stderr();
+                let _ = writeln!(stderr.lock(), "parallel: unable to write the output of job {}: {}", job_id + 1, why);
+                let message = format!("{}: {}: unable to write output: {}\n", job_id + 1, name, why);
+                State::Error(job_id, message)
+            },
+            None => State::Completed(job_id, name)
+        }
+    }
+
+    /// Prints the output of a `Child` process with `--line-buffer` as it is written, one line at a
+    /// time, so that the lines of jobs which are running at the same time are not mixed together.
+    /// Bytes which follow the last newline are printed once the output has ended. As the output
+    /// was printed directly, the job's output files will be empty. With `--tag`, each line is
+    /// printed after the `tag`.
+    pub fn line_buffered(child: &mut Child, job_id: usize, name: String, quiet: bool, base: &str,
+        buffer: &mut [u8], membuffer: &mut [u8], tag: Option<&[u8]>) -> State
+    {
+        create_empty(job_id, base, buffer);
+        if !quiet {
+            let stdout = child.stdout.as_mut().expect("unable to open stdout of child");
+            print_lines(stdout, &mut io::stdout(), membuffer, tag);
+        }
+        let stderr = child.stderr.as_mut().expect("unable to open stderr of child");
+        print_lines(stderr, &mut io::stderr(), membuffer, tag);
+        State::Completed(job_id, name)
+    }
+
+    /// Reads `output` until it has ended, writing the complete lines to `out` at once. The standard
+    /// output and error are only locked while they are written to, so that other jobs may print.
+    fn print_lines<R: Read, W: Write>(output: &mut R, out: &mut W, membuffer: &mut [u8], tag: Option<&[u8]>) {
+        let mut line = Vec::new();
+        let mut tagged = Vec::new();
+        let mut print = |lines: &[u8], out: &mut W| match tag {
+            Some(tag) => {
+                tagged.clear();
+                let _ = Tagger::new(tag).write(&mut tagged, lines);
+                let _ = out.write_all(&tagged).and_then(|_| out.flush());
+            },
+            None => { let _ = out.write_all(lines).and_then(|_| out.flush()); }
+        };
+        while let Ok(bytes_read) = output.read(membuffer) {
+            if bytes_read == 0 { break }
+            line.extend_from_slice(&membuffer[..bytes_read]);
+            if let Some(end) = line.iter().rposition(|&byte| byte == b'\n') {
+                print(&line[..end + 1], out);
+                line.drain(..end + 1);
+            }
+        }
+        if !line.is_empty() { print(&line, out); }
+    }
+
+    /// Signals that a job will not be executed. Empty output files are created for the job, so that
+    /// the receiver may tail the job's files as it would for any other job.
+    pub fn skip(job_id: usize, output_tx: &SyncSender<State>, base: &str, buffer: &mut [u8]) {
+        deterministic::wait(job_id);
+        create_empty(job_id, base, buffer);
+        let _ = output_tx.send(State::Skipped(job_id));
+        deterministic::complete(job_id);
+    }
+
+    /// Returns the signal for a job which was attached to the terminal. Its output was written
+    /// directly to the terminal, so its output files will be empty.
+    pub fn attached(job_id: usize, name: String, base: &str, buffer: &mut [u8]) -> State {
+        create_empty(job_id, base, buffer);
+        State::Completed(job_id, name)
+    }
+
+    /// Signals that a job could not be executed, with empty output files created as with `skip()`.
+    pub fn error(job_id: usize, message: String, output_tx: &SyncSender<State>, base: &str, buffer: &mut [u8]) {
+        deterministic::wait(job_id);
+        create_empty(job_id, base, buffer);
+        let _ = output_tx.send(State::Error(job_id, message));
+        deterministic::complete(job_id);
+    }
+
+    fn create_empty(job_id: usize, base: &str, buffer: &mut [u8]) {
+        let (_, stdout_path, stderr_path) = filepaths::new_job(base, job_id, buffer);
+        File::create(stdout_path).expect("unable to create job stdout file");
+        File::create(stderr_path).expect("unable to create job stderr file");
     }
 }
@@ -0,0 +1,208 @@
+//! Writes the fully expanded jobs of a run to a JSON plan with `--plan`, instead of executing them,
+//! so that the jobs may be reviewed, approved, or compared with those of another invocation, and
+//! later executed as they were planned with `--execute-plan`. Plans are written in the form of:
+//!
+//! ```json
+//! {
+//!   "version": "...",
+//!   "runid": "...",
+//!   "policy": "local",
+//!   "slots": 4,
+//!   "jobs": [
+//!     {"seq": 1, "command": "...", "argv": ["..."], "env": {"PARALLEL_SEQ": "1", "PARALLEL_TOTAL": "2"}}
+//!   ]
+//! }
+//! ```
+//!
+//! Every job is assigned to the local machine, and is executed by the first of the `slots` to become
+//! free, in the order of `seq`. The slot, scratch directory, and GPUs of a job are only known once
+//! it is executed, so they are not planned. Jobs of `--pipe` also have a `stdin`.
+
+use input_iterator::{InputIterator, InputIteratorErr, Provenance};
+use tokenizer::Token;
+use arguments;
+use execute::command;
+use numtoa::NumToA;
+use record::{write_string, LoadErr, Parser};
+use shell;
+
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::Path;
+
+/// The commands of a plan which is to be executed.
+pub struct Plan {
+    /// The run ID of the planned run.
+    pub run_id:   String,
+    /// The command of each job, in order.
+    pub commands: Vec<String>,
+}
+
+/// Writes the plan of the run to the file at `path`, where `slots` is the number of jobs which would
+/// be executed at once.
+pub fn write<IO: Read>(path: &Path, flags: u16, inputs: InputIterator<IO>, arguments: &[Token],
+    provenance: &Provenance, run_id: &str, slots: usize) -> io::Result<()>
+{
+    let unknown = arguments.iter().any(|token| matches!(*token, Token::Host | Token::Slot | Token::Scratch));
+    if unknown {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput,
+            "{%}, {scratch}, and {host} are only known once a job is executed, so they cannot be planned"));
+    }
+
+    let mut file           = BufWriter::new(File::create(path)?);
+    let stderr             = io::stderr();
+    let mut command_buffer = String::new();
+    let pipe               = flags & arguments::PIPE_IS_ENABLED != 0;
+    let mut id_buffer      = [0u8; 20];
+    let mut total_buffer   = [0u8; 20];
+    let total              = inputs.total_arguments;
+    let start_indice       = total.numtoa(10, &mut total_buffer);
+    let job_total          = &total_buffer[start_indice..];
+    let quote              = flags & arguments::SHELL_ENABLED != 0;
+
+    file.write_all(b"{\n  \"version\": ")?;
+    write_string(&mut file, env!("CARGO_PKG_VERSION"))?;
+    file.write_all(b",\n  \"runid\": ")?;
+    write_string(&mut file, run_id)?;
+    write!(file, ",\n  \"policy\": \"local\",\n  \"slots\": {},\n  \"jobs\": [", slots)?;
+
+    let mut planned = 0;
+    for (job_id, input) in inputs.enumerate() {
+        let input = match input {
+            Ok(input) => input,
+            Err(InputIteratorErr::FileRead(path, why)) => {
+                let _ = writeln!(stderr.lock(), "parallel: input file read error: {:?}: {}", path, why);
+                continue
+            }
+        };
+
+        let start_indice = (job_id+1).numtoa(10, &mut id_buffer);
+        let command = command::ParallelCommand {
+            job_no:    &id_buffer[start_indice..],
+            job_total: job_total,
+            source:    provenance.get(job_id),
+            range:     provenance.range(job_id),
+            ..command::ParallelCommand::template(arguments, &input, flags)
+        };
+
+        let mut flags = flags;
+        command_buffer.clear();
+        if flags & arguments::INPUTS_ARE_COMMANDS != 0 {
+            // Inputs which are commands are executed within a shell if they require one.
+            command_buffer.push_str(&input);
+            if shell::required(shell::Kind::Input(&input)) { flags |= arguments::SHELL_ENABLED; }
+        } else {
+            command.build_arguments(&mut command_buffer);
+            if !pipe { command::append_argument(&mut command_buffer, arguments, &input, flags, quote); }
+        }
+        let (argv, variables) = command::argv(&command_buffer, flags);
+
+        file.write_all(if planned == 0 { b"\n    " } else { b",\n    " })?;
+        write!(file, "{{\"seq\": {}, \"command\": ", job_id + 1)?;
+        write_string(&mut file, &command_buffer)?;
+        file.write_all(b", \"argv\": [")?;
+        for (index, argument) in argv.iter().enumerate() {
+            if index != 0 { file.write_all(b", ")?; }
+            write_string(&mut file, argument)?;
+        }
+        file.write_all(b"], \"env\": {")?;
+        for &(ref name, ref value) in &variables {
+            write_string(&mut file, name)?;
+            file.write_all(b": ")?;
+            write_string(&mut file, value)?;
+            file.write_all(b", ")?;
+        }
+        write!(file, "\"PARALLEL_SEQ\": \"{}\", \"PARALLEL_TOTAL\": \"{}\"}}", job_id + 1, total)?;
+        if pipe {
+            file.write_all(b", \"stdin\": ")?;
+            write_string(&mut file, &input)?;
+        }
+        file.write_all(b"}")?;
+        planned += 1;
+    }
+
+    file.write_all(if planned == 0 { b"]\n}\n" } else { b"\n  ]\n}\n" })?;
+    file.flush()
+}
+
+/// Loads the commands of the plan at `path`, which are executed as they were planned. Errors that
+/// concern the contents of the file are returned as a description of the problem.
+pub fn load(path: &Path) -> Result<Plan, LoadErr> {
+    let mut contents = String::new();
+    File::open(path).and_then(|mut file| file.read_to_string(&mut contents)).map_err(LoadErr::IO)?;
+
+    let mut parser = Parser::new(contents.as_bytes());
+    let mut plan = Plan { run_id: String::new(), commands: Vec::new() };
+    let mut jobs = false;
+
+    parser.expect(b'{')?;
+    if !parser.next_is(b'}') {
+        loop {
+            let key = parser.string()?;
+            parser.expect(b':')?;
+            match key.as_str() {
+                "jobs"  => { plan.commands = load_jobs(&mut parser)?; jobs = true; },
+                "runid" => { plan.run_id = parser.string()?; },
+                _       => parser.skip_value()?,
+            }
+            if parser.next_is(b'}') { break }
+            parser.expect(b',')?;
+        }
+    }
+
+    if !jobs { return Err(LoadErr::Invalid("the jobs were not planned".to_owned())); }
+    Ok(plan)
+}
+
+/// Loads the command of each job of a plan.
+fn load_jobs(parser: &mut Parser) -> Result<Vec<String>, LoadErr> {
+    let mut commands = Vec::new();
+    parser.expect(b'[')?;
+    if parser.next_is(b']') { return Ok(commands) }
+    loop {
+        let mut command = None;
+        parser.expect(b'{')?;
+        if !parser.next_is(b'}') {
+            loop {
+                let key = parser.string()?;
+                parser.expect(b':')?;
+                match key.as_str() {
+                    "command" => command = Some(parser.string()?),
+                    "stdin"   => return Err(LoadErr::Invalid("jobs of --pipe cannot be executed from a plan".to_owned())),
+                    _         => parser.skip_value()?,
+                }
+                if parser.next_is(b'}') { break }
+                parser.expect(b',')?;
+            }
+        }
+        let command = command.ok_or_else(|| {
+            LoadErr::Invalid(format!("job {} does not have a command", commands.len() + 1))
+        })?;
+        commands.push(command);
+
+        if parser.next_is(b']') { return Ok(commands) }
+        parser.expect(b',')?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn load_plan() {
+        let path = env::temp_dir().join("parallel_plan_test.json");
+        fs::write(&path, concat!(
+            "{\"version\": \"0.11.3\", \"runid\": \"0123abcd\", \"policy\": \"local\", \"slots\": 4, \"jobs\": [\n",
+            "  {\"seq\": 1, \"command\": \"echo \\\"a b\\\"\", \"argv\": [\"echo\", \"a b\"], \"env\": {\"A\": \"1\"}},\n",
+            "  {\"seq\": 2, \"command\": \"sh -c true\", \"argv\": [], \"env\": {}}\n",
+            "]}\n")).unwrap();
+        let plan = load(&path);
+        let _ = fs::remove_file(&path);
+        let plan = plan.unwrap();
+        assert_eq!(plan.run_id, "0123abcd");
+        assert_eq!(plan.commands, vec!["echo \"a b\"".to_owned(), "sh -c true".to_owned()]);
+    }
+}
@@ -0,0 +1,185 @@
+use execute::command::ParallelCommand;
+use numtoa::NumToA;
+use tokenizer::Token;
+
+use std::io::{self, Write};
+use std::sync::{Condvar, Mutex};
+
+/// Named pools of resource units, such as GPUs, license seats, or database connections, which jobs
+/// claim before they start and return once they complete. A job declares the units that it needs
+/// from the `--needs` template, and each `--class PATTERN=N` is a pool of `N` units, of which jobs
+/// whose command matches the pattern need one. All of the units of a job are claimed at once, so
+/// that no job holds units while waiting on another pool.
+pub struct Pools {
+    /// The patterns of the classes, whose pools precede those of `--pool`.
+    patterns:  Vec<String>,
+    names:     Vec<String>,
+    capacity:  Vec<usize>,
+    available: Mutex<Vec<usize>>,
+    condvar:   Condvar,
+    needs:     &'static [Token],
+}
+
+impl Pools {
+    pub fn new(classes: Vec<(String, usize)>, pools: Vec<(String, usize)>, needs: &'static [Token]) -> Pools {
+        let (patterns, mut capacity): (Vec<_>, Vec<_>) = classes.into_iter().unzip();
+        let (names, pool_capacity): (Vec<_>, Vec<_>) = pools.into_iter().unzip();
+        capacity.extend(pool_capacity);
+        Pools {
+            patterns,
+            names,
+            available: Mutex::new(capacity.clone()),
+            capacity,
+            condvar: Condvar::new(),
+            needs,
+        }
+    }
+
+    /// Parses a definition of the form `NAME=N`, where `N` is a positive number, as given to
+    /// `--class` and `--pool`.
+    pub fn parse(definition: &str) -> Option<(String, usize)> {
+        let position = definition.rfind('=')?;
+        match definition[position+1..].parse::<usize>() {
+            Ok(limit) if limit != 0 && position != 0 => Some((definition[..position].to_owned(), limit)),
+            _ => None
+        }
+    }
+
+    /// Collects the units needed by a job into `needs`, as pairs of a pool and a number of units:
+    /// one unit of the first class whose pattern matches the `command`, and the units declared by
+    /// the `--needs` template. Units of unknown pools are reported and ignored.
+    pub fn needs(&self, command: &str, job_id: usize, slot: &str, input: &str, needs: &mut Vec<(usize, usize)>) {
+        needs.clear();
+        let class = self.patterns.iter().position(|pattern| glob(pattern.as_bytes(), command.as_bytes()));
+        if let Some(class) = class { needs.push((class, 1)); }
+        if self.needs.is_empty() { return }
+
+        let declared = self.render(job_id, slot, input);
+        for entry in declared.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (units, name) = match entry.find('=') {
+                Some(position) => (entry[..position].parse::<usize>().ok(), &entry[position+1..]),
+                None           => (Some(1), entry)
+            };
+            match (units, self.names.iter().position(|pool| pool == name)) {
+                (Some(0), Some(_)) => (),
+                (Some(units), Some(pool)) => {
+                    // Jobs which need more units than a pool has may still run, with every unit.
+                    let pool = self.patterns.len() + pool;
+                    let capacity = self.capacity[pool];
+                    match needs.iter().position(|&(need, _)| need == pool) {
+                        Some(index) => needs[index].1 = (needs[index].1 + units).min(capacity),
+                        None        => needs.push((pool, units.min(capacity)))
+                    }
+                },
+                _ => {
+                    let stderr = io::stderr();
+                    let _ = writeln!(stderr.lock(), "parallel: job {}: invalid or unknown pool, '{}', ignoring",
+                        job_id + 1, entry);
+                }
+            }
+        }
+    }
+
+    /// Blocks until every unit that a job needs is available, and claims them all at once.
+    pub fn acquire(&self, needs: &[(usize, usize)]) {
+        let mut available = self.available.lock().unwrap();
+        while !Pools::has_room(&available, needs) {
+            available = self.condvar.wait(available).unwrap();
+        }
+        for &(pool, units) in needs { available[pool] -= units; }
+    }
+
+    /// Returns the units of a completed job to their pools, waking up any jobs waiting on them.
+    pub fn release(&self, needs: &[(usize, usize)]) {
+        if needs.is_empty() { return }
+        {
+            let mut available = self.available.lock().unwrap();
+            for &(pool, units) in needs { available[pool] += units; }
+        }
+        self.condvar.notify_all();
+    }
+
+    fn has_room(available: &[usize], needs: &[(usize, usize)]) -> bool {
+        needs.iter().all(|&(pool, units)| units <= available[pool])
+    }
+
+    /// Generates the units needed by an input from the `--needs` template.
+    fn render(&self, job_id: usize, slot: &str, input: &str) -> String {
+        let mut id_buffer = [0u8; 20];
+        let start_indice = (job_id+1).numtoa(10, &mut id_buffer);
+        let mut needs = String::new();
+        ParallelCommand {
+            slot_no: slot,
+            job_no:  &id_buffer[start_indice..],
+            ..ParallelCommand::template(self.needs, input, 0)
+        }.build_arguments(&mut needs);
+        needs
+    }
+}
+
+/// Matches `text` against a pattern, where `*` matches any sequence of bytes, and `?` any one byte.
+fn glob(pattern: &[u8], text: &[u8]) -> bool {
+    let (mut p, mut t) = (0, 0);
+    // The position of the last `*` within the pattern, and of the text that it was matched against.
+    let mut star = None;
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == b'?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == b'*' {
+            star = Some((p, t));
+            p += 1;
+        } else if let Some((star_p, star_t)) = star {
+            // Let the last `*` consume one more byte, and attempt the rest of the pattern again.
+            p = star_p + 1;
+            t = star_t + 1;
+            star = Some((star_p, star_t + 1));
+        } else {
+            return false
+        }
+    }
+    pattern[p..].iter().all(|&byte| byte == b'*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn class_patterns() {
+        assert_eq!(Pools::parse("curl*=4"), Some(("curl*".to_owned(), 4)));
+        assert_eq!(Pools::parse("a=b*=2"), Some(("a=b*".to_owned(), 2)));
+        assert_eq!(Pools::parse("curl*=0"), None);
+        assert_eq!(Pools::parse("=4"), None);
+        assert_eq!(Pools::parse("curl"), None);
+
+        let classes = vec![("curl *".to_owned(), 4), ("*.mkv".to_owned(), 1), ("ls ?".to_owned(), 1)];
+        let pools = Pools::new(classes, Vec::new(), &[]);
+        let mut needs = Vec::new();
+        pools.needs("curl -O http://example.com/a.mkv", 0, "1", "", &mut needs);
+        assert_eq!(needs, vec![(0, 1)]);
+        pools.needs("ffmpeg -i a.mkv", 0, "1", "", &mut needs);
+        assert_eq!(needs, vec![(1, 1)]);
+        pools.needs("ls a", 0, "1", "", &mut needs);
+        assert_eq!(needs, vec![(2, 1)]);
+        pools.needs("ls ab", 0, "1", "", &mut needs);
+        assert!(needs.is_empty());
+    }
+
+    #[test]
+    fn pool_needs() {
+        static NEEDS: [Token; 1] = [Token::Placeholder];
+        let pools = Pools::new(vec![("train*".to_owned(), 1)], vec![("gpu".to_owned(), 2), ("seat".to_owned(), 1)],
+            &NEEDS);
+        let mut needs = Vec::new();
+        pools.needs("train", 0, "1", "2=gpu, seat", &mut needs);
+        assert_eq!(needs, vec![(0, 1), (1, 2), (2, 1)]);
+        pools.needs("eval", 0, "1", "gpu,gpu,gpu", &mut needs);
+        assert_eq!(needs, vec![(1, 2)]);
+
+        pools.acquire(&needs);
+        assert!(!Pools::has_room(&pools.available.lock().unwrap(), &[(1, 1)]));
+        pools.release(&needs);
+        assert!(Pools::has_room(&pools.available.lock().unwrap(), &[(1, 2), (2, 1)]));
+    }
+}
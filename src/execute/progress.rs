@@ -0,0 +1,83 @@
+use record::write_string;
+use std::fs::File;
+use std::io::{self, Write};
+use std::sync::Mutex;
+use time::{self, Timespec};
+
+/// With `--progress-fd N`, progress events are written to the file descriptor `N` as JSON lines,
+/// so that wrappers may drive their own progress displays without parsing the standard error:
+///
+/// - `{"event":"run_started","total":N}`, once before any job is executed.
+/// - `{"event":"job_started","job":N,"slot":N,"input":"..."}`, when a job is executed.
+/// - `{"event":"job_finished","job":N,"slot":N,"exit":N,"signal":N,"runtime":SECONDS}`, when it exits.
+/// - `{"event":"job_skipped","job":N,"slot":N}`, when an input is not executed.
+///
+/// Every event also contains the `time` at which it occurred, in seconds since the Unix epoch.
+pub struct ProgressEvents {
+    file: Mutex<File>,
+}
+
+impl ProgressEvents {
+    pub fn new(file: File) -> ProgressEvents {
+        ProgressEvents { file: Mutex::new(file) }
+    }
+
+    /// Takes ownership of a file descriptor which was opened by the caller.
+    #[cfg(unix)]
+    pub fn open(fd: i32) -> io::Result<File> {
+        use std::os::raw::c_int;
+        use std::os::unix::io::FromRawFd;
+
+        const F_GETFD: c_int = 1;
+        const F_SETFD: c_int = 2;
+        const FD_CLOEXEC: c_int = 1;
+        extern "C" {
+            fn fcntl(fd: c_int, command: c_int, ...) -> c_int;
+        }
+
+        // Descriptors which are not open are rejected, rather than failing on every event.
+        let flags = unsafe { fcntl(fd, F_GETFD) };
+        if flags == -1 { return Err(io::Error::last_os_error()) }
+        // The jobs do not inherit the descriptor, so that it is closed once the program exits.
+        unsafe { fcntl(fd, F_SETFD, flags | FD_CLOEXEC); }
+        Ok(unsafe { File::from_raw_fd(fd) })
+    }
+
+    #[cfg(not(unix))]
+    pub fn open(_fd: i32) -> io::Result<File> {
+        Err(io::Error::other("file descriptors are not supported on this platform"))
+    }
+
+    pub fn run_started(&self, total: usize) {
+        self.write("run_started", |line| write!(line, ",\"total\":{}", total));
+    }
+
+    pub fn started(&self, job_id: usize, slot: &str, input: &str) {
+        self.write("job_started", |line| {
+            write!(line, ",\"job\":{},\"slot\":{},\"input\":", job_id + 1, slot)?;
+            write_string(line, input)
+        });
+    }
+
+    pub fn finished(&self, job_id: usize, slot: &str, exit_value: i32, signal: i32, runtime: time::Duration) {
+        let runtime = runtime.num_milliseconds() as f64 / 1000f64;
+        self.write("job_finished", |line| {
+            write!(line, ",\"job\":{},\"slot\":{},\"exit\":{},\"signal\":{},\"runtime\":{:.3}", job_id + 1, slot,
+                exit_value, signal, runtime)
+        });
+    }
+
+    pub fn skipped(&self, job_id: usize, slot: &str) {
+        self.write("job_skipped", |line| write!(line, ",\"job\":{},\"slot\":{}", job_id + 1, slot));
+    }
+
+    /// Writes each event with a single write, so that events of concurrent jobs are not interleaved.
+    fn write<F: FnOnce(&mut Vec<u8>) -> io::Result<()>>(&self, event: &str, fields: F) {
+        let Timespec { sec, nsec } = time::get_time();
+        let mut line = Vec::with_capacity(128);
+        let _ = write!(line, "{{\"event\":\"{}\",\"time\":{}.{:03}", event, sec, nsec / 1_000_000);
+        let _ = fields(&mut line);
+        line.extend_from_slice(b"}\n");
+        let _ = self.file.lock().unwrap().write_all(&line);
+    }
+}
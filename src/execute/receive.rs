@@ -1,40 +1,74 @@
 use std::fs::{self, File};
 use std::io::{self, Write, Read, BufWriter};
 use std::path::Path;
+use std::sync::Arc;
 use std::sync::mpsc::Receiver;
 use std::thread;
 use std::time::Duration;
 use arguments::Args;
+use errors::{open_or_exit, MAX_FAILED_JOBS};
 use filepaths;
-use misc::Digits;
+use input_iterator::stream;
 use super::job_log::{self, JobLog};
+use super::metrics::Metrics;
+use super::collect::Collector;
+use super::downstream::Downstream;
 use super::pipe::disk::State;
+use super::reduce::Reduction;
+use super::spool::Spool;
 use smallvec::SmallVec;
 
-/// Reads the standard output and error files of the current unit, writing them to the standard output/error.
-macro_rules! read_outputs {
-    ($stdout:ident, $stderr:ident, $buffer:ident, $stdout_out:ident, $stderr_out:ident) => {
-        let mut bytes_read = $stdout.read(&mut $buffer).unwrap_or(0);
+/// Copies the remaining contents of an output file of the current unit to `$out`. If `$out` was
+/// closed by its reader, the jobs are stopped, and no further output is printed.
+macro_rules! copy_output {
+    ($file:ident, $buffer:ident, $out:ident, $stderr_out:ident, $name:expr, $downstream:ident) => {
+        let mut bytes_read = $file.read(&mut $buffer).unwrap_or(0);
         while bytes_read != 0 {
-            if let Err(why) = $stdout_out.write(&$buffer[0..bytes_read]) {
-                let _ = write!($stderr_out, "parallel: I/O error: unable to write to standard output: {}\n", why);
+            if let Err(why) = $out.write_all(&$buffer[0..bytes_read]) {
+                if why.kind() == io::ErrorKind::BrokenPipe {
+                    $downstream.close();
+                    return 0
+                }
+                let _ = write!($stderr_out, "parallel: I/O error: unable to write to {}: {}\n", $name, why);
             }
-            bytes_read = $stdout.read(&mut $buffer).unwrap_or(0);
+            bytes_read = $file.read(&mut $buffer).unwrap_or(0);
         }
+    }
+}
 
-        bytes_read = $stderr.read(&mut $buffer).unwrap_or(0);
-        while bytes_read != 0 {
-            if let Err(why) = $stderr_out.write(&$buffer[0..bytes_read]) {
-                let _ = write!($stderr_out, "parallel: I/O error: unable to write to standard error: {}\n", why);
-            }
-            bytes_read = $stderr.read(&mut $buffer).unwrap_or(0);
+/// Reads the standard output and error files of the current unit, writing them to the standard output/error.
+macro_rules! read_outputs {
+    ($stdout:ident, $stderr:ident, $buffer:ident, $stdout_out:ident, $stderr_out:ident, $downstream:ident) => {
+        copy_output!($stdout, $buffer, $stdout_out, $stderr_out, "standard output", $downstream);
+        copy_output!($stderr, $buffer, $stderr_out, $stderr_out, "standard error", $downstream);
+    }
+}
+
+/// Prints the outputs of the current job, unless `--reduce` was given, in which case the standard
+/// output is kept for the reduction, and only the standard error is printed.
+macro_rules! print_outputs {
+    ($reduction:ident, $counter:ident, $stdout_path:ident, $stdout:ident, $stderr:ident, $buffer:ident,
+        $stdout_out:ident, $stderr_out:ident, $downstream:ident) => {
+        match $reduction {
+            Some(ref mut reduction) => {
+                if let Err(why) = reduction.keep($counter, &$stdout_path) {
+                    let _ = write!($stderr_out, "parallel: I/O error: unable to keep output for reduction: {}\n", why);
+                }
+                copy_output!($stderr, $buffer, $stderr_out, $stderr_out, "standard error", $downstream);
+            },
+            None => { read_outputs!($stdout, $stderr, $buffer, $stdout_out, $stderr_out, $downstream); }
         }
     }
 }
 
-/// Removes both the standard output and error file of the current job
+/// Removes both the standard output and error file of the current job, releasing their bytes from
+/// the spool, if a spool limit was set.
 macro_rules! remove_job_files {
-    ($stdout_path:ident, $stderr_path:ident, $stderr:ident) => {{
+    ($stdout_path:ident, $stderr_path:ident, $stderr:ident, $spool:ident, $counter:ident) => {{
+        if let Some(ref spool) = $spool {
+            let size = |path: &str| fs::metadata(path).map(|metadata| metadata.len()).unwrap_or(0);
+            spool.release(size(&$stdout_path) + size(&$stderr_path), $counter + 1);
+        }
         if let Err(why) = fs::remove_file(&$stdout_path).and_then(|_| fs::remove_file(&$stderr_path)) {
             let _ = write!($stderr, "parallel: I/O error: unable to remove job files: {}\n", why);
         }
@@ -58,6 +92,13 @@ macro_rules! open_job_files {
     }}
 }
 
+/// With `--resume` or `--shard-id`, passes over the jobs which were not executed, as they will not be received.
+macro_rules! skip_excluded {
+    ($excluded:expr, $counter:ident) => {{
+        while $excluded.contains(&$counter) { $counter += 1; }
+    }}
+}
+
 /// Append the current job to the processed file
 macro_rules! append_to_processed {
     ($processed:ident, $input:ident, $stderr:ident) => {{
@@ -67,24 +108,68 @@ macro_rules! append_to_processed {
     }}
 }
 
+/// With `--collect`, appends the part of the current job to the collected file.
+macro_rules! collect_part {
+    ($collector:ident, $counter:ident, $stderr:ident) => {{
+        if let Some(ref mut collector) = $collector {
+            match collector.collect($counter) {
+                Ok(true) => (),
+                Ok(false) => {
+                    let _ = write!($stderr, "parallel: job {} did not write {}\n", $counter + 1,
+                        Collector::part($counter));
+                },
+                Err(why) => {
+                    let _ = write!($stderr, "parallel: I/O error: unable to collect {}: {}\n",
+                        Collector::part($counter), why);
+                }
+            }
+        }
+    }}
+}
+
+/// With `--collect`, removes the part of the current job, whose output was discarded.
+macro_rules! discard_part {
+    ($collector:ident, $counter:ident) => {{
+        if let Some(ref collector) = $collector { collector.discard($counter); }
+    }}
+}
+
 #[allow(cyclomatic_complexity)]
 /// Tail and print the standard output and error of each process in the correct order
 pub fn receive_messages (
     input_rx: Receiver<State>,
     args: Args,
+    spool: Option<Arc<Spool>>,
+    mut reduction: Option<&mut Reduction>,
+    metrics: &mut Metrics,
     base: &str,
     processed_path: &Path,
-    errors_path: &Path
+    errors_path: &Path,
+    downstream: &Downstream
 ) -> i32 {
     let stdout = io::stdout();
     let stderr = io::stderr();
 
     // Store the flags value outside of the `args` structure
     let flags = args.flags;
+    // With `--discard-output`, jobs whose output was discarded have no output files to remove.
+    let discard = args.discard;
     // Keeps track of which job is currently allowed to print to standard output/error.
     let mut counter = 0;
     // Keep a record of how many errors have occurred.
     let mut error_count = 0;
+    // Keep a record of how many jobs were skipped.
+    let mut skipped_count = 0;
+    // With `--return-first`, the output of a job may be discarded once it has completed, so the
+    // output of a job cannot be printed while it is still running. Likewise for `--reduce`, which
+    // keeps the output of each job once it has completed, and for `--speculative-max`, whose
+    // duplicates replace the output of the job that they duplicate if they complete first, and for
+    // `--retries`, whose attempts replace the output of the failed attempt before them.
+    let tail_outputs = args.return_first == 0 && reduction.is_none() && args.speculative == 0 && args.retries == 0;
+    // With `--collect`, the part of each job is appended to this file once the job has been processed.
+    let mut collector = args.collect.map(|path| {
+        Collector::new(open_or_exit(fs::OpenOptions::new().truncate(true).create(true).write(true), Path::new(&path)))
+    });
     // In the event that the joblog parameter was passed, a counter will be needed for jobs.
     let mut job_counter = args.ninputs;
     // The following `buffer` is used to store completed jobs that are awaiting processing.
@@ -96,36 +181,65 @@ pub fn receive_messages (
     // Similar to the above for for `JobLog` events.
     let mut job_drop = SmallVec::<[usize; 32]>::new();
     // An opened disk buffer pointing to the processed file.
-    let processed_file = fs::OpenOptions::new().create(true).write(true).open(processed_path).unwrap();
+    let processed_file = open_or_exit(fs::OpenOptions::new().create(true).write(true), processed_path);
     let mut processed_file = BufWriter::new(processed_file);
     // An opened disk buffer pointing to the error file.
-    let error_file = fs::OpenOptions::new().truncate(true).create(true).write(true).open(errors_path).unwrap();
+    let error_file = open_or_exit(fs::OpenOptions::new().truncate(true).create(true).write(true), errors_path);
     let mut error_file = BufWriter::new(error_file);
-    // Obtaining the number of digits in the total number of inputs is required for padding purposes.
-    let mut id_pad_length = args.ninputs.digits();
     // A buffer for buffering the outputs of temporary files on disk.
-    let mut read_buffer = [0u8; 8192];
+    let mut read_buffer = vec![0u8; args.tune.output];
+    // The number of signals that have been received since the queue was last found to be empty.
+    let mut backlog = 0;
     // A buffer for converting job ID's into a byte array representation of a string.
     let mut id_buffer = [0u8; 20];
     // Generates the stdout and stderr paths, along with a truncation value to truncate the job ID from the paths.
     let (truncate_size, mut stdout_path, mut stderr_path) = filepaths::new_job(base, counter, &mut id_buffer);
     // If the joblog parameter was passed, open the file for writing.
+    let run_id = &args.run_id;
+    let resume = args.resume;
+    let labeled = args.label.is_some();
     let mut joblog = args.joblog.map(|path| {
         job_counter = 0;
-        if id_pad_length < 10 { id_pad_length = 10; }
-        let _ = fs::remove_file(&path);
-        let mut file = fs::OpenOptions::new().create(true).write(true).open(path).unwrap();
-        job_log::create(&mut file, id_pad_length, flags);
+        // With `--resume`, the jobs which are executed again are logged after those of previous runs.
+        if !resume { let _ = fs::remove_file(&path); }
+        let file = open_or_exit(fs::OpenOptions::new().create(true).append(true), Path::new(&path));
+        let mut file = BufWriter::new(file);
+        job_log::create(&mut file, flags, run_id, labeled);
         file
     });
+    let excluded = &args.excluded;
+    skip_excluded!(excluded, counter);
+    if joblog.is_some() { skip_excluded!(excluded, job_counter); }
 
     // The loop will only quit once all inputs have been processed
-    while counter < args.ninputs || job_counter < args.ninputs {
+    // With `--total-jobs`, the number of inputs is only known once the standard input has been read.
+    let ninputs = args.ninputs;
+    let logged = joblog.is_some();
+    while stream::pending(counter, ninputs) || (logged && stream::pending(job_counter, ninputs)) {
         // Tracks whether the next file in the queue should be trailed.
         let mut tail_next = false;
 
-        // First receive the next input signal from the running jobs
-        match input_rx.recv().unwrap() {
+        // First receive the next input signal from the running jobs. Job log entries are buffered
+        // while signals are pending, and are written together before waiting on the next signal.
+        let state = match input_rx.try_recv() {
+            Ok(state) => {
+                backlog += 1;
+                if backlog > metrics.queue { metrics.queue = backlog; }
+                state
+            },
+            Err(_) => {
+                backlog = 0;
+                if let Some(ref mut joblog) = joblog { let _ = joblog.flush(); }
+                input_rx.recv().unwrap()
+            }
+        };
+
+        // With `--reduce-order completion`, the outputs are reduced in the order that the jobs completed.
+        if let State::Completed(id, _) = state {
+            if let Some(ref mut reduction) = reduction { reduction.completed(id); }
+        }
+
+        match state {
             // If the job's id matches the current counter, there's no need to buffer it -- print immediately
             State::Completed(id, ref name) if id == counter => {
                 let mut stdout = stdout.lock();
@@ -133,35 +247,64 @@ pub fn receive_messages (
                 filepaths::next_job_path(counter, truncate_size, &mut id_buffer, &mut stdout_path, &mut stderr_path);
                 let (mut stdout_file, mut stderr_file) = open_job_files!(stdout_path, stderr_path);
                 append_to_processed!(processed_file, name, stderr);
-                read_outputs!(stdout_file, stderr_file, read_buffer, stdout, stderr);
-                remove_job_files!(stdout_path, stderr_path, stderr);
+                collect_part!(collector, counter, stderr);
+                print_outputs!(reduction, counter, stdout_path, stdout_file, stderr_file, read_buffer, stdout, stderr,
+                    downstream);
+                remove_job_files!(stdout_path, stderr_path, stderr, spool, counter);
                 counter += 1;
             },
             // Otherwise, add the job to the job complete buffer and mark the current job for trailing
             State::Completed(id, name) => {
                 buffer.push(State::Completed(id, name));
-                tail_next = true;
+                tail_next = tail_outputs;
             },
             // If an error occured and the id matches the counter, print the error immediately.
             State::Error(id, ref message) if id == counter => {
+                {
+                    let mut stderr = stderr.lock();
+                    filepaths::next_job_path(counter, truncate_size, &mut id_buffer, &mut stdout_path, &mut stderr_path);
+                    remove_job_files!(stdout_path, stderr_path, stderr, spool, counter);
+                }
                 counter += 1;
-                if error_count != 254 { error_count += 1; }
+                if error_count != MAX_FAILED_JOBS { error_count += 1; }
                 if let Err(why) = error_file.write(message.as_bytes()) {
                     let mut stderr = stderr.lock();
-                    let _ = write!(stderr, "parallel: I/O error: {}", why);
+                    let _ = write!(stderr, "parallel: I/O error: unable to write to {:?}: {}\n", errors_path, why);
                 }
             },
             // Otherwise add that error to the job complete buffer as well.
             State::Error(id, message) => {
                 buffer.push(State::Error(id, message));
-                if error_count != 254 { error_count += 1; }
+                if error_count != MAX_FAILED_JOBS { error_count += 1; }
+            },
+            // Skipped jobs have no output, so there's nothing to print.
+            State::Skipped(id) if id == counter => {
+                let mut stderr = stderr.lock();
+                filepaths::next_job_path(counter, truncate_size, &mut id_buffer, &mut stdout_path, &mut stderr_path);
+                remove_job_files!(stdout_path, stderr_path, stderr, spool, counter);
+                counter += 1;
+                skipped_count += 1;
             },
+            State::Skipped(id) => {
+                buffer.push(State::Skipped(id));
+                skipped_count += 1;
+            },
+            // Discarded jobs were processed, but their output is removed rather than printed.
+            State::Discarded(id, ref name) if id == counter => {
+                let mut stderr = stderr.lock();
+                filepaths::next_job_path(counter, truncate_size, &mut id_buffer, &mut stdout_path, &mut stderr_path);
+                append_to_processed!(processed_file, name, stderr);
+                discard_part!(collector, counter);
+                if !discard { remove_job_files!(stdout_path, stderr_path, stderr, spool, counter); }
+                counter += 1;
+            },
+            State::Discarded(id, name) => buffer.push(State::Discarded(id, name)),
             // If the joblog parameter was set, a joblog signal can be received.
             // If the job ID matches the current job counter, write the log to the job log.
             State::JobLog(ref data) if data.job_id == job_counter => {
                 job_counter += 1;
                 let mut joblog = joblog.as_mut().unwrap();
-                data.write_entry(&mut joblog, &mut id_buffer, id_pad_length);
+                data.write_entry(&mut joblog, &mut id_buffer);
             },
             // Otherwise, add it to the job buffer.
             State::JobLog(data) => job_buffer.push(data),
@@ -181,8 +324,9 @@ pub fn receive_messages (
                         let mut stdout = stdout.lock();
                         let mut stderr = stderr.lock();
                         append_to_processed!(processed_file, name, stderr);
-                        read_outputs!(stdout_file, stderr_file, read_buffer, stdout, stderr);
-                        remove_job_files!(stdout_path, stderr_path, stderr);
+                        collect_part!(collector, counter, stderr);
+                        read_outputs!(stdout_file, stderr_file, read_buffer, stdout, stderr, downstream);
+                        remove_job_files!(stdout_path, stderr_path, stderr, spool, counter);
                         counter += 1;
                         break
                     },
@@ -190,32 +334,62 @@ pub fn receive_messages (
                     Ok(State::Completed(id, name)) => buffer.push(State::Completed(id, name)),
                     // If an error occured, print the error and break
                     Ok(State::Error(id, ref message)) if id == counter => {
+                        {
+                            let mut stderr = stderr.lock();
+                            remove_job_files!(stdout_path, stderr_path, stderr, spool, counter);
+                        }
                         counter += 1;
                         if let Err(why) = error_file.write(message.as_bytes()) {
                             let mut stderr = stderr.lock();
-                            let _ = write!(stderr, "parallel: I/O error: {}", why);
+                            let _ = write!(stderr, "parallel: I/O error: unable to write to {:?}: {}\n", errors_path, why);
                         }
                         break
                     },
                     // We are only concerned about the current job ID
                     Ok(State::Error(id, message)) => buffer.push(State::Error(id, message)),
+                    Ok(State::Skipped(id)) if id == counter => {
+                        let mut stderr = stderr.lock();
+                        remove_job_files!(stdout_path, stderr_path, stderr, spool, counter);
+                        counter += 1;
+                        skipped_count += 1;
+                        break
+                    },
+                    Ok(State::Skipped(id)) => {
+                        buffer.push(State::Skipped(id));
+                        skipped_count += 1;
+                    },
+                    Ok(State::Discarded(id, ref name)) if id == counter => {
+                        let mut stderr = stderr.lock();
+                        append_to_processed!(processed_file, name, stderr);
+                        discard_part!(collector, counter);
+                        if !discard { remove_job_files!(stdout_path, stderr_path, stderr, spool, counter); }
+                        counter += 1;
+                        break
+                    },
+                    Ok(State::Discarded(id, name)) => buffer.push(State::Discarded(id, name)),
                     // If the job ID matches the current job counter, write the log to the job log.
                     Ok(State::JobLog(ref data)) if data.job_id == job_counter => {
                         job_counter += 1;
                         let mut joblog = joblog.as_mut().unwrap();
-                        data.write_entry(&mut joblog, &mut id_buffer, id_pad_length);
+                        data.write_entry(&mut joblog, &mut id_buffer);
                     },
                     // Otherwise, add it to the job buffer.
                     Ok(State::JobLog(data)) => job_buffer.push(data),
                     // Tail the file and wait a specified time before checking for the next message
                     _ => {
+                        backlog = 0;
+                        if let Some(ref mut joblog) = joblog { let _ = joblog.flush(); }
                         let mut stdout = stdout.lock();
                         let mut stderr = stderr.lock();
                         let mut bytes_read = stdout_file.read(&mut read_buffer).unwrap();
-                        if bytes_read != 0 { stdout.write_all(&read_buffer[0..bytes_read]).unwrap(); }
+                        if bytes_read != 0 {
+                            if let Err(why) = stdout.write_all(&read_buffer[0..bytes_read]) {
+                                if why.kind() == io::ErrorKind::BrokenPipe { downstream.close(); return 0 }
+                            }
+                        }
 
                         bytes_read = stderr_file.read(&mut read_buffer).unwrap();
-                        if bytes_read != 0 { stderr.write_all(&read_buffer[0..bytes_read]).unwrap(); }
+                        if bytes_read != 0 { let _ = stderr.write_all(&read_buffer[0..bytes_read]); }
                         thread::sleep(Duration::from_millis(1));
                     }
                 }
@@ -227,6 +401,7 @@ pub fn receive_messages (
         let mut changed = true;
         while changed {
             changed = false;
+            skip_excluded!(excluded, counter);
             for (index, state) in buffer.iter().enumerate() {
                 match *state {
                     State::Completed(id, ref name) if id == counter => {
@@ -235,19 +410,46 @@ pub fn receive_messages (
                         filepaths::next_job_path(counter, truncate_size, &mut id_buffer, &mut stdout_path, &mut stderr_path);
                         let (mut stdout_file, mut stderr_file) = open_job_files!(stdout_path, stderr_path);
                         append_to_processed!(processed_file, name, stderr);
-                        read_outputs!(stdout_file, stderr_file, read_buffer, stdout, stderr);
-                        remove_job_files!(stdout_path, stderr_path, stderr);
+                        collect_part!(collector, counter, stderr);
+                        print_outputs!(reduction, counter, stdout_path, stdout_file, stderr_file, read_buffer,
+                            stdout, stderr, downstream);
+                        remove_job_files!(stdout_path, stderr_path, stderr, spool, counter);
                         counter += 1;
                         changed = true;
                         drop.push(index);
                     },
                     State::Error(id, ref message) if id == counter => {
+                        {
+                            let mut stderr = stderr.lock();
+                            filepaths::next_job_path(counter, truncate_size, &mut id_buffer, &mut stdout_path, &mut stderr_path);
+                            remove_job_files!(stdout_path, stderr_path, stderr, spool, counter);
+                        }
                         counter += 1;
+                        changed = true;
+                        drop.push(index);
                         if let Err(why) = error_file.write(message.as_bytes()) {
                             let mut stderr = stderr.lock();
-                            let _ = write!(stderr, "parallel: I/O error: {}", why);
+                            let _ = write!(stderr, "parallel: I/O error: unable to write to {:?}: {}\n", errors_path, why);
                         }
                     },
+                    State::Skipped(id) if id == counter => {
+                        let mut stderr = stderr.lock();
+                        filepaths::next_job_path(counter, truncate_size, &mut id_buffer, &mut stdout_path, &mut stderr_path);
+                        remove_job_files!(stdout_path, stderr_path, stderr, spool, counter);
+                        counter += 1;
+                        changed = true;
+                        drop.push(index);
+                    },
+                    State::Discarded(id, ref name) if id == counter => {
+                        let mut stderr = stderr.lock();
+                        filepaths::next_job_path(counter, truncate_size, &mut id_buffer, &mut stdout_path, &mut stderr_path);
+                        append_to_processed!(processed_file, name, stderr);
+                        discard_part!(collector, counter);
+                        if !discard { remove_job_files!(stdout_path, stderr_path, stderr, spool, counter); }
+                        counter += 1;
+                        changed = true;
+                        drop.push(index);
+                    },
                     _ => ()
                 }
             }
@@ -258,17 +460,20 @@ pub fn receive_messages (
             changed = true;
             while changed {
                 changed = false;
+                skip_excluded!(excluded, job_counter);
                 for (index, log) in job_buffer.iter().enumerate() {
                     if log.job_id == job_counter {
                         job_counter += 1;
                         job_drop.push(index);
                         changed = true;
-                        log.write_entry(joblog, &mut id_buffer, id_pad_length);
+                        log.write_entry(joblog, &mut id_buffer);
                     }
                 }
             }
         }
 
+        if buffer.len() > metrics.reorder { metrics.reorder = buffer.len(); }
+        if job_buffer.len() > metrics.joblog { metrics.joblog = job_buffer.len(); }
         drop_used_states(&mut buffer, &mut drop);
         drop_used_logs(&mut job_buffer, &mut job_drop);
     }
@@ -276,19 +481,25 @@ pub fn receive_messages (
     if let Some(mut joblog) = joblog {
         if let Err(why) = joblog.flush() {
             let mut stderr = stderr.lock();
-            let _ = write!(stderr, "parallel: I/O error: {}", why);
+            let _ = write!(stderr, "parallel: I/O error: unable to write to joblog: {}\n", why);
         }
     }
 
     if let Err(why) = processed_file.flush() {
         let mut stderr = stderr.lock();
-        let _ = write!(stderr, "parallel: I/O error: {}", why);
+        let _ = write!(stderr, "parallel: I/O error: unable to write to {:?}: {}\n", processed_path, why);
     }
 
     if let Err(why) = error_file.flush() {
         let mut stderr = stderr.lock();
-        let _ = write!(stderr, "parallel: I/O error: {}", why);
+        let _ = write!(stderr, "parallel: I/O error: unable to write to {:?}: {}\n", errors_path, why);
     }
+
+    if skipped_count != 0 {
+        let mut stderr = stderr.lock();
+        let _ = write!(stderr, "parallel: skipped {} jobs\n", skipped_count);
+    }
+
     error_count
 }
 
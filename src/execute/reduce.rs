@@ -0,0 +1,120 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::{ExitStatus, Stdio};
+use super::command;
+
+/// The order in which the output files of the jobs are given to the `--reduce` command.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReduceOrder { Input, Completion }
+
+impl ReduceOrder {
+    pub fn parse(name: &str) -> Option<ReduceOrder> {
+        match name {
+            "input"      => Some(ReduceOrder::Input),
+            "completion" => Some(ReduceOrder::Completion),
+            _            => None
+        }
+    }
+}
+
+/// With `--reduce`, the standard output of each job which completed is kept within a directory of
+/// the tempdir, rather than being printed, so that a single command may combine the outputs once
+/// every job has completed. The directory is removed, along with the outputs, when dropped.
+pub struct Reduction {
+    dir:       String,
+    order:     ReduceOrder,
+    /// The IDs of the jobs in the order that they completed.
+    completed: Vec<usize>,
+    /// The IDs of the jobs whose output was kept, in input order.
+    kept:      Vec<usize>,
+}
+
+impl Reduction {
+    /// Creates the directory of the outputs within the tempdir `base`, which is named after the run ID.
+    pub fn create(base: &str, run_id: &str, order: ReduceOrder) -> Result<Reduction, (PathBuf, io::Error)> {
+        let dir = format!("{}/reduce_{}", base, run_id);
+        match fs::create_dir_all(&dir) {
+            Ok(()) => Ok(Reduction { dir, order, completed: Vec::new(), kept: Vec::new() }),
+            Err(why) => Err((PathBuf::from(dir), why))
+        }
+    }
+
+    /// Records that the job `id` has completed, for `--reduce-order completion`.
+    pub fn completed(&mut self, id: usize) {
+        if self.order == ReduceOrder::Completion { self.completed.push(id); }
+    }
+
+    /// Keeps the standard output file of the job `id`, which the receiver is about to remove.
+    pub fn keep(&mut self, id: usize, stdout_path: &str) -> io::Result<()> {
+        let path = self.path(id);
+        fs::hard_link(stdout_path, &path).or_else(|_| fs::copy(stdout_path, &path).map(|_| ()))?;
+        self.kept.push(id);
+        Ok(())
+    }
+
+    /// The paths of the outputs that were kept, in the order given by `--reduce-order`.
+    pub fn outputs(&self) -> Vec<String> {
+        match self.order {
+            ReduceOrder::Input => self.kept.iter().map(|&id| self.path(id)).collect(),
+            ReduceOrder::Completion => self.completed.iter()
+                .filter(|id| self.kept.binary_search(id).is_ok())
+                .map(|&id| self.path(id))
+                .collect()
+        }
+    }
+
+    /// Executes the reduction within a shell, where `{outputs}` is replaced with the quoted paths of
+    /// the outputs. If the command does not contain `{outputs}`, the paths are appended to it.
+    pub fn run(&self, template: &str, flags: u16) -> io::Result<ExitStatus> {
+        let mut outputs = String::new();
+        for path in self.outputs() {
+            if !outputs.is_empty() { outputs.push(' '); }
+            command::push_quoted(&mut outputs, &path);
+        }
+
+        let reduction = if template.contains("{outputs}") {
+            template.replace("{outputs}", &outputs)
+        } else {
+            [template, &outputs].join(" ")
+        };
+
+        command::shell_command(reduction, flags, &[]).stdin(Stdio::null()).status()
+    }
+
+    fn path(&self, id: usize) -> String { format!("{}/{}", self.dir, id + 1) }
+}
+
+impl Drop for Reduction {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn reduce_order() {
+        let base = env::temp_dir().join("parallel_reduce_test");
+        let base = base.to_str().unwrap();
+        fs::create_dir_all(base).unwrap();
+        let stdout = format!("{}/stdout", base);
+        fs::write(&stdout, b"output\n").unwrap();
+
+        let mut reduction = Reduction::create(base, "test", ReduceOrder::Completion).unwrap();
+        for &id in &[2, 0, 1] { reduction.completed(id); }
+        reduction.keep(0, &stdout).unwrap();
+        reduction.keep(2, &stdout).unwrap();
+        let dir = format!("{}/reduce_test", base);
+        assert_eq!(reduction.outputs(), vec![format!("{}/3", dir), format!("{}/1", dir)]);
+        reduction.order = ReduceOrder::Input;
+        assert_eq!(reduction.outputs(), vec![format!("{}/1", dir), format!("{}/3", dir)]);
+
+        drop(reduction);
+        assert!(fs::metadata(&dir).is_err());
+        let _ = fs::remove_dir_all(base);
+    }
+}
@@ -0,0 +1,628 @@
+//! With `--sshlogin`, jobs are distributed across hosts over SSH. Each host is given a number of
+//! job slots, as in `4/user@server`, and each slot executes all of its jobs on its own host, so
+//! the output of a remote job is collected from `ssh` as it would be from any other job. The local
+//! machine is written as `:`, and its jobs are executed directly.
+//!
+//! With `--transferfile`, `--return`, and `--cleanup`, the files of a remote job are copied to its
+//! host with `rsync` before the job, copied back from it afterwards, and then removed from the host.
+//! The files of `--basefile` are instead copied to every host once, before any job is executed, and
+//! removed once every job has completed.
+//!
+//! Before any job is dispatched, each host is logged into once, without prompting, so that a host
+//! which can't be logged into is reported once, with the reason, rather than failing each of its
+//! jobs alike. With `--filter-hosts`, such hosts are instead passed over, with a warning.
+//!
+//! With `--nice`, `--ionice`, and `--timeout`, the command of a remote job is wrapped within `nice`,
+//! `ionice`, and `timeout` on its host, as the priorities which are set on the local `ssh` don't
+//! reach the host. Each tool is looked up by the shell of the host, and is left out if it is missing.
+//!
+//! With `--hostfile-watch`, the `--sshloginfile`s are re-read periodically, and on SIGHUP, so that
+//! a host which was removed from them is drained: its slots finish their running jobs, but are given
+//! no further jobs. The slots of a run are spawned once, before the first job, so a host which was
+//! added to them is only reported, and its jobs must wait for the next run.
+
+use super::command::push_quoted;
+use std::ffi::OsStr;
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use regex::Regex;
+use shell;
+use super::signals;
+use tokenizer::Token;
+
+/// A remote host which jobs are executed on.
+pub struct Host {
+    /// The login as it was given, such as `user@server`, which is recorded in the job log.
+    pub login: String,
+    /// The command which executes a command line on the host, such as `ssh user@server`.
+    pub ssh:   Vec<String>,
+    /// The variables which are given to each job on the host, given with `--host-env`.
+    pub env:   Vec<(String, String)>,
+    /// The commands which are executed before each job on the host, given with `--host-setup`.
+    pub setup: Vec<String>,
+}
+
+impl Host {
+    pub fn new(login: String, ssh: Vec<String>) -> Host {
+        Host { login: login, ssh: ssh, env: Vec::new(), setup: Vec::new() }
+    }
+
+    /// The script which the shell executes for a job with files, which copies the `files` to the
+    /// host, executes the command `line` there, copies the `returns` back, and removes both from the
+    /// host if they are to be `cleanup`ed. The status of the script is that of the command.
+    pub fn script(&self, line: &str, files: &[String], returns: &[String], cleanup: bool) -> String {
+        let ssh = self.rsh();
+        let mut script = String::with_capacity(line.len() + 256);
+        let rsync = |script: &mut String| {
+            script.push_str("rsync -Rq -e ");
+            push_quoted(script, &ssh);
+            script.push_str(" --");
+        };
+        if !files.is_empty() {
+            rsync(&mut script);
+            for file in files {
+                script.push(' ');
+                push_quoted(&mut script, file);
+            }
+            script.push(' ');
+            push_quoted(&mut script, &[&self.login, ":"].concat());
+            script.push_str(" && ");
+        }
+        for word in &self.ssh {
+            push_quoted(&mut script, word);
+            script.push(' ');
+        }
+        script.push_str("-- ");
+        push_quoted(&mut script, line);
+        script.push_str("; status=$?");
+        if !returns.is_empty() {
+            script.push_str("; ");
+            rsync(&mut script);
+            for file in returns {
+                script.push(' ');
+                push_quoted(&mut script, &[&self.login, ":", file].concat());
+            }
+            script.push_str(" .");
+        }
+        if cleanup && !(files.is_empty() && returns.is_empty()) {
+            script.push_str("; ");
+            for word in &self.ssh {
+                push_quoted(&mut script, word);
+                script.push(' ');
+            }
+            let mut remove = String::from("rm -f --");
+            for file in files.iter().chain(returns) {
+                remove.push(' ');
+                push_quoted(&mut remove, file);
+            }
+            script.push_str("-- ");
+            push_quoted(&mut script, &remove);
+        }
+        script.push_str("; exit $status");
+        script
+    }
+
+    /// Logs into the host and executes `true`, where `ssh` is told to fail rather than prompt for a
+    /// password or for an unknown host key, and to give up on a host which doesn't answer in time.
+    fn probe(&self) -> Result<(), ProbeErr> {
+        let mut command = Command::new(&self.ssh[0]);
+        if Path::new(&self.ssh[0]).file_name() == Some(OsStr::new("ssh")) {
+            command.args(["-o", "BatchMode=yes", "-o", "ConnectTimeout=10"]);
+        }
+        let output = command.args(&self.ssh[1..]).args(["--", "true"]).stdin(Stdio::null()).output()
+            .map_err(ProbeErr::Exec)?;
+        if output.status.success() { return Ok(()) }
+
+        let message = String::from_utf8_lossy(&output.stderr);
+        let message = message.lines().rev().map(str::trim).find(|line| !line.is_empty()).unwrap_or("");
+        Err(if message.contains("Host key verification failed") {
+            ProbeErr::HostKey
+        } else if message.contains("Permission denied") {
+            ProbeErr::Auth(message.to_owned())
+        } else if message.contains("timed out") {
+            ProbeErr::Timeout
+        } else if message.is_empty() {
+            ProbeErr::Failed(output.status.to_string())
+        } else {
+            ProbeErr::Failed(message.to_owned())
+        })
+    }
+
+    /// The command which `rsync` logs into the host with, such as `ssh -p 2222`.
+    fn rsh(&self) -> String { self.ssh[..self.ssh.len() - 1].join(" ") }
+
+    /// Copies the `files` to the host, where their relative paths are kept under the home directory.
+    fn send(&self, files: &[String]) -> io::Result<()> {
+        let status = Command::new("rsync").args(["-Rq", "-e", &self.rsh(), "--"]).args(files)
+            .arg([&self.login, ":"].concat()).stdin(Stdio::null()).status()?;
+        succeeded("rsync", status)
+    }
+
+    /// Removes the `files` from the host.
+    fn remove(&self, files: &[String]) -> io::Result<()> {
+        let mut remove = String::from("rm -f --");
+        for file in files {
+            remove.push(' ');
+            push_quoted(&mut remove, file);
+        }
+        let status = Command::new(&self.ssh[0]).args(&self.ssh[1..]).arg("--").arg(remove)
+            .stdin(Stdio::null()).status()?;
+        succeeded(&self.ssh[0], status)
+    }
+}
+
+/// The reason that a host could not be logged into before any job was dispatched to it.
+#[derive(Debug)]
+pub enum ProbeErr {
+    /// The host refused every key, and a password may not be prompted for.
+    Auth(String),
+    /// The program which logs into the host could not be executed.
+    Exec(io::Error),
+    /// The login failed for another reason, given by `ssh`, or by its exit status.
+    Failed(String),
+    /// The key of the host is not within `known_hosts`, or differs from the key that is.
+    HostKey,
+    /// The host did not answer in time.
+    Timeout,
+}
+
+impl fmt::Display for ProbeErr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ProbeErr::Auth(ref message) => write!(f, "authentication failed, as no key was accepted: {}", message),
+            ProbeErr::Exec(ref why)     => write!(f, "unable to execute ssh: {}", why),
+            ProbeErr::Failed(ref why)   => write!(f, "{}", why),
+            ProbeErr::HostKey           => write!(f, "the host key is unknown or has changed within known_hosts"),
+            ProbeErr::Timeout           => write!(f, "timed out"),
+        }
+    }
+}
+
+fn succeeded(program: &str, status: ExitStatus) -> io::Result<()> {
+    if status.success() { return Ok(()) }
+    Err(io::Error::other(format!("{} failed with {}", program, status)))
+}
+
+/// The templates of the files which are copied to the host of each job, and back from it, where
+/// the files are named relative to the home directory on the host.
+pub struct Transfer {
+    pub files:   Vec<Vec<Token>>,
+    pub returns: Vec<Vec<Token>>,
+    pub cleanup: bool,
+}
+
+static TRANSFER: OnceLock<Transfer> = OnceLock::new();
+
+/// Enables the transfer of files for each remote job.
+pub fn enable(transfer: Transfer) {
+    let _ = TRANSFER.set(transfer);
+}
+
+/// The files which are transferred for each remote job, if any are.
+pub fn transfer() -> Option<&'static Transfer> {
+    TRANSFER.get()
+}
+
+static WRAPPER: OnceLock<String> = OnceLock::new();
+
+/// Wraps the command of each remote job within `timeout`, `nice`, and `ionice`, with the `timeout`,
+/// `niceness`, and I/O `priority` given, where a `timeout` of zero is none.
+pub fn wrap(niceness: Option<i32>, priority: Option<i32>, timeout: Duration) {
+    let _ = WRAPPER.set(tools(niceness, priority, timeout));
+}
+
+/// The words which precede the command of each remote job, if it is wrapped within any tools.
+pub fn wrapper() -> &'static str {
+    WRAPPER.get().map_or("", |wrapper| wrapper.as_str())
+}
+
+fn tools(niceness: Option<i32>, priority: Option<i32>, timeout: Duration) -> String {
+    let mut wrapper = String::new();
+    {
+        // The words of a tool are only given by the command substitution if the host has the tool.
+        let mut push = |tool: &str, arguments: String| {
+            wrapper.push_str(&format!("$(command -v {0} >/dev/null && echo {0} {1}) ", tool, arguments));
+        };
+        if timeout != Duration::from_millis(0) {
+            // As with the default `--termseq`, the command is killed if it is still running a second later.
+            push("timeout", format!("-k 1 {:.3}", timeout.as_secs_f64()));
+        }
+        if let Some(niceness) = niceness { push("nice", format!("-n {}", niceness)); }
+        match priority.map(|priority| (priority >> 13, priority & 7)) {
+            Some((3, _)) => push("ionice", "-c 3".to_owned()),
+            Some((class, level)) => push("ionice", format!("-c {} -n {}", class, level)),
+            None => ()
+        }
+    }
+    wrapper
+}
+
+/// Whether `--hostfile-watch` is re-reading the `--sshloginfile`s, so that hosts may be drained.
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// The logins of the hosts which were removed from their `--sshloginfile`, whose slots are given no further jobs.
+static DRAINED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Whether the slots of the `host` are to be given no further jobs, as it was removed from its `--sshloginfile`.
+/// The local machine is never drained.
+pub fn drained(host: Option<&Host>) -> bool {
+    match host {
+        Some(host) if WATCHING.load(Ordering::Relaxed) => DRAINED.lock().unwrap().contains(&host.login),
+        _ => false
+    }
+}
+
+/// The state of `--hostfile-watch`, which is moved onto the thread that re-reads the `--sshloginfile`s.
+struct Watch {
+    /// The paths of the `--sshloginfile`s.
+    files:   Vec<String>,
+    /// The logins of the hosts which were read from the files, which may be drained.
+    watched: Vec<String>,
+    /// The logins of every host of the run, including those given with `--sshlogin`.
+    logins:  Vec<String>,
+    /// Whether any slot executes its jobs on the local machine, which is never drained.
+    local:   bool,
+}
+
+impl Watch {
+    fn run(self, interval: Duration) {
+        let mut hangups = signals::hangups();
+        let mut reported: Vec<String> = Vec::new();
+        loop {
+            let deadline = Instant::now() + interval;
+            while Instant::now() < deadline && signals::hangups() == hangups {
+                thread::sleep(Duration::from_millis(100));
+            }
+            hangups = signals::hangups();
+
+            let listed = match self.read() {
+                Ok(listed) => listed,
+                Err(why) => {
+                    let _ = writeln!(io::stderr(), "parallel: warning: unable to re-read the sshloginfile: {}", why);
+                    continue
+                }
+            };
+
+            let drained = self.watched.iter().filter(|login| !listed.contains(login)).cloned().collect::<Vec<_>>();
+            if !self.local && self.logins.iter().all(|login| drained.contains(login)) {
+                let _ = writeln!(io::stderr(), "parallel: warning: every host was removed from the sshloginfile, \
+                    so none of them are drained");
+                continue
+            }
+
+            let mut current = DRAINED.lock().unwrap();
+            for login in drained.iter().filter(|login| !current.contains(login)) {
+                let _ = writeln!(io::stderr(), "parallel: draining {}, which was removed from the sshloginfile", login);
+            }
+            *current = drained;
+            drop(current);
+
+            for login in listed.iter().filter(|login| !self.logins.contains(login)) {
+                if reported.contains(login) { continue }
+                let _ = writeln!(io::stderr(), "parallel: warning: {} was added to the sshloginfile, \
+                    but the hosts of a run are fixed once its jobs have started", login);
+                reported.push(login.clone());
+            }
+        }
+    }
+
+    /// The logins of the hosts which are currently within the files.
+    fn read(&self) -> Result<Vec<String>, String> {
+        let mut remote = Remote::default();
+        for path in &self.files {
+            let lines = fs::read_to_string(path).map_err(|why| format!("{}: {}", path, why))?;
+            remote.add_lines(&lines).map_err(|login| format!("{}: '{}' is not a valid sshlogin", path, login))?;
+        }
+        Ok(remote.distinct().into_iter().map(|host| host.login.clone()).collect())
+    }
+}
+
+/// The hosts which jobs are distributed across, each with its number of job slots.
+#[derive(Default)]
+pub struct Remote {
+    /// Hosts whose number of slots was not given have the number of `--jobs`. The local machine has no host.
+    hosts:          Vec<(Option<usize>, Option<Arc<Host>>)>,
+    /// The templates of the files which are copied to the host, given with `--transferfile`.
+    pub transfers:  Vec<String>,
+    /// The templates of the files which are copied back from the host, given with `--return`.
+    pub returns:    Vec<String>,
+    /// Whether the files are removed from the host once the job has completed.
+    pub cleanup:    bool,
+    /// The files which are copied to every host before any job is executed, given with `--basefile`.
+    pub basefiles:  Vec<String>,
+    /// The variables which are given to each job on the host, with their values, given with `--env`.
+    pub env:        Vec<(String, String)>,
+    /// Whether hosts which can't be logged into are passed over, rather than stopping the run.
+    pub filter:     bool,
+    /// The number of job slots which are reserved for the local machine, given with `--local-slots`.
+    pub local:      usize,
+    /// Matches the inputs which are preferably executed on the local machine, given with `--local-only`.
+    pub local_only: Option<Regex>,
+    /// How often the `--sshloginfile`s are re-read, given with `--hostfile-watch`.
+    pub watch:      Option<Duration>,
+    /// The paths of the `--sshloginfile`s, along with the logins of the hosts which were read from them.
+    files:          Vec<String>,
+    watched:        Vec<String>,
+    /// The variables of `--host-env`, and the commands of `--host-setup`, along with the login of their host.
+    settings:       Vec<(String, Setting)>,
+}
+
+enum Setting {
+    Env(String, String),
+    Setup(String),
+}
+
+impl Remote {
+    pub fn is_empty(&self) -> bool { self.hosts.is_empty() }
+
+    /// Adds the comma-separated hosts of an `--sshlogin`, each of which is the local machine, `:`,
+    /// or a login, such as `user@server`, optionally preceded by the number of slots, as in `4/`.
+    /// The login may also be given with the `ssh` command which logs into it, as in `ssh -p 2222
+    /// server`. Returns the host which is invalid, if any.
+    pub fn add<'a>(&mut self, logins: &'a str) -> Result<(), &'a str> {
+        for entry in logins.split(',').map(str::trim) {
+            let (slots, login) = match entry.find('/') {
+                Some(position) if entry[..position].bytes().all(|byte| byte.is_ascii_digit()) => {
+                    match entry[..position].parse::<usize>() {
+                        Ok(slots) if slots != 0 => (Some(slots), entry[position+1..].trim()),
+                        _ => return Err(entry)
+                    }
+                },
+                _ => (None, entry)
+            };
+
+            let words = login.split_whitespace().map(str::to_owned).collect::<Vec<String>>();
+            let host = match words.len() {
+                0 => return Err(entry),
+                1 if login == ":" => None,
+                1 => Some(Host::new(login.to_owned(), vec!["ssh".to_owned(), login.to_owned()])),
+                _ => Some(Host::new(words[words.len()-1].clone(), words))
+            };
+            self.hosts.push((slots, host.map(Arc::new)));
+        }
+        Ok(())
+    }
+
+    /// Adds a variable which is given to the jobs of a host, written as `LOGIN=NAME=VALUE`. Returns
+    /// `false` if the variable is not written as such.
+    pub fn add_env(&mut self, value: &str) -> bool {
+        match value.find('=').map(|position| (&value[..position], &value[position+1..])) {
+            Some((login, variable)) if !login.is_empty() && shell::is_assignment(variable) => {
+                let position = variable.find('=').unwrap();
+                let (name, value) = (variable[..position].to_owned(), variable[position+1..].to_owned());
+                self.settings.push((login.to_owned(), Setting::Env(name, value)));
+                true
+            },
+            _ => false
+        }
+    }
+
+    /// Adds commands which are executed before each job of a host, written as `LOGIN=COMMANDS`.
+    /// Returns `false` if the commands are not written as such.
+    pub fn add_setup(&mut self, value: &str) -> bool {
+        match value.find('=').map(|position| (&value[..position], &value[position+1..])) {
+            Some((login, commands)) if !login.is_empty() && !commands.trim().is_empty() => {
+                self.settings.push((login.to_owned(), Setting::Setup(commands.to_owned())));
+                true
+            },
+            _ => false
+        }
+    }
+
+    /// Gives the variables and commands of `--host-env` and `--host-setup` to each host of their
+    /// login, returning the login which names no host, if any.
+    pub fn configure(&mut self) -> Result<(), String> {
+        for (login, setting) in self.settings.drain(..) {
+            let mut found = false;
+            for host in self.hosts.iter_mut().filter_map(|&mut (_, ref mut host)| host.as_mut()) {
+                if host.login != login { continue }
+                let host = Arc::get_mut(host).expect("hosts are configured before they are shared");
+                match setting {
+                    Setting::Env(ref name, ref value) => host.env.push((name.clone(), value.clone())),
+                    Setting::Setup(ref commands)      => host.setup.push(commands.clone()),
+                }
+                found = true;
+            }
+            if !found { return Err(login) }
+        }
+        Ok(())
+    }
+
+    /// Adds the hosts of each line of an `--sshloginfile`, other than blank lines and comments,
+    /// which begin with `#`.
+    pub fn add_lines<'a>(&mut self, lines: &'a str) -> Result<(), &'a str> {
+        for line in lines.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#')) {
+            self.add(line)?;
+        }
+        Ok(())
+    }
+
+    /// Adds the hosts of the `--sshloginfile` at `path`, whose hosts may be drained with `--hostfile-watch`.
+    pub fn add_file<'a>(&mut self, path: &str, lines: &'a str) -> Result<(), &'a str> {
+        let start = self.hosts.len();
+        self.add_lines(lines)?;
+        for host in self.hosts[start..].iter().filter_map(|&(_, ref host)| host.as_ref()) {
+            if !self.watched.contains(&host.login) { self.watched.push(host.login.clone()); }
+        }
+        self.files.push(path.to_owned());
+        Ok(())
+    }
+
+    /// Whether any host was read from an `--sshloginfile`, which `--hostfile-watch` may re-read.
+    pub fn watched(&self) -> bool { !self.files.is_empty() }
+
+    /// With `--hostfile-watch`, re-reads the `--sshloginfile`s on a thread of their own, every interval
+    /// and whenever SIGHUP is received, draining each host which is no longer within them.
+    pub fn watch(&self) {
+        let interval = match self.watch { Some(interval) => interval, None => return };
+        let watch = Watch {
+            files:   self.files.clone(),
+            watched: self.watched.clone(),
+            logins:  self.distinct().into_iter().map(|host| host.login.clone()).collect(),
+            local:   self.local_slots() != 0,
+        };
+        signals::catch_hangups();
+        WATCHING.store(true, Ordering::SeqCst);
+        thread::spawn(move || watch.run(interval));
+    }
+
+    /// Gives each host whose number of slots was not given the `default` number, returning the
+    /// total number of slots across every host.
+    pub fn assign(&mut self, default: usize) -> usize {
+        // The slots of `--local-slots` come first, as the local machine needs no login.
+        if self.local != 0 { self.hosts.insert(0, (Some(self.local), None)); }
+        for &mut (ref mut slots, _) in &mut self.hosts {
+            if slots.is_none() { *slots = Some(default); }
+        }
+        self.hosts.iter().map(|&(slots, _)| slots.unwrap_or(default)).sum()
+    }
+
+    /// The number of job slots which execute their jobs on the local machine.
+    pub fn local_slots(&self) -> usize {
+        self.hosts.iter().filter(|&&(_, ref host)| host.is_none()).map(|&(slots, _)| slots.unwrap_or(1)).sum()
+    }
+
+    /// Copies the files of `--basefile` to every host, returning the login of the host which they
+    /// could not be copied to, if any, along with the reason.
+    pub fn stage(&self) -> Result<(), (String, io::Error)> {
+        if self.basefiles.is_empty() { return Ok(()) }
+        for host in self.distinct() {
+            host.send(&self.basefiles).map_err(|why| (host.login.clone(), why))?;
+        }
+        Ok(())
+    }
+
+    /// With `--cleanup`, removes the files of `--basefile` from every host, returning the login of
+    /// each host which they could not be removed from, along with the reason.
+    pub fn unstage(&self) -> Vec<(String, io::Error)> {
+        if self.basefiles.is_empty() || !self.cleanup { return Vec::new() }
+        self.distinct().into_iter()
+            .filter_map(|host| host.remove(&self.basefiles).err().map(|why| (host.login.clone(), why)))
+            .collect()
+    }
+
+    /// Logs into every host at once, returning the login of each host which could not be logged
+    /// into, along with the reason.
+    pub fn probe(&self) -> Vec<(String, ProbeErr)> {
+        let probes = self.distinct().into_iter().map(|host| {
+            let host = Arc::clone(host);
+            thread::spawn(move || host.probe().err().map(|why| (host.login.clone(), why)))
+        }).collect::<Vec<_>>();
+        probes.into_iter().filter_map(|probe| probe.join().unwrap()).collect()
+    }
+
+    /// Removes the hosts of the `logins` given, returning the total number of slots which remain.
+    pub fn remove(&mut self, logins: &[String]) -> usize {
+        self.hosts.retain(|&(_, ref host)| host.as_ref().map_or(true, |host| !logins.contains(&host.login)));
+        self.hosts.iter().map(|&(slots, _)| slots.unwrap_or(1)).sum()
+    }
+
+    /// Each remote host, once, although it may have been given more than once.
+    fn distinct(&self) -> Vec<&Arc<Host>> {
+        let mut distinct: Vec<&Arc<Host>> = Vec::new();
+        for host in self.hosts.iter().filter_map(|&(_, ref host)| host.as_ref()) {
+            if !distinct.iter().any(|other| other.ssh == host.ssh) { distinct.push(host); }
+        }
+        distinct
+    }
+
+    /// The host which the slot at `index`, counting from zero, executes its jobs on, or `None`
+    /// if the jobs of the slot are executed on the local machine.
+    pub fn host(&self, mut index: usize) -> Option<Arc<Host>> {
+        for &(slots, ref host) in &self.hosts {
+            let slots = slots.unwrap_or(1);
+            if index < slots { return host.clone() }
+            index -= slots;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sshlogins() {
+        let mut remote = Remote::default();
+        remote.add("2/user@a, :").unwrap();
+        remote.add("ssh -p 2222 b").unwrap();
+        assert_eq!(remote.assign(3), 8);
+
+        let logins = (0..9).map(|index| remote.host(index).map(|host| host.login.clone())).collect::<Vec<_>>();
+        let (a, b) = (Some("user@a".to_owned()), Some("b".to_owned()));
+        assert_eq!(logins, vec![a.clone(), a, None, None, None, b.clone(), b.clone(), b, None]);
+        assert_eq!(remote.host(5).unwrap().ssh, vec!["ssh", "-p", "2222", "b"]);
+
+        assert_eq!(remote.add("0/a"), Err("0/a"));
+        assert_eq!(remote.add("a,,b"), Err(""));
+    }
+
+    #[test]
+    fn sshloginfile() {
+        let mut remote = Remote::default();
+        remote.add_lines("# Hosts\n\n  4/ssh -p 2222 user@c\n:\n").unwrap();
+        assert_eq!(remote.assign(2), 6);
+        assert_eq!(remote.host(3).unwrap().ssh, vec!["ssh", "-p", "2222", "user@c"]);
+        assert!(remote.host(4).is_none());
+        assert_eq!(remote.remove(&["user@c".to_owned()]), 2);
+        assert!(remote.host(0).is_none());
+    }
+
+    #[test]
+    fn local_slots() {
+        let mut remote = Remote { local: 2, ..Remote::default() };
+        remote.add("a").unwrap();
+        assert_eq!(remote.assign(3), 5);
+        assert_eq!(remote.local_slots(), 2);
+        assert!(remote.host(1).is_none());
+        assert_eq!(remote.host(2).unwrap().login, "a");
+    }
+
+    #[test]
+    fn host_settings() {
+        let mut remote = Remote::default();
+        remote.add("a").unwrap();
+        remote.assign(1);
+        assert!(remote.add_env("a=PATH=/opt/bin:/bin") && remote.add_setup("a=module load gcc"));
+        assert!(!remote.add_env("a=1=b") && !remote.add_setup("a="));
+        remote.configure().unwrap();
+        assert_eq!(remote.host(0).unwrap().env, vec![("PATH".to_owned(), "/opt/bin:/bin".to_owned())]);
+        assert_eq!(remote.host(0).unwrap().setup, vec!["module load gcc"]);
+        assert!(remote.add_env("b=A=1"));
+        assert_eq!(remote.configure(), Err("b".to_owned()));
+    }
+
+    #[test]
+    fn transfer_scripts() {
+        let host = Host::new("b".to_owned(), vec!["ssh".to_owned(), "-p".to_owned(), "22".to_owned(), "b".to_owned()]);
+        assert_eq!(host.script("wc in", &["in".to_owned()], &["in.out".to_owned()], true),
+            "rsync -Rq -e 'ssh -p 22' -- in b: && ssh -p 22 b -- 'wc in'; status=$?; \
+            rsync -Rq -e 'ssh -p 22' -- b:in.out .; ssh -p 22 b -- 'rm -f -- in in.out'; exit $status");
+        assert_eq!(host.script("true", &[], &[], true), "ssh -p 22 b -- true; status=$?; exit $status");
+    }
+
+    #[test]
+    fn remote_tools() {
+        assert_eq!(tools(Some(10), Some(3 << 13), Duration::from_millis(1500)), "$(command -v timeout >/dev/null && echo timeout -k 1 1.500) \
+            $(command -v nice >/dev/null && echo nice -n 10) $(command -v ionice >/dev/null && echo ionice -c 3) ");
+    }
+
+    #[test]
+    fn watched_hosts() {
+        let mut remote = Remote::default();
+        remote.add("c").unwrap();
+        assert!(!remote.watched());
+        remote.add_file("hosts", "# comment\n2/a\n:\n\nssh -p 2222 b, a\n").unwrap();
+        assert!(remote.watched());
+        assert_eq!(remote.watched, vec!["a", "b"]);
+        assert_eq!(remote.files, vec!["hosts"]);
+    }
+}
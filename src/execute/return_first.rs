@@ -0,0 +1,42 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// With `--return-first N`, counts the jobs which have succeeded, so that no further inputs are
+/// executed once `N` jobs have succeeded, and so that only the output of those jobs is printed.
+pub struct ReturnFirst {
+    wanted:    usize,
+    succeeded: AtomicUsize,
+}
+
+impl ReturnFirst {
+    pub fn new(wanted: usize) -> ReturnFirst {
+        ReturnFirst { wanted, succeeded: AtomicUsize::new(0) }
+    }
+
+    /// Whether `N` jobs have succeeded, in which case the remaining inputs are skipped.
+    pub fn reached(&self) -> bool {
+        self.succeeded.load(Ordering::SeqCst) >= self.wanted
+    }
+
+    /// Records the outcome of a job, returning whether its output should be printed. Jobs which
+    /// were still running when the `N`th success was recorded have their output discarded as well.
+    pub fn record(&self, exit_value: i32, signal: i32) -> bool {
+        exit_value == 0 && signal == 0 && self.succeeded.fetch_add(1, Ordering::SeqCst) < self.wanted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn return_first() {
+        let first = ReturnFirst::new(2);
+        assert!(!first.record(1, 0));
+        assert!(!first.record(-1, 15));
+        assert!(first.record(0, 0));
+        assert!(!first.reached());
+        assert!(first.record(0, 0));
+        assert!(first.reached());
+        assert!(!first.record(0, 0));
+    }
+}
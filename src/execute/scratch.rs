@@ -0,0 +1,29 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// A private directory of a job slot, which is available to its jobs as `{scratch}` and
+/// `PARALLEL_SCRATCH`. The directory is removed, along with its contents, when the slot retires.
+pub struct Scratch {
+    path: String,
+}
+
+impl Scratch {
+    /// Creates the scratch directory of a `slot` within the tempdir `base`. The run ID is a part of
+    /// the name, so that concurrent runs sharing a tempdir do not share scratch directories.
+    pub fn create(base: &str, run_id: &str, slot: usize) -> Result<Scratch, (PathBuf, io::Error)> {
+        let path = format!("{}/scratch_{}_{}", base, run_id, slot);
+        match fs::create_dir_all(&path) {
+            Ok(()) => Ok(Scratch { path }),
+            Err(why) => Err((PathBuf::from(path), why))
+        }
+    }
+
+    pub fn path(&self) -> &str { &self.path }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
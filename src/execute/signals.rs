@@ -1,4 +1,11 @@
-use std::process::ExitStatus;
+use std::process::{Child, ExitStatus};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use wait_timeout::ChildExt;
+
+pub const SIGHUP: i32 = 1;
+pub const SIGTERM: i32 = 15;
+pub const SIGKILL: i32 = 9;
 
 #[cfg(unix)]
 pub fn get(status: ExitStatus) -> i32 {
@@ -10,3 +17,129 @@ pub fn get(status: ExitStatus) -> i32 {
 pub fn get(child: ExitStatus) -> i32 {
     0
 }
+
+#[cfg(unix)]
+mod ffi {
+    use std::os::raw::c_int;
+
+    extern "C" {
+        pub fn kill(pid: c_int, signal: c_int) -> c_int;
+        pub fn signal(signal: c_int, handler: usize) -> usize;
+    }
+}
+
+/// Sends a signal to a process.
+#[cfg(unix)]
+pub fn send(pid: u32, signal: i32) {
+    unsafe { ffi::kill(pid as i32, signal); }
+}
+
+/// Signals cannot be sent on this platform, so processes are only killed by `Child::kill()`.
+#[cfg(not(unix))]
+pub fn send(_pid: u32, _signal: i32) {}
+
+/// The number of times that SIGHUP has been received since `catch_hangups()` was called.
+static HANGUPS: AtomicUsize = AtomicUsize::new(0);
+
+#[cfg(unix)]
+extern "C" fn hangup(_signal: std::os::raw::c_int) {
+    HANGUPS.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Counts each SIGHUP which is received, rather than terminating, so that a long run may be told to
+/// re-read its settings. Each reader compares `hangups()` against the count which it last saw.
+#[cfg(unix)]
+pub fn catch_hangups() {
+    unsafe { ffi::signal(SIGHUP, hangup as extern "C" fn(std::os::raw::c_int) as usize); }
+}
+
+/// There is no SIGHUP on this platform, so the settings are only re-read periodically.
+#[cfg(not(unix))]
+pub fn catch_hangups() {}
+
+/// The number of times that SIGHUP has been received.
+pub fn hangups() -> usize {
+    HANGUPS.load(Ordering::SeqCst)
+}
+
+/// With `--termseq`, the signals which are sent to a job which has exceeded its timeout, each
+/// followed by how long to wait for the job to exit before the next signal is sent. The job is
+/// killed if it is still running once the sequence has been exhausted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TermSeq(Vec<(i32, Duration)>);
+
+impl Default for TermSeq {
+    /// Jobs are asked to terminate once, and are killed if they are still running after a grace period.
+    fn default() -> TermSeq {
+        TermSeq(vec![(SIGTERM, Duration::from_millis(1000))])
+    }
+}
+
+impl TermSeq {
+    /// Parses a comma-separated list of signals and milliseconds, such as `TERM,1000,KILL,25`,
+    /// where each signal is given by its name, with or without the `SIG` prefix, or its number.
+    pub fn parse(sequence: &str) -> Option<TermSeq> {
+        let elements = sequence.split(',').map(str::trim).collect::<Vec<&str>>();
+        if elements.len() % 2 != 0 { return None }
+        elements.chunks(2).map(|pair| {
+            let signal = signal_number(pair[0])?;
+            let wait = pair[1].parse::<u64>().ok()?;
+            Some((signal, Duration::from_millis(wait)))
+        }).collect::<Option<Vec<_>>>().map(TermSeq)
+    }
+}
+
+fn signal_number(name: &str) -> Option<i32> {
+    if let Ok(number) = name.parse::<i32>() {
+        return if number > 0 { Some(number) } else { None }
+    }
+    let name = name.strip_prefix("SIG").unwrap_or(name);
+    match name {
+        "HUP"  => Some(SIGHUP),
+        "INT"  => Some(2),
+        "QUIT" => Some(3),
+        "KILL" => Some(SIGKILL),
+        // The numbers of the user signals differ between platforms, so elsewhere they are given by number.
+        #[cfg(target_os = "linux")]
+        "USR1" => Some(10),
+        #[cfg(target_os = "linux")]
+        "USR2" => Some(12),
+        "TERM" => Some(SIGTERM),
+        _      => None
+    }
+}
+
+/// Terminates a job which has exceeded its timeout by following the `termseq`, returning the
+/// signal that the job is recorded as having been terminated by: the signal which ended the job,
+/// or otherwise the last signal that was sent, so that the job is not mistaken for a success.
+pub fn terminate(child: &mut Child, termseq: &TermSeq) -> i32 {
+    for &(signal, wait) in &termseq.0 {
+        send(child.id(), signal);
+        // The child has been reaped by `wait_timeout()`, so its status can't be taken from `wait()`.
+        if let Ok(Some(status)) = child.wait_timeout(wait) {
+            return status.unix_signal().unwrap_or(signal)
+        }
+    }
+
+    let _ = child.kill();
+    match child.wait() {
+        Ok(status) if get(status) != 0 => get(status),
+        _ => SIGKILL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn termseq_parsing() {
+        assert_eq!(TermSeq::parse("TERM,1000,KILL,25"),
+            Some(TermSeq(vec![(SIGTERM, Duration::from_millis(1000)), (SIGKILL, Duration::from_millis(25))])));
+        assert_eq!(TermSeq::parse("SIGINT,50"), Some(TermSeq(vec![(2, Duration::from_millis(50))])));
+        assert_eq!(TermSeq::parse("TERM"), None);
+        assert_eq!(TermSeq::parse("TERM,soon"), None);
+        assert_eq!(TermSeq::parse("WINCH,10"), None);
+        assert_eq!(TermSeq::parse("TERM,1000"), Some(TermSeq::default()));
+    }
+}
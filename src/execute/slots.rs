@@ -0,0 +1,76 @@
+use std::io::{self, Write};
+use std::sync::{Condvar, Mutex};
+use tokenizer::column;
+
+/// Keeps count of how many of the `-j` job slots are currently occupied, so that jobs which
+/// consume more resources than others may claim several slots at once.
+pub struct Slots {
+    available: Mutex<usize>,
+    condvar:   Condvar,
+    capacity:  usize,
+}
+
+impl Slots {
+    pub fn new(capacity: usize) -> Slots {
+        Slots { available: Mutex::new(capacity), condvar: Condvar::new(), capacity: capacity }
+    }
+
+    /// Blocks until `weight` slots are free and claims them, returning the number of slots claimed.
+    /// Weights larger than the total capacity are clamped so that the job may still run on its own.
+    pub fn acquire(&self, weight: usize) -> usize {
+        let weight = if weight > self.capacity { self.capacity } else { weight };
+        let mut available = self.available.lock().unwrap();
+        while *available < weight {
+            available = self.condvar.wait(available).unwrap();
+        }
+        *available -= weight;
+        weight
+    }
+
+    /// Returns previously-claimed slots to the pool and wakes up any jobs waiting on them.
+    pub fn release(&self, weight: usize) {
+        *self.available.lock().unwrap() += weight;
+        self.condvar.notify_all();
+    }
+}
+
+/// Obtains the weight of the current job from the Nth column of its input, as designated by
+/// `--weight-col`. Missing or invalid weights are counted as a weight of one.
+pub fn job_weight(input: &str, job_id: usize, column_no: usize) -> usize {
+    match column(input, column_no).map(|value| (value, value.parse::<usize>())) {
+        Some((_, Ok(weight))) if weight != 0 => weight,
+        Some((value, _)) => {
+            let stderr = io::stderr();
+            let _ = writeln!(stderr.lock(), "parallel: job {}: invalid weight column value, '{}', using 1",
+                job_id + 1, value);
+            1
+        },
+        None => 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn slots_clamp_and_release() {
+        let slots = Arc::new(Slots::new(4));
+        assert_eq!(slots.acquire(8), 4);
+        let waiter = {
+            let slots = slots.clone();
+            thread::spawn(move || slots.acquire(2))
+        };
+        slots.release(4);
+        assert_eq!(waiter.join().unwrap(), 2);
+        assert_eq!(*slots.available.lock().unwrap(), 2);
+    }
+
+    #[test]
+    fn weight_column() {
+        assert_eq!(job_weight("video.mkv 4", 0, 2), 4);
+        assert_eq!(job_weight("video.mkv", 0, 2), 1);
+    }
+}
@@ -0,0 +1,294 @@
+use filepaths;
+use super::child::{handle_child, job_timeout};
+use super::context::{Ended, RunContext};
+use super::pipe::disk;
+use super::remote::Host;
+use super::signals;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::process::Child;
+use std::sync::Mutex;
+use std::sync::mpsc::SyncSender;
+use std::thread;
+use std::time::{Duration, Instant};
+use time;
+
+/// The number of jobs which must have completed before their median runtime is trusted.
+const MIN_SAMPLES: usize = 3;
+/// How many times longer than the median runtime a job must have been running to be duplicated.
+const SLOWDOWN: u32 = 2;
+/// How often a slot which has no more inputs checks for jobs to duplicate.
+const POLL_INTERVAL: u64 = 100;
+
+/// The duplicate of a running job.
+#[derive(Clone, Copy, PartialEq)]
+enum Duplicate {
+    None,
+    /// The duplicate has been claimed by an idle slot, but has not been executed yet.
+    Launching,
+    Running(u32),
+}
+
+struct Running {
+    job_id:    usize,
+    input:     String,
+    started:   Instant,
+    original:  u32,
+    duplicate: Duplicate,
+    /// One of the runs of the job has completed, and the other is to be discarded.
+    decided:   bool,
+}
+
+struct State {
+    /// The runtimes of the jobs which have completed, in ascending order.
+    runtimes: Vec<Duration>,
+    running:  Vec<Running>,
+    launched: usize,
+}
+
+/// With `--speculative-max N`, slots which have no more inputs start a duplicate of any job which
+/// has been running for far longer than the median runtime, as with speculative execution in
+/// Hadoop. Whichever run of the job completes first has its output printed, and the other run is
+/// killed. At most `N` duplicates are started. The output of duplicates is written within their
+/// own directory of the tempdir, and is moved into place if the duplicate completes first.
+pub struct Speculation {
+    max:     usize,
+    tempdir: String,
+    dir:     String,
+    state:   Mutex<State>,
+}
+
+impl Speculation {
+    pub fn new(max: usize, tempdir: &str, run_id: &str) -> Result<Speculation, (PathBuf, io::Error)> {
+        let dir = format!("{}/speculative_{}", tempdir, run_id);
+        if let Err(why) = fs::create_dir_all(&dir) { return Err((PathBuf::from(dir), why)) }
+        Ok(Speculation {
+            max,
+            tempdir: tempdir.to_owned(),
+            dir,
+            state: Mutex::new(State { runtimes: Vec::new(), running: Vec::new(), launched: 0 }),
+        })
+    }
+
+    /// The directory which the output of duplicates is written to.
+    pub fn dir(&self) -> &str { &self.dir }
+
+    /// Records that the original run of a job has started.
+    pub fn start(&self, job_id: usize, input: &str, pid: u32) {
+        self.state.lock().unwrap().running.push(Running {
+            job_id,
+            input:     input.to_owned(),
+            started:   Instant::now(),
+            original:  pid,
+            duplicate: Duplicate::None,
+            decided:   false,
+        });
+    }
+
+    /// Blocks until a job should be duplicated, returning its ID and input. Returns `None` once
+    /// no jobs are running, or once the maximum number of duplicates has been started.
+    pub fn straggler(&self) -> Option<(usize, String)> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                if state.launched >= self.max || state.running.is_empty() { return None }
+                if state.runtimes.len() >= MIN_SAMPLES {
+                    let threshold = state.runtimes[state.runtimes.len() / 2] * SLOWDOWN;
+                    let straggler = state.running.iter_mut().find(|job| {
+                        !job.decided && job.duplicate == Duplicate::None && job.started.elapsed() > threshold
+                    });
+                    if let Some(job) = straggler {
+                        job.duplicate = Duplicate::Launching;
+                        let straggler = (job.job_id, job.input.clone());
+                        state.launched += 1;
+                        return Some(straggler);
+                    }
+                }
+            }
+            thread::sleep(Duration::from_millis(POLL_INTERVAL));
+        }
+    }
+
+    /// Records that the duplicate of a job has started. Returns `false` if the original run has
+    /// completed in the meantime, in which case the duplicate should be killed.
+    pub fn launched(&self, job_id: usize, pid: u32) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let position = match state.running.iter().position(|job| job.job_id == job_id) {
+            Some(position) => position,
+            None => return false
+        };
+        if state.running[position].decided {
+            state.running.remove(position);
+            return false
+        }
+        state.running[position].duplicate = Duplicate::Running(pid);
+        true
+    }
+
+    /// Records that the duplicate of a job could not be executed.
+    pub fn abandon(&self, job_id: usize) {
+        let mut state = self.state.lock().unwrap();
+        if let Some(position) = state.running.iter().position(|job| job.job_id == job_id) {
+            if state.running[position].decided {
+                state.running.remove(position);
+            } else {
+                state.running[position].duplicate = Duplicate::None;
+            }
+        }
+    }
+
+    /// Records that a run of a job has completed, returning whether it was the first to complete,
+    /// in which case the other run is killed. If the `duplicate` completed first, its output files
+    /// replace those of the original run. Otherwise, the output files of the duplicate are removed.
+    pub fn finish(&self, job_id: usize, duplicate: bool, runtime: time::Duration) -> bool {
+        let mut state = self.state.lock().unwrap();
+        let position = match state.running.iter().position(|job| job.job_id == job_id) {
+            Some(position) => position,
+            None => return true
+        };
+
+        if state.running[position].decided {
+            state.running.remove(position);
+            if duplicate { self.remove_outputs(job_id); }
+            return false
+        }
+
+        let runtime = runtime.to_std().unwrap_or_else(|_| Duration::from_millis(0));
+        let index = state.runtimes.binary_search(&runtime).unwrap_or_else(|index| index);
+        state.runtimes.insert(index, runtime);
+
+        let job = &mut state.running[position];
+        let other = if duplicate {
+            self.replace_outputs(job_id);
+            Some(job.original)
+        } else {
+            match job.duplicate { Duplicate::Running(pid) => Some(pid), _ => None }
+        };
+
+        if let Some(pid) = other { signals::send(pid, signals::SIGKILL); }
+        if job.duplicate == Duplicate::None {
+            state.running.remove(position);
+        } else {
+            job.decided = true;
+        }
+        true
+    }
+
+    fn replace_outputs(&self, job_id: usize) {
+        let mut buffer = [0u8; 20];
+        let (_, stdout, stderr) = filepaths::new_job(&self.dir, job_id, &mut buffer);
+        let (_, stdout_to, stderr_to) = filepaths::new_job(&self.tempdir, job_id, &mut buffer);
+        let _ = fs::rename(stdout, stdout_to);
+        let _ = fs::rename(stderr, stderr_to);
+    }
+
+    fn remove_outputs(&self, job_id: usize) {
+        let mut buffer = [0u8; 20];
+        let (_, stdout, stderr) = filepaths::new_job(&self.dir, job_id, &mut buffer);
+        let _ = fs::remove_file(stdout);
+        let _ = fs::remove_file(stderr);
+    }
+}
+
+impl Drop for Speculation {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Once a slot has no more inputs, it duplicates the jobs which have been running for far longer than
+/// the median runtime, until there are none left to duplicate. Each duplicate is executed by `spawn`,
+/// which is given the ID, input, and launcher prefix of the job, and which writes the command that it
+/// executed into the buffer that it is given, returning the child and the flags that it was run with.
+pub fn duplicate_stragglers<F>(context: &RunContext, slot: &str, host: Option<&Host>, output_tx: &SyncSender<disk::State>,
+    membuffer: &mut [u8], mut spawn: F)
+    where F: FnMut(usize, &str, &[String], &mut String) -> Option<(Child, u16)>
+{
+    let speculation = match context.speculation { Some(ref speculation) => speculation, None => return };
+    let mut command = String::with_capacity(64);
+    let mut prefix = Vec::new();
+    let mut job_buffer = [0u8; 20];
+
+    while let Some((job_id, input)) = speculation.straggler() {
+        let stopped = context.first.as_ref().map_or(false, |first| first.reached())
+            || context.budget.as_ref().map_or(false, |budget| budget.exhausted()) || context.downstream.closed();
+        if stopped {
+            speculation.abandon(job_id);
+            break
+        }
+
+        context.launcher.prefix(job_id, &mut prefix);
+        let timeout = job_timeout(&input, job_id, context.timeout_col, context.timeout);
+        let timeout = context.budget.as_ref().map_or(timeout, |budget| budget.timeout(timeout));
+        let has_timeout = timeout != Duration::from_millis(0);
+
+        command.clear();
+        let (mut child, flags) = match spawn(job_id, &input, &prefix, &mut command) {
+            Some(spawned) => spawned,
+            None => {
+                speculation.abandon(job_id);
+                continue
+            }
+        };
+
+        if !speculation.launched(job_id, child.id()) {
+            let _ = child.kill();
+            let _ = child.wait();
+            if let Some(ref stages) = context.stages { stages.finished(); }
+            continue
+        }
+        let pid = child.id();
+        context.downstream.start(pid);
+        if let Some(ref meter) = context.meter { meter.started(); }
+
+        let result = handle_child(child, output_tx, flags, job_id, input.clone(), has_timeout, timeout,
+            &context.termseq, speculation.dir(), &mut job_buffer, membuffer, None,
+            context.first.as_ref().map(|first| &**first), false, Some((&**speculation, true)), context.tag.as_ref());
+        context.downstream.finish(pid);
+        if let Some(ref stages) = context.stages { stages.finished(); }
+        let (start_time, end_time, exit_value, signal) = match result {
+            Some(result) => result,
+            None => {
+                if let Some(ref meter) = context.meter { meter.stopped(); }
+                continue
+            }
+        };
+        if let Some(ref status) = context.status { status.record(exit_value, signal); }
+        if let Some(ref budget) = context.budget {
+            budget.record(exit_value != 0 || signal != 0, end_time - start_time);
+        }
+
+        let ended = Ended { start_time, end_time, exit_value, signal, ran: true, lost: false, retried: false };
+        context.record(slot, host, output_tx, flags, job_id, &input, &command, &prefix, &ended);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn speculation() {
+        let tempdir = env::temp_dir().join("parallel_speculation_test");
+        let tempdir = tempdir.to_str().unwrap();
+        let speculation = Speculation::new(1, tempdir, "test").unwrap();
+
+        // The original and the duplicate are not processes, so there's nothing for `kill` to find.
+        let pid = u32::MAX >> 1;
+        for job_id in 0..3 {
+            speculation.start(job_id, "fast", pid);
+            assert!(speculation.finish(job_id, false, time::Duration::milliseconds(1)));
+        }
+        speculation.start(3, "slow", pid);
+        assert_eq!(speculation.straggler(), Some((3, "slow".to_owned())));
+        assert!(speculation.launched(3, pid));
+        assert!(speculation.finish(3, false, time::Duration::milliseconds(10)));
+        assert!(!speculation.finish(3, true, time::Duration::milliseconds(10)));
+        assert_eq!(speculation.straggler(), None);
+
+        drop(speculation);
+        let _ = fs::remove_dir_all(tempdir);
+    }
+}
@@ -0,0 +1,88 @@
+use std::io::{self, Write};
+use std::sync::{Condvar, Mutex};
+
+/// Bounds the number of bytes of output that are buffered within the tempdir, as with `--max-spool`.
+/// When the limit is reached, threads stop reading the output of their jobs until buffered output
+/// has been printed, so that the jobs block on their full pipes rather than filling the disk.
+pub struct Spool {
+    usage:   Mutex<Usage>,
+    condvar: Condvar,
+    limit:   u64,
+}
+
+struct Usage {
+    /// The number of bytes that are currently buffered.
+    used:     u64,
+    /// The most bytes that were buffered at once.
+    peak:     u64,
+    /// The ID of the job whose output is next to be printed.
+    printing: usize,
+    /// Whether the user has been warned that the limit was reached.
+    warned:   bool,
+}
+
+impl Spool {
+    pub fn new(limit: u64) -> Spool {
+        Spool {
+            usage:   Mutex::new(Usage { used: 0, peak: 0, printing: 0, warned: false }),
+            condvar: Condvar::new(),
+            limit:   limit,
+        }
+    }
+
+    /// Blocks until `bytes` of output from the given job may be buffered without exceeding the limit.
+    /// The job whose output is next to be printed is never paused, as the receiver is waiting on it,
+    /// so the limit may be exceeded by the output of that job.
+    pub fn reserve(&self, job_id: usize, bytes: u64) {
+        let mut usage = self.usage.lock().unwrap();
+        while job_id != usage.printing && usage.used != 0 && usage.used + bytes > self.limit {
+            if !usage.warned {
+                usage.warned = true;
+                let stderr = io::stderr();
+                let _ = writeln!(stderr.lock(), "parallel: buffered output reached the --max-spool limit of {} \
+                    bytes: pausing jobs until their output is printed", self.limit);
+            }
+            usage = self.condvar.wait(usage).unwrap();
+        }
+        usage.used += bytes;
+        if usage.used > usage.peak { usage.peak = usage.used; }
+    }
+
+    /// The most bytes of output that were buffered at once.
+    pub fn peak(&self) -> u64 {
+        self.usage.lock().unwrap().peak
+    }
+
+    /// Releases the `bytes` of a job whose output was printed, where `printing` is the next job to print.
+    pub fn release(&self, bytes: u64, printing: usize) {
+        let mut usage = self.usage.lock().unwrap();
+        usage.used = usage.used.saturating_sub(bytes);
+        usage.printing = printing;
+        self.condvar.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    #[test]
+    fn spool_pauses_until_released() {
+        let spool = Arc::new(Spool::new(10));
+        spool.reserve(0, 8);
+        // The job being printed is never paused.
+        spool.reserve(0, 8);
+
+        let waiting = spool.clone();
+        let handle = thread::spawn(move || waiting.reserve(1, 4));
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(spool.usage.lock().unwrap().used, 16);
+
+        spool.release(16, 1);
+        handle.join().unwrap();
+        assert_eq!(spool.usage.lock().unwrap().used, 4);
+    }
+}
@@ -0,0 +1,133 @@
+//! With `--then`, each job is a pipeline of stages, where each stage after the first is executed as
+//! soon as the stage before it has succeeded, rather than once every job of that stage has completed.
+//! The standard output of a stage replaces `{prev}` within the template of the next stage, while the
+//! other placeholders still refer to the input of the job. With `--then-jobs`, the number of jobs
+//! which may be within a stage at once is limited, so that a stage which needs a scarce resource,
+//! such as the network or a license, is not executed within every job slot.
+
+use arguments::{DASH_EXISTS, ION_EXISTS, QUIET_MODE, SHELL_ENABLED, TTY, UNGROUP};
+use std::io::{self, Write};
+use std::process::Child;
+use tokenizer::Token;
+use super::command::{append_argument, CommandErr, ParallelCommand};
+use super::slots::Slots;
+
+struct Stage {
+    template: &'static [Token],
+    /// The flags which execute the stage within a shell, if its template needs one.
+    shell:    u16,
+    slots:    Option<Slots>,
+}
+
+pub struct Stages {
+    stages: Vec<Stage>,
+}
+
+impl Stages {
+    /// Each stage is given by its template, its shell flags, and the number of its jobs which may be
+    /// executed at once, where zero is unlimited.
+    pub fn new(stages: Vec<(&'static [Token], u16, usize)>) -> Stages {
+        Stages {
+            stages: stages.into_iter().map(|(template, shell, jobs)| Stage {
+                template: template,
+                shell:    shell,
+                slots:    if jobs == 0 { None } else { Some(Slots::new(jobs)) },
+            }).collect()
+        }
+    }
+
+    /// Executes each stage of the job but the last one, which is spawned, so that its output is that
+    /// of the job, and its command is left within `arguments`. The standard error of the earlier
+    /// stages is printed as they complete. If a stage fails, the stages after it are not executed.
+    /// Once the last stage has completed, `finished()` must be called.
+    pub fn exec(&self, command: &ParallelCommand, arguments: &mut String, discard: bool) -> Result<Child, CommandErr> {
+        // The output of the earlier stages is always captured, as it is the input of the next stage.
+        let captured = command.flags & !(QUIET_MODE | TTY | UNGROUP);
+        let mut previous = finish(ParallelCommand { flags: captured, ..*command }.exec(arguments, false)?, 1)?;
+
+        let (last, earlier) = self.stages.split_last().expect("a job has at least one further stage");
+        for (index, stage) in earlier.iter().enumerate() {
+            let flags = stage.flags(captured);
+            let stage_command = ParallelCommand { command_template: stage.template, flags: flags, ..*command };
+            build(&stage_command, &previous, arguments);
+            if let Some(ref slots) = stage.slots { slots.acquire(1); }
+            let result = stage_command.spawn(arguments, false).and_then(|child| finish(child, index + 2));
+            if let Some(ref slots) = stage.slots { slots.release(1); }
+            previous = result?;
+        }
+
+        let stage_command = ParallelCommand { command_template: last.template, flags: last.flags(command.flags), ..*command };
+        build(&stage_command, &previous, arguments);
+        if let Some(ref slots) = last.slots { slots.acquire(1); }
+        stage_command.spawn(arguments, discard).inspect_err(|_| {
+            if let Some(ref slots) = last.slots { slots.release(1); }
+        })
+    }
+
+    /// Releases the place of a job within the last stage, once it has completed.
+    pub fn finished(&self) {
+        if let Some(slots) = self.stages.last().and_then(|stage| stage.slots.as_ref()) { slots.release(1); }
+    }
+}
+
+impl Stage {
+    /// The flags of the job, where the shell is that of this stage, rather than that of the command.
+    fn flags(&self, flags: u16) -> u16 {
+        flags & !(SHELL_ENABLED | DASH_EXISTS | ION_EXISTS) | self.shell
+    }
+}
+
+/// Builds the command of a stage, where the `previous` output replaces `{prev}` as a single quoted
+/// word, or is appended to the command as an input would be, if the template has no `{prev}`.
+fn build(command: &ParallelCommand, previous: &str, arguments: &mut String) {
+    arguments.clear();
+    ParallelCommand { previous: Some(previous), ..*command }.build_arguments(arguments);
+    if !command.command_template.contains(&Token::Previous) {
+        append_argument(arguments, &[], previous, command.flags, command.flags & SHELL_ENABLED != 0);
+    }
+}
+
+/// Waits for an earlier stage of a job to complete, and returns its output, without the line
+/// ending of its last line, if the stage succeeded.
+fn finish(child: Child, stage: usize) -> Result<String, CommandErr> {
+    let output = child.wait_with_output().map_err(CommandErr::IO)?;
+    if !output.stderr.is_empty() {
+        let stderr = io::stderr();
+        let _ = stderr.lock().write_all(&output.stderr);
+    }
+    if !output.status.success() { return Err(CommandErr::Stage(stage, output.status)) }
+
+    let output = String::from_utf8_lossy(&output.stdout);
+    Ok(output.trim_end_matches(['\n', '\r']).to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::borrow::Cow;
+
+    #[test]
+    fn previous_output() {
+        let template = [Token::Argument(Cow::Borrowed("gzip ")), Token::Previous, Token::Argument(Cow::Borrowed(" # ")),
+            Token::Placeholder];
+        let mut command = ParallelCommand {
+            slot_no:   "1",
+            job_no:    b"1",
+            job_total: b"1",
+            ..ParallelCommand::template(&template, "a.txt", SHELL_ENABLED)
+        };
+        let mut arguments = String::new();
+        build(&command, "out/a b.txt", &mut arguments);
+        assert_eq!(arguments, "gzip 'out/a b.txt' # a.txt");
+
+        // The output is never interpreted by the shell, and a `{prev}` within the input is left as it is.
+        command.input = "{prev}";
+        build(&command, "a b;echo INJECTED", &mut arguments);
+        assert_eq!(arguments, "gzip 'a b;echo INJECTED' # {prev}");
+
+        let template = [Token::Argument(Cow::Borrowed("wc -l"))];
+        command.command_template = &template;
+        build(&command, "a.txt b.txt", &mut arguments);
+        assert_eq!(arguments, "wc -l a.txt b.txt");
+    }
+}
@@ -0,0 +1,114 @@
+use std::io;
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+
+/// The conventions that the exit status of the run may follow, rather than counting errors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExitPolicy {
+    /// Under `--xargs`: 123 if a command exited with a status of 1 through 254, 124 if a command
+    /// exited with 255, 125 if a command was killed, 126 if a command could not be executed, and
+    /// 127 if a command was not found.
+    Xargs,
+    /// Under `--gnu-compat`: the number of jobs which failed, up to 100, or 101 if more failed.
+    Gnu,
+}
+
+/// Tracks the exit status of a run from the outcome of each job, according to an `ExitPolicy`,
+/// along with how many jobs succeeded and failed, which `--notify` reports once the run completes.
+pub struct JobStatus {
+    policy:    Option<ExitPolicy>,
+    /// The highest xargs status, or the number of failed jobs.
+    value:     AtomicI32,
+    succeeded: AtomicUsize,
+    failed:    AtomicUsize,
+}
+
+impl JobStatus {
+    pub fn new(policy: Option<ExitPolicy>) -> JobStatus {
+        JobStatus { policy, value: AtomicI32::new(0), succeeded: AtomicUsize::new(0), failed: AtomicUsize::new(0) }
+    }
+
+    /// Records the outcome of a job which was executed.
+    pub fn record(&self, exit_value: i32, signal: i32) {
+        let counter = if exit_value == 0 && signal == 0 { &self.succeeded } else { &self.failed };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        match self.policy {
+            None => (),
+            Some(ExitPolicy::Xargs) => {
+                let status = match (exit_value, signal) {
+                    (0, _)   => 0,
+                    (255, _) => 124,
+                    (-1, 0)  => 0,
+                    (-1, _)  => 125,
+                    _        => 123,
+                };
+                self.value.fetch_max(status, Ordering::Relaxed);
+            },
+            Some(ExitPolicy::Gnu) if exit_value != 0 || signal != 0 => {
+                self.value.fetch_add(1, Ordering::Relaxed);
+            },
+            Some(ExitPolicy::Gnu) => ()
+        }
+    }
+
+    /// Records a job whose command could not be executed.
+    pub fn record_error(&self, why: &io::Error) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+
+        match self.policy {
+            None => (),
+            Some(ExitPolicy::Xargs) => {
+                let status = if why.kind() == io::ErrorKind::NotFound { 127 } else { 126 };
+                self.value.fetch_max(status, Ordering::Relaxed);
+            },
+            Some(ExitPolicy::Gnu) => { self.value.fetch_add(1, Ordering::Relaxed); }
+        }
+    }
+
+    /// The exit status of the run, if it follows an `ExitPolicy`.
+    pub fn code(&self) -> Option<i32> {
+        let value = self.value.load(Ordering::Relaxed);
+        self.policy.map(|policy| match policy {
+            ExitPolicy::Xargs => value,
+            ExitPolicy::Gnu   => if value > 100 { 101 } else { value },
+        })
+    }
+
+    /// The number of jobs which exited successfully.
+    pub fn succeeded(&self) -> usize { self.succeeded.load(Ordering::Relaxed) }
+
+    /// The number of jobs which failed, were killed, or could not be executed.
+    pub fn failed(&self) -> usize { self.failed.load(Ordering::Relaxed) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xargs_status() {
+        let status = JobStatus::new(Some(ExitPolicy::Xargs));
+        status.record(0, 0);
+        assert_eq!(status.code(), Some(0));
+        status.record(1, 0);
+        assert_eq!(status.code(), Some(123));
+        status.record(-1, 15);
+        assert_eq!(status.code(), Some(125));
+        status.record(255, 0);
+        assert_eq!(status.code(), Some(125));
+        status.record_error(&io::Error::from(io::ErrorKind::NotFound));
+        assert_eq!(status.code(), Some(127));
+    }
+
+    #[test]
+    fn gnu_status() {
+        let status = JobStatus::new(Some(ExitPolicy::Gnu));
+        status.record(0, 0);
+        status.record(2, 0);
+        status.record(-1, 9);
+        assert_eq!(status.code(), Some(2));
+        for _ in 0..200 { status.record(1, 0); }
+        assert_eq!(status.code(), Some(101));
+        assert_eq!((status.succeeded(), status.failed()), (1, 202));
+    }
+}
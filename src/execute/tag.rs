@@ -0,0 +1,90 @@
+use std::fmt::Write as FmtWrite;
+use std::io::{self, Write};
+
+/// The colors which the tags of `--ctag` cycle through, by job, as ANSI escape sequences.
+const COLORS: &[&str] = &["\x1b[30;42m", "\x1b[30;43m", "\x1b[30;44m", "\x1b[30;45m", "\x1b[30;46m", "\x1b[30;47m"];
+const RESET: &str = "\x1b[0m";
+
+/// With `--tag`, each line of the output of a job is prefixed with the input of the job, followed
+/// by a `separator`, which is a tab unless another was given with `--tag-sep`. With `--ctag`, the
+/// tag is colored by job, and the separator is a single space by default, as the color already
+/// sets the tag apart.
+#[derive(Clone)]
+pub struct Tag {
+    pub separator: String,
+    pub color:     bool,
+}
+
+impl Tag {
+    /// The prefix which is written before each line of the output of the job of `job_id`.
+    pub fn prefix(&self, job_id: usize, input: &str) -> Vec<u8> {
+        let mut prefix = String::with_capacity(input.len() + 16);
+        if self.color { prefix.push_str(COLORS[job_id % COLORS.len()]); }
+        escape(&mut prefix, input, &self.separator);
+        if self.color { prefix.push_str(RESET); }
+        prefix.push_str(&self.separator);
+        prefix.into_bytes()
+    }
+}
+
+/// Appends the `tag` to `prefix`, escaping backslashes, line endings, and tabs with a backslash,
+/// along with the first character of the `separator`, which is written as its code point. The tag
+/// then never contains the separator that follows it, so each line of tagged output may be split
+/// unambiguously at the first separator.
+fn escape(prefix: &mut String, tag: &str, separator: &str) {
+    for character in tag.chars() {
+        match character {
+            '\\' => prefix.push_str("\\\\"),
+            '\n' => prefix.push_str("\\n"),
+            '\r' => prefix.push_str("\\r"),
+            '\t' => prefix.push_str("\\t"),
+            _ if separator.starts_with(character) => {
+                let _ = write!(prefix, "\\u{{{:x}}}", character as u32);
+            },
+            _ => prefix.push(character),
+        }
+    }
+}
+
+/// Writes the `prefix` of a job at the start of each line of its output, which may be written
+/// in pieces that do not end at a line boundary.
+pub struct Tagger<'a> {
+    prefix:     &'a [u8],
+    line_start: bool,
+}
+
+impl<'a> Tagger<'a> {
+    pub fn new(prefix: &'a [u8]) -> Tagger<'a> { Tagger { prefix, line_start: true } }
+
+    pub fn write<W: Write>(&mut self, out: &mut W, mut bytes: &[u8]) -> io::Result<()> {
+        while !bytes.is_empty() {
+            if self.line_start { out.write_all(self.prefix)?; }
+            let end = bytes.iter().position(|&byte| byte == b'\n').map_or(bytes.len(), |newline| newline + 1);
+            out.write_all(&bytes[..end])?;
+            self.line_start = bytes[end - 1] == b'\n';
+            bytes = &bytes[end..];
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tagged_lines() {
+        let tag = Tag { separator: "\t".to_owned(), color: false };
+        let prefix = tag.prefix(0, "a\tb\\c");
+        assert_eq!(prefix, b"a\\tb\\\\c\t".to_vec());
+
+        let tag = Tag { separator: ": ".to_owned(), color: false };
+        assert_eq!(tag.prefix(0, "x: y"), b"x\\u{3a} y: ".to_vec());
+
+        let mut output = Vec::new();
+        let mut tagger = Tagger::new(b"in\t");
+        tagger.write(&mut output, b"one\ntw").unwrap();
+        tagger.write(&mut output, b"o\nthree").unwrap();
+        assert_eq!(output, b"in\tone\nin\ttwo\nin\tthree".to_vec());
+    }
+}
@@ -0,0 +1,95 @@
+use record::write_string;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+use super::notify::Summary;
+
+/// With `--webhook URL`, a JSON summary of the run is posted to the URL once the run has completed,
+/// and with `--webhook-failures`, each job which failed is also posted as it fails:
+///
+/// - `{"event":"run_completed","total":N,"succeeded":N,"failed":N,"skipped":N,"elapsed":SECONDS,"status":N}`
+/// - `{"event":"job_failed","job":N,"input":"...","exit":N,"signal":N}`
+///
+/// Requests are made with `curl`, so that HTTPS is supported without linking a TLS library. A
+/// request which fails is attempted again up to `retries` times, waiting a second longer each time.
+pub struct Webhook {
+    url:      String,
+    timeout:  Duration,
+    retries:  usize,
+    failures: bool,
+}
+
+impl Webhook {
+    /// How long a request may take, unless `--webhook-timeout` was given.
+    pub const TIMEOUT: Duration = Duration::from_secs(10);
+    /// How many times a failed request is attempted again, unless `--webhook-retries` was given.
+    pub const RETRIES: usize = 2;
+
+    pub fn new(url: String, timeout: Duration, retries: usize, failures: bool) -> Webhook {
+        Webhook { url, timeout, retries, failures }
+    }
+
+    /// Whether each job which failed is to be posted.
+    pub fn failures(&self) -> bool { self.failures }
+
+    pub fn completed(&self, summary: &Summary) {
+        let elapsed = summary.elapsed.as_secs() as f64 + f64::from(summary.elapsed.subsec_nanos()) / 1e9;
+        let body = format!("{{\"event\":\"run_completed\",\"total\":{},\"succeeded\":{},\"failed\":{},\"skipped\":{},\
+            \"elapsed\":{:.3},\"status\":{}}}", summary.total, summary.succeeded, summary.failed, summary.skipped(),
+            elapsed, summary.status);
+        self.send(body.as_bytes());
+    }
+
+    pub fn failed(&self, job_id: usize, input: &str, exit_value: i32, signal: i32) {
+        let mut body = Vec::with_capacity(128);
+        let _ = write!(body, "{{\"event\":\"job_failed\",\"job\":{},\"input\":", job_id + 1);
+        let _ = write_string(&mut body, input);
+        let _ = write!(body, ",\"exit\":{},\"signal\":{}}}", exit_value, signal);
+        self.send(&body);
+    }
+
+    fn send(&self, body: &[u8]) {
+        let mut attempt = 0;
+        let why = loop {
+            let why = match self.post(body) {
+                Ok(()) => return,
+                Err(why) => why
+            };
+            // Without curl, there is no use in trying again.
+            if why.kind() == io::ErrorKind::NotFound || attempt == self.retries { break why }
+            attempt += 1;
+            thread::sleep(Duration::from_secs(attempt as u64));
+        };
+
+        let stderr = io::stderr();
+        let _ = writeln!(stderr.lock(), "parallel: unable to post to webhook {}: {}", self.url, why);
+    }
+
+    fn post(&self, body: &[u8]) -> io::Result<()> {
+        let timeout = self.timeout.as_secs() as f64 + f64::from(self.timeout.subsec_nanos()) / 1e9;
+        let mut curl = Command::new("curl")
+            .args(["--silent", "--show-error", "--fail", "--request", "POST"])
+            .args(["--header", "Content-Type: application/json", "--data-binary", "@-", "--max-time"])
+            .arg(format!("{:.3}", timeout))
+            .arg(&self.url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let written = curl.stdin.take().map_or(Ok(()), |mut stdin| stdin.write_all(body));
+        let output = curl.wait_with_output()?;
+        if output.status.success() {
+            written
+        } else {
+            let message = String::from_utf8_lossy(&output.stderr);
+            let message = message.trim();
+            Err(io::Error::other(if message.is_empty() {
+                format!("curl exited with {}", output.status)
+            } else {
+                message.trim_start_matches("curl: ").to_owned()
+            }))
+        }
+    }
+}
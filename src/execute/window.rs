@@ -0,0 +1,100 @@
+use std::io::{self, Write};
+use std::time::Duration;
+use time::{self, Timespec};
+use tokenizer::column;
+
+/// Determines how jobs whose input is past its `--not-after` deadline will be handled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Expired { Skip, Fail }
+
+impl Expired {
+    pub fn parse(name: &str) -> Option<Expired> {
+        match name {
+            "skip" => Some(Expired::Skip),
+            "fail" => Some(Expired::Fail),
+            _      => None
+        }
+    }
+}
+
+/// Whether a job may be executed at the time of dispatch.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dispatch {
+    Ready,
+    /// The earliest start time of the job has not arrived, and will arrive after this long.
+    Wait(Duration),
+    Expired,
+}
+
+/// With `--not-before` and `--not-after`, the earliest start time and the deadline of each job are
+/// read from columns of its input, as seconds since the Unix epoch or as UTC timestamps of the
+/// form `2024-01-31T12:00:00Z`, where `-` denotes that there is none. Jobs are set aside until their
+/// start time, and jobs which have not been executed by their deadline are skipped or failed.
+#[derive(Clone, Copy, Debug)]
+pub struct Window {
+    pub not_before: usize,
+    pub not_after:  usize,
+    pub expired:    Expired,
+}
+
+impl Window {
+    /// Determines whether the job may be executed now. Columns which are missing are ignored, and
+    /// columns which are invalid are reported and ignored.
+    pub fn dispatch(&self, input: &str, job_id: usize) -> Dispatch {
+        let now = time::get_time();
+        if let Some(deadline) = Window::timestamp(input, job_id, self.not_after) {
+            if now > deadline { return Dispatch::Expired }
+        }
+        match Window::timestamp(input, job_id, self.not_before) {
+            Some(start) if start > now => Dispatch::Wait((start - now).to_std().unwrap_or_default()),
+            _ => Dispatch::Ready
+        }
+    }
+
+    fn timestamp(input: &str, job_id: usize, column_no: usize) -> Option<Timespec> {
+        if column_no == 0 { return None }
+        let value = column(input, column_no).filter(|&value| value != "-")?;
+        let timestamp = parse_timestamp(value);
+        if timestamp.is_none() {
+            let stderr = io::stderr();
+            let _ = writeln!(stderr.lock(), "parallel: job {}: invalid timestamp column value, '{}', ignoring",
+                job_id + 1, value);
+        }
+        timestamp
+    }
+}
+
+/// Parses seconds since the Unix epoch, which may be fractional, or a UTC timestamp of the form
+/// `YYYY-MM-DDTHH:MM:SS`, with an optional trailing `Z`.
+fn parse_timestamp(value: &str) -> Option<Timespec> {
+    if let Ok(seconds) = value.parse::<f64>() {
+        if !seconds.is_finite() { return None }
+        let nanoseconds = (seconds.fract() * 1e9) as i32;
+        return Some(Timespec::new(seconds.trunc() as i64, 0) + time::Duration::nanoseconds(nanoseconds as i64));
+    }
+    let value = value.strip_suffix('Z').unwrap_or(value);
+    time::strptime(value, "%Y-%m-%dT%H:%M:%S").ok().map(|tm| tm.to_timespec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(parse_timestamp("1700000000"), Some(Timespec::new(1700000000, 0)));
+        assert_eq!(parse_timestamp("1700000000.5"), Some(Timespec::new(1700000000, 500_000_000)));
+        assert_eq!(parse_timestamp("2023-11-14T22:13:20Z"), Some(Timespec::new(1700000000, 0)));
+        assert_eq!(parse_timestamp("2023-11-14T22:13:20"), Some(Timespec::new(1700000000, 0)));
+        assert_eq!(parse_timestamp("tomorrow"), None);
+
+        let window = Window { not_before: 2, not_after: 3, expired: Expired::Skip };
+        assert_eq!(window.dispatch("a 0 1", 0), Dispatch::Expired);
+        assert_eq!(window.dispatch("a 0", 0), Dispatch::Ready);
+        assert_eq!(window.dispatch("a - -", 0), Dispatch::Ready);
+        match window.dispatch("a 99999999999", 0) {
+            Dispatch::Wait(delay) => assert!(delay > Duration::from_secs(1_000_000)),
+            dispatch => panic!("expected to wait, found {:?}", dispatch)
+        }
+    }
+}
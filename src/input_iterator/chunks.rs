@@ -0,0 +1,45 @@
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+
+/// Divides a file of `length` bytes into byte ranges of roughly `size` bytes, for `--chunk-file`.
+/// Each range but the last is extended to the end of the line that it ends within, so that no
+/// record is split between two jobs. The end of each range is exclusive.
+pub fn ranges<R: Read + Seek>(file: R, length: u64, size: u64) -> io::Result<Vec<(u64, u64)>> {
+    let mut file = BufReader::new(file);
+    let mut ranges = Vec::new();
+    let mut line = Vec::new();
+    let mut start = 0;
+
+    while start < length {
+        let target = start + size;
+        let end = if target >= length {
+            length
+        } else {
+            // Seek to the last byte of the range, and read up to the end of the line containing it.
+            file.seek(SeekFrom::Start(target - 1))?;
+            line.clear();
+            (target - 1 + file.read_until(b'\n', &mut line)? as u64).min(length)
+        };
+        ranges.push((start, end));
+        start = end;
+    }
+
+    // An empty file is still given a job, so that every input is accounted for.
+    if ranges.is_empty() { ranges.push((0, 0)); }
+    Ok(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn chunk_ranges() {
+        let data = b"aaaa\nbb\ncccccc\nd\n";
+        let length = data.len() as u64;
+        assert_eq!(ranges(Cursor::new(&data[..]), length, 4).unwrap(), vec![(0, 5), (5, 15), (15, 17)]);
+        assert_eq!(ranges(Cursor::new(&data[..]), length, 5).unwrap(), vec![(0, 5), (5, 15), (15, 17)]);
+        assert_eq!(ranges(Cursor::new(&data[..]), length, 100).unwrap(), vec![(0, 17)]);
+        assert_eq!(ranges(Cursor::new(&b""[..]), 0, 4).unwrap(), vec![(0, 0)]);
+    }
+}
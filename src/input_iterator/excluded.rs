@@ -0,0 +1,47 @@
+use std::collections::HashSet;
+
+/// The jobs which are not executed by this invocation, which are passed over without changing the
+/// job IDs of the jobs that remain, so that their sequence numbers are the same in every invocation.
+#[derive(Clone, Default)]
+pub struct Excluded {
+    /// The IDs of the jobs which were already logged, with `--resume`.
+    pub resumed:   HashSet<usize>,
+    /// The IDs of the jobs whose label was not one of those of `--only-label`.
+    pub unlabeled: HashSet<usize>,
+    /// With `--shard-id i/n`, the index, counting from zero, and the number of shards, where only
+    /// every nth job, starting from the ith, belongs to this invocation.
+    pub shard:     Option<(usize, usize)>,
+}
+
+impl Excluded {
+    /// Parses a shard of the form `i/n`, where `i` counts from one and is no greater than `n`.
+    pub fn parse_shard(value: &str) -> Option<(usize, usize)> {
+        let mut parts = value.splitn(2, '/');
+        let index = parts.next()?.trim().parse::<usize>().ok()?;
+        let count = parts.next()?.trim().parse::<usize>().ok()?;
+        if index == 0 || index > count { None } else { Some((index - 1, count)) }
+    }
+
+    /// Whether the job of `job_id` is not to be executed.
+    pub fn contains(&self, job_id: &usize) -> bool {
+        self.shard.map_or(false, |(index, count)| job_id % count != index) || self.resumed.contains(job_id)
+            || self.unlabeled.contains(job_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shards() {
+        assert_eq!(Excluded::parse_shard("2/5"), Some((1, 5)));
+        assert_eq!(Excluded::parse_shard("0/5"), None);
+        assert_eq!(Excluded::parse_shard("6/5"), None);
+        assert_eq!(Excluded::parse_shard("5"), None);
+
+        let excluded = Excluded { resumed: [6].iter().cloned().collect(), unlabeled: HashSet::new(), shard: Some((1, 5)) };
+        let included = (0..12).filter(|id| !excluded.contains(id)).collect::<Vec<usize>>();
+        assert_eq!(included, vec![1, 11]);
+    }
+}
@@ -0,0 +1,261 @@
+use std::cmp::Ordering;
+
+/// A boolean expression which permutations must satisfy to be executed, as given to `--where`.
+/// `{N}` refers to the value of the Nth list within the permutation. Values are compared
+/// numerically when both sides are numbers, and as strings otherwise.
+#[derive(Clone)]
+pub struct Filter {
+    expression: Expression,
+    columns:    usize,
+}
+
+#[derive(Clone)]
+enum Expression {
+    And(Box<Expression>, Box<Expression>),
+    Or(Box<Expression>, Box<Expression>),
+    Not(Box<Expression>),
+    Compare(Operand, Comparison, Operand),
+    /// An operand by itself is true if it is neither empty nor `0`.
+    Truthy(Operand),
+}
+
+#[derive(Clone, PartialEq)]
+enum Operand {
+    Column(usize),
+    Literal(String),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Comparison { Less, LessEqual, Greater, GreaterEqual, Equal, NotEqual }
+
+#[derive(PartialEq)]
+enum Lexeme {
+    Operand(Operand),
+    Compare(Comparison),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl Filter {
+    /// Parses the expression, returning a description of the problem if it is invalid.
+    pub fn parse(input: &str) -> Result<Filter, String> {
+        let lexemes = lex(input)?;
+        let mut parser = Parser { lexemes: &lexemes, index: 0, columns: 0 };
+        let expression = parser.or()?;
+        if parser.index != lexemes.len() { return Err("unexpected trailing tokens".to_owned()); }
+        Ok(Filter { expression, columns: parser.columns })
+    }
+
+    /// The largest `{N}` that the expression refers to.
+    pub fn columns(&self) -> usize { self.columns }
+
+    /// Evaluates the expression, where `column` obtains the value of the Nth list, counting from one.
+    pub fn matches<'a, F: Fn(usize) -> &'a str>(&self, column: &F) -> bool {
+        evaluate(&self.expression, column)
+    }
+}
+
+fn evaluate<'a, F: Fn(usize) -> &'a str>(expression: &Expression, column: &F) -> bool {
+    match *expression {
+        Expression::And(ref left, ref right) => evaluate(left, column) && evaluate(right, column),
+        Expression::Or(ref left, ref right)  => evaluate(left, column) || evaluate(right, column),
+        Expression::Not(ref inner)           => !evaluate(inner, column),
+        Expression::Truthy(ref operand)      => {
+            let value = resolve(operand, column);
+            !value.is_empty() && value != "0"
+        },
+        Expression::Compare(ref left, comparison, ref right) => {
+            let (left, right) = (resolve(left, column), resolve(right, column));
+            let ordering = match (left.parse::<f64>(), right.parse::<f64>()) {
+                (Ok(left), Ok(right)) => match left.partial_cmp(&right) {
+                    Some(ordering) => ordering,
+                    None => return false
+                },
+                _ => left.cmp(right)
+            };
+
+            match comparison {
+                Comparison::Less         => ordering == Ordering::Less,
+                Comparison::LessEqual    => ordering != Ordering::Greater,
+                Comparison::Greater      => ordering == Ordering::Greater,
+                Comparison::GreaterEqual => ordering != Ordering::Less,
+                Comparison::Equal        => ordering == Ordering::Equal,
+                Comparison::NotEqual     => ordering != Ordering::Equal,
+            }
+        }
+    }
+}
+
+fn resolve<'a, 'b, F: Fn(usize) -> &'a str>(operand: &'b Operand, column: &F) -> &'b str where 'a: 'b {
+    match *operand {
+        Operand::Column(id)          => column(id),
+        Operand::Literal(ref string) => string,
+    }
+}
+
+/// Splits the expression into lexemes.
+fn lex(input: &str) -> Result<Vec<Lexeme>, String> {
+    let mut lexemes = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((start, character)) = chars.next() {
+        let lexeme = match character {
+            _ if character.is_whitespace() => continue,
+            '(' => Lexeme::Open,
+            ')' => Lexeme::Close,
+            '<' | '>' | '=' | '!' => {
+                let equals = chars.peek().map_or(false, |&(_, next)| next == '=');
+                if equals { chars.next(); }
+                match (character, equals) {
+                    ('<', false) => Lexeme::Compare(Comparison::Less),
+                    ('<', true)  => Lexeme::Compare(Comparison::LessEqual),
+                    ('>', false) => Lexeme::Compare(Comparison::Greater),
+                    ('>', true)  => Lexeme::Compare(Comparison::GreaterEqual),
+                    ('=', _)     => Lexeme::Compare(Comparison::Equal),
+                    ('!', true)  => Lexeme::Compare(Comparison::NotEqual),
+                    _            => Lexeme::Not,
+                }
+            },
+            '&' | '|' => {
+                match chars.next() {
+                    Some((_, next)) if next == character => (),
+                    _ => return Err(format!("expected '{0}{0}' at position {1}", character, start))
+                }
+                if character == '&' { Lexeme::And } else { Lexeme::Or }
+            },
+            '\'' | '"' => {
+                let mut literal = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, next)) if next == character => break,
+                        Some((_, next)) => literal.push(next),
+                        None => return Err(format!("quote at position {} is not terminated", start))
+                    }
+                }
+                Lexeme::Operand(Operand::Literal(literal))
+            },
+            '{' => {
+                let mut digits = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '}')) => break,
+                        Some((_, next)) => digits.push(next),
+                        None => return Err(format!("brace at position {} is not terminated", start))
+                    }
+                }
+                match digits.parse::<usize>() {
+                    Ok(id) if id != 0 => Lexeme::Operand(Operand::Column(id)),
+                    _ => return Err(format!("'{{{}}}' is not a column, such as {{1}}", digits))
+                }
+            },
+            _ => {
+                let mut word = character.to_string();
+                while let Some(&(_, next)) = chars.peek() {
+                    if next.is_whitespace() || "()<>=!&|'\"{".contains(next) { break }
+                    word.push(next);
+                    chars.next();
+                }
+                match word.as_str() {
+                    "and" => Lexeme::And,
+                    "or"  => Lexeme::Or,
+                    "not" => Lexeme::Not,
+                    _     => Lexeme::Operand(Operand::Literal(word))
+                }
+            }
+        };
+        lexemes.push(lexeme);
+    }
+
+    Ok(lexemes)
+}
+
+/// A recursive descent parser, where `and` binds more tightly than `or`.
+struct Parser<'a> {
+    lexemes: &'a [Lexeme],
+    index:   usize,
+    columns: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn next_is(&mut self, lexeme: Lexeme) -> bool {
+        if self.lexemes.get(self.index) == Some(&lexeme) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn or(&mut self) -> Result<Expression, String> {
+        let mut expression = self.and()?;
+        while self.next_is(Lexeme::Or) {
+            expression = Expression::Or(Box::new(expression), Box::new(self.and()?));
+        }
+        Ok(expression)
+    }
+
+    fn and(&mut self) -> Result<Expression, String> {
+        let mut expression = self.unary()?;
+        while self.next_is(Lexeme::And) {
+            expression = Expression::And(Box::new(expression), Box::new(self.unary()?));
+        }
+        Ok(expression)
+    }
+
+    fn unary(&mut self) -> Result<Expression, String> {
+        if self.next_is(Lexeme::Not) {
+            Ok(Expression::Not(Box::new(self.unary()?)))
+        } else if self.next_is(Lexeme::Open) {
+            let expression = self.or()?;
+            if !self.next_is(Lexeme::Close) { return Err("expected a closing parenthesis".to_owned()); }
+            Ok(expression)
+        } else {
+            let left = self.operand()?;
+            if let Some(&Lexeme::Compare(comparison)) = self.lexemes.get(self.index) {
+                self.index += 1;
+                Ok(Expression::Compare(left, comparison, self.operand()?))
+            } else {
+                Ok(Expression::Truthy(left))
+            }
+        }
+    }
+
+    fn operand(&mut self) -> Result<Operand, String> {
+        match self.lexemes.get(self.index) {
+            Some(&Lexeme::Operand(Operand::Column(id))) => {
+                self.index += 1;
+                if id > self.columns { self.columns = id; }
+                Ok(Operand::Column(id))
+            },
+            Some(&Lexeme::Operand(Operand::Literal(ref literal))) => {
+                self.index += 1;
+                Ok(Operand::Literal(literal.clone()))
+            },
+            _ => Err("expected a value or a column, such as {1}".to_owned())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(expression: &str, values: &[&'static str]) -> bool {
+        Filter::parse(expression).unwrap().matches(&|id| values[id-1])
+    }
+
+    #[test]
+    fn filters() {
+        assert!(matches("{1} < {2}", &["2", "10"]));
+        assert!(!matches("{1} < {2}", &["b", "a"]));
+        assert!(matches("{1} != {2} and ({2} == 3 || !{1})", &["0", "3"]));
+        assert!(matches("not {1} >= 1e3 && {2} = 'a b'", &["999", "a b"]));
+        assert_eq!(Filter::parse("{1} < {3}").unwrap().columns(), 3);
+        assert!(Filter::parse("{1} <").is_err());
+        assert!(Filter::parse("({1} < 2").is_err());
+        assert!(Filter::parse("{a} < 2").is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use super::permutations::PermutatedInputs;
+use numtoa::NumToA;
+use std::str;
+
+/// Inputs which are generated as they are needed, rather than being read from the unprocessed file.
+#[derive(Clone)]
+pub enum Generator {
+    /// The permutations of multiple input lists.
+    Permutations(PermutatedInputs),
+    /// A range of integers, as with `--seq`.
+    Sequence(Sequence),
+}
+
+impl Generator {
+    /// The number of inputs that will be generated.
+    pub fn len(&self) -> usize {
+        match *self {
+            Generator::Permutations(ref inputs) => inputs.len(),
+            Generator::Sequence(ref sequence)   => sequence.len(),
+        }
+    }
+
+    /// Whether no inputs will be generated.
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Writes the next input into the `buffer`, returning `false` if the inputs are exhausted.
+    pub fn next(&mut self, buffer: &mut String) -> bool {
+        match *self {
+            Generator::Permutations(ref mut inputs) => inputs.next(buffer),
+            Generator::Sequence(ref mut sequence)   => sequence.next(buffer),
+        }
+    }
+
+    /// Obtains the Nth input, counting from one, without advancing the generator.
+    pub fn nth(&self, id: usize) -> Option<String> {
+        match *self {
+            Generator::Permutations(ref inputs) => inputs.nth(id),
+            Generator::Sequence(ref sequence)   => sequence.nth(id),
+        }
+    }
+}
+
+/// Generates each integer from `first` to `last`, inclusively, counting down if `last` is less
+/// than `first`.
+#[derive(Clone)]
+pub struct Sequence {
+    first:     i64,
+    last:      i64,
+    generated: u64,
+    /// The number of integers that are grouped into each input, as with `--max-args`.
+    group:     u64,
+}
+
+impl Sequence {
+    pub fn new(first: i64, last: i64, max_args: usize) -> Sequence {
+        Sequence { first, last, generated: 0, group: if max_args < 2 { 1 } else { max_args as u64 } }
+    }
+
+    /// The number of integers within the sequence.
+    fn count(&self) -> u64 {
+        if self.last < self.first {
+            self.first.wrapping_sub(self.last) as u64 + 1
+        } else {
+            self.last.wrapping_sub(self.first) as u64 + 1
+        }
+    }
+
+    /// The number of inputs that will be generated.
+    pub fn len(&self) -> usize {
+        let count = self.count();
+        count.div_ceil(self.group) as usize
+    }
+
+    /// Whether no inputs will be generated.
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Writes the next input into the `buffer`, returning `false` if the sequence is exhausted.
+    pub fn next(&mut self, buffer: &mut String) -> bool {
+        buffer.clear();
+        let (start, count) = (self.generated, self.count());
+        if start >= count { return false; }
+        let end = if count - start < self.group { count } else { start + self.group };
+
+        let mut digits = [0u8; 20];
+        for index in start..end {
+            if index != start { buffer.push(' '); }
+            let value = if self.last < self.first {
+                self.first.wrapping_sub(index as i64)
+            } else {
+                self.first.wrapping_add(index as i64)
+            };
+            let start_indice = value.numtoa(10, &mut digits);
+            buffer.push_str(unsafe { str::from_utf8_unchecked(&digits[start_indice..]) });
+        }
+
+        self.generated = end;
+        true
+    }
+
+    /// Obtains the Nth input, counting from one, without advancing the sequence.
+    pub fn nth(&self, id: usize) -> Option<String> {
+        if id == 0 || id > self.len() { return None; }
+        let mut sequence = Sequence { generated: (id as u64 - 1) * self.group, ..*self };
+        let mut buffer = String::new();
+        sequence.next(&mut buffer);
+        Some(buffer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sequence() {
+        let mut sequence = Sequence::new(-1, 3, 2);
+        assert_eq!(sequence.len(), 3);
+        assert_eq!(sequence.nth(3), Some("3".to_owned()));
+
+        let mut buffer = String::new();
+        let mut inputs = Vec::new();
+        while sequence.next(&mut buffer) { inputs.push(buffer.clone()); }
+        assert_eq!(inputs, vec!["-1 0", "1 2", "3"]);
+
+        let sequence = Sequence::new(3, 1, 1);
+        assert_eq!(sequence.len(), 3);
+        assert_eq!(sequence.nth(2), Some("2".to_owned()));
+        assert_eq!(sequence.nth(4), None);
+    }
+}
@@ -1,82 +1,148 @@
 use disk_buffer::*;
 use arguments::errors::{FileErr};
 use super::InputIteratorErr;
-use itoa;
+use super::excluded::Excluded;
+use super::generator::Generator;
+use super::stream;
 use time;
-use std::io::{self, Write, Read};
-use std::path::{Path, PathBuf};
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
 use std::str;
-
-pub struct ETA {
-    pub left:    u64,
-    pub time:    u64,
-    pub average: u64,
-}
-
-impl ETA {
-    pub fn write_to_stderr(&self, completed: usize) {
-        let stderr = io::stderr();
-        let mut stderr = stderr.lock();
-
-        // Write the ETA to the standard error
-        let _ = stderr.write(b"ETA: ");
-        let _ = itoa::write(&mut stderr, self.time / 1_000_000_000);
-
-        // Write the number of inputs left to process to standard error
-        let _ = stderr.write(b"s Left: ");
-        let _ = itoa::write(&mut stderr, self.left);
-
-        // Write the average runtime of processes (with two decimal places) to standard error
-        let _ = stderr.write(b" AVG: ");
-        let _ = itoa::write(&mut stderr, self.average / 1_000_000_000);
-        let _ = stderr.write(b".");
-        let remainder = (self.average % 1_000_000_000) / 10_000_000;
-        let _ = itoa::write(&mut stderr, remainder);
-        if remainder < 10 { let _ = stderr.write(b"0"); }
-
-        // Write the number of completed units so far to standard error
-        let _ = stderr.write(b"s Completed: ");
-        let _ = itoa::write(&mut stderr, completed);
-        let _ = stderr.write(b"\n");
-    }
+use std::time::{Duration, Instant};
+
+/// An input which was handed back to the iterator to be attempted again once it is `ready`.
+pub struct Requeued {
+    pub job_id:  usize,
+    pub input:   String,
+    pub attempt: usize,
+    ready:       u64,
 }
 
 /// The `InputIterator` tracks the total number of arguments, the current argument counter, and
 /// takes ownership of an `InputBuffer` which buffers input arguments from the disk when arguments
-/// stored in memory are depleted.
+/// stored in memory are depleted. Inputs may instead be produced by a `Generator` as they are needed,
+/// such as the permutations of multiple lists.
 pub struct InputIterator<IO: Read> {
     pub total_arguments: usize,
     pub curr_argument:   usize,
-    pub completed:       usize,
-    start_time:          u64,
-    average_time:        u64,
+    /// The number of times that the unprocessed file was read ahead after the first read.
+    pub refills:         usize,
+    /// With `--delay`, when the last job was started.
+    pub last_start:      Option<Instant>,
+    /// With `--local-only`, the number of job slots on the local machine which may still take inputs.
+    pub local_slots:     usize,
     input_buffer:        InputBuffer<IO>,
+    generator:           Option<Generator>,
+    requeued:            VecDeque<Requeued>,
+    /// The inputs which were set aside by remote slots for the local slots.
+    reserved:            VecDeque<Requeued>,
+    excluded:            Excluded,
 }
 
 impl<IO: Read> InputIterator<IO> {
-    pub fn new(path: &Path, file: IO, args: usize) -> Result<InputIterator<IO>, FileErr> {
-        // Create an `InputBuffer` from the unprocessed file.
-        let disk_buffer = DiskBufferReader::new(path, file);
+    pub fn new(path: &Path, file: IO, args: usize, readahead: usize, generator: Option<Generator>)
+        -> Result<InputIterator<IO>, FileErr>
+    {
+        // Create an `InputBuffer` from the unprocessed file, which reads `readahead` bytes at a time.
+        let disk_buffer = DiskBufferReader::new(path, file, readahead);
 
         let input_buffer = InputBuffer::new(disk_buffer)?;
 
         Ok(InputIterator {
             total_arguments: args,
             curr_argument:   0,
-            completed:       0,
+            refills:         0,
+            last_start:      None,
+            local_slots:     0,
             input_buffer:    input_buffer,
-            start_time:      time::precise_time_ns(),
-            average_time:    0,
+            generator:       generator,
+            requeued:        VecDeque::new(),
+            reserved:        VecDeque::new(),
+            excluded:        Excluded::default(),
         })
     }
 
+    /// Sets aside the jobs which are not to be executed by this invocation, with `--resume` or
+    /// `--shard-id`, so that their inputs are passed over by `next_value()`.
+    pub fn exclude(&mut self, excluded: Excluded) {
+        self.excluded = excluded;
+    }
+
+    /// Hands an input back to the iterator so that it will be attempted again after `delay`.
+    /// The job will retain its original job ID, and `attempt` is the number of times that it
+    /// has been attempted so far.
+    pub fn requeue(&mut self, job_id: usize, input: &str, attempt: usize, delay: Duration) {
+        let delay = delay.as_secs() * 1_000_000_000 + delay.subsec_nanos() as u64;
+        self.requeued.push_back(Requeued {
+            job_id:  job_id,
+            input:   input.to_owned(),
+            attempt: attempt,
+            ready:   time::precise_time_ns() + delay,
+        });
+    }
+
+    /// Takes the first requeued input which is ready to be attempted again.
+    pub fn next_requeued(&mut self) -> Option<Requeued> {
+        let now = time::precise_time_ns();
+        let position = self.requeued.iter().position(|job| job.ready <= now)?;
+        self.requeued.remove(position)
+    }
+
+    /// Sets aside an input which was obtained by a remote slot, to be taken by a local slot.
+    pub fn reserve(&mut self, job_id: usize, input: &str, attempt: usize) {
+        self.reserved.push_back(Requeued { job_id: job_id, input: input.to_owned(), attempt: attempt, ready: 0 });
+    }
+
+    /// Takes the first input which was set aside for the local slots.
+    pub fn next_reserved(&mut self) -> Option<Requeued> {
+        self.reserved.pop_front()
+    }
+
+    /// The time to wait until the next requeued input is ready, if any inputs were requeued.
+    pub fn requeued_wait(&self) -> Option<Duration> {
+        let now = time::precise_time_ns();
+        self.requeued.iter().map(|job| job.ready).min()
+            .map(|ready| Duration::from_millis(ready.saturating_sub(now) / 1_000_000))
+    }
+
+    /// Obtains the Nth input, counting from one, without advancing the iterator.
+    pub fn nth_input(&self, id: usize) -> io::Result<String> {
+        let input = match self.generator {
+            Some(ref generator) => generator.nth(id),
+            None => {
+                let file = File::open(&self.input_buffer.disk_buffer.path)?;
+                BufReader::new(file).lines().nth(id - 1).map_or(Ok(None), |line| line.map(Some))?
+            }
+        };
+        input.ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "input does not exist"))
+    }
+
+    /// Calls `f` with the job ID and input of every input, without advancing the iterator.
+    pub fn each<F: FnMut(usize, &str)>(&self, mut f: F) -> io::Result<()> {
+        match self.generator {
+            Some(ref generator) => for job_id in 0..self.total_arguments {
+                if let Some(input) = generator.nth(job_id + 1) { f(job_id, &input); }
+            },
+            None => {
+                let file = File::open(&self.input_buffer.disk_buffer.path)?;
+                for (job_id, line) in BufReader::new(file).lines().take(self.total_arguments).enumerate() {
+                    f(job_id, &line?);
+                }
+            }
+        }
+        Ok(())
+    }
+
     fn buffer(&mut self) -> Result<(), InputIteratorErr> {
         // Read the next set of arguments from the unprocessed file, but only read as many bytes
         // as the buffer can hold without overwriting the unused bytes that was shifted to the left.
         self.input_buffer.disk_buffer.buffer(self.input_buffer.capacity).map_err(|why| {
-            InputIteratorErr::FileRead(PathBuf::from(self.input_buffer.disk_buffer.path.clone()), why)
+            InputIteratorErr::FileRead(self.input_buffer.disk_buffer.path.clone(), why)
         })?;
         let bytes_read = self.input_buffer.disk_buffer.capacity;
+        self.refills += 1;
 
         // Update the recorded number of arguments and indices.
         self.input_buffer.start = self.input_buffer.end + 1;
@@ -85,24 +151,37 @@ impl<IO: Read> InputIterator<IO> {
         Ok(())
     }
 
-    pub fn eta(&self) -> ETA {
-        let left = self.total_arguments as u64 - self.completed as u64;
-        ETA {
-            left: left,
-            time: left * self.average_time,
-            average: self.average_time
+    /// Obtains the next input which was not set aside with `exclude()`, re-using the same `buffer`.
+    pub fn next_value(&mut self, buffer: &mut String) -> Option<Result<(), InputIteratorErr>> {
+        loop {
+            let job_id = self.curr_argument;
+            match self.read_value(buffer) {
+                Some(Ok(())) if self.excluded.contains(&job_id) => continue,
+                result => return result
+            }
         }
     }
 
-    pub fn next_value(&mut self, buffer: &mut String) -> Option<Result<(), InputIteratorErr>> {
-        if self.curr_argument == self.total_arguments {
+    /// Whether every input has been taken, without waiting for inputs which are still being read.
+    pub fn exhausted(&self) -> bool {
+        stream::exhausted(self.curr_argument, self.total_arguments)
+    }
+
+    fn read_value(&mut self, buffer: &mut String) -> Option<Result<(), InputIteratorErr>> {
+        if !stream::pending(self.curr_argument, self.total_arguments) {
             // If all arguments have been depleted, return `None`.
             return None
+        } else if let Some(ref mut generator) = self.generator {
+            // Generated inputs are written directly into the buffer, rather than read from the disk.
+            if !generator.next(buffer) { return None; }
         } else if self.curr_argument == self.input_buffer.end {
             // If the next argument is not stored in the internal buffer, update the buffer.
             if let Err(err) = self.buffer() { return Some(Err(err)); }
         }
 
+        self.curr_argument += 1;
+        if self.generator.is_some() { return Some(Ok(())); }
+
         // Obtain the start and end indices to know where to find the input in the array.
         let end   = self.input_buffer.indices[self.input_buffer.index + 1];
         let start = if self.input_buffer.index == 0 {
@@ -110,16 +189,6 @@ impl<IO: Read> InputIterator<IO> {
         } else {
             self.input_buffer.indices[self.input_buffer.index] + 1
         };
-
-        // Update times
-        match self.completed {
-            0 => (),
-            1 => self.average_time = time::precise_time_ns() - self.start_time,
-            _ => self.average_time = (time::precise_time_ns() - self.start_time) / self.completed as u64,
-        }
-
-        // Increment the iterator's state.
-        self.curr_argument      += 1;
         self.input_buffer.index += 1;
 
         // Copy the input from the buffer into a `String` and return it
@@ -134,7 +203,12 @@ impl<IO: Read> Iterator for InputIterator<IO> {
     type Item = Result<String, InputIteratorErr>;
 
     fn next(&mut self) -> Option<Result<String, InputIteratorErr>> {
-        if self.curr_argument == self.total_arguments {
+        if self.generator.is_some() {
+            let mut buffer = String::new();
+            return self.read_value(&mut buffer).map(|result| result.map(|_| buffer));
+        }
+
+        if !stream::pending(self.curr_argument, self.total_arguments) {
             // If all arguments have been depleted, return `None`.
             return None
         } else if self.curr_argument == self.input_buffer.end {
@@ -150,13 +224,6 @@ impl<IO: Read> Iterator for InputIterator<IO> {
             self.input_buffer.indices[self.input_buffer.index] + 1
         };
 
-        // Update times
-        match self.completed {
-            0 => (),
-            1 => self.average_time = time::precise_time_ns() - self.start_time,
-            _ => self.average_time = (time::precise_time_ns() - self.start_time) / self.completed as u64,
-        }
-
         // Increment the iterator's state.
         self.curr_argument       += 1;
         self.input_buffer.index  += 1;
@@ -175,7 +242,7 @@ struct InputBuffer<IO: Read> {
     end:         usize,
     capacity:    usize,
     disk_buffer: DiskBufferReader<IO>,
-    indices:     [usize; BUFFER_SIZE / 2],
+    indices:     Vec<usize>,
 }
 
 impl<IO: Read> InputBuffer<IO> {
@@ -184,6 +251,7 @@ impl<IO: Read> InputBuffer<IO> {
     fn new(mut unprocessed: DiskBufferReader<IO>) -> Result<InputBuffer<IO>, FileErr> {
         unprocessed.buffer(0).map_err(|why| FileErr::Read(unprocessed.path.clone(), why))?;
         let bytes_read = unprocessed.capacity;
+        let indices = vec![0usize; unprocessed.data.len() / 2 + 1];
 
         let mut temp = InputBuffer {
             index:       0,
@@ -191,7 +259,7 @@ impl<IO: Read> InputBuffer<IO> {
             end:         0,
             capacity:    0,
             disk_buffer: unprocessed,
-            indices:     [0usize; BUFFER_SIZE / 2]
+            indices:     indices
         };
 
         count_arguments(&mut temp, bytes_read);
@@ -225,10 +293,10 @@ mod tests {
     #[test]
     fn test_input_iterator() {
         let file = File::open("tests/buffer.dat").unwrap();
-        let iterator = InputIterator::new(Path::new("tests/buffer.dat"), file, 4096).unwrap();
+        let iterator = InputIterator::new(Path::new("tests/buffer.dat"), file, 4096, BUFFER_SIZE, None).unwrap();
         assert_eq!(0, iterator.input_buffer.start);
         assert_eq!(1859, iterator.input_buffer.end);
-        for (actual, expected) in iterator.zip((1..4096)) {
+        for (actual, expected) in iterator.zip(1..4096) {
             assert_eq!(actual.unwrap(), expected.to_string());
         }
     }
@@ -1,60 +1,164 @@
-use arguments;
-use super::{InputIterator, InputIteratorErr};
-use sys_info;
+use super::{InputIterator, InputIteratorErr, Resources};
+use super::resources::POLL;
+use arguments::throttles;
+use regex::Regex;
 
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::io::{self, Read, Write};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// Determines whether requeued inputs are attempted before or after the inputs that remain.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RetryPriority { First, Last }
+
+impl RetryPriority {
+    pub fn parse(name: &str) -> Option<RetryPriority> {
+        match name {
+            "first" => Some(RetryPriority::First),
+            "last"  => Some(RetryPriority::Last),
+            _       => None
+        }
+    }
+}
 
 pub struct InputsLock<IO: Read> {
-    pub inputs:    Arc<Mutex<InputIterator<IO>>>,
-    pub memory:    u64,
-    pub delay:     Duration,
-    pub has_delay: bool,
-    pub completed: bool,
-    pub flags:     u16
+    pub inputs:     Arc<Mutex<InputIterator<IO>>>,
+    pub resources:  Resources,
+    pub delay:      Duration,
+    pub has_delay:  bool,
+    /// The number of times that the throttles had been reloaded when this slot last applied them.
+    pub reloads:    usize,
+    /// The number of times that the last input obtained has previously been attempted.
+    pub attempt:    usize,
+    pub priority:   RetryPriority,
+    /// Whether this slot executes its jobs on the local machine.
+    pub local:      bool,
+    /// With `--local-only`, matches the inputs which remote slots set aside for the local slots.
+    pub local_only: Option<Regex>,
+    /// Whether this local slot has found the inputs exhausted, and no longer counts among the local slots.
+    pub retired:    bool,
 }
 
 impl<IO: Read> InputsLock<IO> {
     /// Attempts to obtain the next input in the queue, returning `None` when it is finished.
     /// It works the same as the `Iterator` trait's `next()` method, only re-using the same input buffer.
-    pub fn try_next(&mut self, input: &mut String) -> Option<(usize)> {
-        let mut inputs = self.inputs.lock().unwrap();
-        let job_id = inputs.curr_argument;
-        if self.flags & arguments::ETA != 0 {
-            if self.completed {
-                inputs.completed += 1;
-            } else {
-                self.completed = true;
+    pub fn try_next(&mut self, input: &mut String) -> Option<usize> {
+        // The inputs are locked while waiting, so that the delay and resource checks apply to every slot.
+        let shared = self.inputs.clone();
+        let mut inputs = shared.lock().unwrap();
+        let remaining = !inputs.exhausted() || inputs.requeued_wait().is_some();
+        if remaining {
+            // Jobs are started at least `delay` apart, regardless of when the jobs before them completed.
+            // The throttles are refreshed while the gate is closed, so that relaxing a throttle opens it.
+            self.reload();
+            if let Some(started) = inputs.last_start {
+                while self.has_delay && started.elapsed() < self.delay {
+                    thread::sleep(POLL.min(self.delay.saturating_sub(started.elapsed())));
+                    self.reload();
+                }
             }
-            inputs.eta().write_to_stderr(inputs.completed);
+
+            while !self.resources.available() {
+                thread::sleep(POLL);
+                self.reload();
+            }
+            if self.has_delay { inputs.last_start = Some(Instant::now()); }
         }
 
-        if self.has_delay { thread::sleep(self.delay); }
+        self.take(&shared, inputs, input)
+    }
 
-        if self.memory > 0 {
-            if let Ok(mut mem_available) = sys_info::mem_info().map(|mem_info| mem_info.avail * 1000) {
-                while mem_available < self.memory {
-                    thread::sleep(Duration::from_millis(100));
-                    if let Ok(mem_info) = sys_info::mem_info() { mem_available = mem_info.avail * 1000; }
+    /// Applies the throttles which were reloaded from the `--throttle-file` since this slot last applied them.
+    fn reload(&mut self) {
+        if let Some(reloaded) = throttles::reloaded(&mut self.reloads) {
+            self.resources = reloaded.resources;
+            self.delay = reloaded.delay;
+            self.has_delay = reloaded.delay != Duration::from_millis(0);
+        }
+    }
+
+    /// Obtains the next input in the queue, skipping the delay and resource checks of `try_next()`.
+    /// This is used to fetch a replacement when the input that was just obtained has to be set aside.
+    /// Requeued inputs are obtained once they are ready, either before the remaining inputs or
+    /// after they have been exhausted, according to the retry priority. Requeued inputs will be
+    /// waited upon once all other inputs have been exhausted.
+    pub fn next_input(&mut self, input: &mut String) -> Option<usize> {
+        let shared = self.inputs.clone();
+        let inputs = shared.lock().unwrap();
+        self.take(&shared, inputs, input)
+    }
+
+    /// Obtains the next input from the `inputs` which were locked from `shared`.
+    fn take<'a>(&mut self, shared: &'a Mutex<InputIterator<IO>>, mut inputs: MutexGuard<'a, InputIterator<IO>>,
+        input: &mut String) -> Option<usize>
+    {
+        loop {
+            // Local slots take the inputs which remote slots set aside for them first.
+            if self.local {
+                if let Some(job) = inputs.next_reserved() {
+                    *input = job.input;
+                    self.attempt = job.attempt;
+                    return Some(job.job_id);
+                }
+            }
+
+            let exhausted = inputs.exhausted();
+            if self.priority == RetryPriority::First || exhausted {
+                if let Some(job) = inputs.next_requeued() {
+                    *input = job.input;
+                    self.attempt = job.attempt;
+                    if self.set_aside(&mut inputs, job.job_id, input) { continue }
+                    return Some(job.job_id);
                 }
             }
-        }
 
-        match inputs.next_value(input) {
-            None            => None,
-            Some(Ok(()))    => Some(job_id),
-            Some(Err(why))  => {
-                let stderr = io::stderr();
-                let stderr = &mut stderr.lock();
-                match why {
-                    InputIteratorErr::FileRead(path, why) => {
-                        let _ = write!(stderr, "parallel: input file read error: {:?}: {}\n", path, why);
+            match inputs.next_value(input) {
+                None => match inputs.requeued_wait() {
+                    Some(wait) => {
+                        drop(inputs);
+                        thread::sleep(wait);
+                        inputs = shared.lock().unwrap();
                     },
+                    None => {
+                        // Once the last local slot retires, remote slots no longer set inputs aside.
+                        if self.local && self.local_only.is_some() && !self.retired {
+                            self.retired = true;
+                            inputs.local_slots -= 1;
+                        }
+                        return None
+                    }
+                },
+                Some(Ok(())) => {
+                    // Inputs passed over with `--resume` or `--shard-id` are counted, so this is the ID of the input.
+                    self.attempt = 0;
+                    let job_id = inputs.curr_argument - 1;
+                    if self.set_aside(&mut inputs, job_id, input) { continue }
+                    return Some(job_id);
+                },
+                Some(Err(why)) => {
+                    let stderr = io::stderr();
+                    let stderr = &mut stderr.lock();
+                    match why {
+                        InputIteratorErr::FileRead(path, why) => {
+                            let _ = write!(stderr, "parallel: input file read error: {:?}: {}\n", path, why);
+                        },
+                    }
+                    return None
                 }
-                None
             }
         }
     }
+
+    /// With `--local-only`, a remote slot sets aside the inputs which match, for the local slots,
+    /// while any of them may still take inputs. Returns `true` if the input was set aside.
+    fn set_aside(&self, inputs: &mut InputIterator<IO>, job_id: usize, input: &str) -> bool {
+        match self.local_only {
+            Some(ref matcher) if !self.local && inputs.local_slots != 0 && matcher.is_match(input) => {
+                inputs.reserve(job_id, input, self.attempt);
+                true
+            },
+            _ => false
+        }
+    }
 }
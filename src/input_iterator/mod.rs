@@ -1,8 +1,22 @@
+pub mod chunks;
+mod excluded;
+mod filter;
+mod generator;
 mod lock;
 mod iterator;
+mod permutations;
+mod provenance;
+mod resources;
+pub mod stream;
 
-pub use self::lock::InputsLock;
-pub use self::iterator::{InputIterator, ETA};
+pub use self::excluded::Excluded;
+pub use self::filter::Filter;
+pub use self::generator::{Generator, Sequence};
+pub use self::lock::{InputsLock, RetryPriority};
+pub use self::iterator::InputIterator;
+pub use self::permutations::{PermutatedInputs, PermuteOrder};
+pub use self::provenance::Provenance;
+pub use self::resources::Resources;
 
 use std::io;
 use std::path::PathBuf;
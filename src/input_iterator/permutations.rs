@@ -0,0 +1,255 @@
+use super::filter::Filter;
+
+/// Determines the order in which the permutations of multiple input lists are generated.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PermuteOrder {
+    /// The last list varies the fastest, as with nested loops.
+    RowMajor,
+    /// The first list varies the fastest.
+    ColumnMajor,
+    /// Permutations are ordered by the sum of their indexes, so that the early values of every
+    /// list are covered before the later values of any list.
+    Interleaved,
+}
+
+impl PermuteOrder {
+    pub fn parse(name: &str) -> Option<PermuteOrder> {
+        match name {
+            "row-major"    => Some(PermuteOrder::RowMajor),
+            "column-major" => Some(PermuteOrder::ColumnMajor),
+            "interleaved"  => Some(PermuteOrder::Interleaved),
+            _              => None
+        }
+    }
+}
+
+/// Generates the permutations of multiple input lists as they are requested, so that only the lists
+/// themselves are stored, rather than every permutation.
+#[derive(Clone)]
+pub struct PermutatedInputs {
+    lists:        Vec<Vec<String>>,
+    order:        PermuteOrder,
+    permutations: Permutations,
+    /// The number of permutations that are grouped into each input, as with `--max-args`.
+    group:        usize,
+    /// Permutations which do not match the filter are skipped.
+    filter:       Option<Filter>,
+}
+
+impl PermutatedInputs {
+    pub fn new(lists: Vec<Vec<String>>, order: PermuteOrder, max_args: usize, filter: Option<Filter>)
+        -> PermutatedInputs
+    {
+        let permutations = Permutations::new(lists.iter().map(Vec::len).collect(), order);
+        let group = if max_args < 2 { 1 } else { max_args };
+        PermutatedInputs { lists, order, permutations, group, filter }
+    }
+
+    /// The number of inputs that will be generated. If a filter was given, every permutation
+    /// must be generated in order to count those that match it.
+    pub fn len(&self) -> usize {
+        let permutations = match self.filter {
+            Some(ref filter) => {
+                let mut permutations = Permutations::new(self.lists.iter().map(Vec::len).collect(), self.order);
+                let mut matched = 0;
+                while let Some(indexes) = permutations.next() {
+                    if matches(filter, &self.lists, indexes) { matched += 1; }
+                }
+                matched
+            },
+            None => self.lists.iter()
+                .try_fold(1usize, |total, list| total.checked_mul(list.len()))
+                .unwrap_or(usize::MAX)
+        };
+        permutations / self.group + if permutations % self.group == 0 { 0 } else { 1 }
+    }
+
+    /// Whether no inputs will be generated.
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Writes the next input into the `buffer`, returning `false` if the permutations are exhausted.
+    pub fn next(&mut self, buffer: &mut String) -> bool {
+        buffer.clear();
+        generate(&self.lists, &mut self.permutations, self.group, self.filter.as_ref(), buffer)
+    }
+
+    /// Obtains the Nth input, counting from one, without advancing the permutations.
+    pub fn nth(&self, id: usize) -> Option<String> {
+        let mut permutations = Permutations::new(self.lists.iter().map(Vec::len).collect(), self.order);
+        let mut buffer = String::new();
+        for _ in 0..id {
+            buffer.clear();
+            if !generate(&self.lists, &mut permutations, self.group, self.filter.as_ref(), &mut buffer) {
+                return None;
+            }
+        }
+        Some(buffer)
+    }
+}
+
+/// Determines whether the permutation given by `indexes` matches the filter.
+fn matches(filter: &Filter, lists: &[Vec<String>], indexes: &[usize]) -> bool {
+    filter.matches(&|id| lists[id-1][indexes[id-1]].as_str())
+}
+
+/// Writes up to `group` permutations which match the `filter` into the `buffer`, each separated by a space.
+fn generate(lists: &[Vec<String>], permutations: &mut Permutations, group: usize, filter: Option<&Filter>,
+    buffer: &mut String) -> bool
+{
+    for generated in 0..group {
+        let indexes = loop {
+            match permutations.next() {
+                Some(indexes) => if filter.map_or(true, |filter| matches(filter, lists, indexes)) {
+                    break indexes
+                },
+                None => return generated != 0,
+            }
+        };
+
+        if generated != 0 { buffer.push(' '); }
+        for (id, (list, &index)) in lists.iter().zip(indexes.iter()).enumerate() {
+            if id != 0 { buffer.push(' '); }
+            buffer.push_str(&list[index]);
+        }
+    }
+    true
+}
+
+/// Generates the index of each list's element for every permutation of the lists, in the
+/// requested order, without storing more than the current permutation.
+#[derive(Clone)]
+pub struct Permutations {
+    lengths: Vec<usize>,
+    indexes: Vec<usize>,
+    order:   PermuteOrder,
+    started: bool,
+    done:    bool,
+}
+
+impl Permutations {
+    /// Takes the length of each list that will be permutated.
+    pub fn new(lengths: Vec<usize>, order: PermuteOrder) -> Permutations {
+        let done = lengths.is_empty() || lengths.contains(&0);
+        Permutations { indexes: vec![0; lengths.len()], lengths, order, started: false, done }
+    }
+
+    /// Advances to the next permutation, returning the index of each list's element.
+    pub fn next(&mut self) -> Option<&[usize]> {
+        if self.done { return None; }
+        if !self.started {
+            self.started = true;
+        } else if !self.advance() {
+            self.done = true;
+            return None;
+        }
+        Some(&self.indexes)
+    }
+
+    fn advance(&mut self) -> bool {
+        match self.order {
+            PermuteOrder::RowMajor => {
+                for id in (0..self.indexes.len()).rev() {
+                    if self.increment(id) { return true; }
+                }
+                false
+            },
+            PermuteOrder::ColumnMajor => {
+                for id in 0..self.indexes.len() {
+                    if self.increment(id) { return true; }
+                }
+                false
+            },
+            PermuteOrder::Interleaved => self.advance_interleaved(),
+        }
+    }
+
+    /// Increments the index of the given list, resetting it to zero if it overflows.
+    fn increment(&mut self, id: usize) -> bool {
+        self.indexes[id] += 1;
+        if self.indexes[id] < self.lengths[id] { return true; }
+        self.indexes[id] = 0;
+        false
+    }
+
+    /// Moves to the next permutation whose indexes have the same sum, in lexicographic order.
+    /// Once every permutation with that sum has been generated, the sum is increased by one.
+    fn advance_interleaved(&mut self) -> bool {
+        let sum: usize = self.indexes.iter().sum();
+        let mut prefix: usize = self.indexes.iter().sum();
+        for id in (0..self.indexes.len()).rev() {
+            prefix -= self.indexes[id];
+            let suffix = sum - prefix - self.indexes[id];
+            if suffix != 0 && self.indexes[id] + 1 < self.lengths[id] {
+                self.indexes[id] += 1;
+                self.fill(id + 1, suffix - 1);
+                return true;
+            }
+        }
+
+        if sum + 1 > self.capacity(0) { return false; }
+        self.fill(0, sum + 1);
+        true
+    }
+
+    /// The largest sum that the indexes of the lists from `id` onwards may have.
+    fn capacity(&self, id: usize) -> usize {
+        self.lengths[id..].iter().map(|&length| length - 1).sum()
+    }
+
+    /// Distributes `remainder` among the lists from `id` onwards, giving the
+    /// lexicographically smallest indexes.
+    fn fill(&mut self, id: usize, mut remainder: usize) {
+        for id in id..self.indexes.len() {
+            let value = remainder.saturating_sub(self.capacity(id + 1));
+            self.indexes[id] = value;
+            remainder -= value;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collect(lengths: Vec<usize>, order: PermuteOrder) -> Vec<Vec<usize>> {
+        let mut permutations = Permutations::new(lengths, order);
+        let mut output = Vec::new();
+        while let Some(indexes) = permutations.next() { output.push(indexes.to_vec()); }
+        output
+    }
+
+    #[test]
+    fn permute_orders() {
+        assert_eq!(collect(vec![2, 3], PermuteOrder::RowMajor),
+            vec![vec![0, 0], vec![0, 1], vec![0, 2], vec![1, 0], vec![1, 1], vec![1, 2]]);
+        assert_eq!(collect(vec![2, 3], PermuteOrder::ColumnMajor),
+            vec![vec![0, 0], vec![1, 0], vec![0, 1], vec![1, 1], vec![0, 2], vec![1, 2]]);
+        assert_eq!(collect(vec![2, 3], PermuteOrder::Interleaved),
+            vec![vec![0, 0], vec![0, 1], vec![1, 0], vec![0, 2], vec![1, 1], vec![1, 2]]);
+        assert_eq!(collect(vec![3, 4, 2], PermuteOrder::Interleaved).len(), 24);
+        assert!(collect(vec![2, 0], PermuteOrder::RowMajor).is_empty());
+    }
+
+    #[test]
+    fn permutated_inputs() {
+        let lists = vec![vec!["a".to_owned(), "b".to_owned()], vec!["1".to_owned(), "2".to_owned(), "3".to_owned()]];
+        let mut inputs = PermutatedInputs::new(lists.clone(), PermuteOrder::RowMajor, 4, None);
+        assert_eq!(inputs.len(), 2);
+        assert_eq!(inputs.nth(2), Some("b 2 b 3".to_owned()));
+
+        let mut buffer = String::new();
+        assert!(inputs.next(&mut buffer));
+        assert_eq!(buffer, "a 1 a 2 a 3 b 1");
+        assert!(inputs.next(&mut buffer));
+        assert_eq!(buffer, "b 2 b 3");
+        assert!(!inputs.next(&mut buffer));
+
+        let filter = Filter::parse("{1} == b || {2} == 1").ok();
+        let mut inputs = PermutatedInputs::new(lists, PermuteOrder::RowMajor, 1, filter);
+        assert_eq!(inputs.len(), 4);
+        assert!(inputs.next(&mut buffer));
+        assert_eq!(buffer, "a 1");
+        assert!(inputs.next(&mut buffer));
+        assert_eq!(buffer, "b 1");
+    }
+}
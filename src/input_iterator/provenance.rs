@@ -0,0 +1,112 @@
+/// Records where each input came from, so that a job may be traced back to the line of the input
+/// file that produced it, via the `{src}` and `{srcline}` tokens and the job log. With `--chunk-file`,
+/// the byte range of the input file that each job covers is recorded for `{start}` and `{end}`.
+pub struct Provenance {
+    /// The name of each source: the path of an input file, `:::` for inputs given as arguments,
+    /// or `-` for the standard input.
+    sources: Vec<String>,
+    /// The source ID and line number, counting from one, of each input.
+    origins: Vec<(u32, u32)>,
+    /// The start and exclusive end of the byte range of each input, if inputs were chunked.
+    ranges:  Vec<(u64, u64)>,
+}
+
+impl Default for Provenance {
+    fn default() -> Provenance { Provenance::new() }
+}
+
+impl Provenance {
+    pub fn new() -> Provenance {
+        Provenance { sources: Vec::new(), origins: Vec::new(), ranges: Vec::new() }
+    }
+
+    /// Registers a new source of inputs, returning the ID of the source.
+    pub fn add_source(&mut self, name: &str) -> u32 {
+        self.sources.push(name.to_owned());
+        (self.sources.len() - 1) as u32
+    }
+
+    /// Records the origin of the next input.
+    pub fn push(&mut self, source: u32, line: usize) {
+        self.origins.push((source, line as u32));
+    }
+
+    /// Discards the origins of all inputs after the first `length` inputs.
+    pub fn truncate(&mut self, length: usize) {
+        self.origins.truncate(length);
+    }
+
+    /// Forgets the origin of the first input, which was the header of the inputs rather than an input.
+    pub fn remove_first(&mut self) {
+        if !self.origins.is_empty() { self.origins.remove(0); }
+    }
+
+    /// Forgets the origins of every input, such as when inputs are permutated from multiple lists.
+    pub fn clear(&mut self) {
+        self.origins.clear();
+    }
+
+    /// When inputs are grouped by `--max-args`, each group originates from its first input.
+    pub fn group(&mut self, max_args: usize) {
+        if max_args < 2 { return }
+        self.origins = self.origins.iter().step_by(max_args).cloned().collect();
+        self.ranges = self.ranges.iter().step_by(max_args).cloned().collect();
+    }
+
+    /// When inputs are divided into chunks, each input is replaced by the given byte ranges, which
+    /// originate from the same line as the input.
+    pub fn chunk(&mut self, input: usize, ranges: &[(u64, u64)]) {
+        if let Some(&origin) = self.origins.get(input) {
+            for _ in 1..ranges.len() { self.origins.insert(input, origin); }
+        }
+        self.ranges.extend_from_slice(ranges);
+    }
+
+    /// Obtains the name of the source and the line number of the given job.
+    pub fn get(&self, job_id: usize) -> Option<(&str, usize)> {
+        self.origins.get(job_id).map(|&(source, line)| (self.sources[source as usize].as_str(), line as usize))
+    }
+
+    /// Obtains the byte range of the given job, if inputs were chunked.
+    pub fn range(&self, job_id: usize) -> Option<(u64, u64)> {
+        self.ranges.get(job_id).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn provenance() {
+        let mut provenance = Provenance::new();
+        let arguments = provenance.add_source(":::");
+        let file = provenance.add_source("inputs.txt");
+        provenance.push(arguments, 1);
+        provenance.push(arguments, 2);
+        provenance.push(file, 3);
+        provenance.push(file, 5);
+        provenance.push(file, 6);
+        assert_eq!(provenance.get(2), Some(("inputs.txt", 3)));
+
+        provenance.group(2);
+        assert_eq!(provenance.get(0), Some((":::", 1)));
+        assert_eq!(provenance.get(1), Some(("inputs.txt", 3)));
+        assert_eq!(provenance.get(2), Some(("inputs.txt", 6)));
+        assert_eq!(provenance.get(3), None);
+    }
+
+    #[test]
+    fn chunked_provenance() {
+        let mut provenance = Provenance::new();
+        let arguments = provenance.add_source(":::");
+        provenance.push(arguments, 1);
+        provenance.push(arguments, 2);
+        provenance.chunk(0, &[(0, 10), (10, 20)]);
+        provenance.chunk(2, &[(0, 5)]);
+        assert_eq!(provenance.get(1), Some((":::", 1)));
+        assert_eq!(provenance.get(2), Some((":::", 2)));
+        assert_eq!(provenance.range(1), Some((10, 20)));
+        assert_eq!(provenance.range(2), Some((0, 5)));
+    }
+}
@@ -0,0 +1,68 @@
+//! Before the next input is handed to a slot, the scheduler consults a gate which holds the launch
+//! of the next job back until the system has the resources to spare for it, polling the system
+//! until each of the limits that were given, with `--memfree` and `--load`, is satisfied.
+
+use sys_info;
+
+use std::time::Duration;
+
+/// How often the resources of the system are sampled while the gate is closed.
+pub const POLL: Duration = Duration::from_millis(100);
+
+/// The limits which must be satisfied before the next job is launched. A limit of zero is disabled.
+#[derive(Clone, Default)]
+pub struct Resources {
+    /// The number of bytes of memory which must be available, given with `--memfree`.
+    pub memfree: u64,
+    /// The load average of the last minute must be below this, given with `--load`.
+    pub load:    f64,
+}
+
+impl Resources {
+    /// Whether every limit is satisfied. Limits which cannot be measured on this system are
+    /// considered to be satisfied, so that jobs are never held back indefinitely.
+    pub fn available(&self) -> bool {
+        (self.memfree == 0 || available_memory().map_or(true, |available| available >= self.memfree))
+            && (self.load == 0f64 || sys_info::loadavg().map_or(true, |load| load.one < self.load))
+    }
+}
+
+/// The number of bytes of memory which may be allocated without swapping.
+#[cfg(target_os = "linux")]
+fn available_memory() -> Option<u64> {
+    use std::fs;
+    // Kernels before 3.14 do not report `MemAvailable`.
+    fs::read_to_string("/proc/meminfo").ok()
+        .and_then(|meminfo| parse_meminfo(&meminfo))
+        .or_else(|| sys_info::mem_info().ok().map(|info| info.avail * 1024))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn available_memory() -> Option<u64> {
+    sys_info::mem_info().ok().map(|info| info.avail * 1024)
+}
+
+/// Obtains the `MemAvailable` field of `/proc/meminfo`, which is given in kibibytes.
+#[cfg(target_os = "linux")]
+fn parse_meminfo(meminfo: &str) -> Option<u64> {
+    let line = meminfo.lines().find(|line| line.starts_with("MemAvailable:"))?;
+    let mut fields = line["MemAvailable:".len()..].split_whitespace();
+    let value = fields.next()?.parse::<u64>().ok()?;
+    match fields.next() {
+        Some("kB") => Some(value * 1024),
+        None       => Some(value),
+        Some(_)    => None,
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn meminfo() {
+        let meminfo = "MemTotal:       16318108 kB\nMemFree:         1042540 kB\nMemAvailable:    9203384 kB\n";
+        assert_eq!(parse_meminfo(meminfo), Some(9203384 * 1024));
+        assert_eq!(parse_meminfo("MemTotal:       16318108 kB\nMemFree:         1042540 kB\n"), None);
+    }
+}
@@ -0,0 +1,72 @@
+//! With `--total-jobs`, the standard input is read on a thread of its own while jobs are executed,
+//! rather than before the first job. Until the standard input has been read, the number of inputs
+//! is not known, so the hint stands in for it within `{##}`, `PARALLEL_TOTAL`, and `--eta`, unless
+//! more inputs than the hint have already been read. Once the standard input has been read, the real
+//! count replaces the hint for the jobs which follow, and for the summary of the run.
+
+use std::sync::{Condvar, Mutex, OnceLock};
+
+struct Stream {
+    hint:  usize,
+    /// The number of inputs which have been written to the unprocessed file, and whether that is all of them.
+    state: Mutex<(usize, bool)>,
+    ready: Condvar,
+}
+
+static STREAM: OnceLock<Stream> = OnceLock::new();
+
+/// Marks the inputs as being streamed, where `hint` is the number of inputs which is expected.
+pub fn start(hint: usize) {
+    let _ = STREAM.set(Stream { hint: hint, state: Mutex::new((0, false)), ready: Condvar::new() });
+}
+
+/// Whether the inputs are still being read from the standard input while jobs are executed.
+pub fn streaming() -> bool {
+    STREAM.get().is_some()
+}
+
+/// Records that `count` inputs have been written to the unprocessed file, and whether that is all of them.
+pub fn publish(count: usize, done: bool) {
+    if let Some(stream) = STREAM.get() {
+        *stream.state.lock().unwrap() = (count, done);
+        stream.ready.notify_all();
+    }
+}
+
+/// The number of inputs, which is the `known` number unless the inputs are streamed, in which case the
+/// hint stands in for it until every input has been read.
+pub fn total(known: usize) -> usize {
+    match STREAM.get() {
+        Some(stream) => match *stream.state.lock().unwrap() {
+            (count, true)  => count,
+            (count, false) => count.max(stream.hint),
+        },
+        None => known
+    }
+}
+
+/// Whether there is another input after the first `taken` of the `known` number of inputs. When the
+/// inputs are streamed, this waits until the next input has been read, or the standard input has ended.
+pub fn pending(taken: usize, known: usize) -> bool {
+    let stream = match STREAM.get() {
+        Some(stream) => stream,
+        None => return taken < known
+    };
+    let mut state = stream.state.lock().unwrap();
+    while state.0 <= taken && !state.1 {
+        state = stream.ready.wait(state).unwrap();
+    }
+    taken < state.0
+}
+
+/// Whether every one of the `known` number of inputs has been taken, without waiting for further
+/// inputs to be read from the standard input.
+pub fn exhausted(taken: usize, known: usize) -> bool {
+    match STREAM.get() {
+        Some(stream) => {
+            let (count, done) = *stream.state.lock().unwrap();
+            done && taken >= count
+        },
+        None => taken == known
+    }
+}
@@ -0,0 +1,36 @@
+//! Parallel executes a command for each of its inputs across a number of job slots, which may be on
+//! remote hosts, collecting the output of each job in order. The `parallel` binary is built upon the
+//! modules of this library: `arguments` parses the command line into `Args`, and `execute` runs the
+//! jobs of the slots, while `input_iterator` hands each slot its next input.
+
+#![deny(dead_code)]
+#![allow(unknown_lints)]
+// The crate predates these lints, and keeps the style of its authors throughout, such as `field: field`
+// initializers, `&(ref a, ref b)` patterns, `map_or(false, ...)`, and `&'static` constants.
+#![allow(clippy::redundant_field_names, clippy::write_with_newline, clippy::needless_borrowed_reference,
+    clippy::mem_replace_with_default, clippy::too_many_arguments, clippy::type_complexity,
+    clippy::redundant_static_lifetimes, clippy::option_as_ref_deref, clippy::unnecessary_map_or,
+    clippy::bytes_count_to_len)]
+extern crate arrayvec;
+extern crate itoa;
+extern crate numtoa;
+extern crate num_cpus;
+extern crate regex;
+extern crate smallvec;
+extern crate sys_info;
+extern crate time;
+extern crate wait_timeout;
+
+pub mod arguments;
+pub mod disk_buffer;
+pub mod errors;
+pub mod execute;
+pub mod filepaths;
+pub mod input_iterator;
+pub mod misc;
+pub mod record;
+pub mod supervisor;
+pub mod tokenizer;
+pub mod shell;
+pub mod verbose;
+
@@ -1,46 +1,33 @@
 #![deny(dead_code)]
-#![allow(unknown_lints)]
 extern crate arrayvec;
-extern crate itoa;
-extern crate numtoa;
-extern crate num_cpus;
-extern crate permutate;
-extern crate smallvec;
-extern crate sys_info;
-extern crate time;
-extern crate wait_timeout;
-
-mod arguments;
-mod disk_buffer;
-mod execute;
-mod filepaths;
-mod input_iterator;
-mod misc;
-mod tokenizer;
-mod shell;
-mod verbose;
+extern crate parallel;
 
+use parallel::{arguments, errors, execute, filepaths, input_iterator, misc, record, shell, supervisor, tokenizer, verbose};
+
+use std::borrow::Cow;
 use std::env;
 use std::fs::{self, create_dir_all, File};
 use std::io::{self, BufRead, BufReader, Read, Write};
 use std::mem;
 use std::process::exit;
 use std::thread::{self, JoinHandle};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use std::sync::{Arc, Mutex};
-use std::sync::mpsc::channel;
+use std::sync::mpsc::sync_channel;
 
-use arguments::Args;
+use arguments::{Args, FileErr};
+use arrayvec::ArrayVec;
+use errors::Error;
 use execute::pipe::disk::State;
-use input_iterator::{InputIterator, InputsLock};
-use tokenizer::{Token, tokenize};
+use input_iterator::{InputIterator, InputsLock, Provenance};
+use tokenizer::{lint, Token, tokenize};
 
 /// The command string needs to be available in memory for the entirety of the application, so this
 /// is achievable by transmuting the lifetime of the reference into a static lifetime. To guarantee
 /// that this is perfectly safe, and that the reference will live outside the scope, the value will
 /// also be leaked so that it is forced to remain in memory for the remainder of the application.
 unsafe fn leak_string(comm: String) -> &'static str {
-    let new_comm = mem::transmute(&comm as &str);
+    let new_comm = mem::transmute::<&str, &str>(&comm as &str);
     mem::forget(comm);
     new_comm
 }
@@ -49,6 +36,14 @@ unsafe fn leak_string(comm: String) -> &'static str {
 /// a static lifetime. Prevents needing to copy the token vector to each thread.
 unsafe fn static_arg(args: &[Token]) -> &'static [Token] { mem::transmute(args) }
 
+/// When `--ramp-up` is set, the slots are started one after another, so that the number of jobs
+/// running concurrently grows linearly from one to all slots over the given window.
+fn ramp_up_delay(window: Duration, slot: usize, slots: usize) -> Duration {
+    if slots < 2 { return Duration::from_millis(0); }
+    let window = window.as_secs() * 1000 + window.subsec_nanos() as u64 / 1_000_000;
+    Duration::from_millis(window * slot as u64 / (slots - 1) as u64)
+}
+
 fn main() {
     // Obtain a handle to standard output/error's buffers so we can write directly to them.
     let stdout = io::stdout();
@@ -58,10 +53,8 @@ fn main() {
     if cfg!(target_os = "linux") {
         if let Ok(mut file) = File::open("/sys/kernel/mm/transparent_hugepage/enabled") {
             let mut buffer: [u8; 2] = [0, 0];
-            if let Ok(_) = file.read_exact(&mut buffer) {
-                if &buffer == b"[a" {
-                    let _ = writeln!(stderr.lock(), "ion: /sys/kernel/mm/transparent_hugepage/enabled is set to always instead of madvise. This will gravely effect the performance of Parallel.");
-                }
+            if file.read_exact(&mut buffer).is_ok() && &buffer == b"[a" {
+                let _ = writeln!(stderr.lock(), "ion: /sys/kernel/mm/transparent_hugepage/enabled is set to always instead of madvise. This will gravely effect the performance of Parallel.");
             }
         }
     }
@@ -72,36 +65,33 @@ fn main() {
     let raw_arguments = env::args().collect::<Vec<String>>();
 
     // Attempt to obtain the default tempdir base path.
-    let mut base  = match filepaths::base() {
-        Some(base) => base,
-        None => {
-            let mut stderr = stderr.lock();
-            let _ = stderr.write(b"parallel: unable to open home directory");
-            exit(1);
-        }
-    };
+    let mut base = filepaths::base().unwrap_or_else(|| Error::NoHome.handle());
+
+    // A run which was executed by a job of another run would otherwise share the tempdir of that run.
+    if let (Some(_), Ok(run_id), Ok(seq)) = (arguments::outer_slots(), env::var("PARALLEL_RUNID"), env::var("PARALLEL_SEQ")) {
+        base.push(format!("nested_{}_{}", run_id, seq));
+    }
 
     // Create the base directory if it does not exist
     if let Err(why) = create_dir_all(&base) {
-        let stderr = &mut stderr.lock();
-        let _ = writeln!(stderr, "parallel: unable to create tempdir {:?}: {}", base, why);
-        exit(1);
+        Error::Tempdir(base, why).handle();
     }
 
     // Collect the command, arguments, and tempdir base path.
     args.ninputs = match args.parse(&mut comm, &raw_arguments, &mut base) {
         Ok(inputs) => inputs,
-        Err(why) => why.handle(&raw_arguments)
+        Err(why) => Error::Parse(why).handle()
     };
 
+    // With `--every`, this process supervises the cycles of the run, rather than executing any job.
+    if let Some(interval) = args.every {
+        supervisor::supervise(interval, args.allow_overlap, &raw_arguments, &base);
+    }
+
     // Attempt to convert the base path into a string slice.
     let base_path = match base.to_str() {
         Some(base) => String::from(base),
-        None => {
-            let stderr = &mut stderr.lock();
-            let _ = writeln!(stderr, "parallel: tempdir path, {:?}, is invalid", base);
-            exit(1);
-        }
+        None => Error::TempdirPath(base.clone()).handle()
     };
 
     // Construct the paths of each of the required files using the base tempdir path.
@@ -113,44 +103,224 @@ fn main() {
     processed_path.push("processed");
     errors_path.push("errors");
 
+    // Every job of this run shares its run ID, which is exported to the jobs as `PARALLEL_RUNID`.
+    if args.run_id.is_empty() { args.run_id = misc::run_id::generate(); }
+    env::set_var("PARALLEL_RUNID", &args.run_id);
+
+    // The slots of this run are exported to the jobs, so that nested runs may divide the cores with `--inner-jobs`.
+    let outer = arguments::outer_slots().unwrap_or(1);
+    env::set_var(arguments::OUTER_SLOTS, outer.saturating_mul(args.ncores).to_string());
+
+    // If the `--record` parameter was passed, the options and inputs are recorded before any job
+    // is executed, so that the run may be reproduced with `--replay`.
+    if let Some(path) = args.record.take() {
+        if let Err(why) = record::record(&path, &args.recorded, &args.run_id, &unprocessed_path, args.generator.as_ref()) {
+            Error::Record(path, why).handle();
+        }
+    }
+
     // This file is required by the upcoming `InputIterator`. It will remain open for the
     // remainder of the application.
     let file = match fs::OpenOptions::new().read(true).open(&unprocessed_path) {
         Ok(file) => file,
-        Err(why) => {
-            let stderr = &mut stderr.lock();
-            let _ = writeln!(stderr, "parallel: unable to open unprocessed file: {:?}: {}", &unprocessed_path, why);
-            exit(1);
-        }
+        Err(why) => Error::Unprocessed(FileErr::Open(unprocessed_path, why)).handle()
     };
 
+    // With `--total-jobs`, the unprocessed file is first read once its first input has been written to it.
+    input_iterator::stream::pending(0, args.ninputs);
+
     // Initialize the `InputIterator` structure, which efficiently generates inputs from the
     // above `unprocessed` file until all arguments have been processed, denoted by `args.ninputs`.
-    let inputs = InputIterator::new(&unprocessed_path, file, args.ninputs)
-        .expect("unable to initialize the InputIterator structure");
+    let inputs = InputIterator::new(&unprocessed_path, file, args.ninputs, args.tune.readahead * 1024, args.generator.take())
+        .unwrap_or_else(|why| Error::Unprocessed(why).handle());
 
     // Coerce the `comm` `String` into a `&'static str` so that it may be shared by all threads.
     // This is safe because the original `comm` may no longer be modified due to shadowing rules.
     // It is also safe because `comm` lives to the end of the program.
     let static_comm = unsafe { leak_string(comm) };
 
-    // Attempt to tokenize the command argument into simple primitive placeholders.
-    if let Err(error) = tokenize(&mut args.arguments, static_comm, &unprocessed_path, args.ninputs) {
-        let stderr = &mut stderr.lock();
-        let _ = writeln!(stderr, "{}", error);
-        exit(1)
+    // Attempt to tokenize the command argument into simple primitive placeholders. With `--plain`,
+    // the command is passed through as is, and the input is appended to it.
+    // With `--colsep`, `{N}` denotes the Nth column of each input, rather than the Nth input.
+    let colsep = match args.colsep.take() {
+        Some(separator) => { tokenizer::separate_columns(separator); true },
+        None            => false
+    };
+    if let Some(names) = args.header.take() { tokenizer::name_columns(names); }
+    if args.plain {
+        args.arguments.push(Token::Argument(Cow::Borrowed(static_comm)));
+    } else if let Err(error) = tokenize(&mut args.arguments, static_comm, &|id| inputs.nth_input(id), args.ninputs, colsep) {
+        Error::Token(error).handle();
     }
 
     // Coerce the arguments into a `&'static [Token]` to eliminate the need to make a copy in
     // each thread.
     let arguments = unsafe { static_arg(&args.arguments) };
 
+    // If jobs are to be grouped by a key, tokenize the key template, where `{N}` denotes the Nth column.
+    let mut key_tokens = ArrayVec::<[Token; 128]>::new();
+    let groups = if args.max_per_key != 0 || args.key.is_some() {
+        let template = unsafe { leak_string(args.key.take().unwrap_or_else(|| String::from("{}"))) };
+        if let Err(error) = tokenize(&mut key_tokens, template, &|id| inputs.nth_input(id), args.ninputs, true) {
+            Error::Token(error).handle();
+        }
+        let limit = if args.max_per_key == 0 { 1 } else { args.max_per_key };
+        Some(Arc::new(execute::KeyGroups::new(limit, unsafe { static_arg(&key_tokens) })))
+    } else {
+        None
+    };
+
+    // With `--label`, each job is labeled by a template, where `{N}` denotes the Nth column.
+    let mut label_tokens = ArrayVec::<[Token; 128]>::new();
+    let labels = args.label.clone().map(|template| {
+        let template = unsafe { leak_string(template) };
+        if let Err(error) = tokenize(&mut label_tokens, template, &|id| inputs.nth_input(id), args.ninputs, true) {
+            Error::Token(error).handle();
+        }
+        let only = mem::take(&mut args.only_labels);
+        Arc::new(execute::Labels::new(unsafe { static_arg(&label_tokens) }, only))
+    });
+
+    // With `--then`, each further stage of a job is generated from its own template, where `{prev}`
+    // is replaced by the output of the stage before it.
+    let mut stage_tokens = Vec::with_capacity(args.stages.len());
+    for (template, _) in &args.stages {
+        let template = unsafe { leak_string(template.clone()) };
+        let mut tokens = ArrayVec::<[Token; 128]>::new();
+        if args.plain {
+            tokens.push(Token::Argument(Cow::Borrowed(template)));
+        } else if let Err(error) = tokenize(&mut tokens, template, &|id| inputs.nth_input(id), args.ninputs, colsep) {
+            Error::Token(error).handle();
+        }
+        stage_tokens.push(tokens);
+    }
+    let stages = if stage_tokens.is_empty() { None } else {
+        let stages = stage_tokens.iter().zip(args.stages.iter()).map(|(tokens, &(_, jobs))| {
+            let mut shell = 0;
+            if !args.xargs { shell::set_flags(&mut shell, tokens); }
+            (unsafe { static_arg(tokens) }, shell, jobs)
+        }).collect();
+        Some(Arc::new(execute::Stages::new(stages)))
+    };
+
+    // With `--only-label`, the jobs with other labels are passed over, as with `--resume`.
+    if let Some(labels) = labels.as_ref().filter(|labels| labels.filters()) {
+        let unlabeled = &mut args.excluded.unlabeled;
+        let result = inputs.each(|job_id, input| {
+            if !labels.selected(&labels.render(job_id, input)) { unlabeled.insert(job_id); }
+        });
+        if let Err(why) = result { Error::Unprocessed(FileErr::Read(unprocessed_path.clone(), why)).handle(); }
+    }
+
+    // With `--transferfile` and `--return`, the files of each remote job are named by templates.
+    if !args.remote.transfers.is_empty() || !args.remote.returns.is_empty() {
+        let transfers = mem::take(&mut args.remote.transfers);
+        let returns = mem::take(&mut args.remote.returns);
+        let ninputs = args.ninputs;
+        let templates = |templates: Vec<String>| templates.into_iter().map(|template| {
+            let mut tokens = ArrayVec::<[Token; 128]>::new();
+            let template = unsafe { leak_string(template) };
+            if let Err(error) = tokenize(&mut tokens, template, &|id| inputs.nth_input(id), ninputs, true) {
+                Error::Token(error).handle();
+            }
+            tokens.into_iter().collect::<Vec<Token>>()
+        }).collect::<Vec<Vec<Token>>>();
+        let (files, returns) = (templates(transfers), templates(returns));
+
+        // Jobs which return the same path would overwrite each other, so such paths are reported before any
+        // job is executed. Under `--strict`, they are errors. Streamed inputs have not been read yet.
+        if !input_iterator::stream::streaming() {
+            let collisions = execute::find_collisions(&returns, &inputs, &args.provenance, &args.excluded)
+                .unwrap_or_else(|why| Error::Unprocessed(FileErr::Read(unprocessed_path.clone(), why)).handle());
+            let kind = if args.strict { "error" } else { "warning" };
+            for collision in &collisions {
+                let _ = writeln!(stderr.lock(), "parallel: {}: {}", kind, collision);
+            }
+            if args.strict && !collisions.is_empty() { errors::exit_with(errors::EXIT_TEMPLATE); }
+        }
+        execute::enable_transfer(execute::Transfer { files, returns, cleanup: args.remote.cleanup });
+    }
+
+    // With `--env`, the variables which were named are given to each remote job.
+    if !args.remote.env.is_empty() {
+        execute::environment::export(mem::take(&mut args.remote.env));
+    }
+
+    // With `--class` and `--pool`, jobs claim units of the pools that they need before they start,
+    // where the units that each job needs are declared by the `--needs` template.
+    let mut needs_tokens = ArrayVec::<[Token; 128]>::new();
+    let pools = if !args.classes.is_empty() || !args.pools.is_empty() {
+        if let Some(template) = args.needs.take() {
+            let template = unsafe { leak_string(template) };
+            if let Err(error) = tokenize(&mut needs_tokens, template, &|id| inputs.nth_input(id), args.ninputs, true) {
+                Error::Token(error).handle();
+            }
+        }
+        let classes = mem::take(&mut args.classes);
+        let pools = mem::take(&mut args.pools);
+        Some(Arc::new(execute::Pools::new(classes, pools, unsafe { static_arg(&needs_tokens) })))
+    } else {
+        None
+    };
+
+    // Likely mistakes within the command template are reported before any job is launched.
+    if args.flags & arguments::INPUTS_ARE_COMMANDS == 0 && !args.plain && args.ninputs != 0 {
+        if let Ok(first_input) = inputs.nth_input(1) {
+            let columns = lint::columns(arguments).max(lint::columns(&key_tokens))
+                .max(lint::columns(&needs_tokens)).max(args.timeout_col).max(args.weight_col)
+                .max(args.not_before).max(args.not_after);
+            let append = args.flags & arguments::NO_APPEND == 0;
+            let warnings = lint::lint(static_comm, arguments, columns, &first_input, append);
+            // Under `--strict`, the warnings are errors, and no job is launched.
+            let kind = if args.strict { "error" } else { "warning" };
+            for warning in &warnings {
+                let _ = writeln!(stderr.lock(), "parallel: {}: {}", kind, warning);
+            }
+            if args.strict && !warnings.is_empty() { errors::exit_with(errors::EXIT_TEMPLATE); }
+        }
+    }
+
     // If the `--dry-run` parameter was passsed, the program will simply print all commands to
-    // execute and will subsequently quit. Otherwise, real work will be performed.
-    if args.flags & arguments::DRY_RUN != 0 {
-        execute::dry_run(args.flags, inputs, arguments);
+    // execute and will subsequently quit. Likewise, `--export` prints a job array script for a
+    // cluster scheduler, and `--plan` writes the expanded jobs to a file. Otherwise, real work will
+    // be performed.
+    if let Some(scheduler) = args.export {
+        execute::export(scheduler, args.flags, inputs, arguments, &args.provenance);
+    } else if let Some(path) = args.plan.take() {
+        let mut flags = args.flags;
+        if !args.xargs { shell::set_flags(&mut flags, arguments); }
+        if let Err(why) = execute::plan::write(&path, flags, inputs, arguments, &args.provenance, &args.run_id, args.ncores) {
+            Error::Plan(path, why).handle();
+        }
+    } else if args.flags & arguments::DRY_RUN != 0 {
+        execute::dry_run(args.flags, inputs, arguments, &args.provenance);
     } else {
+        // Each host is logged into before any job is dispatched to it, so that a host which can't be
+        // logged into is reported once. With `--filter-hosts`, the run continues on the other hosts.
+        if !args.remote.is_empty() {
+            let failed = args.remote.probe();
+            let kind = if args.remote.filter { "warning" } else { "error" };
+            for (login, why) in &failed {
+                let _ = writeln!(stderr.lock(), "parallel: {}: unable to log into {}: {}", kind, login, why);
+            }
+            if !failed.is_empty() {
+                if !args.remote.filter { errors::exit_with(errors::EXIT_FILES); }
+                args.ncores = args.remote.remove(&failed.into_iter().map(|(login, _)| login).collect::<Vec<_>>());
+                if args.ncores == 0 {
+                    let _ = writeln!(stderr.lock(), "parallel: error: no host remains to execute jobs on");
+                    errors::exit_with(errors::EXIT_FILES);
+                }
+                env::set_var(arguments::OUTER_SLOTS, outer.saturating_mul(args.ncores).to_string());
+            }
+        }
+
+        // With `--resume` and `--shard-id`, the inputs of jobs which are not executed are passed over.
+        let mut inputs = inputs;
+        inputs.exclude(args.excluded.clone());
 
+        // With `--local-only`, the remote slots set aside the inputs which match for the local slots.
+        let local_only = args.remote.local_only.take();
+        if local_only.is_some() { inputs.local_slots = args.remote.local_slots(); }
 
         // The `InputIterator` will be wrapped within a `Mutex` so that it can safely be shared
         // across all of the upcoming threads. A `Mutex` is required because each time a thread
@@ -159,16 +329,184 @@ fn main() {
         let shared_input = Arc::new(Mutex::new(inputs));
 
         // This channel is used exclusively for signaling back to the main thread when a task
-        // has completed or has errored.
-        let (output_tx, input_rx) = channel::<State>();
+        // has completed or has errored. Threads block when `--tune queue` signals are waiting.
+        let (output_tx, input_rx) = sync_channel::<State>(args.tune.queue);
 
         // Will contain handles to the upcoming threads to know when the threads are finished.
         let mut threads = Vec::with_capacity(args.ncores);
 
+        // With `--gpus`, each job is assigned its own GPUs.
+        let gpus = if args.gpus != 0 {
+            Some(Arc::new(execute::Gpus::new(mem::take(&mut args.gpu_devices), args.gpus)))
+        } else {
+            None
+        };
+
+        // When jobs are weighted, each job will claim as many of the `-j` job slots as its weight.
+        let slots = if args.weight_col != 0 { Some(Arc::new(execute::Slots::new(args.ncores))) } else { None };
+
+        // Each job may be launched within another program, such as a transient systemd scope.
+        let launcher = Arc::new(mem::take(&mut args.launcher));
+
+        // The origin of each input is shared with every thread for `{src}`, `{srcline}`, and the job log.
+        let provenance = Arc::new(mem::replace(&mut args.provenance, Provenance::new()));
+
+        // If `--max-spool` was set, the output buffered within the tempdir will be limited.
+        let spool = if args.max_spool != 0 { Some(Arc::new(execute::Spool::new(args.max_spool))) } else { None };
+
+        // With `--spool-idle`, the I/O of the receiver and of the job threads is moved into the idle class.
+        if args.spool_idle && !execute::ionice::enable() {
+            let _ = writeln!(stderr.lock(), "parallel: unable to lower the I/O priority of the output spool");
+        }
+
+        // With `--nice` and `--ionice`, the priorities of each job are set before it is executed.
+        if let Some(niceness) = args.nice { execute::nice::enable(niceness); }
+        if let Some(priority) = args.ionice { execute::ionice::set_jobs(priority); }
+        // The jobs of remote hosts are given them on the host, along with `--timeout`.
+        if !args.remote.is_empty() { execute::wrap_remote(args.nice, args.ionice, args.timeout); }
+
+        // With `--basefile`, the files which every job needs are copied to each host before any job is executed.
+        if let Err((host, why)) = args.remote.stage() { Error::Basefile(host, why).handle(); }
+        // With `--hostfile-watch`, hosts which are removed from the sshloginfile are drained while jobs run.
+        args.remote.watch();
+        // With `--throttle-file`, the throttles are reloaded from the file on SIGHUP while jobs run.
+        if let Some((path, base)) = args.throttles.take() { base.watch(path); }
+
+        // With `--notify` or `--notify-command`, the end of the run is announced with a summary.
+        let notifiers = if args.notify || args.notify_command.is_some() {
+            Some(execute::Notifiers::new(args.notify, args.notify_command.take(), args.flags))
+        } else {
+            None
+        };
+
+        // With `--webhook`, a summary of the run, and optionally each failed job, is posted to a URL.
+        let webhook = args.webhook.take().map(|url| {
+            let timeout = args.hook_timeout.unwrap_or(execute::Webhook::TIMEOUT);
+            let retries = args.hook_retries.unwrap_or(execute::Webhook::RETRIES);
+            Arc::new(execute::Webhook::new(url, timeout, retries, args.hook_failures))
+        });
+        let failures = webhook.clone().filter(|webhook| webhook.failures());
+        let started = Instant::now();
+
+        // Under `--xargs` and `--gnu-compat`, the exit status is derived from the outcome of each job,
+        // which is also counted for the summary of `--notify` and `--webhook`.
+        let status = if args.exit_policy.is_some() || notifiers.is_some() || webhook.is_some() {
+            Some(Arc::new(execute::JobStatus::new(args.exit_policy)))
+        } else {
+            None
+        };
+
+        // With `--return-first`, successful jobs are counted so that the run may stop early.
+        let first = if args.return_first != 0 { Some(Arc::new(execute::ReturnFirst::new(args.return_first))) } else { None };
+
+        // With `--max-runtime`, `--max-failures`, and `--max-cost`, the run will stop once a limit is
+        // exceeded. The cost of the run is accumulated with `--cost-per-second`.
+        let budget = if args.max_runtime.is_some() || args.max_failures.is_some() || args.cost_rate.is_some() {
+            Some(Arc::new(execute::Budget::new(args.max_runtime, args.kill_overrun, args.max_failures,
+                args.cost_rate, args.max_cost)))
+        } else {
+            None
+        };
+
+        // With `--progress-fd`, progress events are written to a file descriptor given by the caller.
+        let progress = args.progress.take().map(|file| Arc::new(execute::ProgressEvents::new(file)));
+        if let Some(ref progress) = progress { progress.run_started(args.ninputs); }
+
+        // With `--test-deterministic`, jobs complete in an order that is derived from the seed.
+        if let Some(seed) = args.deterministic {
+            execute::enable_deterministic(seed, args.ncores, args.ninputs, &args.excluded);
+        }
+
+        // With `--eta`, `--progress`, or `--bar`, the progress of the run is displayed on the standard error.
+        let meter = if args.flags & arguments::ETA != 0 {
+            let total = (0..args.ninputs).filter(|id| !args.excluded.contains(id)).count();
+            Some(Arc::new(execute::Meter::new(total, args.bar)))
+        } else {
+            None
+        };
+        let display = meter.clone().map(execute::Meter::display);
+
+        // If the standard output is closed by its reader, such as `head`, the run stops early.
+        let downstream = Arc::new(execute::Downstream::new());
+
+        // With `--speculative-max`, idle slots duplicate jobs which are running far longer than the median.
+        let speculation = if args.speculative != 0 {
+            Some(Arc::new(execute::Speculation::new(args.speculative, &base_path, &args.run_id)
+                .unwrap_or_else(|(path, why)| Error::Tempdir(path, why).handle())))
+        } else {
+            None
+        };
+
+        // With `--not-before` and `--not-after`, each job is dispatched within a window of time from its input.
+        let window = if args.not_before != 0 || args.not_after != 0 {
+            Some(execute::Window { not_before: args.not_before, not_after: args.not_after, expired: args.expired })
+        } else {
+            None
+        };
+
+        // With `--scratch`, or if the command refers to `{scratch}`, each slot has a private directory.
+        let scratch = args.scratch || arguments.contains(&Token::Scratch);
+        let run_id = &args.run_id;
+        let create_scratch = |slot: usize| if scratch {
+            Some(execute::Scratch::create(&base_path, run_id, slot)
+                .unwrap_or_else(|(path, why)| Error::Tempdir(path, why).handle()))
+        } else {
+            None
+        };
+
         if args.flags & arguments::VERBOSE_MODE != 0 {
             verbose::total_inputs(&stdout, args.ncores, args.ninputs);
         }
 
+        // The settings and shared state of the run are held by every slot.
+        let context = Arc::new(execute::RunContext {
+            num_inputs:  args.ninputs,
+            timeout:     args.timeout,
+            termseq:     args.termseq.clone(),
+            timeout_col: args.timeout_col,
+            weight_col:  args.weight_col,
+            slots,
+            groups,
+            labels,
+            stages,
+            pools,
+            gpus,
+            launcher,
+            provenance,
+            spool:       spool.clone(),
+            output_size: args.tune.output,
+            missing:     args.missing,
+            window,
+            status:      status.clone(),
+            first,
+            budget:      budget.clone(),
+            progress,
+            meter:       meter.clone(),
+            speculation,
+            retries:     args.retries,
+            webhook:     failures,
+            downstream:  downstream.clone(),
+            tag:         args.tag.clone(),
+            discard:     args.discard,
+            log_argv:    args.log_argv,
+            tempdir:     base_path.clone(),
+        });
+
+        // Each slot takes its inputs from the shared inputs, through the throttles of the run.
+        let (resources, delay, priority) = (args.resources.clone(), args.delay, args.retry_priority);
+        let inputs_lock = |host: bool| InputsLock {
+            inputs:     shared_input.clone(),
+            resources:  resources.clone(),
+            delay,
+            has_delay:  delay != Duration::from_millis(0),
+            reloads:    0,
+            attempt:    0,
+            priority,
+            local:      !host,
+            local_only: local_only.clone(),
+            retired:    false,
+        };
+
         // The `slot` variable is required by the {%} token.
         if args.flags & arguments::INPUTS_ARE_COMMANDS != 0 {
             if shell::ion_exists() {
@@ -177,60 +515,50 @@ fn main() {
                 args.flags |= arguments::DASH_EXISTS;
             }
 
-            for _ in 0..args.ncores {
+            for slot in 0..args.ncores {
                 let flags = args.flags;
+                let ramp_up = ramp_up_delay(args.ramp_up, slot, args.ncores);
+                let host = args.remote.host(slot);
 
                 let mut exec = execute::ExecInputs {
-                    num_inputs: args.ninputs,
-                    timeout:    args.timeout,
-                    output_tx:  output_tx.clone(),
-                    tempdir:    base_path.clone(),
-                    inputs:     InputsLock {
-                        inputs:    shared_input.clone(),
-                        memory:    args.memory,
-                        delay:     args.delay,
-                        has_delay: args.delay != Duration::from_millis(0),
-                        completed: false,
-                        flags:     flags,
-                    }
+                    slot:      slot + 1,
+                    context:   context.clone(),
+                    inputs:    inputs_lock(host.is_some()),
+                    host,
+                    scratch:   create_scratch(slot + 1),
+                    output_tx: output_tx.clone(),
                 };
 
-                let handle: JoinHandle<()> = thread::spawn(move || exec.run(flags));
+                let handle: JoinHandle<()> = thread::spawn(move || {
+                    thread::sleep(ramp_up);
+                    exec.run(flags)
+                });
 
                 // Add the thread handle to the `threads` vector to know when to quit the program.
                 threads.push(handle);
             }
         } else {
-            shell::set_flags(&mut args.flags, arguments);
+            // Like xargs, commands are executed directly under `--xargs`, rather than with a shell.
+            if !args.xargs { shell::set_flags(&mut args.flags, arguments); }
 
             for slot in 1..args.ncores+1 {
-                let timeout    = args.timeout;
-                let num_inputs = args.ninputs;
-                let output_tx  = output_tx.clone();
-                let flags      = args.flags;
-                let base_path  = base_path.clone();
-
-                let inputs = InputsLock {
-                    inputs:    shared_input.clone(),
-                    memory:    args.memory,
-                    delay:     args.delay,
-                    has_delay: args.delay != Duration::from_millis(0),
-                    completed: false,
-                    flags:     flags,
+                let ramp_up = ramp_up_delay(args.ramp_up, slot - 1, args.ncores);
+                let host = args.remote.host(slot - 1);
+
+                let mut exec = execute::ExecCommands {
+                    slot,
+                    flags:     args.flags,
+                    context:   context.clone(),
+                    inputs:    inputs_lock(host.is_some()),
+                    host,
+                    scratch:   create_scratch(slot),
+                    output_tx: output_tx.clone(),
+                    arguments,
                 };
 
                 // The command will be built from the arguments, and inputs will be transferred to the command.
                 let handle: JoinHandle<()> = thread::spawn(move || {
-                    let mut exec = execute::ExecCommands {
-                        slot:       slot,
-                        num_inputs: num_inputs,
-                        flags:      flags,
-                        timeout:    timeout,
-                        inputs:     inputs,
-                        output_tx:  output_tx,
-                        arguments:  arguments,
-                        tempdir:    base_path,
-                    };
+                    thread::sleep(ramp_up);
                     exec.run();
                 });
 
@@ -239,25 +567,125 @@ fn main() {
             }
         }
 
-        /// Prints messages from executed commands in the correct order.
-        let errors = execute::receive_messages(input_rx, args, &base_path, &processed_path, &errors_path);
+        // Inputs which were skipped due to errors will be listed once all jobs have completed.
+        let skipped = args.skipped.take();
 
-        /// Wait for all threads to exit before proceeding.
+        // With `--cleanup`, the files of `--basefile` are removed from each host once every job has completed.
+        let remote = mem::take(&mut args.remote);
+
+        // The sizes of the queues and buffers are needed to report their occupancy with `--metrics`.
+        let (report_metrics, tune) = (args.metrics, args.tune);
+        let mut metrics = execute::Metrics::default();
+
+        // With `--reduce`, the standard output of each job is kept for a single reduction command.
+        let flags = args.flags;
+        let reduce = args.reduce.take();
+        let mut reduction = reduce.as_ref().map(|_| {
+            execute::Reduction::create(&base_path, &args.run_id, args.reduce_order)
+                .unwrap_or_else(|(path, why)| Error::Tempdir(path, why).handle())
+        });
+
+        let ninputs = args.ninputs;
+        let epipe_status = args.epipe_status;
+
+        // Prints messages from executed commands in the correct order.
+        let errors = execute::receive_messages(input_rx, args, spool.clone(), reduction.as_mut(), &mut metrics,
+            &base_path, &processed_path, &errors_path, &downstream);
+
+        // Wait for all threads to exit before proceeding.
         for thread in threads { thread.join().unwrap(); }
+        if let (Some(meter), Some(display)) = (meter, display) { meter.finish(display); }
+
+        // Once the standard output has been closed, the outcome of the run can no longer be reported.
+        if downstream.closed() { exit(epipe_status); }
+
+        // Once every job has completed, the kept outputs are combined by the reduction command.
+        let mut reduce_status = 0;
+        if let (Some(reduction), Some(command)) = (reduction, reduce) {
+            match reduction.run(&command, flags) {
+                Ok(status) => if !status.success() {
+                    reduce_status = status.code().unwrap_or(1);
+                    let _ = writeln!(stderr.lock(), "parallel: reduce command failed with {}", status);
+                },
+                Err(why) => {
+                    reduce_status = 1;
+                    let _ = writeln!(stderr.lock(), "parallel: unable to execute reduce command: {}", why);
+                }
+            }
+        }
+
+        for (host, why) in remote.unstage() {
+            let _ = writeln!(stderr.lock(), "parallel: unable to remove basefiles from {}: {}", host, why);
+        }
+
+        if report_metrics {
+            metrics.refills = shared_input.lock().unwrap().refills;
+            metrics.spool = spool.map(|spool| spool.peak());
+            metrics.cost = budget.as_ref().and_then(|budget| budget.cost());
+            metrics.write_to_stderr(&tune);
+        }
+
+        if let Some(skipped) = skipped {
+            if !skipped.is_empty() {
+                let stderr = &mut stderr.lock();
+                let _ = stderr.write(b"parallel: skipped unreadable inputs:\n");
+                for source in skipped {
+                    let _ = writeln!(stderr, "  {}", source);
+                }
+            }
+        }
+
+        // With `--cost-per-second`, the cost of the run is reported.
+        if let Some(ref budget) = budget {
+            if let Some(cost) = budget.cost() {
+                let _ = writeln!(stderr.lock(), "parallel: cost of {:.2} for {:.3}s of job runtime",
+                    cost, budget.job_runtime());
+            }
+        }
+
+        // If a limit of the run was exceeded, the reason is reported, and the run exits with `EXIT_BUDGET`.
+        let exceeded = budget.and_then(|budget| budget.exceeded());
+        if let Some(exceeded) = exceeded {
+            let _ = writeln!(stderr.lock(), "parallel: stopped executing jobs, as {}", exceeded);
+        }
 
         // If errors have occurred, re-print these errors at the end.
+        let mut reprinted = false;
         if let Ok(file) = File::open(errors_path) {
             if file.metadata().ok().map_or(0, |metadata| metadata.len()) > 0 {
                 let stderr = &mut stderr.lock();
                 let _ = stderr.write(b"parallel: encountered errors during processing:\n");
-                for line in BufReader::new(file).lines() {
-                    if let Ok(line) = line {
-                        let _ = stderr.write(line.as_bytes());
-                        let _ = stderr.write(b"\n");
-                    }
+                for line in BufReader::new(file).lines().map_while(Result::ok) {
+                    let _ = stderr.write(line.as_bytes());
+                    let _ = stderr.write(b"\n");
                 }
-                exit(errors);
+                reprinted = true;
             }
         }
+
+        let policy_code = status.as_ref().and_then(|status| status.code());
+        let code = if exceeded.is_some() {
+            errors::status_of(errors::EXIT_BUDGET)
+        } else if reprinted {
+            policy_code.unwrap_or(errors)
+        } else if reduce_status != 0 {
+            reduce_status
+        } else {
+            policy_code.unwrap_or(0)
+        };
+
+        if let Some(status) = status.filter(|_| notifiers.is_some() || webhook.is_some()) {
+            let summary = execute::Summary {
+                total:     input_iterator::stream::total(ninputs),
+                succeeded: status.succeeded(),
+                failed:    status.failed(),
+                elapsed:   started.elapsed(),
+                status:    code,
+            };
+            if let Some(notifiers) = notifiers { notifiers.send(&summary); }
+            if let Some(webhook) = webhook { webhook.completed(&summary); }
+        }
+
+        if code != 0 { exit(code); }
     }
 }
@@ -1,5 +1,2 @@
-///! The purpose of this module is to supply supporting miscellanious traits for use throughout the project.
-mod digits;
-
-/// The `Digits` trait is used to get the number of digits within a number.
-pub use self::digits::Digits;
+//! The purpose of this module is to supply supporting miscellanious traits for use throughout the project.
+pub mod run_id;
@@ -0,0 +1,15 @@
+use std::process;
+use time;
+
+/// Generates an identifier for this invocation, which is shared by all of its jobs, from the time
+/// at which the run started and the ID of the process.
+pub fn generate() -> String {
+    let now = time::get_time();
+    let mut state = (now.sec as u64) ^ ((now.nsec as u64) << 32) ^ (u64::from(process::id()) << 16);
+
+    // The bits are mixed as with splitmix64, so that consecutive runs have unrelated identifiers.
+    state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    state = (state ^ (state >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    state = (state ^ (state >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    format!("{:016x}", state ^ (state >> 31))
+}
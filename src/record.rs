@@ -0,0 +1,288 @@
+//! Records the arguments and expanded inputs of a run with `--record`, so that the run may be
+//! reproduced later with `--replay`. Records are stored as JSON, in the form of
+//! `{"version": "...", "runid": "...", "arguments": [...], "inputs": [...]}`.
+
+use input_iterator::Generator;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::iter;
+use std::path::Path;
+
+/// The arguments and inputs of a recorded run.
+pub struct Record {
+    /// The options and command, excluding the inputs.
+    pub arguments: Vec<String>,
+    /// The inputs of each job, in order, after they were expanded.
+    pub inputs:    Vec<String>,
+    /// The run ID of the recorded run, or an empty string if it was not recorded.
+    pub run_id:    String,
+}
+
+/// Records the `arguments` and the inputs of the run to the file at `path`. The inputs are read from
+/// the `unprocessed` file, or are generated by a copy of the `generator`, before any job is executed.
+pub fn record(path: &Path, arguments: &[String], run_id: &str, unprocessed: &Path, generator: Option<&Generator>)
+    -> io::Result<()>
+{
+    match generator {
+        Some(generator) => {
+            let mut generator = generator.clone();
+            let mut buffer = String::new();
+            write(path, arguments, run_id, iter::from_fn(move || {
+                if generator.next(&mut buffer) { Some(Ok(buffer.clone())) } else { None }
+            }))
+        },
+        None => write(path, arguments, run_id, BufReader::new(File::open(unprocessed)?).lines())
+    }
+}
+
+/// Writes a record of the run to the file at `path`, where `inputs` are written as they are obtained.
+fn write<I: Iterator<Item = io::Result<String>>>(path: &Path, arguments: &[String], run_id: &str, inputs: I)
+    -> io::Result<()>
+{
+    let mut file = BufWriter::new(File::create(path)?);
+    file.write_all(b"{\n  \"version\": ")?;
+    write_string(&mut file, env!("CARGO_PKG_VERSION"))?;
+    file.write_all(b",\n  \"runid\": ")?;
+    write_string(&mut file, run_id)?;
+    file.write_all(b",\n  \"arguments\": [")?;
+    for (id, argument) in arguments.iter().enumerate() {
+        if id != 0 { file.write_all(b", ")?; }
+        write_string(&mut file, argument)?;
+    }
+    file.write_all(b"],\n  \"inputs\": [")?;
+    for (id, input) in inputs.enumerate() {
+        file.write_all(if id == 0 { b"\n    " } else { b",\n    " })?;
+        write_string(&mut file, &input?)?;
+    }
+    file.write_all(b"\n  ]\n}\n")?;
+    file.flush()
+}
+
+/// Writes `string` as a JSON string, escaping the characters which JSON does not permit.
+pub fn write_string<W: Write>(writer: &mut W, string: &str) -> io::Result<()> {
+    writer.write_all(b"\"")?;
+    for character in string.chars() {
+        match character {
+            '"'  => writer.write_all(b"\\\"")?,
+            '\\' => writer.write_all(b"\\\\")?,
+            '\n' => writer.write_all(b"\\n")?,
+            '\r' => writer.write_all(b"\\r")?,
+            '\t' => writer.write_all(b"\\t")?,
+            _ if (character as u32) < 0x20 => write!(writer, "\\u{:04x}", character as u32)?,
+            _ => write!(writer, "{}", character)?,
+        }
+    }
+    writer.write_all(b"\"")
+}
+
+/// Loads a record from the file at `path`. Errors that concern the contents of the file are
+/// returned as a description of the problem.
+pub fn load(path: &Path) -> Result<Record, LoadErr> {
+    let mut contents = String::new();
+    File::open(path).and_then(|mut file| file.read_to_string(&mut contents)).map_err(LoadErr::IO)?;
+
+    let mut parser = Parser::new(contents.as_bytes());
+    let mut record = Record { arguments: Vec::new(), inputs: Vec::new(), run_id: String::new() };
+    let (mut arguments, mut inputs) = (false, false);
+
+    parser.expect(b'{')?;
+    if !parser.next_is(b'}') {
+        loop {
+            let key = parser.string()?;
+            parser.expect(b':')?;
+            match key.as_str() {
+                "arguments" => { record.arguments = parser.strings()?; arguments = true; },
+                "inputs"    => { record.inputs = parser.strings()?; inputs = true; },
+                "runid"     => { record.run_id = parser.string()?; },
+                _           => parser.skip_value()?,
+            }
+            if parser.next_is(b'}') { break }
+            parser.expect(b',')?;
+        }
+    }
+
+    if !arguments || !inputs {
+        return Err(LoadErr::Invalid("the arguments and inputs were not recorded".to_owned()));
+    }
+    Ok(record)
+}
+
+/// Selects the inputs of the jobs given by `jobs`, a comma-separated list of job numbers and ranges
+/// of job numbers, counting from one, such as `1,4-6`.
+pub fn select(inputs: Vec<String>, jobs: &[(usize, usize)]) -> Vec<String> {
+    inputs.into_iter().enumerate()
+        .filter(|&(id, _)| jobs.iter().any(|&(start, end)| (start..=end).contains(&(id + 1))))
+        .map(|(_, input)| input)
+        .collect()
+}
+
+/// Parses a comma-separated list of job numbers and ranges of job numbers, such as `1,4-6`.
+pub fn parse_jobs(list: &str) -> Option<Vec<(usize, usize)>> {
+    list.split(',').map(|range| {
+        let mut bounds = range.splitn(2, '-');
+        let start = bounds.next()?.trim().parse::<usize>().ok()?;
+        let end = match bounds.next() {
+            Some(end) => end.trim().parse::<usize>().ok()?,
+            None      => start
+        };
+        if start == 0 || end < start { None } else { Some((start, end)) }
+    }).collect()
+}
+
+#[derive(Debug)]
+pub enum LoadErr {
+    IO(io::Error),
+    Invalid(String),
+}
+
+/// Parses the subset of JSON that is written by `write()`, and by `--plan`.
+pub struct Parser<'a> {
+    input: &'a [u8],
+    index: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a [u8]) -> Parser<'a> { Parser { input, index: 0 } }
+
+    fn skip_whitespace(&mut self) {
+        while self.input.get(self.index).map_or(false, |byte| byte.is_ascii_whitespace()) {
+            self.index += 1;
+        }
+    }
+
+    pub fn next_is(&mut self, expected: u8) -> bool {
+        self.skip_whitespace();
+        if self.input.get(self.index) == Some(&expected) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn expect(&mut self, expected: u8) -> Result<(), LoadErr> {
+        if self.next_is(expected) {
+            Ok(())
+        } else {
+            Err(LoadErr::Invalid(format!("expected '{}' at byte {}", expected as char, self.index)))
+        }
+    }
+
+    fn strings(&mut self) -> Result<Vec<String>, LoadErr> {
+        let mut strings = Vec::new();
+        self.expect(b'[')?;
+        if self.next_is(b']') { return Ok(strings); }
+        loop {
+            strings.push(self.string()?);
+            if self.next_is(b']') { return Ok(strings); }
+            self.expect(b',')?;
+        }
+    }
+
+    /// Skips a value of any type, such as the values of keys which are not needed.
+    pub fn skip_value(&mut self) -> Result<(), LoadErr> {
+        self.skip_whitespace();
+        match self.input.get(self.index) {
+            Some(b'"') => self.string().map(|_| ()),
+            Some(b'[') => {
+                self.index += 1;
+                if self.next_is(b']') { return Ok(()) }
+                loop {
+                    self.skip_value()?;
+                    if self.next_is(b']') { return Ok(()) }
+                    self.expect(b',')?;
+                }
+            },
+            Some(b'{') => {
+                self.index += 1;
+                if self.next_is(b'}') { return Ok(()) }
+                loop {
+                    self.string()?;
+                    self.expect(b':')?;
+                    self.skip_value()?;
+                    if self.next_is(b'}') { return Ok(()) }
+                    self.expect(b',')?;
+                }
+            },
+            _ => {
+                // Numbers, booleans, and null.
+                let start = self.index;
+                while self.input.get(self.index).map_or(false, |&byte| byte.is_ascii_alphanumeric() || b"+-.".contains(&byte)) {
+                    self.index += 1;
+                }
+                if self.index == start {
+                    Err(LoadErr::Invalid(format!("expected a value at byte {}", start)))
+                } else {
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    pub fn string(&mut self) -> Result<String, LoadErr> {
+        self.expect(b'"')?;
+        let mut bytes = Vec::new();
+        loop {
+            let byte = *self.input.get(self.index)
+                .ok_or_else(|| LoadErr::Invalid("string is not terminated".to_owned()))?;
+            self.index += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let escaped = *self.input.get(self.index)
+                        .ok_or_else(|| LoadErr::Invalid("string is not terminated".to_owned()))?;
+                    self.index += 1;
+                    match escaped {
+                        b'n' => bytes.push(b'\n'),
+                        b'r' => bytes.push(b'\r'),
+                        b't' => bytes.push(b'\t'),
+                        b'u' => {
+                            let code = self.input.get(self.index..self.index+4)
+                                .and_then(|hex| u32::from_str_radix(&String::from_utf8_lossy(hex), 16).ok())
+                                .and_then(::std::char::from_u32)
+                                .ok_or_else(|| LoadErr::Invalid(format!("invalid escape at byte {}", self.index)))?;
+                            self.index += 4;
+                            let mut buffer = [0u8; 4];
+                            bytes.extend_from_slice(code.encode_utf8(&mut buffer).as_bytes());
+                        },
+                        _ => bytes.push(escaped),
+                    }
+                },
+                _ => bytes.push(byte),
+            }
+        }
+        String::from_utf8(bytes).map_err(|_| LoadErr::Invalid("string is not valid UTF-8".to_owned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn record_and_load() {
+        let path = env::temp_dir().join("parallel_record_test.json");
+        let arguments = vec!["-j".to_owned(), "2".to_owned(), "echo \"{}\"".to_owned()];
+        let inputs = vec!["a\tb".to_owned(), "c\\d\u{1}".to_owned(), "e".to_owned()];
+        write(&path, &arguments, "0123abcd", inputs.clone().into_iter().map(Ok)).unwrap();
+
+        let record = load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(record.arguments, arguments);
+        assert_eq!(record.inputs, inputs);
+        assert_eq!(record.run_id, "0123abcd");
+
+        // Fields which are not known, of any kind, are skipped.
+        fs::write(&path, r#"{"version": 2, "arguments": ["echo"], "opts": {"k": [1, true, null]}, "inputs": ["a"]}"#).unwrap();
+        let extra = load(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(extra.arguments, vec!["echo".to_owned()]);
+        assert_eq!(extra.inputs, vec!["a".to_owned()]);
+
+        let jobs = parse_jobs("1,3-4").unwrap();
+        assert_eq!(select(record.inputs, &jobs), vec!["a\tb".to_owned(), "e".to_owned()]);
+        assert!(parse_jobs("2-1").is_none());
+    }
+}
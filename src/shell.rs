@@ -15,7 +15,8 @@ pub fn required(kind: Kind) -> bool {
         Kind::Tokens(arguments) => {
             for token in arguments {
                 if let Token::Argument(ref arg) = *token {
-                    if arg.as_bytes().iter().any(|&x| x == b';' || x == b'&' || x == b'|' || x == b'$' || x == b'<' || x == b'>' || x == b'[' || x == b']' || x == b'@') {
+                    // Commands which span multiple lines are scripts for the shell.
+                    if arg.as_bytes().iter().any(|&x| x == b';' || x == b'&' || x == b'|' || x == b'$' || x == b'<' || x == b'>' || x == b'[' || x == b']' || x == b'@' || x == b'\n') {
                         return true
                     }
                 }
@@ -28,33 +29,43 @@ pub fn required(kind: Kind) -> bool {
     false
 }
 
-/// Returns `true` if the Dash shell was found within the `PATH` environment variable.
-pub fn dash_exists() -> bool {
-    if let Ok(path) = env::var("PATH") {
-        for path in path.split(':') {
-            if let Ok(directory) = fs::read_dir(path) {
-                for entry in directory {
-                    if let Ok(entry) = entry {
-                        let path = entry.path();
-                        if path.is_file() && path.file_name() == Some(OsStr::new("dash")) { return true; }
-                    }
-                }
-            }
+/// Returns `true` if the word assigns a value to an environment variable, as in `NAME=value`.
+pub fn is_assignment(word: &str) -> bool {
+    match word.find('=') {
+        Some(0) | None => false,
+        Some(position) => {
+            let name = &word[..position];
+            !name.as_bytes()[0].is_ascii_digit()
+                && name.bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'_')
         }
     }
-    false
 }
 
+/// If the command begins with `#!SHELL`, returns the shell that it should be executed within, and
+/// the remainder of the command.
+pub fn shebang(command: &str) -> Option<(&str, &str)> {
+    if !command.starts_with("#!") { return None }
+    let command = &command[2..];
+    match command.find(' ') {
+        Some(position) if position != 0 => Some((&command[..position], command[position+1..].trim_start())),
+        _ => None
+    }
+}
+
+/// Returns `true` if the Dash shell was found within the `PATH` environment variable.
+pub fn dash_exists() -> bool { program_exists("dash") }
+
 /// Returns `true` if the Ion shell was found within the `PATH` environment variable.
-pub fn ion_exists() -> bool {
+pub fn ion_exists() -> bool { program_exists("ion") }
+
+/// Returns `true` if the given program was found within the `PATH` environment variable.
+pub fn program_exists(program: &str) -> bool {
     if let Ok(path) = env::var("PATH") {
         for path in path.split(':') {
             if let Ok(directory) = fs::read_dir(path) {
-                for entry in directory {
-                    if let Ok(entry) = entry {
-                        let path = entry.path();
-                        if path.is_file() && path.file_name() == Some(OsStr::new("ion")) { return true; }
-                    }
+                for entry in directory.flatten() {
+                    let path = entry.path();
+                    if path.is_file() && path.file_name() == Some(OsStr::new(program)) { return true; }
                 }
             }
         }
@@ -0,0 +1,81 @@
+//! Repeats the run on a fixed interval with `--every`, as a lightweight alternative to scheduling
+//! the program with cron and guarding it with flock. The supervisor executes this program again
+//! with the same arguments for each cycle, which is told its number through `PARALLEL_CYCLE`, and
+//! which keeps its inputs and outputs within its own tempdir.
+
+use std::env;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Child, Command};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the supervisor checks whether the running cycles have completed.
+const POLL_INTERVAL: u64 = 100;
+
+/// The environment variable which gives the number of the cycle, counting from one.
+pub const CYCLE_VAR: &str = "PARALLEL_CYCLE";
+
+/// The tempdir of a cycle, within the tempdir of the supervisor.
+pub fn cycle_dir(base: &Path, cycle: &str) -> String {
+    format!("{}/cycle_{}", base.display(), cycle)
+}
+
+/// Starts a cycle every `interval`, until the supervisor is killed. A cycle which is due while
+/// the previous cycle is still running is skipped, unless `allow_overlap` is set. A summary of
+/// each cycle is printed to the standard error once it has completed.
+pub fn supervise(interval: Duration, allow_overlap: bool, arguments: &[String], base: &Path) -> ! {
+    let stderr = io::stderr();
+    let program = env::current_exe().unwrap_or_else(|_| arguments[0].clone().into());
+    let mut running: Vec<(usize, Child, Instant)> = Vec::new();
+    let mut next = Instant::now();
+    let mut cycle = 0;
+
+    loop {
+        let mut index = 0;
+        while index < running.len() {
+            let status = match running[index].1.try_wait() {
+                Ok(None) => {
+                    index += 1;
+                    continue
+                },
+                Ok(Some(status)) => Some(status),
+                Err(why) => {
+                    let _ = writeln!(stderr.lock(), "parallel: cycle {}: unable to wait on cycle: {}",
+                        running[index].0, why);
+                    None
+                }
+            };
+            let (number, _, started) = running.remove(index);
+            if let Some(status) = status {
+                let elapsed = started.elapsed();
+                let _ = writeln!(stderr.lock(), "parallel: cycle {} completed in {}.{:02}s with {}", number,
+                    elapsed.as_secs(), elapsed.subsec_nanos() / 10_000_000, status);
+            }
+            let _ = fs::remove_dir_all(cycle_dir(base, &number.to_string()));
+        }
+
+        let now = Instant::now();
+        if now >= next {
+            cycle += 1;
+            if running.is_empty() || allow_overlap {
+                let spawned = Command::new(&program).args(&arguments[1..]).env(CYCLE_VAR, cycle.to_string()).spawn();
+                match spawned {
+                    Ok(child) => running.push((cycle, child, now)),
+                    Err(why) => {
+                        let _ = writeln!(stderr.lock(), "parallel: cycle {}: unable to execute {:?}: {}", cycle,
+                            program, why);
+                    }
+                }
+            } else {
+                let _ = writeln!(stderr.lock(), "parallel: cycle {} skipped, as cycle {} is still running", cycle,
+                    running[0].0);
+            }
+            // Cycles which were missed while the supervisor was busy are not made up for.
+            while next <= now { next += interval; }
+        }
+
+        thread::sleep((next - now).min(Duration::from_millis(POLL_INTERVAL)));
+    }
+}
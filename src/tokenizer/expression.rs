@@ -0,0 +1,291 @@
+use super::functions::{basename, column, dirname, remove_extension};
+
+/// The contents of a `{= ... =}` placeholder: a source value, which may be computed with integer
+/// arithmetic, followed by a chain of functions that transform it, as in `{=1 basename | upper=}`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Expression {
+    source:    Arithmetic,
+    functions: Vec<Function>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Value {
+    /// `_`, or an empty source: the input, unmodified.
+    Input,
+    /// `$N`, or a source consisting of only `N`: the Nth whitespace-separated column of the input.
+    Column(usize),
+    /// `#`: the job ID.
+    Job,
+    /// `%`: the slot ID.
+    Slot,
+    Integer(i64),
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Operator { Add, Subtract, Multiply, Divide, Remainder }
+
+#[derive(Clone, PartialEq, Debug)]
+enum Arithmetic {
+    Value(Value),
+    Negate(Box<Arithmetic>),
+    Binary(Box<Arithmetic>, Operator, Box<Arithmetic>),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+enum Function {
+    Basename,
+    Dirname,
+    TrimExt,
+    Upper,
+    Lower,
+    Trim,
+    Length,
+    /// Pads the value with leading zeroes to the given width.
+    Pad(usize),
+    Replace(String, String),
+}
+
+impl Expression {
+    /// Parses the contents of a `{= ... =}` placeholder, returning the reason if it is invalid.
+    pub fn parse(input: &str) -> Result<Expression, String> {
+        // Functions begin with a letter, which may not appear within the source.
+        let split = input.find(|c: char| c.is_ascii_alphabetic() || c == '|').unwrap_or(input.len());
+        let (source, functions) = (input[..split].trim(), &input[split..]);
+
+        let source = if !source.is_empty() && source.bytes().all(|byte| byte.is_ascii_digit()) {
+            match source.parse::<usize>() {
+                Ok(number) if number != 0 => Arithmetic::Value(Value::Column(number)),
+                _ => return Err(format!("'{}' is not a column", source))
+            }
+        } else if source.is_empty() {
+            Arithmetic::Value(Value::Input)
+        } else {
+            let mut parser = Parser { input: source.as_bytes(), index: 0 };
+            let arithmetic = parser.sum()?;
+            parser.skip_whitespace();
+            if parser.index != parser.input.len() {
+                return Err(format!("unexpected '{}'", &source[parser.index..]));
+            }
+            arithmetic
+        };
+
+        Ok(Expression { source, functions: parse_functions(functions)? })
+    }
+
+    /// Evaluates the expression for the given job, appending the result to `output`.
+    pub fn evaluate(&self, input: &str, job: &str, slot: &str, output: &mut String) {
+        let mut value = match self.source {
+            Arithmetic::Value(value) => resolve(value, input, job, slot).to_owned(),
+            ref arithmetic => compute(arithmetic, input, job, slot).map_or(String::new(), |value| value.to_string())
+        };
+
+        for function in &self.functions {
+            value = match *function {
+                Function::Basename  => basename(&value).to_owned(),
+                Function::Dirname   => dirname(&value).to_owned(),
+                Function::TrimExt   => remove_extension(&value).to_owned(),
+                Function::Upper     => value.to_uppercase(),
+                Function::Lower     => value.to_lowercase(),
+                Function::Trim      => value.trim().to_owned(),
+                Function::Length    => value.chars().count().to_string(),
+                Function::Pad(width) => {
+                    let length = value.chars().count();
+                    if length >= width { value } else { "0".repeat(width - length) + &value }
+                },
+                Function::Replace(ref from, ref to) => value.replace(from.as_str(), to),
+            };
+        }
+
+        output.push_str(&value);
+    }
+
+    /// The largest column that the expression refers to, or zero if it refers to none.
+    pub fn columns(&self) -> usize {
+        fn largest(arithmetic: &Arithmetic) -> usize {
+            match *arithmetic {
+                Arithmetic::Value(Value::Column(number)) => number,
+                Arithmetic::Value(_) => 0,
+                Arithmetic::Negate(ref inner) => largest(inner),
+                Arithmetic::Binary(ref left, _, ref right) => largest(left).max(largest(right)),
+            }
+        }
+        largest(&self.source)
+    }
+}
+
+fn resolve<'a>(value: Value, input: &'a str, job: &'a str, slot: &'a str) -> &'a str {
+    match value {
+        Value::Input          => input,
+        Value::Column(number) => column(input, number).unwrap_or(""),
+        Value::Job            => job,
+        Value::Slot           => slot,
+        // Integers are only resolved within arithmetic.
+        Value::Integer(_)     => "",
+    }
+}
+
+/// Computes the result of integer arithmetic, where values that are not integers are treated as
+/// zero. Division by zero and overflows result in `None`.
+fn compute(arithmetic: &Arithmetic, input: &str, job: &str, slot: &str) -> Option<i64> {
+    match *arithmetic {
+        Arithmetic::Value(Value::Integer(integer)) => Some(integer),
+        Arithmetic::Value(value) => Some(resolve(value, input, job, slot).trim().parse::<i64>().unwrap_or(0)),
+        Arithmetic::Negate(ref inner) => compute(inner, input, job, slot)?.checked_neg(),
+        Arithmetic::Binary(ref left, operator, ref right) => {
+            let (left, right) = (compute(left, input, job, slot)?, compute(right, input, job, slot)?);
+            match operator {
+                Operator::Add       => left.checked_add(right),
+                Operator::Subtract  => left.checked_sub(right),
+                Operator::Multiply  => left.checked_mul(right),
+                Operator::Divide    => left.checked_div(right),
+                Operator::Remainder => left.checked_rem(right),
+            }
+        }
+    }
+}
+
+/// Parses a chain of functions, which are separated by `|` or whitespace.
+fn parse_functions(input: &str) -> Result<Vec<Function>, String> {
+    let mut functions = Vec::new();
+    let mut remaining = input.trim_start_matches(|c: char| c == '|' || c.is_whitespace());
+
+    while !remaining.is_empty() {
+        let end = remaining.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(remaining.len());
+        let name = &remaining[..end];
+        remaining = &remaining[end..];
+
+        let mut arguments = Vec::new();
+        if remaining.starts_with('(') {
+            let close = remaining.find(')').ok_or_else(|| format!("arguments of '{}' are not terminated", name))?;
+            arguments.extend(remaining[1..close].split(','));
+            remaining = &remaining[close+1..];
+        }
+
+        let function = match (name, arguments.len()) {
+            ("basename", 0) => Function::Basename,
+            ("dirname", 0)  => Function::Dirname,
+            ("trim_ext", 0) => Function::TrimExt,
+            ("upper", 0)    => Function::Upper,
+            ("lower", 0)    => Function::Lower,
+            ("trim", 0)     => Function::Trim,
+            ("len", 0)      => Function::Length,
+            ("pad", 1)      => Function::Pad(arguments[0].trim().parse::<usize>()
+                .map_err(|_| format!("pad width, '{}', is not a number", arguments[0]))?),
+            ("replace", 2)  => Function::Replace(arguments[0].to_owned(), arguments[1].to_owned()),
+            ("", _)         => return Err(format!("unexpected '{}'", remaining)),
+            _               => return Err(format!("'{}' is not a function", name)),
+        };
+
+        functions.push(function);
+        remaining = remaining.trim_start_matches(|c: char| c == '|' || c.is_whitespace());
+    }
+
+    Ok(functions)
+}
+
+/// A recursive descent parser for integer arithmetic, where `*`, `/`, and `%` bind more tightly
+/// than `+` and `-`.
+struct Parser<'a> {
+    input: &'a [u8],
+    index: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.input.get(self.index).map_or(false, |byte| byte.is_ascii_whitespace()) {
+            self.index += 1;
+        }
+    }
+
+    fn operator(&mut self, operators: &[(u8, Operator)]) -> Option<Operator> {
+        self.skip_whitespace();
+        let byte = *self.input.get(self.index)?;
+        let &(_, operator) = operators.iter().find(|&&(character, _)| character == byte)?;
+        self.index += 1;
+        Some(operator)
+    }
+
+    fn sum(&mut self) -> Result<Arithmetic, String> {
+        let mut arithmetic = self.product()?;
+        while let Some(operator) = self.operator(&[(b'+', Operator::Add), (b'-', Operator::Subtract)]) {
+            arithmetic = Arithmetic::Binary(Box::new(arithmetic), operator, Box::new(self.product()?));
+        }
+        Ok(arithmetic)
+    }
+
+    fn product(&mut self) -> Result<Arithmetic, String> {
+        let operators = [(b'*', Operator::Multiply), (b'/', Operator::Divide), (b'%', Operator::Remainder)];
+        let mut arithmetic = self.factor()?;
+        while let Some(operator) = self.operator(&operators) {
+            arithmetic = Arithmetic::Binary(Box::new(arithmetic), operator, Box::new(self.factor()?));
+        }
+        Ok(arithmetic)
+    }
+
+    fn factor(&mut self) -> Result<Arithmetic, String> {
+        self.skip_whitespace();
+        let byte = *self.input.get(self.index).ok_or_else(|| "expected a value".to_owned())?;
+        self.index += 1;
+        match byte {
+            b'_' => Ok(Arithmetic::Value(Value::Input)),
+            b'#' => Ok(Arithmetic::Value(Value::Job)),
+            b'%' => Ok(Arithmetic::Value(Value::Slot)),
+            b'-' => Ok(Arithmetic::Negate(Box::new(self.factor()?))),
+            b'(' => {
+                let arithmetic = self.sum()?;
+                self.skip_whitespace();
+                if self.input.get(self.index) != Some(&b')') {
+                    return Err("expected a closing parenthesis".to_owned());
+                }
+                self.index += 1;
+                Ok(arithmetic)
+            },
+            b'$' => match self.integer() {
+                Some(number) if number > 0 => Ok(Arithmetic::Value(Value::Column(number as usize))),
+                _ => Err("expected a column number after '$'".to_owned())
+            },
+            b'0'..=b'9' => {
+                self.index -= 1;
+                let integer = self.integer().ok_or_else(|| "integer is too large".to_owned())?;
+                Ok(Arithmetic::Value(Value::Integer(integer)))
+            },
+            _ => Err(format!("unexpected '{}'", byte as char))
+        }
+    }
+
+    fn integer(&mut self) -> Option<i64> {
+        let start = self.index;
+        while self.input.get(self.index).map_or(false, |byte| byte.is_ascii_digit()) {
+            self.index += 1;
+        }
+        // The input was originally a `str`, and only ASCII digits were consumed.
+        unsafe { ::std::str::from_utf8_unchecked(&self.input[start..self.index]) }.parse::<i64>().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(expression: &str, input: &str) -> String {
+        let mut output = String::new();
+        Expression::parse(expression).unwrap().evaluate(input, "3", "1", &mut output);
+        output
+    }
+
+    #[test]
+    fn expressions() {
+        assert_eq!(evaluate("1 basename | trim_ext | upper", "a/bc.txt d"), "BC");
+        assert_eq!(evaluate("#*2", "x"), "6");
+        assert_eq!(evaluate(" (# + $2) % 4 ", "x 5"), "0");
+        assert_eq!(evaluate("_", "x"), "x");
+        assert_eq!(evaluate("replace(.txt,.md) | dirname", "a/b.txt"), "a");
+        assert_eq!(evaluate("# pad(4)", ""), "0003");
+        assert_eq!(evaluate("#/0", ""), "");
+        assert!(Expression::parse("1 frobnicate").is_err());
+        assert!(Expression::parse("#*").is_err());
+        assert!(Expression::parse("pad(x)").is_err());
+        assert_eq!(Expression::parse("$2 + $5 * #").unwrap().columns(), 5);
+        assert_eq!(Expression::parse("_ upper").unwrap().columns(), 0);
+    }
+}
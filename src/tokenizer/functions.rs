@@ -1,3 +1,10 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+static COLSEP: OnceLock<Regex> = OnceLock::new();
+static COLUMN_NAMES: OnceLock<HashMap<String, usize>> = OnceLock::new();
+
 /// Removes the extension of a given input
 pub fn remove_extension(input: &str) -> &str {
     let mut dir_index = 0;
@@ -36,6 +43,43 @@ pub fn dirname(input: &str) -> &str {
     if index == 0 { "." } else { &input[0..index] }
 }
 
+/// With `--colsep`, columns are separated by each match of the `separator`, rather than by whitespace.
+pub fn separate_columns(separator: Regex) {
+    let _ = COLSEP.set(separator);
+}
+
+/// Obtains the Nth column of the input, counting from 1, which is separated by whitespace, unless
+/// another separator was given with `--colsep`.
+pub fn column(input: &str, number: usize) -> Option<&str> {
+    nth_column(input, number, COLSEP.get())
+}
+
+/// With `--header :`, columns may also be referred to by the names which the header gave them.
+pub fn name_columns(names: HashMap<String, usize>) {
+    let _ = COLUMN_NAMES.set(names);
+}
+
+/// The number of the column of the given `name`, counting from 1, if the header named one so.
+pub fn named_column(name: &str) -> Option<usize> {
+    COLUMN_NAMES.get()?.get(name).cloned()
+}
+
+/// The number of columns of the input.
+pub fn count_columns(input: &str) -> usize {
+    match COLSEP.get() {
+        Some(separator) => separator.split(input).count(),
+        None            => input.split_whitespace().count(),
+    }
+}
+
+fn nth_column<'a>(input: &'a str, number: usize, separator: Option<&Regex>) -> Option<&'a str> {
+    if number == 0 { return None }
+    match separator {
+        Some(separator) => separator.split(input).nth(number - 1),
+        None            => input.split_whitespace().nth(number - 1),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -106,4 +150,19 @@ mod tests {
     fn path_dirname_empty() {
         assert_eq!(dirname(""), ".");
     }
+
+    #[test]
+    fn input_column() {
+        assert_eq!(column("a.mkv 600", 1), Some("a.mkv"));
+        assert_eq!(column("a.mkv 600", 2), Some("600"));
+        assert_eq!(column("a.mkv  600", 2), Some("600"));
+        assert_eq!(column("a.mkv 600", 3), None);
+        assert_eq!(column("a.mkv 600", 0), None);
+
+        let separator = Regex::new(r"\s*,\s*").unwrap();
+        assert_eq!(nth_column("a b, c,,d", 1, Some(&separator)), Some("a b"));
+        assert_eq!(nth_column("a b, c,,d", 3, Some(&separator)), Some(""));
+        assert_eq!(nth_column("a b, c,,d", 4, Some(&separator)), Some("d"));
+        assert_eq!(nth_column("a b, c,,d", 5, Some(&separator)), None);
+    }
 }
@@ -0,0 +1,113 @@
+use std::borrow::Cow;
+use std::fmt;
+use super::{count_columns, Token};
+
+/// A likely mistake within the command template, which is reported before any job is launched.
+#[derive(Debug, PartialEq)]
+pub enum Warning {
+    /// A pattern such as `{file}` is not a placeholder, and will be passed to the command as is.
+    UnknownPlaceholder(&'static str),
+    /// `{N}` refers to the Nth input, which is the same for every job, and as no other placeholder
+    /// varies with each input, each input will also be appended to the command.
+    NthInputAppended,
+    /// A column is referenced which the first input does not have: the column, and the number of
+    /// columns within the first input.
+    MissingColumn(usize, usize),
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Warning::UnknownPlaceholder(pattern) => {
+                write!(f, "'{}' is not a placeholder, and will be passed to the command as is", pattern)
+            },
+            Warning::NthInputAppended => {
+                write!(f, "{{N}} refers to the Nth input, rather than the Nth column, and as no placeholder \
+                    varies with each input, each input will be appended to the command")
+            },
+            Warning::MissingColumn(column, columns) => {
+                write!(f, "column {} is referenced, but the first input has only {} columns", column, columns)
+            },
+        }
+    }
+}
+
+/// Whether any token varies with each input. If none do, the input is appended to the command.
+pub fn has_placeholder(tokens: &[Token]) -> bool {
+    tokens.iter().any(|x| matches!(*x,
+        Token::BaseAndExt | Token::Basename | Token::Column(_) | Token::Dirname | Token::Expression(_) |
+        Token::Job | Token::Placeholder | Token::RemoveExtension | Token::RemoveSuffix(_) | Token::Slot))
+}
+
+/// The largest column that the tokens refer to, through `{N}` within a key or `{= ... =}` expressions.
+pub fn columns(tokens: &[Token]) -> usize {
+    tokens.iter().map(|token| match *token {
+        Token::Column(number)             => number,
+        Token::Expression(ref expression) => expression.columns(),
+        _                                 => 0,
+    }).max().unwrap_or(0)
+}
+
+/// Inspects the tokens of the command template for likely mistakes, where `columns` is the largest
+/// column referenced by the template and other options, `first_input` is the first input, and
+/// `append` is whether inputs are appended to commands without a placeholder.
+pub fn lint(template: &str, tokens: &[Token], columns: usize, first_input: &str, append: bool) -> Vec<Warning> {
+    let mut warnings = Vec::new();
+
+    // Patterns that were not matched are kept as arguments, braces included. Only those which
+    // resemble a placeholder are reported, so that shell and awk code is left alone, including
+    // shell variables such as `${HOME}`.
+    let mut variable = false;
+    for token in tokens {
+        if let Token::Argument(Cow::Borrowed(pattern)) = *token {
+            if !variable && pattern.len() > 2 && pattern.starts_with('{') && pattern.ends_with('}')
+                && pattern[1..pattern.len()-1].bytes().all(|byte| byte.is_ascii_alphanumeric() || byte == b'_')
+            {
+                warnings.push(Warning::UnknownPlaceholder(pattern));
+            }
+        }
+        variable = match *token {
+            Token::Argument(ref argument) => argument.ends_with('$'),
+            _ => false
+        };
+    }
+
+    // `{N}` tokens were already substituted with the Nth input, so the template is searched for them.
+    let nth_input = template.split('{').skip(1)
+        .any(|pattern| pattern.starts_with(|c: char| c.is_ascii_digit()) && pattern.contains('}'));
+    if append && nth_input && !has_placeholder(tokens) {
+        warnings.push(Warning::NthInputAppended);
+    }
+
+    let available = count_columns(first_input);
+    if columns > available {
+        warnings.push(Warning::MissingColumn(columns, available));
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrayvec::ArrayVec;
+    use std::io;
+    use tokenizer::tokenize;
+
+    fn lint_template(template: &'static str, first_input: &str) -> Vec<Warning> {
+        let mut tokens = ArrayVec::<[Token; 128]>::new();
+        tokenize(&mut tokens, template, &|_| Ok(String::from("a b")) as io::Result<String>, 2, false).unwrap();
+        lint(template, &tokens, columns(&tokens), first_input, true)
+    }
+
+    #[test]
+    fn template_warnings() {
+        assert!(lint_template("echo {} {=2=}", "a b").is_empty());
+        assert!(lint_template("awk '{print $1}'", "a").is_empty());
+        assert!(lint_template("echo ${HOME}", "a").is_empty());
+        assert_eq!(lint_template("echo {file}", "a"), vec![Warning::UnknownPlaceholder("{file}")]);
+        assert_eq!(lint_template("echo {2}", "a"), vec![Warning::NthInputAppended]);
+        assert!(lint_template("echo {2} {}", "a").is_empty());
+        assert_eq!(lint_template("echo {=$3=}", "a b"), vec![Warning::MissingColumn(3, 2)]);
+    }
+}
@@ -1,23 +1,46 @@
+mod expression;
 pub mod functions;
+pub mod lint;
 
 use arrayvec::ArrayVec;
+use input_iterator::stream;
+use std::env;
+use std::error::Error;
 use std::fmt;
 use std::io;
-use std::path::Path;
 use std::borrow::Cow;
+use time;
+pub use self::expression::Expression;
 pub use self::functions::*;
 
 #[derive(Debug)]
 pub enum TokenErr {
     File(io::Error),
+    /// The contents of a `{= ... =}` placeholder are invalid, for the given reason.
+    InvalidExpression(&'static str, String),
+    /// The format of a `{now:...}` placeholder is invalid.
+    InvalidTimeFormat(&'static str),
     OutOfBounds,
 }
 
 impl fmt::Display for TokenErr {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match *self {
-            TokenErr::File(ref io) => write!(f, "parallel: unable to obtain the Nth input: {}", io),
-            TokenErr::OutOfBounds  => write!(f, "parallel: input token out of bounds")
+            TokenErr::File(ref io) => write!(f, "unable to obtain the Nth input: {}", io),
+            TokenErr::InvalidExpression(pattern, ref why) => {
+                write!(f, "invalid expression, '{{{}}}': {}", pattern, why)
+            },
+            TokenErr::InvalidTimeFormat(format) => write!(f, "invalid time format, '{{now:{}}}'", format),
+            TokenErr::OutOfBounds  => write!(f, "input token out of bounds")
+        }
+    }
+}
+
+impl Error for TokenErr {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            TokenErr::File(ref io) => Some(io),
+            _                      => None
         }
     }
 }
@@ -33,18 +56,40 @@ pub enum Token {
     BaseAndSuffix(&'static str),
     /// Takes the basename (file name) of the input with the directory path removed.
     Basename,
+    /// Takes the Nth whitespace-separated column of the input.
+    Column(usize),
     /// Takes the directory path of the input with the basename removed.
     Dirname,
+    /// Evaluates a `{= ... =}` expression with the input.
+    Expression(Box<Expression>),
+    /// Returns the login of the `--sshlogin` host which the job is executed on, or `:` for the local machine.
+    Host,
     /// Returns the job ID of the current input.
     Job,
+    /// Formats the time at which the job started, as with `strftime`.
+    Now(&'static str),
     /// Takes the input, unmodified.
     Placeholder,
+    /// Returns the output of the previous stage of the job, with `--then`.
+    Previous,
+    /// Returns the exclusive end of the byte range of the input file, with `--chunk-file`.
+    RangeEnd,
+    /// Returns the start of the byte range of the input file, with `--chunk-file`.
+    RangeStart,
     /// Removes the extension from the input.
     RemoveExtension,
     /// Removes a specified extension pattern
     RemoveSuffix(&'static str),
+    /// Returns the scratch directory of the job slot.
+    Scratch,
     /// Returns the thread ID.
-    Slot
+    Slot,
+    /// Returns the name of the source that the input was read from, such as the path of an input file.
+    Source,
+    /// Returns the line number of the input within its source.
+    SourceLine,
+    /// Returns the total number of jobs, while the inputs are still being read with `--total-jobs`.
+    Total,
 }
 
 struct Number {
@@ -57,31 +102,43 @@ impl Number {
         Number{ id: id, token: token }
     }
 
-    fn into_argument(self, path: &Path) -> Result<String, TokenErr> {
-        use std::fs::File;
-        use std::io::{BufRead, BufReader};
-        let file = File::open(path).map_err(TokenErr::File)?;
-        let input = &BufReader::new(file).lines().nth(self.id-1).unwrap().map_err(TokenErr::File)?;
+    fn into_argument(self, nth_input: &NthInput) -> Result<String, TokenErr> {
+        let input = &nth_input(self.id).map_err(TokenErr::File)?;
         let argument = match self.token {
             Token::Argument(_)        => unreachable!(),
             Token::Basename           => basename(input),
+            Token::Column(_)          => unreachable!(),
             Token::BaseAndExt         => basename(remove_extension(input)),
             Token::BaseAndSuffix(pat) => basename(remove_pattern(input, pat)),
             Token::Dirname            => dirname(input),
+            Token::Expression(_)      => unreachable!(),
+            Token::Host               => unreachable!(),
             Token::Job                => unreachable!(),
+            Token::Now(_)             => unreachable!(),
             Token::Placeholder        => input,
+            Token::Previous           => unreachable!(),
+            Token::RangeEnd           => unreachable!(),
+            Token::RangeStart         => unreachable!(),
             Token::RemoveExtension    => remove_extension(input),
             Token::RemoveSuffix(pat)  => remove_pattern(input, pat),
-            Token::Slot               => unreachable!()
+            Token::Scratch            => unreachable!(),
+            Token::Slot               => unreachable!(),
+            Token::Source             => unreachable!(),
+            Token::SourceLine         => unreachable!(),
+            Token::Total              => unreachable!(),
         };
         Ok(String::from(argument))
     }
 }
 
+/// Obtains the Nth input, counting from one, for `{N}` tokens.
+pub type NthInput<'a> = dyn Fn(usize) -> io::Result<String> + 'a;
+
 /// Takes the command arguments as the input and reduces it into tokens,
 /// which allows for easier management of string manipulation later on.
-pub fn tokenize(tokens: &mut ArrayVec<[Token; 128]>, template: &'static str, path: &Path, nargs: usize)
-    -> Result<(), TokenErr>
+/// If `columns` is set, `{N}` tokens will refer to the Nth column of each input, rather than the Nth input.
+pub fn tokenize(tokens: &mut ArrayVec<[Token; 128]>, template: &'static str, nth_input: &NthInput,
+    nargs: usize, columns: bool) -> Result<(), TokenErr>
 {
     // When set to true, the characters following will be collected into `pattern`.
     let mut pattern_matching = false;
@@ -116,7 +173,7 @@ pub fn tokenize(tokens: &mut ArrayVec<[Token; 128]>, template: &'static str, pat
                     tokens.push(Token::Placeholder);
                 } else {
                     // Supply the internal contents of the pattern to the token matcher.
-                    match match_token(&template[pattern_start+1..id], path, nargs)? {
+                    match match_token(&template[pattern_start+1..id], nth_input, nargs, columns)? {
                         // If the token is a match, add the matched token.
                         Some(token) => { tokens.push(token); },
                         // If the token is not a match, add it as an argument.
@@ -146,7 +203,9 @@ pub fn tokenize(tokens: &mut ArrayVec<[Token; 128]>, template: &'static str, pat
 }
 
 /// Matches a pattern to it's associated token.
-fn match_token(pattern: &'static str, path: &Path, nargs: usize) -> Result<Option<Token>, TokenErr> {
+fn match_token(pattern: &'static str, nth_input: &NthInput, nargs: usize, columns: bool)
+    -> Result<Option<Token>, TokenErr>
+{
     match pattern {
         "."  => Ok(Some(Token::RemoveExtension)),
         "#"  => Ok(Some(Token::Job)),
@@ -154,9 +213,30 @@ fn match_token(pattern: &'static str, path: &Path, nargs: usize) -> Result<Optio
         "/"  => Ok(Some(Token::Basename)),
         "//" => Ok(Some(Token::Dirname)),
         "/." => Ok(Some(Token::BaseAndExt)),
+        "##" if stream::streaming() => Ok(Some(Token::Total)),
         "##" => Ok(Some(Token::Argument(Cow::Owned(nargs.to_string())))),
+        // The run ID is the same for every job, and was exported before the command was tokenized.
+        "end"     => Ok(Some(Token::RangeEnd)),
+        "host"    => Ok(Some(Token::Host)),
+        "prev"    => Ok(Some(Token::Previous)),
+        "runid"   => Ok(Some(Token::Argument(Cow::Owned(env::var("PARALLEL_RUNID").unwrap_or_default())))),
+        "scratch" => Ok(Some(Token::Scratch)),
+        "src"     => Ok(Some(Token::Source)),
+        "srcline" => Ok(Some(Token::SourceLine)),
+        "start"   => Ok(Some(Token::RangeStart)),
         _    => {
-            if pattern.starts_with('^') && pattern.len() > 1 {
+            if let Some(format) = pattern.strip_prefix("now:") {
+                time::strftime(format, &time::now()).map_err(|_| TokenErr::InvalidTimeFormat(format))?;
+                Ok(Some(Token::Now(format)))
+            } else if let Some(variable) = pattern.strip_prefix("env:") {
+                // The environment of the program will not change, so variables are expanded in advance.
+                let value = env::var(variable).unwrap_or_default();
+                Ok(Some(Token::Argument(Cow::Owned(value))))
+            } else if pattern.len() > 1 && pattern.starts_with('=') && pattern.ends_with('=') {
+                Expression::parse(&pattern[1..pattern.len()-1])
+                    .map(|expression| Some(Token::Expression(Box::new(expression))))
+                    .map_err(|why| TokenErr::InvalidExpression(pattern, why))
+            } else if pattern.starts_with('^') && pattern.len() > 1 {
                 Ok(Some(Token::RemoveSuffix(&pattern[1..])))
             } else if pattern.starts_with("/^") && pattern.len() > 2 {
                 Ok(Some(Token::BaseAndSuffix(&pattern[2..])))
@@ -165,19 +245,30 @@ fn match_token(pattern: &'static str, path: &Path, nargs: usize) -> Result<Optio
                 let nchars  = ndigits + pattern.bytes().skip(ndigits).count();
                 if ndigits != 0 {
                     let number = pattern[0..ndigits].parse::<usize>().unwrap();
-                    if ndigits == nchars {
+                    if ndigits == nchars && columns {
+                        if number == 0 { return Err(TokenErr::OutOfBounds); }
+                        Ok(Some(Token::Column(number)))
+                    } else if ndigits == nchars {
                         if number == 0 || number > nargs { return Err(TokenErr::OutOfBounds); }
-                        let argument = Number::new(number, Token::Placeholder).into_argument(path)?;
+                        let argument = Number::new(number, Token::Placeholder).into_argument(nth_input)?;
                         Ok(Some(Token::Argument(Cow::Owned(argument))))
+                    } else if columns {
+                        Ok(None)
                     } else {
-                        match match_token(&pattern[ndigits..], path, nargs)? {
-                            None | Some(Token::Job) |  Some(Token::Slot) => Ok(None),
+                        match match_token(&pattern[ndigits..], nth_input, nargs, columns)? {
+                            None | Some(Token::Argument(_)) | Some(Token::Expression(_)) | Some(Token::Host) | Some(Token::Job) |
+                                Some(Token::Now(_)) | Some(Token::Previous) | Some(Token::RangeEnd) | Some(Token::RangeStart) |
+                                Some(Token::Scratch) | Some(Token::Slot) | Some(Token::Source) |
+                                Some(Token::SourceLine) | Some(Token::Total) => Ok(None),
                             Some(token) => {
-                                let argument = Number::new(number, token).into_argument(path)?;
+                                let argument = Number::new(number, token).into_argument(nth_input)?;
                                 Ok(Some(Token::Argument(Cow::Owned(argument))))
                             },
                         }
                     }
+                } else if columns {
+                    // With `--header :`, columns may be referred to by name, as in `{name}`.
+                    Ok(named_column(pattern).map(Token::Column))
                 } else {
                     Ok(None)
                 }
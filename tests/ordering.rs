@@ -0,0 +1,73 @@
+//! Verifies the order in which the output of jobs is printed, with `--test-deterministic`, which
+//! makes the order in which the jobs complete reproducible from a seed. These tests execute the
+//! built program, so they may also be run by packagers against their own builds with `cargo test`.
+
+use std::env;
+use std::fs;
+use std::process::{Command, Stdio};
+
+/// Executes `echo` for each of the `inputs`, returning the lines that were printed.
+fn run(test: &str, seed: u64, options: &[&str], inputs: usize) -> Vec<String> {
+    let tempdir = env::temp_dir().join(format!("parallel_ordering_{}_{}", test, seed));
+    let output = Command::new(env!("CARGO_BIN_EXE_parallel"))
+        .args(["--tempdir", tempdir.to_str().unwrap(), "--test-deterministic", &seed.to_string(), "-j4"])
+        .args(options)
+        .args(["echo", ":::"])
+        .args((1..inputs + 1).map(|input| input.to_string()))
+        .stdin(Stdio::piped())
+        .output()
+        .expect("unable to execute parallel");
+    let _ = fs::remove_dir_all(&tempdir);
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).unwrap().lines().map(str::to_owned).collect()
+}
+
+fn numbers(range: std::ops::Range<usize>) -> Vec<String> {
+    range.map(|number| number.to_string()).collect()
+}
+
+#[test]
+fn grouped_output_keeps_input_order() {
+    for seed in 0..8 {
+        assert_eq!(run("grouped", seed, &[], 25), numbers(1..26), "seed {}", seed);
+        assert_eq!(run("keep_order", seed, &["--keep-order"], 25), numbers(1..26), "seed {}", seed);
+    }
+}
+
+#[test]
+fn line_buffer_follows_completion_order() {
+    for seed in 0..8 {
+        let lines = run("line_buffer", seed, &["--line-buffer"], 10);
+        assert_eq!(lines, run("line_buffer_again", seed, &["--line-buffer"], 10), "seed {}", seed);
+
+        // Jobs complete within batches of as many jobs as there are slots.
+        for (batch, expected) in lines.chunks(4).zip(numbers(1..11).chunks(4)) {
+            let mut batch = batch.to_vec();
+            batch.sort_by_key(|line| line.parse::<usize>().unwrap());
+            assert_eq!(batch, expected, "seed {}", seed);
+        }
+    }
+}
+
+#[test]
+fn joblog_is_reproducible() {
+    let joblog = |name: &str| {
+        let path = env::temp_dir().join(format!("parallel_ordering_{}.log", name));
+        run(name, 5, &["--joblog", path.to_str().unwrap()], 12);
+        let contents = fs::read_to_string(&path).unwrap();
+        let _ = fs::remove_file(&path);
+        // Each run has an ID of its own.
+        contents.lines().filter(|line| !line.starts_with("# RunID")).map(str::to_owned).collect::<Vec<String>>()
+    };
+    let (first, second) = (joblog("joblog"), joblog("joblog_again"));
+    assert_eq!(first.len(), 13);
+    assert_eq!(first, second);
+}
+
+#[test]
+fn tagged_output_keeps_input_order() {
+    for seed in 0..4 {
+        let expected = numbers(1..11).into_iter().map(|number| format!("{}\t{}", number, number)).collect::<Vec<_>>();
+        assert_eq!(run("tag", seed, &["--tag"], 10), expected, "seed {}", seed);
+    }
+}
@@ -0,0 +1,41 @@
+//! Verifies that jobs executed with `--sandbox` may write within their working directory, but not
+//! outside of it. The test is passed over where neither bubblewrap nor user namespaces are available.
+
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+fn sandboxes() -> bool {
+    let works = |program: &str, arguments: &[&str]| Command::new(program).args(arguments)
+        .stdout(Stdio::null()).stderr(Stdio::null()).status().is_ok_and(|status| status.success());
+    works("bwrap", &["--ro-bind", "/", "/", "true"]) || works("unshare", &["--map-root-user", "--mount", "true"])
+}
+
+/// Executes the `command` for the input `file` within the sandbox, from the `workdir`.
+fn run(workdir: &Path, command: &str, file: &str) -> Output {
+    let tempdir = workdir.join("tempdir");
+    Command::new(env!("CARGO_BIN_EXE_parallel"))
+        .args(["--sandbox", "--tempdir", tempdir.to_str().unwrap(), command, ":::", file])
+        .current_dir(workdir)
+        .stdin(Stdio::piped())
+        .output()
+        .expect("unable to execute parallel")
+}
+
+#[test]
+fn workdir_is_writable() {
+    if !sandboxes() { return }
+    let parent = Path::new(env!("CARGO_TARGET_TMPDIR")).join("parallel_sandbox");
+    let workdir = parent.join("workdir");
+    let _ = fs::remove_dir_all(&parent);
+    fs::create_dir_all(&workdir).unwrap();
+
+    let inside = run(&workdir, "echo written > {}", "inside");
+    assert!(inside.status.success(), "{}", String::from_utf8_lossy(&inside.stderr));
+    assert_eq!(fs::read_to_string(workdir.join("inside")).unwrap(), "written\n");
+
+    run(&workdir, "echo written > ../{}", "outside");
+    assert!(!parent.join("outside").exists());
+    let _ = fs::remove_dir_all(&parent);
+}
@@ -0,0 +1,45 @@
+//! Verifies that with `--total-jobs`, jobs are executed while the standard input is still being read,
+//! and that the hint stands in for the total number of jobs until it is known.
+
+use std::env;
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn jobs_start_before_the_standard_input_ends() {
+    let tempdir = env::temp_dir().join("parallel_streaming");
+    let mut child = Command::new(env!("CARGO_BIN_EXE_parallel"))
+        .args(["--tempdir", tempdir.to_str().unwrap(), "--total-jobs", "3", "-j1", "echo {} of {##}"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("unable to execute parallel");
+
+    let mut stdin = child.stdin.take().unwrap();
+    let (lines_tx, lines_rx) = mpsc::channel();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    thread::spawn(move || for line in stdout.lines() { let _ = lines_tx.send(line.unwrap()); });
+
+    // The first job is executed while the standard input is still open, so its total is the hint.
+    writeln!(stdin, "a").unwrap();
+    let first = lines_rx.recv_timeout(Duration::from_secs(10));
+    assert_eq!(first.as_ref().map(String::as_str), Ok("a of 3"));
+
+    // Once the standard input has ended, the number of inputs that were read replaces the hint.
+    writeln!(stdin, "b\nc\nd").unwrap();
+    drop(stdin);
+    assert!(child.wait().unwrap().success());
+    let _ = fs::remove_dir_all(&tempdir);
+
+    let rest = lines_rx.iter().collect::<Vec<String>>();
+    assert_eq!(rest.len(), 3);
+    for (line, input) in rest.iter().zip(["b", "c", "d"]) {
+        assert!(line.starts_with(&format!("{} of ", input)), "{}", line);
+    }
+    assert_eq!(rest[2], "d of 4");
+}